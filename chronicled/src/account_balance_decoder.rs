@@ -0,0 +1,278 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{AccountBalance, BalanceLock, BalanceLockKind};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use subxt::{
+    events::Events,
+    ext::{
+        scale_value::{Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::debug;
+
+const BALANCES_PALLET: &str = "Balances";
+
+/// Storage items holding an account's locks, with the kind they are recorded as
+const LOCK_STORAGE: [(&str, BalanceLockKind); 4] = [
+    ("Locks", BalanceLockKind::Lock),
+    ("Reserves", BalanceLockKind::Reserve),
+    ("Holds", BalanceLockKind::Hold),
+    ("Freezes", BalanceLockKind::Freeze),
+];
+
+/// Decoder for account balances (`System::Account`) and the locks, reserves, holds and
+/// freezes on them (`Balances::Locks` / `Reserves` / `Holds` / `Freezes`)
+pub struct AccountBalanceDecoder;
+
+impl AccountBalanceDecoder {
+    /// Create a new account balance decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Accounts whose locks, reserves, holds or freezes changed in a block.
+    ///
+    /// `Locked` / `Unlocked` and `Frozen` / `Thawed` follow locks and freezes; holds and
+    /// named reserves move funds between free and reserved (`Reserved`, `Unreserved`,
+    /// `ReserveRepatriated`) or are slashed (`Slashed`).
+    pub fn decode_lock_accounts(
+        &self,
+        events: &Events<PolkadotConfig>,
+    ) -> Result<BTreeSet<Vec<u8>>> {
+        let mut accounts = BTreeSet::new();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != BALANCES_PALLET {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let names: &[&str] = match event.variant_name() {
+                "Locked" | "Unlocked" | "Frozen" | "Thawed" | "Reserved" | "Unreserved"
+                | "Slashed" => &["who"],
+                // ReserveRepatriated { from, to, amount, destination_status }
+                "ReserveRepatriated" => &["from", "to"],
+                _ => continue,
+            };
+            accounts.extend(
+                names
+                    .iter()
+                    .filter_map(|name| value_ext::account_field(&fields, name)),
+            );
+        }
+
+        Ok(accounts)
+    }
+
+    /// Read an account's balance from `System::Account` storage at a block
+    pub async fn fetch_balance(
+        &self,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        account: &[u8],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<AccountBalance> {
+        let key = rpc::storage_map_key("System", "Account", account);
+        let (free, reserved, frozen) = match rpc.get_storage(&key, block_hash).await? {
+            Some(bytes) => {
+                let value = rpc::decode_storage_value(metadata, "System", "Account", &bytes)?;
+                account_data(&value)
+            }
+            // Reaped accounts have no entry
+            None => (0, 0, 0),
+        };
+
+        Ok(AccountBalance::new(
+            account.to_vec(),
+            block_number,
+            free,
+            reserved,
+            frozen,
+            block_timestamp,
+        ))
+    }
+
+    /// Read an account's locks, named reserves, holds and freezes at a block. Storage
+    /// items the runtime does not declare are skipped.
+    pub async fn fetch_locks(
+        &self,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        account: &[u8],
+        block_number: i64,
+    ) -> Result<Vec<BalanceLock>> {
+        let Some(storage) = metadata
+            .pallet_by_name(BALANCES_PALLET)
+            .and_then(|p| p.storage())
+        else {
+            return Ok(vec![]);
+        };
+
+        let mut locks = Vec::new();
+        for (item, kind) in LOCK_STORAGE {
+            if storage.entry_by_name(item).is_none() {
+                continue;
+            }
+            let key = rpc::storage_map_key(BALANCES_PALLET, item, account);
+            let Some(bytes) = rpc.get_storage(&key, block_hash).await? else {
+                continue;
+            };
+            let value = rpc::decode_storage_value(metadata, BALANCES_PALLET, item, &bytes)?;
+            locks.extend(locks_from_value(&value, kind, account, block_number));
+        }
+
+        debug!(
+            "{} locks on {} at block {}",
+            locks.len(),
+            hex::encode(account),
+            block_number
+        );
+        Ok(locks)
+    }
+}
+
+impl Default for AccountBalanceDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Free, reserved and frozen amounts of an `AccountInfo { data: AccountData, .. }`
+pub fn account_data<T>(value: &Value<T>) -> (u128, u128, u128) {
+    let ValueDef::Composite(info) = &value.value else {
+        return (0, 0, 0);
+    };
+    let Some(ValueDef::Composite(data)) = value_ext::field(info, "data").map(|d| &d.value) else {
+        return (0, 0, 0);
+    };
+    let amount = |name| value_ext::u128_field(data, name).unwrap_or_default();
+    (amount("free"), amount("reserved"), amount("frozen"))
+}
+
+/// Build lock records from a decoded `BalanceLock` / `ReserveData` / `IdAmount` list
+fn locks_from_value<T>(
+    value: &Value<T>,
+    kind: BalanceLockKind,
+    account: &[u8],
+    block_number: i64,
+) -> Vec<BalanceLock> {
+    let Some(entries) = value_ext::sequence(value) else {
+        return vec![];
+    };
+
+    entries
+        .values()
+        .filter_map(|entry| {
+            let ValueDef::Composite(fields) = &entry.value else {
+                return None;
+            };
+            Some(BalanceLock {
+                account: account.to_vec(),
+                block_number,
+                kind,
+                lock_id: lock_id(value_ext::field(fields, "id")?),
+                amount: value_ext::u128_field(fields, "amount")?.to_string(),
+                reasons: value_ext::field(fields, "reasons")
+                    .and_then(value_ext::variant_name)
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Readable lock identifier: `[u8; 8]` ids as text when printable (`vesting `) and hex
+/// otherwise, runtime reasons as their variant path (`Preimage::Preimage`)
+fn lock_id<T>(value: &Value<T>) -> String {
+    if let ValueDef::Variant(variant) = &value.value {
+        return match variant.values.values().next() {
+            Some(inner)
+                if variant.values.len() == 1 && value_ext::variant_name(inner).is_some() =>
+            {
+                format!("{}::{}", variant.name, lock_id(inner))
+            }
+            _ => variant.name.clone(),
+        };
+    }
+    match value_ext::as_bytes(value) {
+        Some(bytes) if bytes.iter().all(|b| (0x20..0x7f).contains(b)) => {
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        Some(bytes) => format!("0x{}", hex::encode(bytes)),
+        None => value_ext::to_json(value).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locks_from_value() {
+        let id =
+            |bytes: &[u8]| Value::unnamed_composite(bytes.iter().map(|b| Value::u128(*b as u128)));
+        // `WeakBoundedVec<BalanceLock>` decodes as a newtype around the list
+        let locks = Value::unnamed_composite([Value::unnamed_composite([
+            Value::named_composite([
+                ("id", id(b"vesting ")),
+                ("amount", Value::u128(1000)),
+                ("reasons", Value::unnamed_variant("All", [])),
+            ]),
+            Value::named_composite([
+                ("id", id(&[0, 1, 2, 3, 4, 5, 6, 7])),
+                ("amount", Value::u128(5)),
+                ("reasons", Value::unnamed_variant("Fee", [])),
+            ]),
+        ])]);
+        let locks = locks_from_value(&locks, BalanceLockKind::Lock, &[1; 32], 42);
+        assert_eq!(locks.len(), 2);
+        assert_eq!(locks[0].lock_id, "vesting ");
+        assert_eq!(locks[0].amount, "1000");
+        assert_eq!(locks[0].reasons.as_deref(), Some("All"));
+        assert_eq!(locks[1].lock_id, "0x0001020304050607");
+
+        // A single hold must not be mistaken for the wrapper
+        let holds =
+            Value::unnamed_composite([Value::unnamed_composite([Value::named_composite([
+                (
+                    "id",
+                    Value::unnamed_variant("Preimage", [Value::unnamed_variant("Preimage", [])]),
+                ),
+                ("amount", Value::u128(300)),
+            ])])]);
+        let holds = locks_from_value(&holds, BalanceLockKind::Hold, &[1; 32], 42);
+        assert_eq!(holds.len(), 1);
+        assert_eq!(holds[0].lock_id, "Preimage::Preimage");
+        assert_eq!(holds[0].reasons, None);
+    }
+
+    #[test]
+    fn test_account_data() {
+        let info = Value::named_composite([
+            ("nonce", Value::u128(3)),
+            (
+                "data",
+                Value::named_composite([
+                    ("free", Value::u128(1000)),
+                    ("reserved", Value::u128(200)),
+                    ("frozen", Value::u128(500)),
+                    ("flags", Value::unnamed_composite([Value::u128(0)])),
+                ]),
+            ),
+        ]);
+        let (free, reserved, frozen) = account_data(&info);
+        assert_eq!((free, reserved, frozen), (1000, 200, 500));
+
+        // The frozen amount not covered by reserves is untouchable
+        let balance = AccountBalance::new(vec![1; 32], 42, free, reserved, frozen, Utc::now());
+        assert_eq!(balance.transferable, "700");
+        let balance = AccountBalance::new(vec![1; 32], 42, 100, 600, 500, Utc::now());
+        assert_eq!(balance.transferable, "100");
+    }
+}
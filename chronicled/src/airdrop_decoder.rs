@@ -0,0 +1,375 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    Airdrop, AirdropClaim, AirdropRepository, BalanceChange, BalanceChangeReason, DbExecutor,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Composite, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// A change to a campaign carried by a MerkleAirdrop event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AirdropChange {
+    /// `AirdropCreated`, with the campaign's `AirdropMetadata`
+    Created {
+        merkle_root: Option<Vec<u8>>,
+        creator: Option<Vec<u8>>,
+        vesting_period: Option<i64>,
+        vesting_delay: Option<i64>,
+        balance: u128,
+    },
+    /// `AirdropFunded`
+    Funded(u128),
+    /// `Claimed`
+    Claimed(u128),
+    /// `AirdropDeleted`
+    Deleted,
+}
+
+/// A change to one campaign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirdropUpdate {
+    pub airdrop_id: i64,
+    pub change: AirdropChange,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+impl AirdropUpdate {
+    /// Apply the change to the campaign's record
+    pub fn apply(&self, airdrop: &mut Airdrop, block_number: i64) {
+        match &self.change {
+            AirdropChange::Created {
+                merkle_root,
+                creator,
+                vesting_period,
+                vesting_delay,
+                balance,
+            } => {
+                airdrop.merkle_root = merkle_root.clone();
+                airdrop.creator = creator.clone();
+                airdrop.vesting_period = *vesting_period;
+                airdrop.vesting_delay = *vesting_delay;
+                airdrop.balance = balance.to_string();
+                airdrop.created_block = Some(block_number);
+                airdrop.created_extrinsic_hash = self.extrinsic_hash.clone();
+                airdrop.deleted_block = None;
+            }
+            AirdropChange::Funded(amount) => {
+                airdrop.funded = (parse(&airdrop.funded) + amount).to_string();
+                airdrop.balance = (parse(&airdrop.balance) + amount).to_string();
+            }
+            AirdropChange::Claimed(amount) => {
+                airdrop.claimed = (parse(&airdrop.claimed) + amount).to_string();
+                airdrop.claim_count += 1;
+                airdrop.balance = parse(&airdrop.balance).saturating_sub(*amount).to_string();
+            }
+            AirdropChange::Deleted => airdrop.deleted_block = Some(block_number),
+        }
+        airdrop.updated_block = block_number;
+    }
+}
+
+/// MerkleAirdrop activity found in a block's events
+#[derive(Debug, Default)]
+pub struct AirdropEvents {
+    /// Campaign changes, in event order
+    pub updates: Vec<AirdropUpdate>,
+    /// Claims paid out in the block
+    pub claims: Vec<AirdropClaim>,
+}
+
+/// Decoder for the MerkleAirdrop pallet (campaigns and claims)
+pub struct AirdropDecoder;
+
+impl AirdropDecoder {
+    /// Create a new airdrop decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract campaign changes and claims from a block's events
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<AirdropEvents> {
+        let mut decoded = AirdropEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != "MerkleAirdrop" {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let Some(airdrop_id) = value_ext::u128_field(&fields, "airdrop_id").map(|id| id as i64)
+            else {
+                continue;
+            };
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+
+            let change = match event.variant_name() {
+                // AirdropCreated { airdrop_id, airdrop_metadata }
+                "AirdropCreated" => match value_ext::field(&fields, "airdrop_metadata") {
+                    Some(metadata) => match &metadata.value {
+                        ValueDef::Composite(metadata) => created_change(metadata),
+                        _ => continue,
+                    },
+                    None => continue,
+                },
+                // AirdropFunded { airdrop_id, amount }
+                "AirdropFunded" => {
+                    let Some(amount) = value_ext::u128_field(&fields, "amount") else {
+                        continue;
+                    };
+                    AirdropChange::Funded(amount)
+                }
+                // Claimed { airdrop_id, account, amount }
+                "Claimed" => {
+                    let (Some(account), Some(amount)) = (
+                        value_ext::account_field(&fields, "account"),
+                        value_ext::u128_field(&fields, "amount"),
+                    ) else {
+                        warn!("Undecodable airdrop claim at block {}", block_number);
+                        continue;
+                    };
+                    decoded.claims.push(AirdropClaim {
+                        airdrop_id,
+                        account,
+                        amount: amount.to_string(),
+                        block_number,
+                        event_index: event.index() as i32,
+                        extrinsic_hash: extrinsic_hash.clone(),
+                        block_ts: block_timestamp,
+                    });
+                    AirdropChange::Claimed(amount)
+                }
+                // AirdropDeleted { airdrop_id }
+                "AirdropDeleted" => AirdropChange::Deleted,
+                _ => continue,
+            };
+
+            debug!(
+                "Airdrop {} changed by MerkleAirdrop::{} at block {}",
+                airdrop_id,
+                event.variant_name(),
+                block_number
+            );
+            decoded.updates.push(AirdropUpdate {
+                airdrop_id,
+                change,
+                extrinsic_hash,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Apply campaign changes on top of the stored records.
+    ///
+    /// Campaigns created before indexing began are seeded from `AirdropInfo` storage at
+    /// the parent block the first time they are seen.
+    pub async fn resolve_airdrops(
+        &self,
+        updates: &[AirdropUpdate],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        parent_hash: &H256,
+        block_number: i64,
+    ) -> Result<Vec<Airdrop>> {
+        let repo = AirdropRepository::new(conn);
+        let mut airdrops: BTreeMap<i64, Airdrop> = BTreeMap::new();
+
+        for update in updates {
+            let airdrop = match airdrops.entry(update.airdrop_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let known = match repo.get(update.airdrop_id).await? {
+                        Some(airdrop) => Some(airdrop),
+                        None if matches!(update.change, AirdropChange::Created { .. }) => None,
+                        None => {
+                            fetch_airdrop_info(rpc, metadata, parent_hash, update.airdrop_id)
+                                .await?
+                        }
+                    };
+                    entry.insert(
+                        known.unwrap_or_else(|| Airdrop::new(update.airdrop_id, block_number)),
+                    )
+                }
+            };
+            update.apply(airdrop, block_number);
+        }
+
+        Ok(airdrops.into_values().collect())
+    }
+
+    /// Retag the `Balances::Transfer` paying out each claim with the airdrop claim reason.
+    ///
+    /// The pallet pays claims from its own account, so both legs of the transfer in the
+    /// claim's extrinsic are retagged.
+    pub fn tag_claim_transfers(&self, claims: &[AirdropClaim], changes: &mut [BalanceChange]) {
+        for claim in claims {
+            let payout = changes.iter().find(|c| {
+                c.is_native()
+                    && c.reason == BalanceChangeReason::Transfer
+                    && c.event_pallet == "Balances"
+                    && c.event_variant == "Transfer"
+                    && c.extrinsic_hash == claim.extrinsic_hash
+                    && c.event_index < claim.event_index
+                    && c.account == claim.account
+                    && c.delta == claim.amount
+            });
+            let Some(event_index) = payout.map(|c| c.event_index) else {
+                warn!(
+                    "No payout transfer found for airdrop {} claim at block {}",
+                    claim.airdrop_id, claim.block_number
+                );
+                continue;
+            };
+
+            for change in changes
+                .iter_mut()
+                .filter(|c| c.event_index == event_index && c.event_variant == "Transfer")
+            {
+                change.reason = BalanceChangeReason::AirdropClaim;
+            }
+        }
+    }
+}
+
+impl Default for AirdropDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Campaign creation from an `AirdropMetadata` value
+fn created_change<T>(metadata: &Composite<T>) -> AirdropChange {
+    AirdropChange::Created {
+        merkle_root: value_ext::account_field(metadata, "merkle_root"),
+        creator: value_ext::account_field(metadata, "creator"),
+        vesting_period: value_ext::option_u128_field(metadata, "vesting_period").map(|b| b as i64),
+        vesting_delay: value_ext::option_u128_field(metadata, "vesting_delay").map(|b| b as i64),
+        balance: value_ext::u128_field(metadata, "balance").unwrap_or_default(),
+    }
+}
+
+/// Read a campaign from `MerkleAirdrop::AirdropInfo` storage
+async fn fetch_airdrop_info(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    airdrop_id: i64,
+) -> Result<Option<Airdrop>> {
+    let key = rpc::storage_map_key(
+        "MerkleAirdrop",
+        "AirdropInfo",
+        &(airdrop_id as u32).to_le_bytes(),
+    );
+    let Some(bytes) = rpc.get_storage(&key, at).await? else {
+        return Ok(None);
+    };
+    let value = rpc::decode_storage_value(metadata, "MerkleAirdrop", "AirdropInfo", &bytes)?;
+    let ValueDef::Composite(fields) = &value.value else {
+        return Ok(None);
+    };
+
+    let mut airdrop = Airdrop::new(airdrop_id, 0);
+    AirdropUpdate {
+        airdrop_id,
+        change: created_change(fields),
+        extrinsic_hash: None,
+    }
+    .apply(&mut airdrop, 0);
+    airdrop.created_block = None;
+    Ok(Some(airdrop))
+}
+
+fn parse(amount: &str) -> u128 {
+    amount.parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(change: AirdropChange) -> AirdropUpdate {
+        AirdropUpdate {
+            airdrop_id: 3,
+            change,
+            extrinsic_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_airdrop_updates() {
+        let mut airdrop = Airdrop::new(3, 10);
+        update(AirdropChange::Created {
+            merkle_root: Some(vec![9; 32]),
+            creator: Some(vec![1; 32]),
+            vesting_period: None,
+            vesting_delay: None,
+            balance: 0,
+        })
+        .apply(&mut airdrop, 10);
+        update(AirdropChange::Funded(1000)).apply(&mut airdrop, 11);
+        update(AirdropChange::Claimed(300)).apply(&mut airdrop, 12);
+        update(AirdropChange::Claimed(200)).apply(&mut airdrop, 12);
+
+        assert_eq!(airdrop.funded, "1000");
+        assert_eq!(airdrop.claimed, "500");
+        assert_eq!(airdrop.claim_count, 2);
+        assert_eq!(airdrop.balance, "500");
+        assert_eq!(airdrop.created_block, Some(10));
+        assert_eq!(airdrop.updated_block, 12);
+
+        update(AirdropChange::Deleted).apply(&mut airdrop, 13);
+        assert_eq!(airdrop.deleted_block, Some(13));
+    }
+
+    #[test]
+    fn test_tag_claim_transfers() {
+        let transfer = |account: u8, delta: &str| {
+            BalanceChange::new(
+                vec![account; 32],
+                12,
+                2,
+                delta.into(),
+                BalanceChangeReason::Transfer,
+                Some(vec![5; 32]),
+                "Balances".into(),
+                "Transfer".into(),
+                Utc::now(),
+            )
+        };
+        let mut changes = vec![transfer(8, "-300"), transfer(2, "300")];
+        let claim = AirdropClaim {
+            airdrop_id: 3,
+            account: vec![2; 32],
+            amount: "300".into(),
+            block_number: 12,
+            event_index: 3,
+            extrinsic_hash: Some(vec![5; 32]),
+            block_ts: Utc::now(),
+        };
+
+        AirdropDecoder::new().tag_claim_transfers(&[claim], &mut changes);
+        assert!(changes
+            .iter()
+            .all(|c| c.reason == BalanceChangeReason::AirdropClaim));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::value_ext;
+use anyhow::Result;
+use chron_db::{Asset, AssetRepository, DbExecutor};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{events::Events, ext::scale_value::Composite, PolkadotConfig};
+use tracing::debug;
+
+/// A change to an asset's registry record carried by an Assets pallet event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetChange {
+    /// `Created` / `ForceCreated`
+    Created {
+        creator: Option<Vec<u8>>,
+        owner: Option<Vec<u8>>,
+    },
+    /// `MetadataSet` / `MetadataCleared` (all None when cleared)
+    Metadata {
+        name: Option<String>,
+        symbol: Option<String>,
+        decimals: Option<i32>,
+    },
+    /// `OwnerChanged`
+    Owner(Option<Vec<u8>>),
+    /// `TeamChanged`
+    Team {
+        issuer: Option<Vec<u8>>,
+        admin: Option<Vec<u8>>,
+        freezer: Option<Vec<u8>>,
+    },
+    /// `AssetFrozen` / `AssetThawed`
+    Frozen(bool),
+    /// `Destroyed`
+    Destroyed,
+}
+
+/// A registry change for one asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetUpdate {
+    pub asset_id: i64,
+    pub change: AssetChange,
+}
+
+impl AssetUpdate {
+    /// Apply the change to the asset's record
+    pub fn apply(&self, asset: &mut Asset, block_number: i64) {
+        match &self.change {
+            AssetChange::Created { creator, owner } => {
+                asset.creator = creator.clone().or_else(|| owner.clone());
+                asset.owner = owner.clone();
+                // New assets start with the owner in every team role
+                asset.issuer = owner.clone();
+                asset.admin = owner.clone();
+                asset.freezer = owner.clone();
+                asset.created_block = Some(block_number);
+                asset.destroyed_block = None;
+            }
+            AssetChange::Metadata {
+                name,
+                symbol,
+                decimals,
+            } => {
+                asset.name = name.clone();
+                asset.symbol = symbol.clone();
+                asset.decimals = *decimals;
+            }
+            AssetChange::Owner(owner) => asset.owner = owner.clone(),
+            AssetChange::Team {
+                issuer,
+                admin,
+                freezer,
+            } => {
+                asset.issuer = issuer.clone();
+                asset.admin = admin.clone();
+                asset.freezer = freezer.clone();
+            }
+            AssetChange::Frozen(frozen) => asset.is_frozen = *frozen,
+            AssetChange::Destroyed => asset.destroyed_block = Some(block_number),
+        }
+        asset.updated_block = block_number;
+    }
+}
+
+/// Read the `asset_id` field of an Assets pallet event.
+///
+/// Asset ids are stored as BIGINT; ids that do not fit are ignored.
+pub fn asset_id_field<T>(fields: &Composite<T>) -> Option<i64> {
+    value_ext::u128_field(fields, "asset_id").and_then(|id| i64::try_from(id).ok())
+}
+
+/// Decoder for the Assets pallet registry (creation, metadata, team and status)
+pub struct AssetDecoder;
+
+impl AssetDecoder {
+    /// Create a new asset decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract registry changes from a block's events, in event order
+    pub fn decode_asset_updates(
+        &self,
+        events: &Events<PolkadotConfig>,
+    ) -> Result<Vec<AssetUpdate>> {
+        let mut updates = Vec::new();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != "Assets" {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let Some(asset_id) = asset_id_field(&fields) else {
+                continue;
+            };
+            let account = |name| value_ext::account_field(&fields, name);
+
+            let change = match event.variant_name() {
+                "Created" => AssetChange::Created {
+                    creator: account("creator"),
+                    owner: account("owner"),
+                },
+                "ForceCreated" => AssetChange::Created {
+                    creator: None,
+                    owner: account("owner"),
+                },
+                "MetadataSet" => AssetChange::Metadata {
+                    name: value_ext::text_field(&fields, "name"),
+                    symbol: value_ext::text_field(&fields, "symbol"),
+                    decimals: value_ext::u128_field(&fields, "decimals").map(|d| d as i32),
+                },
+                "MetadataCleared" => AssetChange::Metadata {
+                    name: None,
+                    symbol: None,
+                    decimals: None,
+                },
+                "OwnerChanged" => AssetChange::Owner(account("owner")),
+                "TeamChanged" => AssetChange::Team {
+                    issuer: account("issuer"),
+                    admin: account("admin"),
+                    freezer: account("freezer"),
+                },
+                "AssetFrozen" => AssetChange::Frozen(true),
+                "AssetThawed" => AssetChange::Frozen(false),
+                "Destroyed" => AssetChange::Destroyed,
+                _ => continue,
+            };
+
+            debug!(
+                "Asset {} registry change from Assets::{}",
+                asset_id,
+                event.variant_name()
+            );
+            updates.push(AssetUpdate { asset_id, change });
+        }
+
+        Ok(updates)
+    }
+
+    /// Apply registry changes on top of the stored records and write them back.
+    ///
+    /// Returns the number of assets written.
+    pub async fn store_asset_updates(
+        &self,
+        conn: &dyn DbExecutor,
+        updates: &[AssetUpdate],
+        block_number: i64,
+    ) -> Result<usize> {
+        let repo = AssetRepository::new(conn);
+        let mut assets: BTreeMap<i64, Asset> = BTreeMap::new();
+
+        for update in updates {
+            let asset = match assets.entry(update.asset_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let stored = repo.get(update.asset_id).await?;
+                    entry
+                        .insert(stored.unwrap_or_else(|| Asset::new(update.asset_id, block_number)))
+                }
+            };
+            update.apply(asset, block_number);
+        }
+
+        for asset in assets.values() {
+            repo.upsert(asset).await?;
+        }
+
+        Ok(assets.len())
+    }
+}
+
+impl Default for AssetDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_asset_updates() {
+        let mut asset = Asset::new(7, 10);
+        let updates = [
+            AssetChange::Created {
+                creator: Some(vec![1; 32]),
+                owner: Some(vec![2; 32]),
+            },
+            AssetChange::Metadata {
+                name: Some("Tether".into()),
+                symbol: Some("USDT".into()),
+                decimals: Some(6),
+            },
+            AssetChange::Owner(Some(vec![3; 32])),
+            AssetChange::Frozen(true),
+        ];
+        for change in updates {
+            AssetUpdate {
+                asset_id: 7,
+                change,
+            }
+            .apply(&mut asset, 10);
+        }
+
+        assert_eq!(asset.creator, Some(vec![1; 32]));
+        assert_eq!(asset.owner, Some(vec![3; 32]));
+        assert_eq!(asset.issuer, Some(vec![2; 32]));
+        assert_eq!(asset.symbol.as_deref(), Some("USDT"));
+        assert_eq!(asset.decimals, Some(6));
+        assert!(asset.is_frozen);
+        assert_eq!(asset.created_block, Some(10));
+
+        AssetUpdate {
+            asset_id: 7,
+            change: AssetChange::Destroyed,
+        }
+        .apply(&mut asset, 12);
+        assert_eq!(asset.destroyed_block, Some(12));
+        assert_eq!(asset.updated_block, 12);
+    }
+}
@@ -1,9 +1,11 @@
+use crate::{account_balance_decoder, asset_decoder, rpc, rpc::RpcHelper, value_ext};
 use anyhow::Result;
 use chron_db::{BalanceChange, BalanceChangeReason};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
+use parity_scale_codec::Decode;
 use subxt::{
-    events::{EventDetails, Events},
-    OnlineClient, PolkadotConfig,
+    events::{EventDetails, Events, Phase},
+    Metadata, OnlineClient, PolkadotConfig,
 };
 use tracing::{debug, info};
 
@@ -19,17 +21,21 @@ impl BalanceDecoder {
     }
 
     /// Process events from a block and extract balance changes
+    ///
+    /// `extrinsic_hashes` are the hashes of the block's extrinsics in order, used to
+    /// attribute events emitted while applying an extrinsic.
     pub async fn decode_balance_changes(
         &self,
-        events: Events<PolkadotConfig>,
+        events: &Events<PolkadotConfig>,
         block_number: i64,
         block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
     ) -> Result<Vec<BalanceChange>> {
         let mut balance_changes = Vec::new();
-        let mut event_index = 0i32;
 
         for event in events.iter() {
             let event = event?;
+            let event_index = event.index() as i32;
 
             // Get pallet and event names
             let pallet_name = event.pallet_name();
@@ -41,10 +47,10 @@ impl BalanceDecoder {
             );
 
             // Get the extrinsic hash if this event is part of an extrinsic
-            // For now, we'll use None as getting the actual extrinsic hash
-            // requires accessing the block's extrinsics which would need
-            // additional context beyond just the Events object
-            let extrinsic_hash: Option<Vec<u8>> = None;
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
 
             // Extract balance changes based on event type
             let changes = match (pallet_name, event_name) {
@@ -134,12 +140,20 @@ impl BalanceDecoder {
                         extrinsic_hash,
                     )?,
 
+                // Assets pallet: per-asset deltas
+                ("Assets", _) => self.decode_asset_event(
+                    &event,
+                    block_number,
+                    event_index,
+                    block_timestamp,
+                    extrinsic_hash,
+                )?,
+
                 // Skip other events
                 _ => vec![],
             };
 
             balance_changes.extend(changes);
-            event_index += 1;
         }
 
         Ok(balance_changes)
@@ -154,87 +168,65 @@ impl BalanceDecoder {
         block_timestamp: DateTime<Utc>,
         extrinsic_hash: Option<Vec<u8>>,
     ) -> Result<Vec<BalanceChange>> {
-        use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
-
-        let decoded = event.as_root_event::<Value>()?;
+        let fields = event.field_values()?;
         let mut changes = Vec::new();
 
         // Transfer event structure: { from: AccountId, to: AccountId, amount: Balance }
-        if let ValueDef::Composite(Composite::Named(fields)) = &decoded.value {
-            let mut from_account: Option<Vec<u8>> = None;
-            let mut to_account: Option<Vec<u8>> = None;
-            let mut amount: Option<u128> = None;
-
-            for (name, value) in fields {
-                match name.as_str() {
-                    "from" => {
-                        if let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
-                            if let Some(first_val) = vals.first() {
-                                if let ValueDef::Primitive(Primitive::U128(bytes)) =
-                                    &first_val.value
-                                {
-                                    from_account = Some(bytes.to_le_bytes().to_vec());
-                                }
-                            }
-                        }
-                    }
-                    "to" => {
-                        if let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
-                            if let Some(first_val) = vals.first() {
-                                if let ValueDef::Primitive(Primitive::U128(bytes)) =
-                                    &first_val.value
-                                {
-                                    to_account = Some(bytes.to_le_bytes().to_vec());
-                                }
-                            }
-                        }
-                    }
-                    "amount" => {
-                        if let ValueDef::Primitive(Primitive::U128(val)) = &value.value {
-                            amount = Some(*val);
-                        }
-                    }
-                    _ => {}
-                }
+        let from_account = value_ext::account_field(&fields, "from");
+        let to_account = value_ext::account_field(&fields, "to");
+        let amount = value_ext::u128_field(&fields, "amount");
+
+        if let (Some(from), Some(to), Some(amt)) = (from_account, to_account, amount) {
+            // A transfer to self does not change the balance
+            if from == to {
+                return Ok(changes);
             }
 
-            if let (Some(from), Some(to), Some(amt)) = (from_account, to_account, amount) {
-                // Create negative balance change for sender
-                changes.push(BalanceChange {
-                    id: None,
-                    account: from.clone(),
-                    block_number,
-                    event_index,
-                    delta: (-(amt as i128)).to_string(),
-                    reason: BalanceChangeReason::Transfer,
-                    extrinsic_hash: extrinsic_hash.clone(),
-                    event_pallet: "Balances".to_string(),
-                    event_variant: "Transfer".to_string(),
-                    block_ts: block_timestamp,
-                });
-
-                // Create positive balance change for receiver
-                changes.push(BalanceChange {
-                    id: None,
-                    account: to.clone(),
-                    block_number,
-                    event_index: event_index + 1,
-                    delta: (amt as i128).to_string(),
-                    reason: BalanceChangeReason::Transfer,
-                    extrinsic_hash,
-                    event_pallet: "Balances".to_string(),
-                    event_variant: "Transfer".to_string(),
-                    block_ts: block_timestamp,
-                });
+            // Create negative balance change for sender
+            changes.push(BalanceChange {
+                id: None,
+                account: from.clone(),
+                asset_id: None,
+                block_number,
+                event_index,
+                delta: (-(amt as i128)).to_string(),
+                reason: BalanceChangeReason::Transfer,
+                extrinsic_hash: extrinsic_hash.clone(),
+                event_pallet: "Balances".to_string(),
+                event_variant: "Transfer".to_string(),
+                batch_index: None,
+                call_path: None,
+                block_hash: None,
+                is_canonical: true,
+                block_ts: block_timestamp,
+            });
+
+            // Create positive balance change for receiver
+            changes.push(BalanceChange {
+                id: None,
+                account: to.clone(),
+                asset_id: None,
+                block_number,
+                event_index,
+                delta: (amt as i128).to_string(),
+                reason: BalanceChangeReason::Transfer,
+                extrinsic_hash,
+                event_pallet: "Balances".to_string(),
+                event_variant: "Transfer".to_string(),
+                batch_index: None,
+                call_path: None,
+                block_hash: None,
+                is_canonical: true,
+                block_ts: block_timestamp,
+            });
 
-                debug!(
-                    "Decoded Transfer at block {}: {} tokens from {} to {}",
-                    block_number,
-                    amt,
-                    hex::encode(&from),
-                    hex::encode(&to)
-                );
-            }
+            debug!(
+                "Decoded Transfer at block {}: {} tokens from {} to {}",
+                block_number,
+                amt,
+                hex::encode(&from),
+                hex::encode(&to)
+            );
         }
 
         Ok(changes)
@@ -249,56 +241,35 @@ impl BalanceDecoder {
         block_timestamp: DateTime<Utc>,
         extrinsic_hash: Option<Vec<u8>>,
     ) -> Result<Vec<BalanceChange>> {
-        use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
-
         // Decode the event dynamically
-        let decoded = event.as_root_event::<Value>()?;
+        let fields = event.field_values()?;
 
         let mut changes = Vec::new();
 
         // Endowed event structure: { account: AccountId, free_balance: Balance }
-        if let ValueDef::Composite(Composite::Named(fields)) = &decoded.value {
-            let mut account: Option<Vec<u8>> = None;
-            let mut balance: Option<i128> = None;
-
-            for (name, value) in fields {
-                match name.as_str() {
-                    "account" => {
-                        if let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
-                            if let Some(first_val) = vals.first() {
-                                if let ValueDef::Primitive(Primitive::U128(bytes)) =
-                                    &first_val.value
-                                {
-                                    account = Some(bytes.to_le_bytes().to_vec());
-                                }
-                            }
-                        }
-                    }
-                    "free_balance" => {
-                        if let ValueDef::Primitive(Primitive::U128(val)) = &value.value {
-                            balance = Some(*val as i128);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-
-            if let (Some(acc), Some(bal)) = (account, balance) {
-                changes.push(BalanceChange {
-                    id: None,
-                    account: acc,
-                    block_number,
-                    event_index,
-                    delta: bal.to_string(),
-                    reason: BalanceChangeReason::Endowment,
-                    extrinsic_hash,
-                    event_pallet: "Balances".to_string(),
-                    event_variant: "Endowed".to_string(),
-                    block_ts: block_timestamp,
-                });
-
-                debug!("Decoded Endowed at block {}: {} tokens", block_number, bal);
-            }
+        let account = value_ext::account_field(&fields, "account");
+        let balance = value_ext::u128_field(&fields, "free_balance").map(|b| b as i128);
+
+        if let (Some(acc), Some(bal)) = (account, balance) {
+            changes.push(BalanceChange {
+                id: None,
+                account: acc,
+                asset_id: None,
+                block_number,
+                event_index,
+                delta: bal.to_string(),
+                reason: BalanceChangeReason::Endowment,
+                extrinsic_hash,
+                event_pallet: "Balances".to_string(),
+                event_variant: "Endowed".to_string(),
+                batch_index: None,
+                call_path: None,
+                block_hash: None,
+                is_canonical: true,
+                block_ts: block_timestamp,
+            });
+
+            debug!("Decoded Endowed at block {}: {} tokens", block_number, bal);
         }
 
         Ok(changes)
@@ -313,54 +284,33 @@ impl BalanceDecoder {
         block_timestamp: DateTime<Utc>,
         extrinsic_hash: Option<Vec<u8>>,
     ) -> Result<Vec<BalanceChange>> {
-        use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
-
-        let decoded = event.as_root_event::<Value>()?;
+        let fields = event.field_values()?;
         let mut changes = Vec::new();
 
         // Deposit event structure: { who: AccountId, amount: Balance }
-        if let ValueDef::Composite(Composite::Named(fields)) = &decoded.value {
-            let mut account: Option<Vec<u8>> = None;
-            let mut amount: Option<i128> = None;
-
-            for (name, value) in fields {
-                match name.as_str() {
-                    "who" => {
-                        if let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
-                            if let Some(first_val) = vals.first() {
-                                if let ValueDef::Primitive(Primitive::U128(bytes)) =
-                                    &first_val.value
-                                {
-                                    account = Some(bytes.to_le_bytes().to_vec());
-                                }
-                            }
-                        }
-                    }
-                    "amount" => {
-                        if let ValueDef::Primitive(Primitive::U128(val)) = &value.value {
-                            amount = Some(*val as i128);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-
-            if let (Some(acc), Some(amt)) = (account, amount) {
-                changes.push(BalanceChange {
-                    id: None,
-                    account: acc,
-                    block_number,
-                    event_index,
-                    delta: amt.to_string(),
-                    reason: BalanceChangeReason::Deposit,
-                    extrinsic_hash,
-                    event_pallet: "Balances".to_string(),
-                    event_variant: "Deposit".to_string(),
-                    block_ts: block_timestamp,
-                });
-
-                debug!("Decoded Deposit at block {}: {} tokens", block_number, amt);
-            }
+        let account = value_ext::account_field(&fields, "who");
+        let amount = value_ext::u128_field(&fields, "amount").map(|a| a as i128);
+
+        if let (Some(acc), Some(amt)) = (account, amount) {
+            changes.push(BalanceChange {
+                id: None,
+                account: acc,
+                asset_id: None,
+                block_number,
+                event_index,
+                delta: amt.to_string(),
+                reason: BalanceChangeReason::Deposit,
+                extrinsic_hash,
+                event_pallet: "Balances".to_string(),
+                event_variant: "Deposit".to_string(),
+                batch_index: None,
+                call_path: None,
+                block_hash: None,
+                is_canonical: true,
+                block_ts: block_timestamp,
+            });
+
+            debug!("Decoded Deposit at block {}: {} tokens", block_number, amt);
         }
 
         Ok(changes)
@@ -375,54 +325,33 @@ impl BalanceDecoder {
         block_timestamp: DateTime<Utc>,
         extrinsic_hash: Option<Vec<u8>>,
     ) -> Result<Vec<BalanceChange>> {
-        use subxt::ext::scale_value::{Composite, Primitive, Value, ValueDef};
-
-        let decoded = event.as_root_event::<Value>()?;
+        let fields = event.field_values()?;
         let mut changes = Vec::new();
 
         // Withdraw event structure: { who: AccountId, amount: Balance }
-        if let ValueDef::Composite(Composite::Named(fields)) = &decoded.value {
-            let mut account: Option<Vec<u8>> = None;
-            let mut amount: Option<i128> = None;
-
-            for (name, value) in fields {
-                match name.as_str() {
-                    "who" => {
-                        if let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
-                            if let Some(first_val) = vals.first() {
-                                if let ValueDef::Primitive(Primitive::U128(bytes)) =
-                                    &first_val.value
-                                {
-                                    account = Some(bytes.to_le_bytes().to_vec());
-                                }
-                            }
-                        }
-                    }
-                    "amount" => {
-                        if let ValueDef::Primitive(Primitive::U128(val)) = &value.value {
-                            amount = Some(*val as i128);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-
-            if let (Some(acc), Some(amt)) = (account, amount) {
-                changes.push(BalanceChange {
-                    id: None,
-                    account: acc,
-                    block_number,
-                    event_index,
-                    delta: (-(amt as i128)).to_string(), // Withdrawal is negative
-                    reason: BalanceChangeReason::Withdrawal,
-                    extrinsic_hash,
-                    event_pallet: "Balances".to_string(),
-                    event_variant: "Withdraw".to_string(),
-                    block_ts: block_timestamp,
-                });
-
-                debug!("Decoded Withdraw at block {}: {} tokens", block_number, amt);
-            }
+        let account = value_ext::account_field(&fields, "who");
+        let amount = value_ext::u128_field(&fields, "amount").map(|a| a as i128);
+
+        if let (Some(acc), Some(amt)) = (account, amount) {
+            changes.push(BalanceChange {
+                id: None,
+                account: acc,
+                asset_id: None,
+                block_number,
+                event_index,
+                delta: (-amt).to_string(), // Withdrawal is negative
+                reason: BalanceChangeReason::Withdrawal,
+                extrinsic_hash,
+                event_pallet: "Balances".to_string(),
+                event_variant: "Withdraw".to_string(),
+                batch_index: None,
+                call_path: None,
+                block_hash: None,
+                is_canonical: true,
+                block_ts: block_timestamp,
+            });
+
+            debug!("Decoded Withdraw at block {}: {} tokens", block_number, amt);
         }
 
         Ok(changes)
@@ -546,17 +475,114 @@ impl BalanceDecoder {
         Ok(vec![])
     }
 
-    /// Query genesis endowments from storage at block 0
+    /// Decode an Assets pallet event into per-asset balance changes
+    fn decode_asset_event(
+        &self,
+        event: &EventDetails<PolkadotConfig>,
+        block_number: i64,
+        event_index: i32,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hash: Option<Vec<u8>>,
+    ) -> Result<Vec<BalanceChange>> {
+        let fields = event.field_values()?;
+        let variant = event.variant_name();
+        let Some(asset_id) = asset_decoder::asset_id_field(&fields) else {
+            return Ok(vec![]);
+        };
+        let account = |name| value_ext::account_field(&fields, name);
+        let amount = |name| value_ext::u128_field(&fields, name).map(|a| a as i128);
+
+        // (account, signed amount, reason) for each side of the event
+        let deltas = match variant {
+            // Issued { asset_id, owner, amount }
+            "Issued" => vec![(
+                account("owner"),
+                amount("amount"),
+                BalanceChangeReason::Issue,
+            )],
+            // Burned { asset_id, owner, balance }
+            "Burned" => vec![(
+                account("owner"),
+                amount("balance").map(|a| -a),
+                BalanceChangeReason::Burn,
+            )],
+            // Transferred { asset_id, from, to, amount }
+            "Transferred" if account("from") != account("to") => vec![
+                (
+                    account("from"),
+                    amount("amount").map(|a| -a),
+                    BalanceChangeReason::Transfer,
+                ),
+                (
+                    account("to"),
+                    amount("amount"),
+                    BalanceChangeReason::Transfer,
+                ),
+            ],
+            // TransferredApproved { asset_id, owner, delegate, destination, amount }
+            "TransferredApproved" if account("owner") != account("destination") => vec![
+                (
+                    account("owner"),
+                    amount("amount").map(|a| -a),
+                    BalanceChangeReason::Transfer,
+                ),
+                (
+                    account("destination"),
+                    amount("amount"),
+                    BalanceChangeReason::Transfer,
+                ),
+            ],
+            // Deposited { asset_id, who, amount }
+            "Deposited" => vec![(
+                account("who"),
+                amount("amount"),
+                BalanceChangeReason::Deposit,
+            )],
+            // Withdrawn { asset_id, who, amount }
+            "Withdrawn" => vec![(
+                account("who"),
+                amount("amount").map(|a| -a),
+                BalanceChangeReason::Withdrawal,
+            )],
+            _ => vec![],
+        };
+
+        let changes: Vec<BalanceChange> = deltas
+            .into_iter()
+            .filter_map(|(account, delta, reason)| {
+                Some(
+                    BalanceChange::new(
+                        account?,
+                        block_number,
+                        event_index,
+                        delta?.to_string(),
+                        reason,
+                        extrinsic_hash.clone(),
+                        "Assets".to_string(),
+                        variant.to_string(),
+                        block_timestamp,
+                    )
+                    .with_asset(asset_id),
+                )
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            debug!(
+                "Decoded Assets::{} for asset {} at block {}",
+                variant, asset_id, block_number
+            );
+        }
+        Ok(changes)
+    }
+
+    /// Read every account's balance from `System::Account` at the genesis block as an
+    /// endowment, since genesis balances are set without `Endowed` events.
     ///
-    /// This is a simplified implementation. For a full implementation,
-    /// you would need to:
-    /// 1. Use the metadata to understand the storage layout
-    /// 2. Decode the storage values properly based on the chain's types
-    /// 3. Handle different account representations (AccountId32, etc.)
-    pub async fn query_genesis_endowments(&self) -> Result<Vec<BalanceChange>> {
-        let endowments = Vec::new();
-
-        // Get genesis block hash
+    /// Keys and values are read through `rpc`, so with read proofs enabled each account
+    /// and its balance is proven against the genesis state root. The endowments carry the
+    /// genesis `Timestamp::Now`, so their ledger hash does not depend on when they were read.
+    pub async fn query_genesis_endowments(&self, rpc: &RpcHelper) -> Result<Vec<BalanceChange>> {
         let genesis_hash = self.client.genesis_hash();
 
         info!(
@@ -564,12 +590,50 @@ impl BalanceDecoder {
             hex::encode(genesis_hash)
         );
 
-        // In a real implementation, you would:
-        // 1. Query System.Account storage entries at genesis
-        // 2. Decode the AccountInfo structure to get balances
-        // 3. Create BalanceChange entries for non-zero balances
+        let metadata = Metadata::decode(&mut &rpc.get_metadata_bytes(&genesis_hash).await?[..])?;
+        let keys = rpc
+            .get_keys(&rpc::storage_key("System", "Account"), &genesis_hash)
+            .await?;
+        let block_timestamp = genesis_timestamp(
+            rpc.get_storage(&rpc::storage_key("Timestamp", "Now"), &genesis_hash)
+                .await?,
+        );
 
-        // For now, return empty as this requires chain-specific implementation
+        let mut endowments = Vec::new();
+        for key in &keys {
+            let Some(account) = endowed_account(key) else {
+                continue;
+            };
+            let Some(bytes) = rpc.get_storage(key, &genesis_hash).await? else {
+                continue;
+            };
+            let value = rpc::decode_storage_value(&metadata, "System", "Account", &bytes)?;
+            let (free, reserved, _) = account_balance_decoder::account_data(&value);
+            let total = free.saturating_add(reserved);
+            if total == 0 {
+                continue;
+            }
+
+            let mut endowment = BalanceChange::new(
+                account.to_vec(),
+                0,
+                endowments.len() as i32,
+                total.to_string(),
+                BalanceChangeReason::Endowment,
+                None,
+                "System".to_string(),
+                "Account".to_string(),
+                block_timestamp,
+            );
+            endowment.block_hash = Some(genesis_hash.as_bytes().to_vec());
+            endowments.push(endowment);
+        }
+
+        debug!(
+            "Read {} genesis endowments from {} accounts",
+            endowments.len(),
+            keys.len()
+        );
         Ok(endowments)
     }
 
@@ -632,3 +696,52 @@ impl BalanceDecoder {
         Ok(vec![])
     }
 }
+
+/// Account of a `System::Account` storage key: the key ends with the `Blake2_128Concat`
+/// hashed account id
+fn endowed_account(key: &[u8]) -> Option<&[u8]> {
+    // Pallet and item prefixes, then the 16-byte Blake2-128 hash of the account
+    let account = key.get(32 + 16..)?;
+    (!account.is_empty()).then_some(account)
+}
+
+/// Block time of genesis from its `Timestamp::Now` value; the Unix epoch when it is unset
+fn genesis_timestamp(now: Option<Vec<u8>>) -> DateTime<Utc> {
+    let millis = now
+        .and_then(|bytes| u64::decode(&mut &bytes[..]).ok())
+        .unwrap_or_default();
+    Utc.timestamp_millis_opt(millis as i64)
+        .single()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+
+    #[test]
+    fn test_genesis_timestamp() {
+        let millis = 1_700_000_000_123u64;
+        assert_eq!(
+            genesis_timestamp(Some(millis.encode())),
+            Utc.timestamp_millis_opt(millis as i64).unwrap()
+        );
+        assert_eq!(genesis_timestamp(None), DateTime::<Utc>::UNIX_EPOCH);
+        assert_eq!(
+            genesis_timestamp(Some(vec![1, 2])),
+            DateTime::<Utc>::UNIX_EPOCH
+        );
+    }
+
+    #[test]
+    fn test_endowed_account() {
+        let account = [7u8; 32];
+        let key = rpc::storage_map_key("System", "Account", &account);
+        assert_eq!(endowed_account(&key), Some(&account[..]));
+        assert_eq!(
+            endowed_account(&rpc::storage_key("System", "Account")),
+            None
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::rpc::{RpcBlock, RpcBlockData, RpcHeader, RpcHelper};
+use anyhow::Result;
+use parity_scale_codec::Encode;
+use subxt::{
+    config::substrate::{BlakeTwo256 as HeaderHasher, Digest, SubstrateHeader},
+    ext::{
+        sp_core::{blake2_256, H256},
+        sp_runtime::{
+            traits::{BlakeTwo256, Hash},
+            StateVersion,
+        },
+    },
+};
+use tracing::warn;
+
+/// Why a fetched block was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationFailure {
+    /// The SCALE-encoded header does not hash to the requested block hash
+    HeaderHash { computed: H256 },
+    /// The body does not match the header's extrinsics root
+    ExtrinsicsRoot { expected: H256, computed: H256 },
+    /// The header's parent is not the block indexed just before it
+    ParentHash { expected: H256, found: H256 },
+    /// The header number could not be read
+    Number(String),
+}
+
+impl std::fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HeaderHash { computed } => {
+                write!(f, "header hashes to {}", hex::encode(computed))
+            }
+            Self::ExtrinsicsRoot { expected, computed } => write!(
+                f,
+                "extrinsics root {} does not match the body ({})",
+                hex::encode(expected),
+                hex::encode(computed)
+            ),
+            Self::ParentHash { expected, found } => write!(
+                f,
+                "parent {} is not the indexed block {}",
+                hex::encode(found),
+                hex::encode(expected)
+            ),
+            Self::Number(number) => write!(f, "unreadable block number {}", number),
+        }
+    }
+}
+
+/// Checks blocks served over RPC against their own commitments before they are indexed,
+/// re-fetching a block that fails from the fallback endpoints in turn
+pub struct BlockVerifier {
+    fallbacks: Vec<(String, RpcHelper)>,
+}
+
+impl BlockVerifier {
+    /// Create a verifier without fallback endpoints
+    pub fn new() -> Self {
+        Self {
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Re-fetch blocks that fail verification from this endpoint
+    pub fn with_fallback(mut self, url: String, rpc: RpcHelper) -> Self {
+        self.fallbacks.push((url, rpc));
+        self
+    }
+
+    /// Fetch a block from `primary`, then from each fallback endpoint, until one serves a
+    /// block that passes [`verify_block`]
+    pub async fn fetch_verified(
+        &self,
+        primary: &RpcHelper,
+        hash: &H256,
+        expected_parent: Option<&H256>,
+    ) -> Result<RpcBlock> {
+        let endpoints = std::iter::once(("primary endpoint", primary))
+            .chain(self.fallbacks.iter().map(|(url, rpc)| (url.as_str(), rpc)));
+
+        let mut failures = Vec::new();
+        for (endpoint, rpc) in endpoints {
+            let block = match rpc.get_block_by_hash(hash).await {
+                Ok(block) => block,
+                Err(e) => {
+                    failures.push(format!("{}: {}", endpoint, e));
+                    continue;
+                }
+            };
+            match verify_block(&block.block, hash, expected_parent) {
+                Ok(()) => return Ok(block),
+                Err(failure) => {
+                    warn!(
+                        "Rejected block {} from {}: {}",
+                        hex::encode(hash),
+                        endpoint,
+                        failure
+                    );
+                    failures.push(format!("{}: {}", endpoint, failure));
+                }
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "No endpoint served a valid block {} ({})",
+            hex::encode(hash),
+            failures.join("; ")
+        ))
+    }
+}
+
+impl Default for BlockVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check that a block's header hashes to `hash`, that its body matches the header's
+/// extrinsics root and, when known, that it builds on `expected_parent`
+pub fn verify_block(
+    block: &RpcBlockData,
+    hash: &H256,
+    expected_parent: Option<&H256>,
+) -> std::result::Result<(), VerificationFailure> {
+    let header = &block.header;
+    verify_header(header, hash)?;
+
+    let extrinsics = block
+        .extrinsic_bytes()
+        .map_err(|_| VerificationFailure::ExtrinsicsRoot {
+            expected: header.extrinsics_root,
+            computed: H256::zero(),
+        })?;
+    let computed = extrinsics_root(&extrinsics, header.extrinsics_root);
+    if computed != header.extrinsics_root {
+        return Err(VerificationFailure::ExtrinsicsRoot {
+            expected: header.extrinsics_root,
+            computed,
+        });
+    }
+
+    match expected_parent {
+        Some(parent) if *parent != header.parent_hash => Err(VerificationFailure::ParentHash {
+            expected: *parent,
+            found: header.parent_hash,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Check that a header hashes to `hash`, which makes its state and extrinsics roots as
+/// trustworthy as the hash itself
+pub fn verify_header(
+    header: &RpcHeader,
+    hash: &H256,
+) -> std::result::Result<(), VerificationFailure> {
+    let number = u32::from_str_radix(header.number.trim_start_matches("0x"), 16)
+        .map_err(|_| VerificationFailure::Number(header.number.clone()))?;
+
+    let computed = header_hash(
+        header.parent_hash,
+        number,
+        header.state_root,
+        header.extrinsics_root,
+        Digest {
+            logs: header.digest_logs(),
+        },
+    );
+    if computed != *hash {
+        return Err(VerificationFailure::HeaderHash { computed });
+    }
+    Ok(())
+}
+
+/// Blake2-256 of the SCALE-encoded header
+fn header_hash(
+    parent_hash: H256,
+    number: u32,
+    state_root: H256,
+    extrinsics_root: H256,
+    digest: Digest,
+) -> H256 {
+    let header = SubstrateHeader::<u32, HeaderHasher> {
+        parent_hash,
+        number,
+        state_root,
+        extrinsics_root,
+        digest,
+    };
+    H256(blake2_256(&header.encode()))
+}
+
+/// Ordered trie root of the encoded extrinsics. The layout follows the runtime's system
+/// version, so the V1 root is computed when the V0 root does not match `expected`.
+fn extrinsics_root(extrinsics: &[Vec<u8>], expected: H256) -> H256 {
+    let root = BlakeTwo256::ordered_trie_root(extrinsics.to_vec(), StateVersion::V0);
+    if root == expected {
+        return root;
+    }
+    BlakeTwo256::ordered_trie_root(extrinsics.to_vec(), StateVersion::V1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use subxt::config::substrate::DigestItem;
+
+    fn block(extrinsics: &[Vec<u8>], parent: H256) -> (RpcBlockData, H256) {
+        let digest = Digest {
+            logs: vec![DigestItem::Seal(*b"pow_", vec![1, 2, 3])],
+        };
+        let extrinsics_root = extrinsics_root(extrinsics, H256::zero());
+        let state_root = H256::repeat_byte(5);
+        let hash = header_hash(parent, 42, state_root, extrinsics_root, digest.clone());
+        let block = RpcBlockData {
+            header: RpcHeader {
+                parent_hash: parent,
+                number: "0x2a".into(),
+                state_root,
+                extrinsics_root,
+                digest: serde_json::to_value(&digest).unwrap(),
+            },
+            extrinsics: extrinsics
+                .iter()
+                .map(|e| format!("0x{}", hex::encode(e)))
+                .collect(),
+        };
+        (block, hash)
+    }
+
+    #[test]
+    fn test_empty_extrinsics_root() {
+        // Root of an empty trie, Blake2-256 of the empty node
+        let empty = crate::rpc::hex_to_h256(
+            "0x03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314",
+        )
+        .unwrap();
+        assert_eq!(extrinsics_root(&[], empty), empty);
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let extrinsics = vec![vec![0x10, 1, 2, 3, 4], vec![0x08, 9, 9]];
+        let parent = H256::repeat_byte(1);
+        let (valid, hash) = block(&extrinsics, parent);
+        assert_eq!(verify_block(&valid, &hash, Some(&parent)), Ok(()));
+        assert_eq!(verify_block(&valid, &hash, None), Ok(()));
+
+        // Another hash than the one requested
+        assert!(matches!(
+            verify_block(&valid, &H256::repeat_byte(2), None),
+            Err(VerificationFailure::HeaderHash { .. })
+        ));
+
+        // A body swapped under an honest header
+        let (mut tampered, hash) = block(&extrinsics, parent);
+        tampered.extrinsics.pop();
+        assert!(matches!(
+            verify_block(&tampered, &hash, None),
+            Err(VerificationFailure::ExtrinsicsRoot { .. })
+        ));
+
+        // A valid block on another branch
+        let other = H256::repeat_byte(3);
+        assert_eq!(
+            verify_block(&valid, &hash, Some(&other)),
+            Err(VerificationFailure::ParentHash {
+                expected: other,
+                found: parent
+            })
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::rpc::RpcHelper;
+use anyhow::Result;
+use chron_db::ChainInfo;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use subxt::ext::sp_core::H256;
+
+/// Query the node for the chain's identity and token properties.
+///
+/// `endpoint` is recorded so the table lists every node the indexer has used.
+pub async fn fetch_chain_info(
+    rpc: &RpcHelper,
+    chain_id: &str,
+    genesis_hash: H256,
+    ss58_prefix: u16,
+    spec_version: u32,
+    endpoint: &str,
+) -> Result<ChainInfo> {
+    let properties = rpc.system_properties().await?;
+
+    Ok(ChainInfo {
+        chain_id: chain_id.to_string(),
+        genesis_hash: genesis_hash.as_bytes().to_vec(),
+        chain_name: rpc.system_chain().await?,
+        node_name: rpc.system_name().await?,
+        node_version: rpc.system_version().await?,
+        token_symbol: first_property(&properties, "tokenSymbol")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        token_decimals: first_property(&properties, "tokenDecimals")
+            .and_then(|v| v.as_u64())
+            .map(|d| d as i32),
+        ss58_prefix: ss58_prefix as i32,
+        properties: Value::Object(properties),
+        spec_version: spec_version as i32,
+        endpoints: vec![endpoint.to_string()],
+        updated_at: Utc::now(),
+    })
+}
+
+/// Re-query the node after a runtime upgrade, keeping the previously recorded endpoints
+pub async fn refresh_chain_info(
+    rpc: &RpcHelper,
+    previous: &ChainInfo,
+    spec_version: u32,
+) -> Result<ChainInfo> {
+    let mut info = fetch_chain_info(
+        rpc,
+        &previous.chain_id,
+        H256::from_slice(&previous.genesis_hash),
+        previous.ss58_prefix as u16,
+        spec_version,
+        "",
+    )
+    .await?;
+    info.endpoints = previous.endpoints.clone();
+    Ok(info)
+}
+
+/// Read a property that multi-token chains report as an array, taking the native token's entry
+fn first_property<'a>(properties: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    match properties.get(key)? {
+        Value::Array(values) => values.first(),
+        value => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_first_property() {
+        let properties = json!({
+            "tokenSymbol": ["QU", "USDT"],
+            "tokenDecimals": 12,
+        });
+        let properties = properties.as_object().unwrap();
+
+        assert_eq!(
+            first_property(properties, "tokenSymbol"),
+            Some(&json!("QU"))
+        );
+        assert_eq!(
+            first_property(properties, "tokenDecimals"),
+            Some(&json!(12))
+        );
+        assert_eq!(first_property(properties, "ss58Format"), None);
+    }
+}
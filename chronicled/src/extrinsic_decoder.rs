@@ -0,0 +1,303 @@
+use crate::{signature, value_ext};
+use anyhow::Result;
+use chron_db::{Extrinsic, PublicKey};
+use chrono::{DateTime, TimeZone, Utc};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{scale::decode_as_type, Composite, Value, ValueDef},
+        sp_core::blake2_256,
+        subxt_core::blocks::{ExtrinsicDetails, Extrinsics},
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// Extrinsic decoder for turning raw block bodies into extrinsic records
+pub struct ExtrinsicDecoder;
+
+impl ExtrinsicDecoder {
+    /// Create a new extrinsic decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Decode the extrinsics of a block and build one record per extrinsic.
+    ///
+    /// `raw` are the extrinsics as returned by `chain_getBlock` (with their length prefix),
+    /// used for hashing. Dispatch results and fees are filled in separately by
+    /// [`ExtrinsicDecoder::apply_dispatch_results`] once the block's events are known.
+    pub fn decode_extrinsics(
+        &self,
+        raw: &[Vec<u8>],
+        extrinsics: &Extrinsics<PolkadotConfig>,
+        metadata: &Metadata,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<Vec<Extrinsic>> {
+        let mut records = Vec::with_capacity(raw.len());
+
+        for (ext, bytes) in extrinsics.iter().zip(raw) {
+            let ext = ext?;
+            let call_pallet = ext.pallet_name()?.to_string();
+            let call_function = ext.variant_name()?.to_string();
+
+            let args = match ext.field_values() {
+                Ok(fields) => value_ext::composite_to_json(&fields),
+                Err(e) => {
+                    warn!(
+                        "Failed to decode args of {}::{} at block {} index {}: {}",
+                        call_pallet,
+                        call_function,
+                        block_number,
+                        ext.index(),
+                        e
+                    );
+                    serde_json::Value::Null
+                }
+            };
+
+            let (nonce, tip) = match ext.signed_extensions() {
+                Some(extensions) => (
+                    extensions.nonce().map(|n| n as i64),
+                    extensions.tip().map(|t| t.to_string()),
+                ),
+                None => (None, None),
+            };
+
+            let signature = ext
+                .signature_bytes()
+                .and_then(|bytes| signature::inspect_signature(bytes, metadata));
+
+            debug!(
+                "Decoded extrinsic {}::{} at block {} index {}",
+                call_pallet,
+                call_function,
+                block_number,
+                ext.index()
+            );
+
+            records.push(Extrinsic {
+                id: None,
+                block_number,
+                extrinsic_index: ext.index() as i32,
+                hash: blake2_256(bytes).to_vec(),
+                is_signed: ext.is_signed(),
+                signer: ext.address_bytes().and_then(decode_address),
+                nonce,
+                tip,
+                call_pallet,
+                call_function,
+                args,
+                success: true,
+                dispatch_error: None,
+                fee: None,
+                signature_scheme: signature.as_ref().map(|s| s.scheme.clone()),
+                signature_len: signature.as_ref().map(|s| s.signature.len() as i32),
+                signer_public_key: signature.and_then(|s| s.public_key),
+                signature_valid: None,
+                signature_context: None,
+                block_ts: block_timestamp,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Extract the block timestamp from the `Timestamp::set` inherent
+    pub fn block_timestamp(
+        &self,
+        extrinsics: &Extrinsics<PolkadotConfig>,
+    ) -> Option<DateTime<Utc>> {
+        extrinsics
+            .iter()
+            .filter_map(|ext| ext.ok())
+            .find(|ext| is_call(ext, "Timestamp", "set"))
+            .and_then(|ext| ext.field_values().ok())
+            .and_then(|fields| value_ext::u128_field(&fields, "now"))
+            .and_then(|ms| Utc.timestamp_millis_opt(ms as i64).single())
+    }
+
+    /// Fill in success/failure, dispatch errors and fees from the block's events
+    pub fn apply_dispatch_results(
+        &self,
+        records: &mut [Extrinsic],
+        events: &Events<PolkadotConfig>,
+        metadata: &Metadata,
+    ) -> Result<()> {
+        for event in events.iter() {
+            let event = event?;
+            let Phase::ApplyExtrinsic(index) = event.phase() else {
+                continue;
+            };
+            let Some(record) = records.get_mut(index as usize) else {
+                continue;
+            };
+
+            match (event.pallet_name(), event.variant_name()) {
+                ("System", "ExtrinsicSuccess") => {
+                    record.success = true;
+                }
+                ("System", "ExtrinsicFailed") => {
+                    record.success = false;
+                    let fields = event.field_values()?;
+                    record.dispatch_error = value_ext::field(&fields, "dispatch_error")
+                        .map(|err| describe_dispatch_error(err, metadata));
+                }
+                ("TransactionPayment", "TransactionFeePaid") => {
+                    let fields = event.field_values()?;
+                    record.fee =
+                        value_ext::u128_field(&fields, "actual_fee").map(|f| f.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collect the public keys revealed by signed extrinsics, checking that each
+    /// signer account derives from its key
+    pub fn public_keys(&self, records: &[Extrinsic]) -> Vec<PublicKey> {
+        records
+            .iter()
+            .filter_map(|record| {
+                let account = record.signer.as_ref()?;
+                let public_key = record.signer_public_key.as_ref()?;
+                let derivation = signature::account_derivation(public_key, account);
+
+                if derivation.is_none() {
+                    warn!(
+                        "Signer 0x{} of extrinsic {}-{} does not derive from its public key",
+                        hex::encode(account),
+                        record.block_number,
+                        record.extrinsic_index
+                    );
+                }
+
+                Some(PublicKey {
+                    account: account.clone(),
+                    public_key: public_key.clone(),
+                    signature_scheme: record.signature_scheme.clone().unwrap_or_default(),
+                    derivation: derivation.map(str::to_string),
+                    account_matches: derivation.is_some(),
+                    first_seen_block: record.block_number,
+                    first_extrinsic_index: record.extrinsic_index,
+                    first_extrinsic_hash: record.hash.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for ExtrinsicDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether an extrinsic is the given call
+fn is_call(ext: &ExtrinsicDetails<PolkadotConfig>, pallet: &str, function: &str) -> bool {
+    ext.pallet_name().ok() == Some(pallet) && ext.variant_name().ok() == Some(function)
+}
+
+/// Decode a `MultiAddress` into account bytes.
+///
+/// Only the `Id`, `Address32` and `Address20` variants identify an account directly;
+/// `Index` and `Raw` addresses are not resolvable offline.
+fn decode_address(bytes: &[u8]) -> Option<Vec<u8>> {
+    match bytes.split_first()? {
+        (0, account) | (3, account) if account.len() == 32 => Some(account.to_vec()),
+        (4, account) if account.len() == 20 => Some(account.to_vec()),
+        _ => None,
+    }
+}
+
+/// Decode an encoded `RuntimeCall` (an inline or preimage call) into its pallet, function
+/// and JSON arguments
+pub fn decode_call(
+    bytes: &[u8],
+    metadata: &Metadata,
+) -> Option<(String, String, serde_json::Value)> {
+    let call_ty = metadata.outer_enums().call_enum_ty();
+    let call = decode_as_type(&mut &bytes[..], call_ty, metadata.types()).ok()?;
+    let ValueDef::Variant(pallet) = call.value else {
+        return None;
+    };
+    let ValueDef::Variant(function) = &pallet.values.values().next()?.value else {
+        return None;
+    };
+    Some((
+        pallet.name.clone(),
+        function.name.clone(),
+        value_ext::composite_to_json(&function.values),
+    ))
+}
+
+/// Success flag and readable error of a `DispatchResult` value (`Ok(())` / `Err(error)`)
+pub fn dispatch_result<T>(value: &Value<T>, metadata: &Metadata) -> (Option<bool>, Option<String>) {
+    match &value.value {
+        ValueDef::Variant(result) if result.name == "Ok" => (Some(true), None),
+        ValueDef::Variant(result) => (
+            Some(false),
+            result
+                .values
+                .values()
+                .next()
+                .map(|err| describe_dispatch_error(err, metadata)),
+        ),
+        _ => (None, None),
+    }
+}
+
+/// Render a `DispatchError` as a readable string, resolving module errors through metadata
+pub fn describe_dispatch_error<T>(value: &Value<T>, metadata: &Metadata) -> String {
+    let ValueDef::Variant(variant) = &value.value else {
+        return value_ext::to_json(value).to_string();
+    };
+
+    if variant.name == "Module" {
+        let module = module_error(&variant.values, metadata);
+        if let Some(module) = module {
+            return module;
+        }
+    }
+
+    match variant
+        .values
+        .values()
+        .next()
+        .and_then(value_ext::variant_name)
+    {
+        Some(inner) => format!("{}::{}", variant.name, inner),
+        None => variant.name.clone(),
+    }
+}
+
+fn module_error<T>(values: &Composite<T>, metadata: &Metadata) -> Option<String> {
+    let module = values.values().next()?;
+    let ValueDef::Composite(fields) = &module.value else {
+        return None;
+    };
+    let pallet_index = value_ext::u128_field(fields, "index")? as u8;
+    let error_bytes = value_ext::field(fields, "error").and_then(value_ext::as_bytes)?;
+    let pallet = metadata.pallet_by_index(pallet_index)?;
+    let error = pallet.error_variant_by_index(*error_bytes.first()?)?;
+    Some(format!("{}::{}", pallet.name(), error.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_address() {
+        let mut id = vec![0u8];
+        id.extend([9u8; 32]);
+        assert_eq!(decode_address(&id), Some(vec![9u8; 32]));
+
+        let mut raw = vec![2u8, 4];
+        raw.extend([1u8; 4]);
+        assert_eq!(decode_address(&raw), None);
+    }
+}
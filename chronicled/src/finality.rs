@@ -0,0 +1,531 @@
+use crate::rpc::RpcHelper;
+use anyhow::Result;
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+};
+use subxt::{
+    ext::{scale_value::scale::decode_as_type, sp_core::H256},
+    Metadata,
+};
+use tracing::{debug, warn};
+
+/// Pallets that may declare a `MaxReorgDepth` constant, in order of preference
+const MAX_REORG_DEPTH_PALLETS: [&str; 5] = ["QPoW", "Resonance", "PoW", "Difficulty", "System"];
+
+/// Confirmations used when neither an override nor the runtime says otherwise
+const DEFAULT_DEPTH: u32 = 10;
+
+/// Block time constants used to estimate a depth, in order of preference
+const BLOCK_TIME_CONSTANTS: [(&str, &str); 2] =
+    [("QPoW", "TargetBlockTime"), ("Timestamp", "MinimumPeriod")];
+
+/// Confirmation depths set in the environment.
+///
+/// `FINALITY_CONFIRMATIONS` applies to every chain; `FINALITY_CONFIRMATIONS_BY_CHAIN` takes
+/// `<base58 genesis>=<depth>` pairs separated by commas and wins for the chains it lists.
+#[derive(Debug, Default, Clone)]
+pub struct FinalityOverrides {
+    global: Option<u32>,
+    per_chain: HashMap<String, u32>,
+}
+
+impl FinalityOverrides {
+    /// Read the overrides from the environment
+    pub fn from_env() -> Self {
+        Self::parse(
+            std::env::var("FINALITY_CONFIRMATIONS").ok().as_deref(),
+            std::env::var("FINALITY_CONFIRMATIONS_BY_CHAIN")
+                .ok()
+                .as_deref(),
+        )
+    }
+
+    /// Parse the override variables, ignoring malformed entries
+    pub fn parse(global: Option<&str>, per_chain: Option<&str>) -> Self {
+        let global = global.and_then(|s| s.trim().parse().ok());
+        let per_chain = per_chain
+            .unwrap_or_default()
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let parsed = entry
+                    .split_once('=')
+                    .filter(|(chain, _)| !chain.trim().is_empty())
+                    .and_then(|(chain, depth)| Some((chain.trim(), depth.trim().parse().ok()?)));
+                if parsed.is_none() {
+                    warn!("Ignoring malformed finality override '{}'", entry.trim());
+                }
+                parsed.map(|(chain, depth)| (chain.to_string(), depth))
+            })
+            .collect();
+        Self { global, per_chain }
+    }
+
+    fn get(&self, chain_id: &str) -> Option<(u32, &'static str)> {
+        match self.per_chain.get(chain_id) {
+            Some(depth) => Some((*depth, "FINALITY_CONFIRMATIONS_BY_CHAIN")),
+            None => self.global.map(|depth| (depth, "FINALITY_CONFIRMATIONS")),
+        }
+    }
+}
+
+/// How many confirmations a best block needs before it is indexed, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalityPolicy {
+    /// Confirmations to wait for (0 means the chain has deterministic finality)
+    pub depth: u32,
+    /// Where the depth came from: an override variable, a runtime constant or `default`
+    pub source: String,
+}
+
+impl FinalityPolicy {
+    /// Choose the finality depth for a chain.
+    ///
+    /// Overrides win, then `MaxReorgDepth` (finality one block short of it, as reorgs up to
+    /// that depth are allowed), then GRANDPA (instant finality), BABE epochs and finally
+    /// half an hour of blocks at the target block time, capped at 180.
+    pub fn discover(metadata: &Metadata, chain_id: &str, overrides: &FinalityOverrides) -> Self {
+        if let Some((depth, source)) = overrides.get(chain_id) {
+            return Self::new(depth, source);
+        }
+
+        for pallet in MAX_REORG_DEPTH_PALLETS {
+            if let Some(depth) = constant_u128(metadata, pallet, "MaxReorgDepth") {
+                return Self::new(
+                    (depth as u32).saturating_sub(1),
+                    format!("{}::MaxReorgDepth", pallet),
+                );
+            }
+        }
+
+        if metadata.pallet_by_name("Grandpa").is_some() {
+            return Self::new(0, "Grandpa");
+        }
+
+        if let Some(duration) = constant_u128(metadata, "Babe", "EpochDuration") {
+            return Self::new((duration / 4) as u32, "Babe::EpochDuration");
+        }
+
+        for (pallet, name) in BLOCK_TIME_CONSTANTS {
+            if let Some(period_ms) = constant_u128(metadata, pallet, name).filter(|p| *p > 0) {
+                let depth = ((30 * 60 * 1000) / period_ms).min(180) as u32;
+                return Self::new(depth, format!("{}::{}", pallet, name));
+            }
+        }
+
+        debug!("No finality constants found; candidate constants:");
+        for pallet in metadata.pallets() {
+            for constant in pallet.constants() {
+                let name = constant.name();
+                if ["Reorg", "Depth", "Finality", "Confirmations"]
+                    .iter()
+                    .any(|hint| name.contains(hint))
+                {
+                    debug!("  {}::{}", pallet.name(), name);
+                }
+            }
+        }
+        Self::new(DEFAULT_DEPTH, "default")
+    }
+
+    fn new(depth: u32, source: impl Into<String>) -> Self {
+        Self {
+            depth,
+            source: source.into(),
+        }
+    }
+
+    /// Whether a reorg came within 80% of the finality depth
+    pub fn is_near_limit(&self, reorg_depth: u32) -> bool {
+        reorg_depth > 0 && reorg_depth as u64 * 5 >= self.depth as u64 * 4
+    }
+}
+
+/// Decode a numeric runtime constant
+fn constant_u128(metadata: &Metadata, pallet: &str, name: &str) -> Option<u128> {
+    let constant = metadata.pallet_by_name(pallet)?.constant_by_name(name)?;
+    decode_as_type(&mut constant.value(), constant.ty(), metadata.types())
+        .ok()?
+        .as_u128()
+}
+
+/// A change of the best chain
+#[derive(Debug, Default, PartialEq)]
+pub struct Reorg {
+    /// Number of best blocks replaced, 0 when the new block extends the chain
+    pub depth: u32,
+    /// Height of the last block both chains share
+    pub fork_point: i64,
+    /// Previously seen best blocks that are no longer on the best chain
+    pub replaced: Vec<(i64, H256)>,
+}
+
+/// Recent best-chain hashes, used to measure how deep reorgs go and which blocks they drop
+pub struct ReorgTracker {
+    seen: BTreeMap<i64, H256>,
+    window: i64,
+}
+
+impl ReorgTracker {
+    /// Track the last `window` heights of the best chain
+    pub fn new(window: u32) -> Self {
+        Self {
+            seen: BTreeMap::new(),
+            window: window.max(1) as i64,
+        }
+    }
+
+    /// Record a new best block and return the blocks it replaced (none when it extends the
+    /// chain).
+    ///
+    /// The fork point is found by walking the new block's ancestors until one matches a
+    /// hash seen before at that height.
+    pub async fn observe(
+        &mut self,
+        rpc: &RpcHelper,
+        number: i64,
+        hash: H256,
+        parent_hash: H256,
+    ) -> Result<Reorg> {
+        self.observe_with(number, hash, parent_hash, |cursor| async move {
+            Ok(rpc.get_header_by_hash(&cursor).await?.parent_hash)
+        })
+        .await
+    }
+
+    /// [`Self::observe`] with the parent of a block looked up by `parent_of`
+    async fn observe_with<F, Fut>(
+        &mut self,
+        number: i64,
+        hash: H256,
+        parent_hash: H256,
+        mut parent_of: F,
+    ) -> Result<Reorg>
+    where
+        F: FnMut(H256) -> Fut,
+        Fut: Future<Output = Result<H256>>,
+    {
+        if self.seen.get(&number) == Some(&hash) {
+            return Ok(Reorg {
+                fork_point: number,
+                ..Default::default()
+            });
+        }
+
+        let mut ancestor = number - 1;
+        let mut cursor = parent_hash;
+
+        // Notifications can skip heights; walk down to the highest block seen before
+        // comparing, so a competing branch announced above the tip is still detected
+        if let Some(tip) = self.seen.keys().next_back().copied() {
+            while ancestor > tip {
+                cursor = parent_of(cursor).await?;
+                ancestor -= 1;
+            }
+        }
+
+        while let Some(seen) = self.seen.get(&ancestor) {
+            if *seen == cursor {
+                break;
+            }
+            cursor = parent_of(cursor).await?;
+            ancestor -= 1;
+        }
+
+        Ok(self.record(number, hash, ancestor))
+    }
+
+    /// Replace everything above the fork point with the new block
+    fn record(&mut self, number: i64, hash: H256, ancestor: i64) -> Reorg {
+        let tip = self.seen.keys().next_back().copied().unwrap_or(ancestor);
+        let depth = tip.saturating_sub(ancestor).max(0) as u32;
+        let replaced = self.seen.split_off(&(ancestor + 1)).into_iter().collect();
+
+        self.seen.insert(number, hash);
+        let oldest = number - self.window;
+        self.seen.retain(|n, _| *n > oldest);
+        Reorg {
+            depth,
+            fork_point: ancestor,
+            replaced,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides() {
+        let overrides = FinalityOverrides::parse(Some("200"), Some("ChainA=5, ChainB = 0,bogus"));
+        assert_eq!(
+            overrides.get("ChainA"),
+            Some((5, "FINALITY_CONFIRMATIONS_BY_CHAIN"))
+        );
+        assert_eq!(
+            overrides.get("ChainB"),
+            Some((0, "FINALITY_CONFIRMATIONS_BY_CHAIN"))
+        );
+        assert_eq!(
+            overrides.get("ChainC"),
+            Some((200, "FINALITY_CONFIRMATIONS"))
+        );
+        assert_eq!(FinalityOverrides::parse(None, None).get("ChainA"), None);
+    }
+
+    #[test]
+    fn test_malformed_overrides() {
+        let overrides = FinalityOverrides::parse(
+            Some(" twelve "),
+            Some("ChainA, =5, ChainB=, ChainC=-1, ChainD=x, ChainE=7=8,,  , ChainF = 3 "),
+        );
+        assert_eq!(overrides.global, None);
+        assert_eq!(
+            overrides.per_chain,
+            [("ChainF".to_string(), 3)].into_iter().collect()
+        );
+        assert_eq!(overrides.get(""), None);
+        assert_eq!(overrides.get("ChainA"), None);
+
+        // A malformed per-chain entry falls back to the global depth
+        let overrides = FinalityOverrides::parse(Some(" 12 "), Some("ChainA=x"));
+        assert_eq!(
+            overrides.get("ChainA"),
+            Some((12, "FINALITY_CONFIRMATIONS"))
+        );
+    }
+
+    #[test]
+    fn test_override_precedence() {
+        let overrides = FinalityOverrides::parse(Some("200"), Some("ChainA=5,ChainA=6,ChainB=0"));
+        // The last entry for a chain wins, and a per-chain 0 is not a missing value
+        assert_eq!(
+            overrides.get("ChainA"),
+            Some((6, "FINALITY_CONFIRMATIONS_BY_CHAIN"))
+        );
+        assert_eq!(
+            overrides.get("ChainB"),
+            Some((0, "FINALITY_CONFIRMATIONS_BY_CHAIN"))
+        );
+
+        // Overrides win over anything the runtime declares
+        let metadata = metadata(&[("QPoW", &[("MaxReorgDepth", 180)]), ("Grandpa", &[])]);
+        assert_eq!(
+            FinalityPolicy::discover(&metadata, "ChainA", &overrides),
+            FinalityPolicy::new(6, "FINALITY_CONFIRMATIONS_BY_CHAIN")
+        );
+        assert_eq!(
+            FinalityPolicy::discover(&metadata, "ChainC", &overrides),
+            FinalityPolicy::new(200, "FINALITY_CONFIRMATIONS")
+        );
+    }
+
+    /// Metadata declaring only the given pallets and numeric constants
+    fn metadata(pallets: &[(&'static str, &[(&'static str, u64)])]) -> Metadata {
+        use parity_scale_codec::Encode;
+        use scale_info::meta_type;
+        use subxt::ext::frame_metadata::v15::{
+            CustomMetadata, ExtrinsicMetadata, OuterEnums, PalletConstantMetadata, PalletMetadata,
+            RuntimeMetadataV15,
+        };
+
+        let pallets = pallets
+            .iter()
+            .enumerate()
+            .map(|(index, (name, constants))| PalletMetadata {
+                name: *name,
+                storage: None,
+                calls: None,
+                event: None,
+                constants: constants
+                    .iter()
+                    .map(|(name, value)| PalletConstantMetadata {
+                        name: *name,
+                        ty: meta_type::<u64>(),
+                        value: value.encode(),
+                        docs: vec![],
+                    })
+                    .collect(),
+                error: None,
+                index: index as u8,
+                docs: vec![],
+            })
+            .collect();
+        let metadata = RuntimeMetadataV15::new(
+            pallets,
+            ExtrinsicMetadata {
+                version: 4,
+                address_ty: meta_type::<()>(),
+                call_ty: meta_type::<()>(),
+                signature_ty: meta_type::<()>(),
+                extra_ty: meta_type::<()>(),
+                signed_extensions: vec![],
+            },
+            meta_type::<()>(),
+            vec![],
+            OuterEnums {
+                call_enum_ty: meta_type::<()>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            CustomMetadata {
+                map: Default::default(),
+            },
+        );
+        subxt::metadata::types::Metadata::try_from(metadata)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_discovery_order() {
+        let none = FinalityOverrides::default();
+        let discover = |pallets: &[(&'static str, &[(&'static str, u64)])]| {
+            FinalityPolicy::discover(&metadata(pallets), "Chain", &none)
+        };
+
+        // MaxReorgDepth first, by pallet preference, one block short of the limit
+        assert_eq!(
+            discover(&[
+                ("System", &[("MaxReorgDepth", 50)]),
+                (
+                    "QPoW",
+                    &[("MaxReorgDepth", 180), ("TargetBlockTime", 10_000)]
+                ),
+                ("Grandpa", &[]),
+            ]),
+            FinalityPolicy::new(179, "QPoW::MaxReorgDepth")
+        );
+        assert_eq!(
+            discover(&[("System", &[("MaxReorgDepth", 50)]), ("Grandpa", &[])]),
+            FinalityPolicy::new(49, "System::MaxReorgDepth")
+        );
+
+        // Then GRANDPA's instant finality, then BABE epochs
+        assert_eq!(
+            discover(&[("Grandpa", &[]), ("Babe", &[("EpochDuration", 2400)])]),
+            FinalityPolicy::new(0, "Grandpa")
+        );
+        assert_eq!(
+            discover(&[
+                ("Babe", &[("EpochDuration", 2400)]),
+                ("Timestamp", &[("MinimumPeriod", 3000)]),
+            ]),
+            FinalityPolicy::new(600, "Babe::EpochDuration")
+        );
+
+        // Then half an hour of blocks, capped at 180, from the target block time
+        assert_eq!(
+            discover(&[
+                ("QPoW", &[("TargetBlockTime", 20_000)]),
+                ("Timestamp", &[("MinimumPeriod", 3000)]),
+            ]),
+            FinalityPolicy::new(90, "QPoW::TargetBlockTime")
+        );
+        assert_eq!(
+            discover(&[
+                ("QPoW", &[("TargetBlockTime", 0)]),
+                ("Timestamp", &[("MinimumPeriod", 3000)]),
+            ]),
+            FinalityPolicy::new(180, "Timestamp::MinimumPeriod")
+        );
+
+        assert_eq!(
+            discover(&[("Balances", &[("ExistentialDeposit", 1)])]),
+            FinalityPolicy::new(DEFAULT_DEPTH, "default")
+        );
+    }
+
+    #[test]
+    fn test_near_limit() {
+        let policy = FinalityPolicy::new(179, "QPoW::MaxReorgDepth");
+        assert!(!policy.is_near_limit(0));
+        assert!(!policy.is_near_limit(143));
+        assert!(policy.is_near_limit(144));
+        assert!(FinalityPolicy::new(0, "Grandpa").is_near_limit(1));
+    }
+
+    #[test]
+    fn test_reorg_depth() {
+        let hash = |b: u8| H256::repeat_byte(b);
+        let mut tracker = ReorgTracker::new(100);
+        assert_eq!(tracker.record(10, hash(10), 9).depth, 0);
+        assert_eq!(tracker.record(11, hash(11), 10).depth, 0);
+        assert_eq!(tracker.record(12, hash(12), 11).depth, 0);
+        // A sibling of #12
+        let reorg = tracker.record(12, hash(0x12), 11);
+        assert_eq!(reorg.depth, 1);
+        assert_eq!(reorg.replaced, vec![(12, hash(12))]);
+        // A heavier fork from #10 replaces #11 and #12
+        assert_eq!(
+            tracker.record(13, hash(0x13), 10),
+            Reorg {
+                depth: 2,
+                fork_point: 10,
+                replaced: vec![(11, hash(11)), (12, hash(0x12))],
+            }
+        );
+        assert_eq!(tracker.seen.len(), 2);
+
+        let mut tracker = ReorgTracker::new(2);
+        for n in 0..5 {
+            tracker.record(n, hash(n as u8), n - 1);
+        }
+        assert_eq!(tracker.seen.keys().copied().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_gapped_notifications() {
+        let hash = |b: u8| H256::repeat_byte(b);
+        // Best chain #10..#13, and a competing branch forking after #10 up to #14
+        let parents: HashMap<H256, H256> = [
+            (hash(13), hash(12)),
+            (hash(12), hash(11)),
+            (hash(11), hash(10)),
+            (hash(0x14), hash(0x13)),
+            (hash(0x13), hash(0x12)),
+            (hash(0x12), hash(0x11)),
+            (hash(0x11), hash(10)),
+        ]
+        .into_iter()
+        .collect();
+        let parent_of = |cursor: H256| {
+            let parent = parents.get(&cursor).copied();
+            async move { parent.ok_or_else(|| anyhow::anyhow!("unknown block {}", cursor)) }
+        };
+
+        let mut tracker = ReorgTracker::new(100);
+        for n in 10..=12 {
+            tracker.record(n, hash(n as u8), n - 1);
+        }
+
+        // #14 of the competing branch arrives without #13 having been announced
+        let reorg = tracker
+            .observe_with(14, hash(0x14), hash(0x13), parent_of)
+            .await
+            .unwrap();
+        assert_eq!(
+            reorg,
+            Reorg {
+                depth: 2,
+                fork_point: 10,
+                replaced: vec![(11, hash(11)), (12, hash(12))],
+            }
+        );
+
+        // A gapped block extending the best chain replaces nothing
+        let mut tracker = ReorgTracker::new(100);
+        for n in 10..=11 {
+            tracker.record(n, hash(n as u8), n - 1);
+        }
+        let reorg = tracker
+            .observe_with(13, hash(13), hash(12), parent_of)
+            .await
+            .unwrap();
+        assert_eq!(reorg.depth, 0);
+        assert_eq!(reorg.fork_point, 11);
+        assert!(reorg.replaced.is_empty());
+    }
+}
@@ -0,0 +1,934 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    BalanceChange, BalanceChangeReason, CollectiveRankChange, DbExecutor, Extrinsic,
+    GovernanceRepository, Referendum, ReferendumStatus, ReferendumStatusChange, ReferendumVote,
+    VoteDelegation,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Composite, Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// `Referenda` pallet instances
+const REFERENDA_PALLETS: [&str; 2] = ["Referenda", "TechReferenda"];
+
+/// Ranked collective pallet instances
+const COLLECTIVE_PALLETS: [&str; 1] = ["TechCollective"];
+
+/// Referenda instance whose polls a voting pallet votes on
+fn poll_pallet(voting_pallet: &str) -> &'static str {
+    match voting_pallet {
+        "TechCollective" => "TechReferenda",
+        _ => "Referenda",
+    }
+}
+
+/// A referendum tally: ayes, nays and support (bare ayes for ranked collectives)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub ayes: u128,
+    pub nays: u128,
+    pub support: u128,
+}
+
+impl Tally {
+    /// Read a `Tally` value of either conviction voting or a ranked collective
+    fn from_fields<T>(fields: &Composite<T>) -> Self {
+        Self {
+            ayes: value_ext::u128_field(fields, "ayes").unwrap_or_default(),
+            nays: value_ext::u128_field(fields, "nays").unwrap_or_default(),
+            support: value_ext::u128_field(fields, "support")
+                .or_else(|| value_ext::u128_field(fields, "bare_ayes"))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn apply(&self, referendum: &mut Referendum) {
+        referendum.ayes = self.ayes.to_string();
+        referendum.nays = self.nays.to_string();
+        referendum.support = self.support.to_string();
+    }
+}
+
+/// A change to a referendum carried by a Referenda event
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferendumChange {
+    /// `Submitted`
+    Submitted {
+        track: i32,
+        proposal: serde_json::Value,
+    },
+    /// `DecisionDepositPlaced`
+    DecisionDepositPlaced { who: Vec<u8>, amount: u128 },
+    /// `DecisionDepositRefunded`
+    DecisionDepositRefunded { who: Vec<u8>, amount: u128 },
+    /// `SubmissionDepositRefunded`
+    SubmissionDepositRefunded { who: Vec<u8>, amount: u128 },
+    /// `DecisionStarted`
+    DecisionStarted(Tally),
+    /// `ConfirmStarted`
+    ConfirmStarted,
+    /// `ConfirmAborted`
+    ConfirmAborted,
+    /// `Confirmed`
+    Confirmed(Tally),
+    /// `Approved`, `Rejected`, `TimedOut`, `Cancelled` or `Killed`
+    Concluded(ReferendumStatus, Option<Tally>),
+}
+
+impl ReferendumChange {
+    /// Status of the referendum after the change, for lifecycle transitions
+    pub fn status(&self) -> Option<ReferendumStatus> {
+        match self {
+            Self::Submitted { .. } => Some(ReferendumStatus::Preparing),
+            Self::DecisionStarted(_) | Self::ConfirmAborted => Some(ReferendumStatus::Deciding),
+            Self::ConfirmStarted | Self::Confirmed(_) => Some(ReferendumStatus::Confirming),
+            Self::Concluded(status, _) => Some(*status),
+            _ => None,
+        }
+    }
+
+    fn tally(&self) -> Option<&Tally> {
+        match self {
+            Self::DecisionStarted(tally) | Self::Confirmed(tally) => Some(tally),
+            Self::Concluded(_, tally) => tally.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// A change to one referendum
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferendumUpdate {
+    pub pallet: String,
+    pub referendum_index: i64,
+    pub change: ReferendumChange,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+impl ReferendumUpdate {
+    /// Apply the change to the referendum's record
+    pub fn apply(&self, referendum: &mut Referendum, block_number: i64) {
+        match &self.change {
+            ReferendumChange::Submitted { track, proposal } => {
+                referendum.track = Some(*track);
+                referendum.proposal = Some(proposal.clone());
+                referendum.submitted_block = Some(block_number);
+                referendum.submitted_extrinsic_hash = self.extrinsic_hash.clone();
+            }
+            ReferendumChange::DecisionDepositPlaced { who, amount } => {
+                referendum.decision_deposit_account = Some(who.clone());
+                referendum.decision_deposit = Some(amount.to_string());
+            }
+            ReferendumChange::DecisionDepositRefunded { who, amount } => {
+                referendum.decision_deposit_account = Some(who.clone());
+                referendum.decision_deposit = Some(amount.to_string());
+                referendum.decision_deposit_refunded_block = Some(block_number);
+            }
+            ReferendumChange::SubmissionDepositRefunded { who, amount } => {
+                referendum.submission_deposit_account = Some(who.clone());
+                referendum.submission_deposit = Some(amount.to_string());
+                referendum.submission_deposit_refunded_block = Some(block_number);
+            }
+            ReferendumChange::DecisionStarted(_) => {
+                referendum.decision_started_block = Some(block_number)
+            }
+            ReferendumChange::ConfirmStarted => {
+                referendum.confirm_started_block = Some(block_number)
+            }
+            ReferendumChange::ConfirmAborted => {
+                referendum.confirm_started_block = None;
+                referendum.confirm_end_block = None;
+            }
+            ReferendumChange::Confirmed(_) => {}
+            ReferendumChange::Concluded(..) => referendum.concluded_block = Some(block_number),
+        }
+        if let Some(status) = self.change.status() {
+            referendum.status = status;
+        }
+        if let Some(tally) = self.change.tally() {
+            tally.apply(referendum);
+        }
+        referendum.updated_block = block_number;
+    }
+}
+
+/// Governance activity found in a block's events
+#[derive(Debug, Default)]
+pub struct GovernanceEvents {
+    /// Referendum changes, in event order
+    pub referenda: Vec<ReferendumUpdate>,
+    /// Lifecycle transitions of referenda
+    pub status_changes: Vec<ReferendumStatusChange>,
+    /// Votes cast and removed
+    pub votes: Vec<ReferendumVote>,
+    /// Delegations and undelegations
+    pub delegations: Vec<VoteDelegation>,
+    /// Ranked collective membership and rank changes
+    pub rank_changes: Vec<CollectiveRankChange>,
+    /// Slashed and refunded referendum deposits
+    pub deposits: Vec<BalanceChange>,
+}
+
+/// Decoder for Referenda, ConvictionVoting and ranked collective pallets
+pub struct GovernanceDecoder;
+
+impl GovernanceDecoder {
+    /// Create a new governance decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract referendum changes, votes, delegations, rank changes and deposit slashes and refunds.
+    ///
+    /// `ConvictionVoting` events carry neither the poll nor the delegation class; they are
+    /// taken from the `vote` / `remove_vote` / `delegate` / `undelegate` call when it was
+    /// submitted directly.
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        extrinsics: &[Extrinsic],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<GovernanceEvents> {
+        let mut decoded = GovernanceEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            let pallet = event.pallet_name();
+            let variant = event.variant_name();
+            let is_referenda = REFERENDA_PALLETS.contains(&pallet);
+            let is_collective = COLLECTIVE_PALLETS.contains(&pallet);
+            if !is_referenda && !is_collective && pallet != "ConvictionVoting" {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let event_index = event.index() as i32;
+            let extrinsic = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsics.get(i as usize),
+                _ => None,
+            };
+            let extrinsic_hash = extrinsic.map(|e| e.hash.clone());
+
+            if is_referenda {
+                if let Some((who, delta, reason)) = deposit_change(variant, &fields) {
+                    decoded.deposits.push(BalanceChange::new(
+                        who,
+                        block_number,
+                        event_index,
+                        delta,
+                        reason,
+                        extrinsic_hash.clone(),
+                        pallet.to_string(),
+                        variant.to_string(),
+                        block_timestamp,
+                    ));
+                }
+                if variant == "DepositSlashed" {
+                    continue;
+                }
+
+                let Some(referendum_index) =
+                    value_ext::u128_field(&fields, "index").map(|i| i as i64)
+                else {
+                    continue;
+                };
+                let Some(change) = referendum_change(variant, &fields) else {
+                    continue;
+                };
+
+                debug!(
+                    "Referendum {}#{} changed by {} at block {}",
+                    pallet, referendum_index, variant, block_number
+                );
+                if let Some(status) = change.status() {
+                    let tally = change.tally();
+                    decoded.status_changes.push(ReferendumStatusChange {
+                        block_number,
+                        event_index,
+                        pallet: pallet.to_string(),
+                        referendum_index,
+                        event_variant: variant.to_string(),
+                        status,
+                        ayes: tally.map(|t| t.ayes.to_string()),
+                        nays: tally.map(|t| t.nays.to_string()),
+                        support: tally.map(|t| t.support.to_string()),
+                        extrinsic_hash: extrinsic_hash.clone(),
+                        block_ts: block_timestamp,
+                    });
+                }
+                decoded.referenda.push(ReferendumUpdate {
+                    pallet: pallet.to_string(),
+                    referendum_index,
+                    change,
+                    extrinsic_hash,
+                });
+                continue;
+            }
+
+            let call_args = extrinsic
+                .filter(|e| e.call_pallet == pallet)
+                .map(|e| (e.call_function.as_str(), &e.args));
+
+            match (pallet, variant) {
+                // Voted { who, vote } / VoteRemoved { who, vote }
+                ("ConvictionVoting", "Voted" | "VoteRemoved") => {
+                    let who = value_ext::account_field(&fields, "who");
+                    let vote = value_ext::field(&fields, "vote").and_then(account_vote);
+                    let (Some(account), Some(vote)) = (who, vote) else {
+                        warn!(
+                            "Undecodable ConvictionVoting::{} at block {}",
+                            variant, block_number
+                        );
+                        continue;
+                    };
+                    let referendum_index = call_args.and_then(|(function, args)| {
+                        let name = match function {
+                            "vote" => "poll_index",
+                            "remove_vote" | "remove_other_vote" => "index",
+                            _ => return None,
+                        };
+                        json_u128(&args[name]).map(|i| i as i64)
+                    });
+                    decoded.votes.push(vote.into_record(
+                        block_number,
+                        event_index,
+                        poll_pallet(pallet),
+                        referendum_index,
+                        account,
+                        variant == "VoteRemoved",
+                        extrinsic_hash,
+                        block_timestamp,
+                    ));
+                }
+                // Delegated(who, target) / Undelegated(who)
+                ("ConvictionVoting", "Delegated" | "Undelegated") => {
+                    let mut accounts = fields.values().map(value_ext::as_bytes);
+                    let Some(Some(account)) = accounts.next() else {
+                        continue;
+                    };
+                    let target = accounts.next().flatten();
+                    let args = call_args
+                        .filter(|(function, _)| matches!(*function, "delegate" | "undelegate"))
+                        .map(|(_, args)| args);
+                    decoded.delegations.push(VoteDelegation {
+                        block_number,
+                        event_index,
+                        account,
+                        class: args.and_then(|a| json_u128(&a["class"])).map(|c| c as i32),
+                        conviction: target
+                            .as_ref()
+                            .and(args)
+                            .and_then(|a| a["conviction"].as_object())
+                            .and_then(|c| c.keys().next())
+                            .and_then(|name| conviction_index(name)),
+                        balance: target
+                            .as_ref()
+                            .and(args)
+                            .and_then(|a| json_u128(&a["balance"]))
+                            .map(|b| b.to_string()),
+                        target,
+                        extrinsic_hash,
+                        block_ts: block_timestamp,
+                    });
+                }
+                // Voted { who, poll, vote: Aye(weight) | Nay(weight), tally }
+                (_, "Voted") => {
+                    let who = value_ext::account_field(&fields, "who");
+                    let poll = value_ext::u128_field(&fields, "poll").map(|p| p as i64);
+                    let vote = value_ext::field(&fields, "vote").and_then(|vote| {
+                        let weight = match &vote.value {
+                            ValueDef::Variant(v) => {
+                                v.values.values().next().and_then(value_ext::as_u128)
+                            }
+                            _ => None,
+                        };
+                        Some((
+                            value_ext::variant_name(vote)? == "Aye",
+                            weight.unwrap_or_default(),
+                        ))
+                    });
+                    let (Some(account), Some((aye, weight))) = (who, vote) else {
+                        warn!("Undecodable {}::Voted at block {}", pallet, block_number);
+                        continue;
+                    };
+                    decoded.votes.push(
+                        AccountVote {
+                            kind: if aye { "aye" } else { "nay" },
+                            aye: Some(aye),
+                            conviction: None,
+                            aye_amount: if aye { weight } else { 0 },
+                            nay_amount: if aye { 0 } else { weight },
+                            abstain_amount: 0,
+                            lock_amount: 0,
+                        }
+                        .into_record(
+                            block_number,
+                            event_index,
+                            poll_pallet(pallet),
+                            poll,
+                            account,
+                            false,
+                            extrinsic_hash,
+                            block_timestamp,
+                        ),
+                    );
+                }
+                // MemberAdded { who } / RankChanged { who, rank } / MemberRemoved { who, rank }
+                (_, "MemberAdded" | "RankChanged" | "MemberRemoved") => {
+                    let Some(account) = value_ext::account_field(&fields, "who") else {
+                        continue;
+                    };
+                    let change = match variant {
+                        "MemberAdded" => "added",
+                        "RankChanged" => "rank_changed",
+                        _ => "removed",
+                    };
+                    decoded.rank_changes.push(CollectiveRankChange {
+                        block_number,
+                        event_index,
+                        collective: pallet.to_string(),
+                        account,
+                        change: change.to_string(),
+                        rank: value_ext::u128_field(&fields, "rank").unwrap_or_default() as i32,
+                        extrinsic_hash,
+                        block_ts: block_timestamp,
+                    });
+                }
+                // MemberExchanged { who, new_who }: ranks are read from storage afterwards
+                (_, "MemberExchanged") => {
+                    let (Some(who), Some(new_who)) = (
+                        value_ext::account_field(&fields, "who"),
+                        value_ext::account_field(&fields, "new_who"),
+                    ) else {
+                        continue;
+                    };
+                    for (account, change) in [(who, "exchanged_out"), (new_who, "exchanged_in")] {
+                        decoded.rank_changes.push(CollectiveRankChange {
+                            block_number,
+                            event_index,
+                            collective: pallet.to_string(),
+                            account,
+                            change: change.to_string(),
+                            rank: 0,
+                            extrinsic_hash: extrinsic_hash.clone(),
+                            block_ts: block_timestamp,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Apply referendum changes on top of the stored records and refresh every referendum
+    /// touched by the block (changed or voted on) from `ReferendumInfoFor` at the block.
+    pub async fn resolve_referenda(
+        &self,
+        events: &GovernanceEvents,
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        block_number: i64,
+    ) -> Result<Vec<Referendum>> {
+        let repo = GovernanceRepository::new(conn);
+        let mut touched: BTreeMap<(String, i64), Vec<&ReferendumUpdate>> = BTreeMap::new();
+        for update in &events.referenda {
+            touched
+                .entry((update.pallet.clone(), update.referendum_index))
+                .or_default()
+                .push(update);
+        }
+        for vote in &events.votes {
+            if let Some(index) = vote.referendum_index {
+                touched.entry((vote.pallet.clone(), index)).or_default();
+            }
+        }
+
+        let mut referenda = Vec::with_capacity(touched.len());
+        for ((pallet, index), updates) in touched {
+            let mut referendum = repo
+                .get_referendum(&pallet, index)
+                .await?
+                .unwrap_or_else(|| Referendum::new(&pallet, index, block_number));
+            for update in updates {
+                update.apply(&mut referendum, block_number);
+            }
+
+            let key =
+                rpc::storage_map_key(&pallet, "ReferendumInfoFor", &(index as u32).to_le_bytes());
+            match rpc.get_storage(&key, block_hash).await? {
+                Some(bytes) => {
+                    let info =
+                        rpc::decode_storage_value(metadata, &pallet, "ReferendumInfoFor", &bytes)?;
+                    apply_referendum_info(&mut referendum, &info);
+                }
+                None => warn!(
+                    "No ReferendumInfoFor {}#{} at block {}",
+                    pallet, index, block_number
+                ),
+            }
+            referendum.updated_block = block_number;
+            referenda.push(referendum);
+        }
+
+        Ok(referenda)
+    }
+
+    /// Fill in the ranks of exchanged members from the collective's `Members` storage
+    pub async fn resolve_exchange_ranks(
+        &self,
+        changes: &mut [CollectiveRankChange],
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+    ) -> Result<()> {
+        let mut exchanged = changes
+            .iter_mut()
+            .filter(|c| c.change.starts_with("exchanged"))
+            .peekable();
+        while let Some(out) = exchanged.next() {
+            let Some(new) = exchanged.next_if(|c| c.change == "exchanged_in") else {
+                continue;
+            };
+            let key = rpc::storage_twox64_map_key(&new.collective, "Members", &new.account);
+            let Some(bytes) = rpc.get_storage(&key, block_hash).await? else {
+                continue;
+            };
+            let record = rpc::decode_storage_value(metadata, &new.collective, "Members", &bytes)?;
+            if let ValueDef::Composite(fields) = &record.value {
+                let rank = value_ext::u128_field(fields, "rank").unwrap_or_default() as i32;
+                out.rank = rank;
+                new.rank = rank;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for GovernanceDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A decoded vote: conviction voting's `AccountVote` or a collective's `VoteRecord`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AccountVote {
+    kind: &'static str,
+    aye: Option<bool>,
+    conviction: Option<i16>,
+    aye_amount: u128,
+    nay_amount: u128,
+    abstain_amount: u128,
+    lock_amount: u128,
+}
+
+impl AccountVote {
+    #[allow(clippy::too_many_arguments)]
+    fn into_record(
+        self,
+        block_number: i64,
+        event_index: i32,
+        pallet: &str,
+        referendum_index: Option<i64>,
+        account: Vec<u8>,
+        removed: bool,
+        extrinsic_hash: Option<Vec<u8>>,
+        block_ts: DateTime<Utc>,
+    ) -> ReferendumVote {
+        ReferendumVote {
+            block_number,
+            event_index,
+            pallet: pallet.to_string(),
+            referendum_index,
+            account,
+            vote_kind: self.kind.to_string(),
+            aye: self.aye,
+            conviction: self.conviction,
+            aye_amount: self.aye_amount.to_string(),
+            nay_amount: self.nay_amount.to_string(),
+            abstain_amount: self.abstain_amount.to_string(),
+            lock_amount: self.lock_amount.to_string(),
+            removed,
+            extrinsic_hash,
+            block_ts,
+        }
+    }
+}
+
+/// Decode an `AccountVote` (`Standard`, `Split` or `SplitAbstain`).
+///
+/// A standard vote packs the direction into the top bit of its `Vote` byte and the
+/// conviction into the low bits.
+fn account_vote<T>(value: &Value<T>) -> Option<AccountVote> {
+    let ValueDef::Variant(variant) = &value.value else {
+        return None;
+    };
+    let fields = &variant.values;
+    let amount = |name| value_ext::u128_field(fields, name);
+
+    match variant.name.as_str() {
+        "Standard" => {
+            let vote = amount("vote")?;
+            let balance = amount("balance")?;
+            let aye = vote & 0x80 != 0;
+            Some(AccountVote {
+                kind: "standard",
+                aye: Some(aye),
+                conviction: Some((vote & 0x7f) as i16),
+                aye_amount: if aye { balance } else { 0 },
+                nay_amount: if aye { 0 } else { balance },
+                abstain_amount: 0,
+                lock_amount: balance,
+            })
+        }
+        "Split" | "SplitAbstain" => {
+            let (aye, nay) = (amount("aye")?, amount("nay")?);
+            let abstain = amount("abstain").unwrap_or_default();
+            Some(AccountVote {
+                kind: if variant.name == "Split" {
+                    "split"
+                } else {
+                    "split_abstain"
+                },
+                aye: None,
+                conviction: None,
+                aye_amount: aye,
+                nay_amount: nay,
+                abstain_amount: abstain,
+                lock_amount: aye + nay + abstain,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Referendum change carried by a Referenda event
+fn referendum_change<T>(variant: &str, fields: &Composite<T>) -> Option<ReferendumChange> {
+    let tally = || match &value_ext::field(fields, "tally")?.value {
+        ValueDef::Composite(tally) => Some(Tally::from_fields(tally)),
+        _ => None,
+    };
+    let concluded = |status| Some(ReferendumChange::Concluded(status, tally()));
+
+    match variant {
+        // Submitted { index, track, proposal }
+        "Submitted" => Some(ReferendumChange::Submitted {
+            track: value_ext::u128_field(fields, "track")? as i32,
+            proposal: value_ext::field(fields, "proposal")
+                .map(value_ext::to_json)
+                .unwrap_or_default(),
+        }),
+        // DecisionDepositPlaced { index, who, amount }
+        "DecisionDepositPlaced" => Some(ReferendumChange::DecisionDepositPlaced {
+            who: value_ext::account_field(fields, "who")?,
+            amount: value_ext::u128_field(fields, "amount")?,
+        }),
+        // DecisionDepositRefunded { index, who, amount }
+        "DecisionDepositRefunded" => Some(ReferendumChange::DecisionDepositRefunded {
+            who: value_ext::account_field(fields, "who")?,
+            amount: value_ext::u128_field(fields, "amount")?,
+        }),
+        // SubmissionDepositRefunded { index, who, amount }
+        "SubmissionDepositRefunded" => Some(ReferendumChange::SubmissionDepositRefunded {
+            who: value_ext::account_field(fields, "who")?,
+            amount: value_ext::u128_field(fields, "amount")?,
+        }),
+        "DecisionStarted" => Some(ReferendumChange::DecisionStarted(
+            tally().unwrap_or_default(),
+        )),
+        "ConfirmStarted" => Some(ReferendumChange::ConfirmStarted),
+        "ConfirmAborted" => Some(ReferendumChange::ConfirmAborted),
+        "Confirmed" => Some(ReferendumChange::Confirmed(tally().unwrap_or_default())),
+        "Approved" => concluded(ReferendumStatus::Approved),
+        "Rejected" => concluded(ReferendumStatus::Rejected),
+        "TimedOut" => concluded(ReferendumStatus::TimedOut),
+        "Cancelled" => concluded(ReferendumStatus::Cancelled),
+        "Killed" => concluded(ReferendumStatus::Killed),
+        _ => None,
+    }
+}
+
+/// Account, signed delta and reason of a slashed or refunded referendum deposit
+fn deposit_change<T>(
+    variant: &str,
+    fields: &Composite<T>,
+) -> Option<(Vec<u8>, String, BalanceChangeReason)> {
+    let (sign, reason) = match variant {
+        // DepositSlashed { who, amount }
+        "DepositSlashed" => ("-", BalanceChangeReason::DepositSlash),
+        // DecisionDepositRefunded / SubmissionDepositRefunded { index, who, amount }
+        "DecisionDepositRefunded" | "SubmissionDepositRefunded" => {
+            ("", BalanceChangeReason::DepositRefund)
+        }
+        _ => return None,
+    };
+    let who = value_ext::account_field(fields, "who")?;
+    let amount = value_ext::u128_field(fields, "amount")?;
+    Some((who, format!("{}{}", sign, amount), reason))
+}
+
+/// Refresh a referendum from its `ReferendumInfo` storage value
+fn apply_referendum_info<T>(referendum: &mut Referendum, info: &Value<T>) {
+    let ValueDef::Variant(variant) = &info.value else {
+        return;
+    };
+    let fields = &variant.values;
+
+    if variant.name != "Ongoing" {
+        // Approved/Rejected/Cancelled/TimedOut(since, submission_deposit, decision_deposit), Killed(since)
+        let mut values = fields.values();
+        referendum.status = match variant.name.as_str() {
+            "Approved" => ReferendumStatus::Approved,
+            "Rejected" => ReferendumStatus::Rejected,
+            "Cancelled" => ReferendumStatus::Cancelled,
+            "TimedOut" => ReferendumStatus::TimedOut,
+            _ => ReferendumStatus::Killed,
+        };
+        if let Some(since) = values.next().and_then(value_ext::as_u128) {
+            referendum.concluded_block = Some(since as i64);
+        }
+        if let Some((who, amount)) = values.next().and_then(deposit) {
+            referendum.submission_deposit_account = Some(who);
+            referendum.submission_deposit = Some(amount.to_string());
+        }
+        if let Some((who, amount)) = values.next().and_then(deposit) {
+            referendum.decision_deposit_account = Some(who);
+            referendum.decision_deposit = Some(amount.to_string());
+        }
+        return;
+    }
+
+    let field = |name| value_ext::field(fields, name);
+    referendum.track = value_ext::u128_field(fields, "track")
+        .map(|t| t as i32)
+        .or(referendum.track);
+    referendum.origin = field("origin").map(value_ext::to_json);
+    referendum.proposal = field("proposal").map(value_ext::to_json);
+    referendum.enactment = field("enactment").map(value_ext::to_json);
+    referendum.submitted_block = value_ext::u128_field(fields, "submitted").map(|b| b as i64);
+    if let Some((who, amount)) = field("submission_deposit").and_then(deposit) {
+        referendum.submission_deposit_account = Some(who);
+        referendum.submission_deposit = Some(amount.to_string());
+    }
+    if let Some((who, amount)) = field("decision_deposit").and_then(deposit) {
+        referendum.decision_deposit_account = Some(who);
+        referendum.decision_deposit = Some(amount.to_string());
+    }
+    if let Some(ValueDef::Composite(tally)) = field("tally").map(|t| &t.value) {
+        Tally::from_fields(tally).apply(referendum);
+    }
+
+    // deciding: Option<DecidingStatus { since, confirming: Option<BlockNumber> }>
+    match field("deciding").and_then(some).map(|d| &d.value) {
+        Some(ValueDef::Composite(deciding)) => {
+            let since = value_ext::u128_field(deciding, "since").map(|b| b as i64);
+            referendum.decision_started_block = referendum.decision_started_block.or(since);
+            referendum.confirm_end_block =
+                value_ext::option_u128_field(deciding, "confirming").map(|b| b as i64);
+            referendum.status = if referendum.confirm_end_block.is_some() {
+                ReferendumStatus::Confirming
+            } else {
+                ReferendumStatus::Deciding
+            };
+        }
+        _ => referendum.status = ReferendumStatus::Preparing,
+    }
+}
+
+/// Unwrap an `Option` value, passing other values through
+fn some<T>(value: &Value<T>) -> Option<&Value<T>> {
+    match &value.value {
+        ValueDef::Variant(v) if v.name == "Some" => v.values.values().next(),
+        ValueDef::Variant(v) if v.name == "None" => None,
+        _ => Some(value),
+    }
+}
+
+/// Depositor and amount of a (possibly optional) `Deposit { who, amount }`
+fn deposit<T>(value: &Value<T>) -> Option<(Vec<u8>, u128)> {
+    let ValueDef::Composite(fields) = &some(value)?.value else {
+        return None;
+    };
+    Some((
+        value_ext::account_field(fields, "who")?,
+        value_ext::u128_field(fields, "amount")?,
+    ))
+}
+
+/// Conviction multiplier from a `Conviction` variant name (`None`, `Locked1x`..`Locked6x`)
+fn conviction_index(name: &str) -> Option<i16> {
+    match name {
+        "None" => Some(0),
+        _ => name.strip_prefix("Locked")?.strip_suffix('x')?.parse().ok(),
+    }
+}
+
+/// Unsigned integer from call arguments, rendered as a number or a decimal string
+fn json_u128(value: &serde_json::Value) -> Option<u128> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        value => value.as_u64().map(u128::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_vote() {
+        let standard = Value::named_variant(
+            "Standard",
+            [
+                ("vote", Value::unnamed_composite([Value::u128(0x83)])),
+                ("balance", Value::u128(500)),
+            ],
+        );
+        let vote = account_vote(&standard).unwrap();
+        assert_eq!(vote.aye, Some(true));
+        assert_eq!(vote.conviction, Some(3));
+        assert_eq!(
+            (vote.aye_amount, vote.nay_amount, vote.lock_amount),
+            (500, 0, 500)
+        );
+
+        let split = Value::named_variant(
+            "SplitAbstain",
+            [
+                ("aye", Value::u128(1)),
+                ("nay", Value::u128(2)),
+                ("abstain", Value::u128(3)),
+            ],
+        );
+        let vote = account_vote(&split).unwrap();
+        assert_eq!(vote.kind, "split_abstain");
+        assert_eq!(vote.lock_amount, 6);
+    }
+
+    #[test]
+    fn test_referendum_lifecycle() {
+        let update = |change| ReferendumUpdate {
+            pallet: "Referenda".into(),
+            referendum_index: 2,
+            change,
+            extrinsic_hash: None,
+        };
+        let mut referendum = Referendum::new("Referenda", 2, 10);
+        update(ReferendumChange::Submitted {
+            track: 1,
+            proposal: serde_json::Value::Null,
+        })
+        .apply(&mut referendum, 10);
+        update(ReferendumChange::DecisionStarted(Tally::default())).apply(&mut referendum, 12);
+        update(ReferendumChange::ConfirmStarted).apply(&mut referendum, 20);
+        assert_eq!(referendum.status, ReferendumStatus::Confirming);
+
+        let tally = Tally {
+            ayes: 7,
+            nays: 1,
+            support: 5,
+        };
+        update(ReferendumChange::Concluded(
+            ReferendumStatus::Approved,
+            Some(tally),
+        ))
+        .apply(&mut referendum, 30);
+        assert_eq!(referendum.status, ReferendumStatus::Approved);
+        assert_eq!(referendum.submitted_block, Some(10));
+        assert_eq!(referendum.decision_started_block, Some(12));
+        assert_eq!(referendum.confirm_started_block, Some(20));
+        assert_eq!(referendum.concluded_block, Some(30));
+        assert_eq!(referendum.ayes, "7");
+    }
+
+    fn deposit_event(variant: &str, amount: u128) -> Composite<()> {
+        let who = Value::unnamed_composite((0..32).map(|_| Value::u128(7)));
+        let mut fields = vec![("who", who), ("amount", Value::u128(amount))];
+        if variant != "DepositSlashed" {
+            fields.insert(0, ("index", Value::u128(2)));
+        }
+        match Value::named_composite(fields).value {
+            ValueDef::Composite(fields) => fields,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_decision_deposit_refunded() {
+        let fields = deposit_event("DecisionDepositRefunded", 300);
+        assert_eq!(
+            deposit_change("DecisionDepositRefunded", &fields),
+            Some((
+                vec![7; 32],
+                "300".into(),
+                BalanceChangeReason::DepositRefund
+            ))
+        );
+
+        let change = referendum_change("DecisionDepositRefunded", &fields).unwrap();
+        assert_eq!(change.status(), None);
+        let mut referendum = Referendum::new("Referenda", 2, 10);
+        ReferendumUpdate {
+            pallet: "Referenda".into(),
+            referendum_index: 2,
+            change,
+            extrinsic_hash: None,
+        }
+        .apply(&mut referendum, 40);
+        assert_eq!(referendum.decision_deposit_account, Some(vec![7; 32]));
+        assert_eq!(referendum.decision_deposit.as_deref(), Some("300"));
+        assert_eq!(referendum.decision_deposit_refunded_block, Some(40));
+    }
+
+    #[test]
+    fn test_submission_deposit_refunded() {
+        let fields = deposit_event("SubmissionDepositRefunded", 50);
+        assert_eq!(
+            deposit_change("SubmissionDepositRefunded", &fields),
+            Some((vec![7; 32], "50".into(), BalanceChangeReason::DepositRefund))
+        );
+
+        let mut referendum = Referendum::new("Referenda", 2, 10);
+        ReferendumUpdate {
+            pallet: "Referenda".into(),
+            referendum_index: 2,
+            change: referendum_change("SubmissionDepositRefunded", &fields).unwrap(),
+            extrinsic_hash: None,
+        }
+        .apply(&mut referendum, 41);
+        assert_eq!(referendum.submission_deposit_account, Some(vec![7; 32]));
+        assert_eq!(referendum.submission_deposit.as_deref(), Some("50"));
+        assert_eq!(referendum.submission_deposit_refunded_block, Some(41));
+    }
+
+    #[test]
+    fn test_deposit_slashed() {
+        let fields = deposit_event("DepositSlashed", 80);
+        assert_eq!(
+            deposit_change("DepositSlashed", &fields),
+            Some((vec![7; 32], "-80".into(), BalanceChangeReason::DepositSlash))
+        );
+        assert_eq!(deposit_change("DecisionDepositPlaced", &fields), None);
+    }
+
+    #[test]
+    fn test_conviction_index() {
+        assert_eq!(conviction_index("None"), Some(0));
+        assert_eq!(conviction_index("Locked6x"), Some(6));
+        assert_eq!(conviction_index("Other"), None);
+    }
+}
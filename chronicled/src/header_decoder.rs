@@ -0,0 +1,147 @@
+use crate::rpc::RpcBlockData;
+use anyhow::Result;
+use chron_db::{BlockHeader, DigestLog, DigestLogKind};
+use chrono::{DateTime, Utc};
+use parity_scale_codec::{Compact, CompactLen, Encode};
+use subxt::{
+    config::substrate::{ConsensusEngineId, Digest, DigestItem},
+    ext::sp_core::H256,
+};
+
+/// A block's header record and the digest logs it carries
+pub struct HeaderRecords {
+    pub header: BlockHeader,
+    pub digest_logs: Vec<DigestLog>,
+}
+
+/// Decoder for block headers: state and extrinsics roots, body sizes and digest logs
+pub struct HeaderDecoder;
+
+impl HeaderDecoder {
+    /// Create a new header decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build the header record and digest logs of a block. `event_count` is `None` when
+    /// the block's events could not be read (e.g. a pruned fork block).
+    pub fn decode_block(
+        &self,
+        block: &RpcBlockData,
+        block_hash: &H256,
+        block_number: i64,
+        event_count: Option<u32>,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<HeaderRecords> {
+        let extrinsics = block.extrinsic_bytes()?;
+        let digest = Digest {
+            logs: block.header.digest_logs(),
+        };
+
+        let header = BlockHeader {
+            block_hash: block_hash.as_bytes().to_vec(),
+            block_number,
+            state_root: block.header.state_root.as_bytes().to_vec(),
+            extrinsics_root: block.header.extrinsics_root.as_bytes().to_vec(),
+            extrinsic_count: extrinsics.len() as i32,
+            event_count: event_count.map(|count| count as i32),
+            block_size: block_size(block_number, &digest, &extrinsics) as i32,
+            block_ts: block_timestamp,
+        };
+        let digest_logs = digest_logs(&digest.logs, block_hash, block_number);
+
+        Ok(HeaderRecords {
+            header,
+            digest_logs,
+        })
+    }
+}
+
+impl Default for HeaderDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SCALE-encoded size of a block: the header (parent hash, compact number, state and
+/// extrinsics roots, digest) followed by the length-prefixed extrinsics, each of which
+/// already carries its own length prefix
+fn block_size(block_number: i64, digest: &Digest, extrinsics: &[Vec<u8>]) -> usize {
+    let header = 3 * H256::len_bytes()
+        + Compact::<u32>::compact_len(&(block_number as u32))
+        + digest.encoded_size();
+    let body = Compact::<u32>::compact_len(&(extrinsics.len() as u32))
+        + extrinsics.iter().map(Vec::len).sum::<usize>();
+    header + body
+}
+
+/// One record per digest item, in digest order
+fn digest_logs(logs: &[DigestItem], block_hash: &H256, block_number: i64) -> Vec<DigestLog> {
+    logs.iter()
+        .enumerate()
+        .map(|(index, log)| {
+            let (kind, engine_id, data) = match log {
+                DigestItem::PreRuntime(id, data) => (DigestLogKind::PreRuntime, Some(id), data),
+                DigestItem::Consensus(id, data) => (DigestLogKind::Consensus, Some(id), data),
+                DigestItem::Seal(id, data) => (DigestLogKind::Seal, Some(id), data),
+                DigestItem::Other(data) => (DigestLogKind::Other, None, data),
+                DigestItem::RuntimeEnvironmentUpdated => {
+                    (DigestLogKind::RuntimeEnvironmentUpdated, None, &Vec::new())
+                }
+            };
+            DigestLog {
+                block_hash: block_hash.as_bytes().to_vec(),
+                block_number,
+                log_index: index as i32,
+                kind,
+                engine_id: engine_id.map(engine_name),
+                data: data.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Engine ids are four ASCII bytes (`pow_`, `aura`, `FRNK`); anything else is kept as hex
+fn engine_name(id: &ConsensusEngineId) -> String {
+    if id.iter().all(|b| b.is_ascii_graphic()) {
+        String::from_utf8_lossy(id).into_owned()
+    } else {
+        format!("0x{}", hex::encode(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_logs() {
+        let logs = vec![
+            DigestItem::PreRuntime(*b"pow_", vec![7; 32]),
+            DigestItem::RuntimeEnvironmentUpdated,
+            DigestItem::Seal(*b"pow_", vec![1, 2, 3]),
+            DigestItem::Consensus([0, 1, 2, 3], vec![]),
+        ];
+        let records = digest_logs(&logs, &H256::repeat_byte(9), 42);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].kind, DigestLogKind::PreRuntime);
+        assert_eq!(records[0].engine_id.as_deref(), Some("pow_"));
+        assert_eq!(records[1].kind, DigestLogKind::RuntimeEnvironmentUpdated);
+        assert_eq!(records[1].engine_id, None);
+        assert!(records[1].data.is_empty());
+        assert_eq!(records[2].log_index, 2);
+        assert_eq!(records[2].data, vec![1, 2, 3]);
+        assert_eq!(records[3].engine_id.as_deref(), Some("0x00010203"));
+    }
+
+    #[test]
+    fn test_block_size() {
+        let digest = Digest {
+            logs: vec![DigestItem::Seal(*b"pow_", vec![0; 32])],
+        };
+        // 96 bytes of hashes, 1-byte compact number, 1 + (1 + 4 + 1 + 32) digest,
+        // 1-byte extrinsic count and the extrinsics themselves
+        let extrinsics = vec![vec![0; 10], vec![0; 20]];
+        assert_eq!(block_size(42, &digest, &extrinsics), 96 + 1 + 39 + 1 + 30);
+    }
+}
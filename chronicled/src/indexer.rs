@@ -0,0 +1,1034 @@
+use crate::{
+    account_balance_decoder::AccountBalanceDecoder,
+    airdrop_decoder::AirdropDecoder,
+    asset_decoder::AssetDecoder,
+    balance_decoder::BalanceDecoder,
+    block_verifier::BlockVerifier,
+    chain_info,
+    extrinsic_decoder::ExtrinsicDecoder,
+    governance_decoder::GovernanceDecoder,
+    header_decoder::HeaderDecoder,
+    ledger::{self, LedgerSigner},
+    mining_decoder::{self, MiningDecoder},
+    recovery_decoder::RecoveryDecoder,
+    reversible_decoder::ReversibleTransferDecoder,
+    rpc::{RpcBlock, RpcHelper},
+    scheduler_decoder::SchedulerDecoder,
+    signature,
+    signature_verifier::{SignatureVerifier, SigningContext, Verification},
+    sudo_decoder::SudoDecoder,
+    treasury_decoder::{self, TreasuryDecoder},
+    upgrade_decoder::UpgradeDecoder,
+    utility_decoder::UtilityDecoder,
+    vesting_decoder::VestingDecoder,
+    wormhole_decoder::WormholeDecoder,
+};
+use anyhow::Result;
+use chron_db::{
+    Account, AccountBalanceRepository, AccountId, AccountRepository, AirdropRepository,
+    BalanceChangeRepository, Block, BlockHeaderRepository, BlockRepository, ChainInfo,
+    ChainInfoRepository, ChainRepository, ConnectionPool, Extrinsic, ExtrinsicRepository,
+    GovernanceRepository, IndexProgress, LedgerRepository, MiningRepository, PublicKeyRepository,
+    RecoveryRepository, ReversibleTransferRepository, RuntimeMetadataRepository,
+    RuntimeUpgradeRepository, SchedulerRepository, SudoRepository, TransactionWrapper,
+    TreasuryRepository, UtilityRepository, VestingRepository, WormholeRepository,
+    DEFAULT_SS58_PREFIX,
+};
+use chrono::Utc;
+use parity_scale_codec::Decode;
+use std::collections::{BTreeSet, HashMap};
+use subxt::{
+    events::Events,
+    ext::{sp_core::H256, subxt_core::blocks::Extrinsics},
+    Metadata, OnlineClient, PolkadotConfig,
+};
+use tracing::{debug, info, warn};
+
+/// Indexes single blocks: fetches the body and events over JSON-RPC, decodes them
+/// with the runtime metadata that was active at the block and commits the block,
+/// its extrinsics and balance changes in one transaction.
+pub struct BlockIndexer {
+    client: OnlineClient<PolkadotConfig>,
+    rpc: RpcHelper,
+    pool: ConnectionPool,
+    chain_id: String,
+    balance_decoder: BalanceDecoder,
+    extrinsic_decoder: ExtrinsicDecoder,
+    asset_decoder: AssetDecoder,
+    vesting_decoder: VestingDecoder,
+    account_balance_decoder: AccountBalanceDecoder,
+    reversible_decoder: ReversibleTransferDecoder,
+    wormhole_decoder: WormholeDecoder,
+    airdrop_decoder: AirdropDecoder,
+    treasury_decoder: TreasuryDecoder,
+    governance_decoder: GovernanceDecoder,
+    mining_decoder: MiningDecoder,
+    header_decoder: HeaderDecoder,
+    recovery_decoder: RecoveryDecoder,
+    sudo_decoder: SudoDecoder,
+    scheduler_decoder: SchedulerDecoder,
+    utility_decoder: UtilityDecoder,
+    upgrade_decoder: UpgradeDecoder,
+    /// SS58 prefix used to render addresses
+    ss58_prefix: u16,
+    /// Optional offline verifier for extrinsic signatures
+    signature_verifier: Option<SignatureVerifier>,
+    block_verifier: Option<BlockVerifier>,
+    /// Optional operator key signing periodic ledger checkpoints
+    ledger_signer: Option<LedgerSigner>,
+    /// Chain identity record, refreshed when a newer runtime is seen
+    chain_info: Option<ChainInfo>,
+    /// Decoded runtimes by spec version
+    runtime_cache: HashMap<i32, Runtime>,
+}
+
+/// Runtime version and metadata active at a block
+#[derive(Clone)]
+struct Runtime {
+    spec_version: u32,
+    transaction_version: u32,
+    metadata: Metadata,
+}
+
+impl BlockIndexer {
+    /// Create a new block indexer
+    pub fn new(
+        client: OnlineClient<PolkadotConfig>,
+        rpc: RpcHelper,
+        pool: ConnectionPool,
+        chain_id: String,
+    ) -> Self {
+        Self {
+            balance_decoder: BalanceDecoder::new(client.clone()),
+            extrinsic_decoder: ExtrinsicDecoder::new(),
+            asset_decoder: AssetDecoder::new(),
+            vesting_decoder: VestingDecoder::new(),
+            account_balance_decoder: AccountBalanceDecoder::new(),
+            reversible_decoder: ReversibleTransferDecoder::new(),
+            wormhole_decoder: WormholeDecoder::new(),
+            airdrop_decoder: AirdropDecoder::new(),
+            treasury_decoder: TreasuryDecoder::new(),
+            governance_decoder: GovernanceDecoder::new(),
+            mining_decoder: MiningDecoder::new(),
+            header_decoder: HeaderDecoder::new(),
+            recovery_decoder: RecoveryDecoder::new(),
+            sudo_decoder: SudoDecoder::new(),
+            scheduler_decoder: SchedulerDecoder::new(),
+            utility_decoder: UtilityDecoder::new(),
+            upgrade_decoder: UpgradeDecoder::new(),
+            client,
+            rpc,
+            pool,
+            chain_id,
+            ss58_prefix: DEFAULT_SS58_PREFIX,
+            signature_verifier: None,
+            block_verifier: None,
+            ledger_signer: None,
+            chain_info: None,
+            runtime_cache: HashMap::new(),
+        }
+    }
+
+    /// Render account addresses with the chain's SS58 prefix
+    pub fn with_ss58_prefix(mut self, prefix: u16) -> Self {
+        self.ss58_prefix = prefix;
+        self
+    }
+
+    /// Build address records for the 32-byte accounts among `accounts`
+    pub fn account_records<'b>(
+        &self,
+        block_number: i64,
+        accounts: impl IntoIterator<Item = &'b [u8]>,
+    ) -> Vec<Account> {
+        let unique: BTreeSet<AccountId> = accounts
+            .into_iter()
+            .filter_map(AccountId::from_slice)
+            .collect();
+        unique
+            .iter()
+            .map(|account| Account::new(account, self.ss58_prefix, block_number))
+            .collect()
+    }
+
+    /// Verify extrinsic signatures offline before storing them
+    pub fn with_signature_verifier(mut self, verifier: SignatureVerifier) -> Self {
+        self.signature_verifier = Some(verifier);
+        self
+    }
+
+    /// Verify fetched blocks against their header hash and extrinsics root, re-fetching
+    /// blocks that fail from the verifier's fallback endpoints
+    pub fn with_block_verifier(mut self, verifier: BlockVerifier) -> Self {
+        self.block_verifier = Some(verifier);
+        self
+    }
+
+    /// Sign the ledger hash of every `interval`-th block with the operator's key
+    pub fn with_ledger_signer(mut self, signer: LedgerSigner) -> Self {
+        self.ledger_signer = Some(signer);
+        self
+    }
+
+    /// Refresh the stored chain info whenever a block runs a newer runtime than `info`
+    pub fn with_chain_info(mut self, info: ChainInfo) -> Self {
+        self.chain_info = Some(info);
+        self
+    }
+
+    /// JSON-RPC helper used for fetching blocks
+    pub fn rpc(&self) -> &RpcHelper {
+        &self.rpc
+    }
+
+    /// Balance decoder used for this chain
+    pub fn balance_decoder(&self) -> &BalanceDecoder {
+        &self.balance_decoder
+    }
+
+    /// Resolve the runtime version and metadata active at a block.
+    ///
+    /// Uses the `metadata` table and falls back to the client's current runtime
+    /// when no stored runtime covers the block.
+    async fn runtime_for_block(&mut self, block_number: i64) -> Result<Runtime> {
+        let conn = self.pool.get().await?;
+        let stored = RuntimeMetadataRepository::new(&conn)
+            .get_for_block(block_number)
+            .await?;
+
+        let Some(stored) = stored else {
+            debug!(
+                "No stored runtime covers block {}, using current metadata",
+                block_number
+            );
+            let version = self.client.runtime_version();
+            return Ok(Runtime {
+                spec_version: version.spec_version,
+                transaction_version: version.transaction_version,
+                metadata: self.client.metadata(),
+            });
+        };
+
+        if let Some(runtime) = self.runtime_cache.get(&stored.spec_version) {
+            return Ok(runtime.clone());
+        }
+
+        let metadata = match Metadata::decode(&mut &stored.metadata_bytes[..]) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!(
+                    "Failed to decode stored metadata for runtime v{}: {}; using current metadata",
+                    stored.spec_version, e
+                );
+                self.client.metadata()
+            }
+        };
+        let runtime = Runtime {
+            spec_version: stored.spec_version as u32,
+            transaction_version: stored.transaction_version as u32,
+            metadata,
+        };
+        self.runtime_cache
+            .insert(stored.spec_version, runtime.clone());
+        Ok(runtime)
+    }
+
+    /// Verify the signatures of a block's signed extrinsics offline and record the results
+    async fn verify_signatures(
+        &self,
+        verifier: &SignatureVerifier,
+        extrinsics: &Extrinsics<PolkadotConfig>,
+        records: &mut [Extrinsic],
+        runtime: &Runtime,
+        block_number: i64,
+    ) -> Result<()> {
+        let mut birth_hashes: HashMap<u64, H256> = HashMap::new();
+
+        for (ext, record) in extrinsics.iter().zip(records.iter_mut()) {
+            let ext = ext?;
+            let Some(info) = ext
+                .signature_bytes()
+                .and_then(|bytes| signature::inspect_signature(bytes, &runtime.metadata))
+            else {
+                continue;
+            };
+
+            let birth_hash = match verifier.mortality_birth(&ext, block_number as u64) {
+                None => verifier.genesis_hash(),
+                Some(birth) => match birth_hashes.get(&birth) {
+                    Some(hash) => *hash,
+                    None => {
+                        let hash = self.rpc.get_block_hash_by_number(birth).await?;
+                        birth_hashes.insert(birth, hash);
+                        hash
+                    }
+                },
+            };
+
+            let context = SigningContext {
+                spec_version: runtime.spec_version,
+                transaction_version: runtime.transaction_version,
+                birth_hash,
+            };
+            let verification = verifier.verify(&ext, &info, &runtime.metadata, &context);
+
+            match &verification {
+                Verification::Valid => {}
+                Verification::Invalid => warn!(
+                    "Signature of extrinsic {}-{} ({}) does not verify",
+                    block_number,
+                    record.extrinsic_index,
+                    record.hash_hex()
+                ),
+                Verification::Unsupported(reason) => debug!(
+                    "Cannot verify signature of extrinsic {}-{}: {}",
+                    block_number, record.extrinsic_index, reason
+                ),
+            }
+            record.signature_valid = verification.as_db_value();
+            record.signature_context = record.signature_valid.map(|_| {
+                String::from_utf8_lossy(verifier.extrinsic_context(runtime.spec_version))
+                    .into_owned()
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Re-query and store the chain info after a runtime upgrade
+    async fn refresh_chain_info(&mut self, spec_version: u32) -> Result<()> {
+        let Some(previous) = &self.chain_info else {
+            return Ok(());
+        };
+        if spec_version as i32 <= previous.spec_version {
+            return Ok(());
+        }
+
+        let info = chain_info::refresh_chain_info(&self.rpc, previous, spec_version).await?;
+        let conn = self.pool.get().await?;
+        ChainInfoRepository::new(&conn).upsert(&info).await?;
+        info!(
+            "Refreshed chain info for runtime v{} ({} {})",
+            spec_version, info.node_name, info.node_version
+        );
+        self.chain_info = Some(info);
+        Ok(())
+    }
+
+    /// Record blocks the best chain dropped in a reorg as non-canonical, with their miner
+    /// and timestamp but without indexing their contents. Returns how many were new.
+    pub async fn record_orphans(&mut self, replaced: &[(i64, H256)]) -> Result<u64> {
+        let mut orphans = Vec::with_capacity(replaced.len());
+        for (number, hash) in replaced {
+            let rpc_block = self.fetch_block(hash, None).await?;
+            let runtime = self.runtime_for_block(*number).await?;
+            let timestamp = Extrinsics::<PolkadotConfig>::decode_from(
+                rpc_block.block.extrinsic_bytes()?,
+                runtime.metadata.clone(),
+            )
+            .ok()
+            .and_then(|extrinsics| self.extrinsic_decoder.block_timestamp(&extrinsics))
+            .unwrap_or_else(Utc::now);
+            let author = mining_decoder::digest_miner(&rpc_block.block.header.digest_logs());
+            // A fork block's state may already be pruned; its header is stored regardless
+            let event_count = match self.rpc.get_events_bytes(hash).await {
+                Ok(bytes) if !bytes.is_empty() => Some(
+                    Events::<PolkadotConfig>::decode_from(bytes, runtime.metadata.clone()).len(),
+                ),
+                _ => None,
+            };
+            let header = self.header_decoder.decode_block(
+                &rpc_block.block,
+                hash,
+                *number,
+                event_count,
+                timestamp,
+            )?;
+            orphans.push((
+                Block::new(
+                    *number,
+                    hash.as_bytes().to_vec(),
+                    rpc_block.block.header.parent_hash.as_bytes().to_vec(),
+                    timestamp,
+                    runtime.spec_version as i64,
+                )
+                .with_author(author)
+                .orphaned(),
+                header,
+            ));
+        }
+
+        let conn = self.pool.get().await?;
+        let blocks_repo = BlockRepository::new(&conn);
+        let headers_repo = BlockHeaderRepository::new(&conn);
+        let mut recorded = 0;
+        for (orphan, header) in &orphans {
+            recorded += blocks_repo.insert_orphan(orphan).await?;
+            headers_repo.insert(&header.header).await?;
+            headers_repo.insert_digest_logs(&header.digest_logs).await?;
+        }
+        Ok(recorded)
+    }
+
+    /// Fetch a block, verified when a block verifier is configured
+    async fn fetch_block(&self, hash: &H256, expected_parent: Option<&H256>) -> Result<RpcBlock> {
+        match &self.block_verifier {
+            Some(verifier) => {
+                verifier
+                    .fetch_verified(&self.rpc, hash, expected_parent)
+                    .await
+            }
+            None => self.rpc.get_block_by_hash(hash).await,
+        }
+    }
+
+    /// Rewind the index to just before `from_block` after a reorg replaced blocks that were
+    /// already indexed. Their blocks and balance changes are kept, flagged non-canonical;
+    /// the rest of their data is dropped and re-indexed from the new best chain.
+    pub async fn rewind(&mut self, from_block: i64, progress: &mut IndexProgress) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let tx = conn.transaction().await?;
+        let tx_wrapper = TransactionWrapper::new(tx, Some(self.chain_id.clone()));
+        ChainRepository::new(&tx_wrapper)
+            .begin_reorg(from_block)
+            .await?;
+        let parent = BlockRepository::new(&tx_wrapper)
+            .get_by_number(from_block - 1)
+            .await?;
+        tx_wrapper.commit().await?;
+
+        progress.latest_block = from_block - 1;
+        if let Some(parent) = parent {
+            progress.latest_block_hash = parent.hash;
+            progress.latest_block_ts = parent.timestamp;
+        }
+        Ok(())
+    }
+
+    /// Fetch, decode and store a single block, advancing `progress` on commit.
+    ///
+    /// Returns the number of balance changes recorded.
+    pub async fn index_block(
+        &mut self,
+        block_number: i64,
+        block_hash: H256,
+        progress: &mut IndexProgress,
+    ) -> Result<u64> {
+        // The block indexed just before is the parent a verified block must build on
+        let expected_parent = (progress.latest_block == block_number - 1
+            && progress.latest_block_hash.len() == 32)
+            .then(|| H256::from_slice(&progress.latest_block_hash));
+        let rpc_block = self
+            .fetch_block(&block_hash, expected_parent.as_ref())
+            .await?;
+        let raw_extrinsics = rpc_block.block.extrinsic_bytes()?;
+        let parent_hash = rpc_block.block.header.parent_hash;
+
+        let runtime = self.runtime_for_block(block_number).await?;
+        let metadata = runtime.metadata.clone();
+
+        // Decode the block body; the timestamp inherent gives us the block time
+        let extrinsics =
+            Extrinsics::<PolkadotConfig>::decode_from(raw_extrinsics.clone(), metadata.clone())?;
+        let timestamp = self
+            .extrinsic_decoder
+            .block_timestamp(&extrinsics)
+            .unwrap_or_else(Utc::now);
+        let mut extrinsic_records = self.extrinsic_decoder.decode_extrinsics(
+            &raw_extrinsics,
+            &extrinsics,
+            &metadata,
+            block_number,
+            timestamp,
+        )?;
+        if let Some(verifier) = &self.signature_verifier {
+            self.verify_signatures(
+                verifier,
+                &extrinsics,
+                &mut extrinsic_records,
+                &runtime,
+                block_number,
+            )
+            .await?;
+        }
+        let public_keys = self.extrinsic_decoder.public_keys(&extrinsic_records);
+
+        // Decode events and attribute them to their extrinsics
+        let event_bytes = self.rpc.get_events_bytes(&block_hash).await?;
+        let events = Events::<PolkadotConfig>::decode_from(event_bytes, metadata.clone());
+        self.extrinsic_decoder.apply_dispatch_results(
+            &mut extrinsic_records,
+            &events,
+            &metadata,
+        )?;
+
+        let extrinsic_hashes: Vec<Vec<u8>> =
+            extrinsic_records.iter().map(|e| e.hash.clone()).collect();
+        let mut balance_changes = self
+            .balance_decoder
+            .decode_balance_changes(&events, block_number, timestamp, &extrinsic_hashes)
+            .await?;
+        let asset_updates = self.asset_decoder.decode_asset_updates(&events)?;
+
+        // Reversible transfers: lifecycle records, and balance changes once executed
+        let reversible = self.reversible_decoder.decode_events(
+            &events,
+            &metadata,
+            block_number,
+            &extrinsic_hashes,
+        )?;
+        let (pending_transfers, high_security) = if reversible.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            let conn = self.pool.get().await?;
+            let transfers = self
+                .reversible_decoder
+                .resolve_transfers(&reversible, &conn, &self.rpc, &metadata, &parent_hash)
+                .await?;
+            let executed = self.reversible_decoder.execution_balance_changes(
+                &transfers,
+                &balance_changes,
+                block_number,
+                timestamp,
+            );
+            balance_changes.extend(executed);
+            (transfers, reversible.high_security)
+        };
+
+        // Wormhole exits: verified proofs, the credits they minted and the nullifiers used
+        let wormhole = self.wormhole_decoder.decode_proofs(
+            &events,
+            &extrinsic_records,
+            block_number,
+            timestamp,
+        )?;
+        balance_changes.extend(wormhole.credits);
+        let nullifiers = self
+            .wormhole_decoder
+            .fetch_new_nullifiers(
+                &self.rpc,
+                &block_hash,
+                &parent_hash,
+                &wormhole.proofs,
+                &extrinsic_records,
+            )
+            .await?;
+
+        // Airdrop campaigns and claims; claim payouts get their own reason
+        let airdrop_events = self.airdrop_decoder.decode_events(
+            &events,
+            block_number,
+            timestamp,
+            &extrinsic_hashes,
+        )?;
+        self.airdrop_decoder
+            .tag_claim_transfers(&airdrop_events.claims, &mut balance_changes);
+        let airdrops = if airdrop_events.updates.is_empty() {
+            Vec::new()
+        } else {
+            let conn = self.pool.get().await?;
+            self.airdrop_decoder
+                .resolve_airdrops(
+                    &airdrop_events.updates,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &parent_hash,
+                    block_number,
+                )
+                .await?
+        };
+
+        // Governance: referenda refreshed from storage, votes, delegations and collective ranks
+        let mut governance = self.governance_decoder.decode_events(
+            &events,
+            &extrinsic_records,
+            block_number,
+            timestamp,
+        )?;
+        balance_changes.append(&mut governance.deposits);
+        let referenda = if governance.referenda.is_empty() && governance.votes.is_empty() {
+            Vec::new()
+        } else {
+            let conn = self.pool.get().await?;
+            self.governance_decoder
+                .resolve_referenda(
+                    &governance,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    block_number,
+                )
+                .await?
+        };
+        self.governance_decoder
+            .resolve_exchange_ranks(
+                &mut governance.rank_changes,
+                &self.rpc,
+                &metadata,
+                &block_hash,
+            )
+            .await?;
+
+        // Scheduler: task lifecycle with dispatch results, and the preimages calls resolve to
+        let scheduler = self.scheduler_decoder.decode_events(
+            &events,
+            &metadata,
+            block_number,
+            timestamp,
+            &extrinsic_hashes,
+        )?;
+        let (preimages, scheduled_tasks) = if scheduler.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            let conn = self.pool.get().await?;
+            let preimages = self
+                .scheduler_decoder
+                .resolve_preimages(
+                    &scheduler.preimages,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    block_number,
+                )
+                .await?;
+            let tasks = self
+                .scheduler_decoder
+                .resolve_tasks(
+                    &scheduler.tasks,
+                    &preimages,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    &parent_hash,
+                )
+                .await?;
+            (preimages, tasks)
+        };
+
+        // Recovery: configurations, recovery attempts, vouches and lost to rescuer links
+        let recovery = self.recovery_decoder.decode_events(
+            &events,
+            block_number,
+            timestamp,
+            &extrinsic_hashes,
+        )?;
+        let (recovery_configs, recoveries) = if recovery.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            let conn = self.pool.get().await?;
+            let configs = self
+                .recovery_decoder
+                .resolve_configs(
+                    &recovery.configs,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    &parent_hash,
+                )
+                .await?;
+            let recoveries = self
+                .recovery_decoder
+                .resolve_recoveries(
+                    &recovery.updates,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    &parent_hash,
+                )
+                .await?;
+            (configs, recoveries)
+        };
+
+        // Treasury: spend lifecycle, inflows and outflows, and the treasury's balance
+        let treasury_events = self.treasury_decoder.decode_events(
+            &events,
+            block_number,
+            timestamp,
+            &extrinsic_hashes,
+        )?;
+        let treasury_spends = if treasury_events.spends.is_empty() {
+            Vec::new()
+        } else {
+            let conn = self.pool.get().await?;
+            self.treasury_decoder
+                .resolve_spends(
+                    &treasury_events.spends,
+                    &conn,
+                    &self.rpc,
+                    &metadata,
+                    &parent_hash,
+                )
+                .await?
+        };
+        let mut treasury_ledger = treasury_events.ledger.clone();
+        treasury_ledger.extend(self.treasury_decoder.payout_entries(
+            &treasury_events.spends,
+            &treasury_spends,
+            timestamp,
+        ));
+        let treasury_balance = match treasury_decoder::treasury_account(&metadata) {
+            Some(treasury) => {
+                self.treasury_decoder.reward_balance_changes(
+                    &treasury_events.ledger,
+                    &treasury,
+                    &mut balance_changes,
+                );
+                let touched = !treasury_ledger.is_empty()
+                    || balance_changes.iter().any(|c| c.account == treasury);
+                if touched {
+                    self.treasury_decoder
+                        .fetch_balance(
+                            &self.rpc,
+                            &metadata,
+                            &block_hash,
+                            &treasury,
+                            block_number,
+                            timestamp,
+                        )
+                        .await?
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        // QPoW: miner, nonce, difficulty, block time and total work
+        let mining = self.mining_decoder.decode_block(
+            &events,
+            &rpc_block.block.header.digest_logs(),
+            &metadata,
+            block_number,
+            timestamp,
+        )?;
+        let mining = match mining {
+            Some(mut mining) => {
+                self.mining_decoder
+                    .fetch_state(&mut mining.block, &self.rpc, &block_hash, &parent_hash)
+                    .await?;
+                Some(mining)
+            }
+            None => None,
+        };
+
+        // Sudo: privileged actions with their wrapped call and the key in effect
+        let mut sudo_actions = self.sudo_decoder.decode_events(
+            &events,
+            &extrinsic_records,
+            &metadata,
+            block_number,
+            timestamp,
+        )?;
+        if !sudo_actions.is_empty() {
+            self.sudo_decoder
+                .resolve_keys(&mut sudo_actions, &self.rpc, &parent_hash)
+                .await?;
+        }
+
+        // Utility: place events emitted inside batches and dispatch_as in the call tree
+        let batches = self.utility_decoder.decode_events(
+            &events,
+            &extrinsic_records,
+            &metadata,
+            block_number,
+            timestamp,
+        )?;
+        batches.tag_balance_changes(&mut balance_changes);
+
+        // Runtime upgrades: a code update switches to a new runtime from the next block
+        let mut upgrades = self.upgrade_decoder.decode_events(
+            &events,
+            &extrinsic_records,
+            block_number,
+            timestamp,
+        )?;
+        let new_runtime = match upgrades.upgrade.as_mut() {
+            Some(upgrade) => Some(
+                self.upgrade_decoder
+                    .resolve_upgrade(upgrade, &self.rpc, &metadata, &block_hash)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        // Transfer proofs are only written by transfers
+        let has_transfers = balance_changes.iter().any(|c| {
+            c.is_native() && c.event_pallet == "Balances" && c.event_variant == "Transfer"
+        });
+        let transfer_proofs = if has_transfers {
+            self.wormhole_decoder
+                .fetch_transfer_proofs(
+                    &self.rpc,
+                    &block_hash,
+                    &parent_hash,
+                    block_number,
+                    timestamp,
+                    &balance_changes,
+                )
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        // Vesting events: snapshot the affected accounts' schedules at this block
+        let vesting_updates = self.vesting_decoder.decode_vesting_updates(
+            &events,
+            block_number,
+            timestamp,
+            &extrinsic_hashes,
+        )?;
+        let vesting_accounts: BTreeSet<&[u8]> = vesting_updates
+            .iter()
+            .map(|u| u.account.as_slice())
+            .collect();
+        let mut vesting_schedules = Vec::new();
+        for account in vesting_accounts {
+            vesting_schedules.extend(
+                self.vesting_decoder
+                    .fetch_schedules(&self.rpc, &metadata, &block_hash, block_number, account)
+                    .await?,
+            );
+        }
+
+        // Balances of every account touched by a native balance change or a lock event,
+        // with the locks re-read for the latter
+        let lock_accounts = self.account_balance_decoder.decode_lock_accounts(&events)?;
+        let balance_accounts: BTreeSet<&[u8]> = balance_changes
+            .iter()
+            .filter(|c| c.asset_id.is_none())
+            .map(|c| c.account.as_slice())
+            .chain(lock_accounts.iter().map(Vec::as_slice))
+            .collect();
+        let mut account_balances = Vec::with_capacity(balance_accounts.len());
+        let mut balance_locks = Vec::new();
+        for account in balance_accounts {
+            let mut balance = self
+                .account_balance_decoder
+                .fetch_balance(
+                    &self.rpc,
+                    &metadata,
+                    &block_hash,
+                    account,
+                    block_number,
+                    timestamp,
+                )
+                .await?;
+            if lock_accounts.contains(account) {
+                balance_locks.extend(
+                    self.account_balance_decoder
+                        .fetch_locks(&self.rpc, &metadata, &block_hash, account, block_number)
+                        .await?,
+                );
+                balance.locks_refreshed = true;
+            }
+            account_balances.push(balance);
+        }
+
+        let accounts = self.account_records(
+            block_number,
+            balance_changes
+                .iter()
+                .map(|c| c.account.as_slice())
+                .chain(extrinsic_records.iter().filter_map(|e| e.signer.as_deref())),
+        );
+
+        let block_record = Block::new(
+            block_number,
+            block_hash.as_bytes().to_vec(),
+            parent_hash.as_bytes().to_vec(),
+            timestamp,
+            runtime.spec_version as i64,
+        )
+        .with_author(mining.as_ref().and_then(|m| m.block.miner.clone()));
+        for change in &mut balance_changes {
+            change.block_hash = Some(block_record.hash.clone());
+        }
+        let header = self.header_decoder.decode_block(
+            &rpc_block.block,
+            &block_hash,
+            block_number,
+            Some(events.len()),
+            timestamp,
+        )?;
+
+        // Store everything atomically
+        let mut conn = self.pool.get().await?;
+        let tx = conn.transaction().await?;
+        let tx_wrapper = TransactionWrapper::new(tx, Some(self.chain_id.clone()));
+
+        BlockRepository::new(&tx_wrapper)
+            .insert(&block_record)
+            .await?;
+        let headers_repo = BlockHeaderRepository::new(&tx_wrapper);
+        headers_repo.insert(&header.header).await?;
+        headers_repo.insert_digest_logs(&header.digest_logs).await?;
+        ExtrinsicRepository::new(&tx_wrapper)
+            .insert_batch(&extrinsic_records)
+            .await?;
+        AccountRepository::new(&tx_wrapper)
+            .insert_batch(&accounts)
+            .await?;
+        let new_keys = PublicKeyRepository::new(&tx_wrapper)
+            .insert_batch(&public_keys)
+            .await?;
+        let changes_repo = BalanceChangeRepository::new(&tx_wrapper);
+        changes_repo
+            .delete_non_canonical(&block_record.hash)
+            .await?;
+        let recorded = changes_repo.insert_batch(&balance_changes).await?;
+
+        // Chain the block's balance changes, as stored, onto the previous block's ledger hash
+        let ledger_repo = LedgerRepository::new(&tx_wrapper);
+        let previous = ledger_repo.get_ledger_hash(block_number - 1).await?;
+        let ledger_changes = ledger_repo.get_changes(block_number, block_number).await?;
+        let ledger_hash = ledger::ledger_hash(
+            &ledger::previous_hash(previous),
+            block_number,
+            block_hash.as_bytes(),
+            &ledger_changes,
+        );
+        ledger_repo
+            .set_ledger_hash(block_hash.as_bytes(), &ledger_hash)
+            .await?;
+        if let Some(signer) = self
+            .ledger_signer
+            .as_ref()
+            .filter(|signer| signer.is_due(block_number))
+        {
+            let checkpoint = signer.sign_checkpoint(
+                &self.chain_id,
+                block_number,
+                block_hash.as_bytes(),
+                &ledger_hash,
+            )?;
+            ledger_repo.insert_checkpoint(&checkpoint).await?;
+        }
+        self.asset_decoder
+            .store_asset_updates(&tx_wrapper, &asset_updates, block_number)
+            .await?;
+        let reversible_repo = ReversibleTransferRepository::new(&tx_wrapper);
+        for transfer in &pending_transfers {
+            reversible_repo.upsert(transfer).await?;
+        }
+        for account in &high_security {
+            reversible_repo.upsert_high_security(account).await?;
+        }
+        let vesting_repo = VestingRepository::new(&tx_wrapper);
+        vesting_repo.insert_updates(&vesting_updates).await?;
+        vesting_repo.insert_schedules(&vesting_schedules).await?;
+        let balances_repo = AccountBalanceRepository::new(&tx_wrapper);
+        balances_repo.insert_balances(&account_balances).await?;
+        balances_repo.insert_locks(&balance_locks).await?;
+        let wormhole_repo = WormholeRepository::new(&tx_wrapper);
+        wormhole_repo.insert_proofs(&wormhole.proofs).await?;
+        wormhole_repo.insert_nullifiers(&nullifiers).await?;
+        wormhole_repo
+            .insert_transfer_proofs(&transfer_proofs)
+            .await?;
+        let airdrop_repo = AirdropRepository::new(&tx_wrapper);
+        for airdrop in &airdrops {
+            airdrop_repo.upsert(airdrop).await?;
+        }
+        airdrop_repo.insert_claims(&airdrop_events.claims).await?;
+        let governance_repo = GovernanceRepository::new(&tx_wrapper);
+        for referendum in &referenda {
+            governance_repo.upsert_referendum(referendum).await?;
+        }
+        governance_repo
+            .insert_status_changes(&governance.status_changes)
+            .await?;
+        governance_repo.insert_votes(&governance.votes).await?;
+        governance_repo
+            .insert_delegations(&governance.delegations)
+            .await?;
+        governance_repo
+            .apply_rank_changes(&governance.rank_changes)
+            .await?;
+        let treasury_repo = TreasuryRepository::new(&tx_wrapper);
+        for spend in &treasury_spends {
+            treasury_repo.upsert_spend(spend).await?;
+        }
+        treasury_repo.insert_ledger(&treasury_ledger).await?;
+        if let Some(balance) = &treasury_balance {
+            treasury_repo.insert_balance(balance).await?;
+        }
+        if let Some(mining) = &mining {
+            let mining_repo = MiningRepository::new(&tx_wrapper);
+            mining_repo.insert_block(&mining.block).await?;
+            mining_repo.insert_adjustments(&mining.adjustments).await?;
+        }
+        let recovery_repo = RecoveryRepository::new(&tx_wrapper);
+        for config in &recovery_configs {
+            recovery_repo.upsert_config(config).await?;
+        }
+        for attempt in &recoveries {
+            recovery_repo.upsert_recovery(attempt).await?;
+        }
+        recovery_repo.insert_vouches(&recovery.vouches).await?;
+        recovery_repo.insert_recovered(&recovery.recovered).await?;
+        let scheduler_repo = SchedulerRepository::new(&tx_wrapper);
+        for preimage in &preimages {
+            scheduler_repo.upsert_preimage(preimage).await?;
+        }
+        for task in &scheduled_tasks {
+            scheduler_repo.upsert_task(task).await?;
+        }
+        scheduler_repo.insert_events(&scheduler.events).await?;
+        SudoRepository::new(&tx_wrapper)
+            .insert_actions(&sudo_actions)
+            .await?;
+        UtilityRepository::new(&tx_wrapper)
+            .insert_items(&batches.items)
+            .await?;
+        let upgrade_repo = RuntimeUpgradeRepository::new(&tx_wrapper);
+        for authorization in &upgrades.authorizations {
+            upgrade_repo.record_authorization(authorization).await?;
+        }
+        if let Some(upgrade) = &upgrades.upgrade {
+            upgrade_repo.record_applied(upgrade).await?;
+        }
+        if let Some(runtime) = &new_runtime {
+            RuntimeMetadataRepository::new(&tx_wrapper)
+                .record_upgrade(runtime)
+                .await?;
+        }
+
+        progress.latest_block = block_number;
+        progress.latest_block_hash = block_hash.as_bytes().to_vec();
+        progress.latest_block_ts = timestamp;
+        progress.blocks_indexed += 1;
+        progress.balance_changes_recorded += recorded as i64;
+        ChainRepository::new(&tx_wrapper)
+            .update_progress(progress)
+            .await?;
+
+        tx_wrapper.commit().await?;
+
+        // An upgrade keeping its spec version replaces the cached metadata
+        if let Some(runtime) = &new_runtime {
+            self.runtime_cache.remove(&runtime.spec_version);
+        }
+        if let Err(e) = self.refresh_chain_info(runtime.spec_version).await {
+            warn!("Failed to refresh chain info: {}", e);
+        }
+        if new_keys > 0 {
+            debug!(
+                "Block #{} revealed {} new public keys",
+                block_number, new_keys
+            );
+        }
+        info!(
+            "Indexed block #{} with {} extrinsics and {} balance changes",
+            block_number,
+            extrinsic_records.len(),
+            recorded
+        );
+        Ok(recorded)
+    }
+}
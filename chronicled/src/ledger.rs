@@ -0,0 +1,426 @@
+use anyhow::Result;
+use chron_db::{BalanceChange, Block, DbExecutor, LedgerCheckpoint, LedgerRepository};
+use chrono::Utc;
+use parity_scale_codec::Encode;
+use qp_rusty_crystals_dilithium::{ml_dsa_87, SensitiveBytes32};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use subxt::ext::sp_core::blake2_256;
+
+/// Signature scheme of ledger checkpoints
+pub const CHECKPOINT_SCHEME: &str = "ML-DSA-87";
+
+/// ML-DSA context string separating checkpoint signatures from any other use of the key
+const CHECKPOINT_CONTEXT: &[u8] = b"CHRONICLE_LEDGER";
+
+/// Blocks whose ledger hash is checked per database round trip when verifying
+const VERIFY_BATCH_SIZE: i64 = 1000;
+
+/// Running ledger hash of a block: Blake2-256 over the previous block's ledger hash, the
+/// block number and hash, and the block's canonical balance changes in ledger order
+/// (see `LedgerRepository::get_changes`). The first indexed block chains from zeros.
+pub fn ledger_hash(
+    previous: &[u8; 32],
+    block_number: i64,
+    block_hash: &[u8],
+    changes: &[BalanceChange],
+) -> [u8; 32] {
+    let mut payload = (previous, block_number, block_hash).encode();
+    for change in changes {
+        (
+            change.event_index,
+            &change.account,
+            change.asset_id,
+            &change.delta,
+            change.reason.as_str(),
+            &change.extrinsic_hash,
+            &change.event_pallet,
+            &change.event_variant,
+        )
+            .encode_to(&mut payload);
+    }
+    blake2_256(&payload)
+}
+
+/// The previous ledger hash a block chains from; blocks without a hashed predecessor
+/// chain from zeros
+pub fn previous_hash(stored: Option<Vec<u8>>) -> [u8; 32] {
+    stored
+        .and_then(|hash| <[u8; 32]>::try_from(hash).ok())
+        .unwrap_or_default()
+}
+
+/// The message a checkpoint signs: the chain, the block and its ledger hash
+fn checkpoint_message(
+    chain_id: &str,
+    block_number: i64,
+    block_hash: &[u8],
+    ledger_hash: &[u8],
+) -> Vec<u8> {
+    (chain_id, block_number, block_hash, ledger_hash).encode()
+}
+
+/// Signs ledger checkpoints with the operator's ML-DSA-87 key every `interval` blocks
+pub struct LedgerSigner {
+    keypair: ml_dsa_87::Keypair,
+    interval: i64,
+}
+
+impl LedgerSigner {
+    /// Derive the signing key from a 32-byte seed
+    pub fn from_seed(mut seed: [u8; 32], interval: i64) -> Self {
+        Self {
+            keypair: ml_dsa_87::Keypair::generate(&mut SensitiveBytes32::from(&mut seed)),
+            interval: interval.max(1),
+        }
+    }
+
+    /// Read the seed from a file holding it as hex
+    pub fn from_seed_file(path: &Path, interval: i64) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let seed = hex::decode(contents.trim().trim_start_matches("0x"))?;
+        let seed = <[u8; 32]>::try_from(seed).map_err(|seed| {
+            anyhow::anyhow!(
+                "Ledger signing seed in {} is {} bytes, expected 32",
+                path.display(),
+                seed.len()
+            )
+        })?;
+        Ok(Self::from_seed(seed, interval))
+    }
+
+    /// Public key checkpoints verify under
+    pub fn public_key(&self) -> Vec<u8> {
+        self.keypair.public().to_bytes().to_vec()
+    }
+
+    /// Whether a checkpoint is taken at this block
+    pub fn is_due(&self, block_number: i64) -> bool {
+        block_number % self.interval == 0
+    }
+
+    /// Sign the ledger hash of a block
+    pub fn sign_checkpoint(
+        &self,
+        chain_id: &str,
+        block_number: i64,
+        block_hash: &[u8],
+        ledger_hash: &[u8],
+    ) -> Result<LedgerCheckpoint> {
+        let message = checkpoint_message(chain_id, block_number, block_hash, ledger_hash);
+        let signature = self
+            .keypair
+            .sign(&message, Some(CHECKPOINT_CONTEXT), None)
+            .map_err(|e| anyhow::anyhow!("Failed to sign ledger checkpoint: {}", e))?;
+
+        Ok(LedgerCheckpoint {
+            block_number,
+            block_hash: block_hash.to_vec(),
+            ledger_hash: ledger_hash.to_vec(),
+            scheme: CHECKPOINT_SCHEME.to_string(),
+            public_key: self.public_key(),
+            signature: signature.to_vec(),
+            signed_at: Utc::now(),
+        })
+    }
+}
+
+/// Check a checkpoint's signature, and its key against `trusted_key` when given
+pub fn verify_checkpoint(
+    chain_id: &str,
+    checkpoint: &LedgerCheckpoint,
+    trusted_key: Option<&[u8]>,
+) -> std::result::Result<(), String> {
+    if checkpoint.scheme != CHECKPOINT_SCHEME {
+        return Err(format!("unsupported scheme {}", checkpoint.scheme));
+    }
+    if trusted_key.is_some_and(|key| key != checkpoint.public_key) {
+        return Err("signed by an unknown key".into());
+    }
+    let key = ml_dsa_87::PublicKey::from_bytes(&checkpoint.public_key)
+        .map_err(|e| format!("malformed public key ({})", e))?;
+    let message = checkpoint_message(
+        chain_id,
+        checkpoint.block_number,
+        &checkpoint.block_hash,
+        &checkpoint.ledger_hash,
+    );
+    if !key.verify(&message, &checkpoint.signature, Some(CHECKPOINT_CONTEXT)) {
+        return Err("invalid signature".into());
+    }
+    Ok(())
+}
+
+/// Outcome of [`verify_ledger`]
+#[derive(Debug, Default)]
+pub struct LedgerReport {
+    /// Canonical blocks whose ledger hash was recomputed
+    pub blocks_checked: u64,
+    /// Canonical blocks indexed without a ledger hash
+    pub unhashed_blocks: u64,
+    /// Blocks whose stored ledger hash does not match their balance changes
+    pub mismatched_blocks: Vec<i64>,
+    /// Hashed blocks after the ledger's first whose predecessor is missing or unhashed, so
+    /// their hash restarts from zeros and the changes in between are not covered
+    pub gaps: Vec<i64>,
+    /// Canonical balance changes at heights without a canonical block
+    pub unanchored_changes: i64,
+    /// Checkpoints whose signature was checked
+    pub checkpoints_checked: u64,
+    /// Checkpoints that failed, with the reason
+    pub bad_checkpoints: Vec<(i64, String)>,
+}
+
+impl LedgerReport {
+    /// True when no block or checkpoint failed
+    pub fn is_clean(&self) -> bool {
+        self.mismatched_blocks.is_empty()
+            && self.gaps.is_empty()
+            && self.unanchored_changes == 0
+            && self.bad_checkpoints.is_empty()
+    }
+}
+
+/// Recomputes the ledger hashes of canonical blocks fed to it in chain order
+#[derive(Default)]
+struct LedgerWalk {
+    /// Height and stored ledger hash of the last block checked
+    previous: Option<(i64, Option<Vec<u8>>)>,
+    /// Whether a hashed block was seen, i.e. the ledger has started
+    started: bool,
+    report: LedgerReport,
+}
+
+impl LedgerWalk {
+    /// Check the next canonical block against its balance changes
+    fn check(&mut self, block: &Block, changes: &[BalanceChange]) {
+        let chained = match &self.previous {
+            Some((number, hash)) if *number == block.number - 1 => hash.clone(),
+            _ => None,
+        };
+        self.previous = Some((block.number, block.ledger_hash.clone()));
+
+        let Some(stored) = &block.ledger_hash else {
+            self.report.unhashed_blocks += 1;
+            return;
+        };
+        if self.started && chained.is_none() {
+            self.report.gaps.push(block.number);
+        }
+        self.started = true;
+
+        let computed = ledger_hash(&previous_hash(chained), block.number, &block.hash, changes);
+        if stored.as_slice() != computed {
+            self.report.mismatched_blocks.push(block.number);
+        }
+        self.report.blocks_checked += 1;
+    }
+}
+
+/// Recompute every canonical block's ledger hash from `balance_changes` and check the
+/// signed checkpoints against the stored chain.
+///
+/// Each block is recomputed from its predecessor's stored hash, so an edit shows up at
+/// the block it was made in (and at the next block when a stored hash was rewritten to
+/// hide it). A chain rewritten consistently up to the head is caught by the checkpoints.
+/// A block skipped during indexing breaks the chain; the next hashed block is reported
+/// as a gap.
+pub async fn verify_ledger(
+    conn: &dyn DbExecutor,
+    chain_id: &str,
+    trusted_key: Option<&[u8]>,
+) -> Result<LedgerReport> {
+    let repo = LedgerRepository::new(conn);
+    let mut walk = LedgerWalk::default();
+
+    // Stored ledger hashes at checkpoint heights, filled in while walking the blocks
+    let checkpoints = repo.get_checkpoints().await?;
+    let mut checkpointed: HashMap<i64, (Vec<u8>, Option<Vec<u8>>)> = HashMap::new();
+    let heights: HashSet<i64> = checkpoints.iter().map(|c| c.block_number).collect();
+
+    let mut from_block = 0;
+    loop {
+        let blocks = repo.get_blocks(from_block, VERIFY_BATCH_SIZE).await?;
+        let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+            break;
+        };
+        let mut changes: HashMap<i64, Vec<BalanceChange>> = HashMap::new();
+        for change in repo.get_changes(first.number, last.number).await? {
+            changes.entry(change.block_number).or_default().push(change);
+        }
+
+        for block in &blocks {
+            if heights.contains(&block.number) {
+                checkpointed.insert(
+                    block.number,
+                    (block.hash.clone(), block.ledger_hash.clone()),
+                );
+            }
+            walk.check(block, changes.get(&block.number).map_or(&[], Vec::as_slice));
+        }
+        from_block = last.number + 1;
+    }
+
+    let mut report = walk.report;
+    report.unanchored_changes = repo.count_unanchored_changes().await?;
+
+    for checkpoint in &checkpoints {
+        report.checkpoints_checked += 1;
+        let failure = match verify_checkpoint(chain_id, checkpoint, trusted_key) {
+            Err(failure) => Some(failure),
+            Ok(()) => match checkpointed.get(&checkpoint.block_number) {
+                Some((hash, _)) if *hash != checkpoint.block_hash => {
+                    Some("signed another block at this height".into())
+                }
+                Some((_, Some(stored))) if *stored == checkpoint.ledger_hash => None,
+                Some(_) => Some("ledger hash differs from the stored block".into()),
+                None => Some("no canonical block at this height".into()),
+            },
+        };
+        if let Some(failure) = failure {
+            report
+                .bad_checkpoints
+                .push((checkpoint.block_number, failure));
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chron_db::BalanceChangeReason;
+
+    fn change(event_index: i32, delta: &str) -> BalanceChange {
+        BalanceChange::new(
+            vec![1; 32],
+            7,
+            event_index,
+            delta.to_string(),
+            BalanceChangeReason::Transfer,
+            None,
+            "Balances".into(),
+            "Transfer".into(),
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn test_ledger_hash_covers_changes() {
+        let previous = [3u8; 32];
+        let changes = vec![change(0, "-100"), change(1, "100")];
+        let hash = ledger_hash(&previous, 7, &[9; 32], &changes);
+
+        // The block timestamp and row ids are not part of the ledger
+        let mut same = changes.clone();
+        same[0].id = Some(42);
+        assert_eq!(ledger_hash(&previous, 7, &[9; 32], &same), hash);
+
+        let mut edited = changes.clone();
+        edited[1].delta = "1000".into();
+        assert_ne!(ledger_hash(&previous, 7, &[9; 32], &edited), hash);
+        assert_ne!(ledger_hash(&previous, 7, &[9; 32], &changes[..1]), hash);
+        assert_ne!(ledger_hash(&[0; 32], 7, &[9; 32], &changes), hash);
+        assert_ne!(ledger_hash(&previous, 7, &[8; 32], &changes), hash);
+    }
+
+    /// Blocks hashed the way the indexer hashes them: each onto the stored hash of the
+    /// block below, from zeros when there is none
+    fn hashed_chain(numbers: &[i64], changes: &HashMap<i64, Vec<BalanceChange>>) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for &number in numbers {
+            let previous = blocks
+                .last()
+                .filter(|block| block.number == number - 1)
+                .and_then(|block| block.ledger_hash.clone());
+            let mut block = Block::new(number, vec![number as u8; 32], vec![], Utc::now(), 1);
+            let block_changes = changes.get(&number).map_or(&[][..], Vec::as_slice);
+            block.ledger_hash = Some(
+                ledger_hash(&previous_hash(previous), number, &block.hash, block_changes).to_vec(),
+            );
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn walk(blocks: &[Block], changes: &HashMap<i64, Vec<BalanceChange>>) -> LedgerReport {
+        let mut walk = LedgerWalk::default();
+        for block in blocks {
+            walk.check(block, changes.get(&block.number).map_or(&[], Vec::as_slice));
+        }
+        walk.report
+    }
+
+    #[test]
+    fn test_verify_ledger_walk() {
+        let changes: HashMap<i64, Vec<BalanceChange>> = (1..=5)
+            .map(|number| (number, vec![change(0, "-100"), change(1, "100")]))
+            .collect();
+
+        let blocks = hashed_chain(&[1, 2, 3, 4, 5], &changes);
+        let report = walk(&blocks, &changes);
+        assert!(report.is_clean());
+        assert_eq!(report.blocks_checked, 5);
+
+        // A balance change edited in a middle block shows up at that block only
+        let mut tampered = changes.clone();
+        tampered.get_mut(&3).unwrap()[1].delta = "1000".into();
+        let report = walk(&blocks, &tampered);
+        assert_eq!(report.mismatched_blocks, vec![3]);
+        assert!(!report.is_clean());
+
+        // A skipped block restarts the chain from zeros at the next one
+        let skipped = hashed_chain(&[1, 2, 4, 5], &changes);
+        let report = walk(&skipped, &changes);
+        assert!(report.mismatched_blocks.is_empty());
+        assert_eq!(report.gaps, vec![4]);
+        assert!(!report.is_clean());
+
+        // So does a block indexed without a ledger hash
+        let mut unhashed = hashed_chain(&[1, 2], &changes);
+        let mut block = Block::new(3, vec![3; 32], vec![], Utc::now(), 1);
+        block.ledger_hash = None;
+        unhashed.push(block);
+        unhashed.extend(hashed_chain(&[4, 5], &changes));
+        let report = walk(&unhashed, &changes);
+        assert_eq!(report.unhashed_blocks, 1);
+        assert_eq!(report.gaps, vec![4]);
+        assert!(!report.is_clean());
+
+        // Blocks indexed before the ledger existed are not gaps
+        let mut late_start = unhashed[..3].to_vec();
+        for block in &mut late_start {
+            block.ledger_hash = None;
+        }
+        late_start.extend(hashed_chain(&[4, 5], &changes));
+        let report = walk(&late_start, &changes);
+        assert_eq!(report.unhashed_blocks, 3);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_checkpoint_signatures() {
+        let signer = LedgerSigner::from_seed([5; 32], 100);
+        assert!(signer.is_due(300));
+        assert!(!signer.is_due(301));
+
+        let checkpoint = signer
+            .sign_checkpoint("chain", 300, &[1; 32], &[2; 32])
+            .unwrap();
+        let key = signer.public_key();
+        assert_eq!(verify_checkpoint("chain", &checkpoint, Some(&key)), Ok(()));
+        assert!(verify_checkpoint("other chain", &checkpoint, None).is_err());
+
+        let mut tampered = checkpoint.clone();
+        tampered.ledger_hash = vec![3; 32];
+        assert!(verify_checkpoint("chain", &tampered, None).is_err());
+
+        // A valid checkpoint re-signed with another key
+        let forged = LedgerSigner::from_seed([6; 32], 100)
+            .sign_checkpoint("chain", 300, &[1; 32], &[3; 32])
+            .unwrap();
+        assert_eq!(verify_checkpoint("chain", &forged, None), Ok(()));
+        assert!(verify_checkpoint("chain", &forged, Some(&key)).is_err());
+    }
+}
@@ -1,138 +1,49 @@
 #![allow(dead_code)]
+mod account_balance_decoder;
+mod airdrop_decoder;
+mod asset_decoder;
 mod balance_decoder;
+mod block_verifier;
+mod chain_info;
+mod extrinsic_decoder;
+mod finality;
+mod governance_decoder;
+mod header_decoder;
+mod indexer;
+mod ledger;
+mod mining_decoder;
+mod read_proof;
+mod recovery_decoder;
+mod reversible_decoder;
+mod rpc;
+mod scheduler_decoder;
+mod signature;
+mod signature_verifier;
+mod sudo_decoder;
+mod treasury_decoder;
+mod upgrade_decoder;
+mod utility_decoder;
+mod value_ext;
+mod vesting_decoder;
+mod wormhole_decoder;
 
 use anyhow::Result;
-use balance_decoder::BalanceDecoder;
+use block_verifier::BlockVerifier;
 use chron_db::{
-    Block, ChainRepository, ConnectionPool, DbConfig, RuntimeMetadata, RuntimeMetadataRepository,
-    SchemaManager,
+    AccountRepository, BalanceChangeRepository, ChainInfoRepository, ChainRepository,
+    ConnectionPool, DbConfig, IndexProgress, RuntimeMetadata, RuntimeMetadataRepository,
+    SchemaManager, DEFAULT_SS58_PREFIX,
 };
-use chrono::Utc;
-use serde::Deserialize;
-use subxt::ext::sp_core::H256;
+use finality::{FinalityOverrides, FinalityPolicy, ReorgTracker};
+use indexer::BlockIndexer;
+use ledger::LedgerSigner;
+use rpc::RpcHelper;
+use signature_verifier::SignatureVerifier;
+use std::path::Path;
+use subxt::ext::sp_core::{blake2_256, H256};
 use subxt::{backend::rpc::RpcClient, OnlineClient, PolkadotConfig};
 use tracing::{debug, info, warn};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RpcHeader {
-    parent_hash: H256,
-    number: String,
-    state_root: H256,
-    extrinsics_root: H256,
-    #[serde(default)]
-    digest: serde_json::Value,
-}
-
-#[derive(Debug, Deserialize)]
-struct RpcBlockData {
-    header: RpcHeader,
-    extrinsics: Vec<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct RpcBlock {
-    block: RpcBlockData,
-    justifications: Option<serde_json::Value>,
-}
-
-struct RpcHelper {
-    client: RpcClient,
-}
-
-impl RpcHelper {
-    fn new(client: RpcClient) -> Self {
-        Self { client }
-    }
-
-    async fn get_block_hash_by_number(&self, number: u64) -> anyhow::Result<H256> {
-        use subxt::backend::legacy::rpc_methods::NumberOrHex;
-        use subxt::backend::legacy::LegacyRpcMethods;
-
-        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
-        let block_number = NumberOrHex::Number(number);
-        let hash = legacy_rpc
-            .chain_get_block_hash(Some(block_number))
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("No block hash found for block #{}", number))?;
-        Ok(hash)
-    }
-
-    async fn get_latest_block_hash(&self) -> anyhow::Result<H256> {
-        use subxt::backend::legacy::LegacyRpcMethods;
-
-        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
-        let hash = legacy_rpc
-            .chain_get_block_hash(None)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("No latest block hash found"))?;
-        Ok(hash)
-    }
-
-    async fn get_block_by_hash(&self, hash: &H256) -> anyhow::Result<RpcBlock> {
-        use subxt::backend::legacy::LegacyRpcMethods;
-
-        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
-        let signed_block = legacy_rpc
-            .chain_get_block(Some(*hash))
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("No block found for hash"))?;
-
-        // Convert from legacy SignedBlock to our RpcBlock structure
-        let block = RpcBlock {
-            block: RpcBlockData {
-                header: RpcHeader {
-                    parent_hash: signed_block.block.header.parent_hash,
-                    number: format!("{:#x}", signed_block.block.header.number),
-                    state_root: signed_block.block.header.state_root,
-                    extrinsics_root: signed_block.block.header.extrinsics_root,
-                    digest: serde_json::Value::Null,
-                },
-                extrinsics: signed_block
-                    .block
-                    .extrinsics
-                    .into_iter()
-                    .map(|ext| format!("0x{}", hex::encode(ext.0)))
-                    .collect(),
-            },
-            justifications: None,
-        };
-        Ok(block)
-    }
-
-    async fn get_header_by_hash(&self, hash: &H256) -> anyhow::Result<RpcHeader> {
-        use subxt::backend::legacy::LegacyRpcMethods;
-
-        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
-        let header = legacy_rpc
-            .chain_get_header(Some(*hash))
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("No header found for hash"))?;
-
-        Ok(RpcHeader {
-            parent_hash: header.parent_hash,
-            number: format!("{:#x}", header.number),
-            state_root: header.state_root,
-            extrinsics_root: header.extrinsics_root,
-            digest: serde_json::Value::Null,
-        })
-    }
-}
-
-fn hex_to_h256(s: &str) -> anyhow::Result<H256> {
-    let s = s.strip_prefix("0x").unwrap_or(s);
-    let bytes = hex::decode(s)?;
-    if bytes.len() != 32 {
-        return Err(anyhow::anyhow!(
-            "expected 32 bytes for H256, got {}",
-            bytes.len()
-        ));
-    }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
-    Ok(H256::from(arr))
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing
@@ -140,6 +51,24 @@ async fn main() -> Result<()> {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
+    // Commands that work on the database alone
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("verify-ledger") => {
+            let chain_id = args
+                .get(2)
+                .ok_or_else(|| anyhow::anyhow!("Usage: chronicled verify-ledger <chain_id>"))?;
+            return verify_ledger(chain_id).await;
+        }
+        Some("ledger-public-key") => {
+            let signer = ledger_signer(0)?
+                .ok_or_else(|| anyhow::anyhow!("LEDGER_SIGNING_KEY_FILE is not set"))?;
+            println!("{}", hex::encode(signer.public_key()));
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Load configuration from environment
     let ws_url = std::env::var("WS_URL").unwrap_or_else(|_| "wss://a.t.res.fm".into());
     let enable_timescale = std::env::var("ENABLE_TIMESCALE")
@@ -148,15 +77,53 @@ async fn main() -> Result<()> {
         .unwrap_or(false);
 
     // PoW-specific configuration
-    let finality_confirmations_env = std::env::var("FINALITY_CONFIRMATIONS")
-        .ok()
-        .and_then(|s| s.parse::<u32>().ok());
+    let finality_overrides = FinalityOverrides::from_env();
 
     let follow_best = std::env::var("FOLLOW_BEST")
         .ok()
         .and_then(|s| s.parse::<bool>().ok())
         .unwrap_or(true); // Default to following best blocks for PoW
 
+    // Optional offline verification of extrinsic signatures
+    let verify_signatures = std::env::var("VERIFY_SIGNATURES")
+        .ok()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or(false);
+    // First spec version signing extrinsics under the QUANTUS_EXTRINSIC context; earlier
+    // runtimes are verified under the empty context
+    let extrinsic_context_since_spec = std::env::var("EXTRINSIC_CONTEXT_SINCE_SPEC")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .unwrap_or(0);
+
+    // Optional verification of fetched blocks against their header hash and extrinsics root,
+    // with endpoints to re-fetch rejected blocks from
+    let verify_blocks = std::env::var("VERIFY_BLOCKS")
+        .ok()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or(false);
+    let fallback_urls: Vec<String> = std::env::var("FALLBACK_WS_URLS")
+        .map(|s| {
+            s.split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Optional read proofs for every storage value the indexer reads
+    let verify_read_proofs = std::env::var("VERIFY_READ_PROOFS")
+        .ok()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or(false);
+
+    // Optional operator key signing a ledger checkpoint every LEDGER_CHECKPOINT_INTERVAL blocks
+    let ledger_checkpoint_interval = std::env::var("LEDGER_CHECKPOINT_INTERVAL")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(1000);
+    let ledger_signer = ledger_signer(ledger_checkpoint_interval)?;
+
     // Connect to the blockchain
     info!("Connecting to blockchain at {}", ws_url);
     let rpc_client = RpcClient::from_url(&ws_url).await?;
@@ -189,82 +156,122 @@ async fn main() -> Result<()> {
     let chain_repo = ChainRepository::new(&conn);
     let mut progress = chain_repo.get_or_create_progress(&chain_id).await?;
 
-    // Create balance decoder
-    let decoder = BalanceDecoder::new(client.clone());
+    // Block indexer: fetches blocks over JSON-RPC and decodes extrinsics and events
+    let mut rpc = RpcHelper::new(rpc_client.clone());
+    if verify_read_proofs {
+        info!("Proving storage reads against block state roots");
+        rpc = rpc.with_read_proofs();
+    }
+    let mut indexer = BlockIndexer::new(client.clone(), rpc, pool.clone(), chain_id.clone());
+    let ss58_prefix = query_ss58_prefix(&client, indexer.rpc()).await;
+    info!("Using SS58 prefix {}", ss58_prefix);
+    indexer = indexer.with_ss58_prefix(ss58_prefix);
+
+    // Record the chain's identity; the indexer refreshes it on runtime upgrades
+    match chain_info::fetch_chain_info(
+        indexer.rpc(),
+        &chain_id,
+        genesis_hash,
+        ss58_prefix,
+        client.runtime_version().spec_version,
+        &ws_url,
+    )
+    .await
+    {
+        Ok(info) => {
+            ChainInfoRepository::new(&conn).upsert(&info).await?;
+            info!(
+                "Chain {} ({} {})",
+                info.chain_name, info.node_name, info.node_version
+            );
+            indexer = indexer.with_chain_info(info);
+        }
+        Err(e) => warn!("Failed to query chain info: {}", e),
+    }
+    if verify_signatures {
+        info!("Verifying extrinsic signatures offline");
+        indexer = indexer.with_signature_verifier(
+            SignatureVerifier::new(genesis_hash)
+                .with_context_since_spec(extrinsic_context_since_spec),
+        );
+    }
+
+    if verify_blocks {
+        let mut verifier = BlockVerifier::new();
+        for url in &fallback_urls {
+            match RpcClient::from_url(url).await {
+                Ok(fallback) => {
+                    verifier = verifier.with_fallback(url.clone(), RpcHelper::new(fallback))
+                }
+                Err(e) => warn!("Failed to connect to fallback endpoint {}: {}", url, e),
+            }
+        }
+        info!(
+            "Verifying fetched blocks against their headers ({} fallback endpoints)",
+            fallback_urls.len()
+        );
+        indexer = indexer.with_block_verifier(verifier);
+    }
+
+    if let Some(signer) = ledger_signer {
+        info!(
+            "Signing ledger checkpoints every {} blocks with key {}",
+            ledger_checkpoint_interval,
+            hex::encode(&blake2_256(&signer.public_key())[..8])
+        );
+        indexer = indexer.with_ledger_signer(signer);
+    }
 
     // Process genesis endowments if starting from the beginning
     if progress.latest_block < 0 {
         info!("Processing genesis endowments...");
-        let genesis_endowments = decoder.query_genesis_endowments().await?;
+        let genesis_endowments = indexer
+            .balance_decoder()
+            .query_genesis_endowments(indexer.rpc())
+            .await?;
         if !genesis_endowments.is_empty() {
             // Store genesis endowments in database
             let mut conn = pool.get().await?;
             let tx = conn.transaction().await?;
             let tx_wrapper = chron_db::TransactionWrapper::new(tx, Some(chain_id.clone()));
 
-            let schema = tx_wrapper.schema_name()?;
-            for endowment in &genesis_endowments {
-                let sql = format!(
-                    r#"
-                    INSERT INTO {schema}.balance_changes
-                    (account, block_number, event_index, delta, reason, extrinsic_hash, event_pallet, event_variant, block_ts)
-                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-                    "#,
-                    schema = schema
-                );
-                tx_wrapper
-                    .execute(
-                        &sql,
-                        &[
-                            &endowment.account,
-                            &endowment.block_number,
-                            &endowment.event_index,
-                            &endowment.delta,
-                            &endowment.reason.as_str(),
-                            &endowment.extrinsic_hash,
-                            &endowment.event_pallet,
-                            &endowment.event_variant,
-                            &endowment.block_ts,
-                        ],
-                    )
-                    .await?;
-            }
+            let stored = BalanceChangeRepository::new(&tx_wrapper)
+                .insert_batch(&genesis_endowments)
+                .await?;
+            let accounts =
+                indexer.account_records(0, genesis_endowments.iter().map(|e| e.account.as_slice()));
+            AccountRepository::new(&tx_wrapper)
+                .insert_batch(&accounts)
+                .await?;
+            progress.balance_changes_recorded += stored as i64;
 
             tx_wrapper.commit().await?;
-            info!("Stored {} genesis endowments", genesis_endowments.len());
+            info!("Stored {} genesis endowments", stored);
         }
     }
 
-    // Scan for runtime versions from genesis to current
-    info!("Scanning for runtime versions...");
-    let runtime_versions_discovered =
-        scan_and_store_runtime_versions(&client, &pool, &chain_id).await?;
-    info!(
-        "Discovered {} runtime versions",
-        runtime_versions_discovered
-    );
+    // Record the genesis runtime; later runtimes are recorded as their upgrades are indexed
+    let runtime_versions = store_genesis_runtime(indexer.rpc(), &pool, genesis_hash).await?;
+    info!("{} runtime versions known", runtime_versions);
 
-    // Query chain for finality depth from runtime constants
-    let finality_confirmations = match query_finality_depth(&client).await {
-        Ok(depth) => {
-            info!("Discovered finality depth from chain constants: {}", depth);
-            depth
-        }
-        Err(e) => {
-            warn!("Failed to query finality depth from chain: {}", e);
-            let fallback = finality_confirmations_env.unwrap_or(10);
-            info!("Using fallback finality confirmations: {}", fallback);
-            fallback
-        }
-    };
+    // Choose the finality depth from overrides or runtime constants and record it
+    let finality = FinalityPolicy::discover(&client.metadata(), &chain_id, &finality_overrides);
+    let finality_confirmations = finality.depth;
+    progress.finality_depth = Some(finality.depth as i32);
+    progress.finality_source = Some(finality.source.clone());
+    chain_repo.update_progress(&progress).await?;
 
     info!("Resuming indexing from block {}", progress.latest_block + 1);
     info!(
-        "Using {} confirmations for finality",
-        finality_confirmations
+        "Using {} confirmations for finality (from {})",
+        finality_confirmations, finality.source
     );
-
-    let _last_runtime_version: Option<u32> = None;
+    if progress.deepest_reorg > 0 {
+        info!(
+            "Deepest reorg observed so far: {} blocks",
+            progress.deepest_reorg
+        );
+    }
 
     // Catch up on historical blocks before starting subscription
     let current_best = client.blocks().at_latest().await?;
@@ -285,13 +292,13 @@ async fn main() -> Result<()> {
             safe_block_number
         );
 
-        // Process historical blocks using JSON-RPC (chain_getBlock/chain_getHeader) only; no subxt block fetching
-        let rpc = RpcHelper::new(rpc_client.clone());
-
-        // Process historical blocks using subxt's legacy RPC methods
         for block_num in (progress.latest_block + 1)..=safe_block_number {
             // Step 1: Get block hash using direct RPC
-            let block_hash = match rpc.get_block_hash_by_number(block_num as u64).await {
+            let block_hash = match indexer
+                .rpc()
+                .get_block_hash_by_number(block_num as u64)
+                .await
+            {
                 Ok(h) => h,
                 Err(e) => {
                     warn!("No block hash found for block #{}: {}", block_num, e);
@@ -299,129 +306,19 @@ async fn main() -> Result<()> {
                 }
             };
 
-            // Step 2: Fetch the block using chain_getBlock
+            // Step 2: Fetch, decode and store the block
             debug!(
-                "Requesting historical block #{} via chain_getBlock by hash {}",
+                "Processing historical block #{} ({})",
                 block_num,
                 hex::encode(block_hash.as_bytes())
             );
-            match rpc.get_block_by_hash(&block_hash).await {
-                Ok(rpc_block) => {
-                    let header = rpc_block.block.header;
-
-                    // We asked for a specific number; keep it authoritative
-                    let block_number = block_num as i64;
-                    let parent_hash = header.parent_hash;
-
-                    info!(
-                        "Processing historical block #{} ({})",
-                        block_number,
-                        hex::encode(block_hash.as_bytes())
-                    );
-
-                    // Build block record from header
-                    let timestamp = Utc::now(); // TODO: use timestamp from extrinsics if needed
-                    let runtime_version = client.runtime_version();
-                    let runtime_spec = runtime_version.spec_version as i64;
-
-                    let block_record = Block::new(
-                        block_number,
-                        block_hash.as_bytes().to_vec(),
-                        parent_hash.as_bytes().to_vec(),
-                        timestamp,
-                        runtime_spec,
-                    );
-
-                    // Store block in database (no events in this path)
-                    let mut conn = pool.get().await?;
-                    let tx = conn.transaction().await?;
-                    let tx_wrapper = chron_db::TransactionWrapper::new(tx, Some(chain_id.clone()));
-
-                    {
-                        let schema = tx_wrapper.schema_name()?;
-                        let block_sql = format!(
-                            r#"
-                        INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-                        VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT (number) DO UPDATE SET
-                            hash = EXCLUDED.hash,
-                            parent_hash = EXCLUDED.parent_hash,
-                            timestamp = EXCLUDED.timestamp,
-                            is_canonical = EXCLUDED.is_canonical,
-                            runtime_spec = EXCLUDED.runtime_spec
-                        "#,
-                            schema = schema
-                        );
-                        tx_wrapper
-                            .execute(
-                                &block_sql,
-                                &[
-                                    &block_record.number,
-                                    &block_record.hash,
-                                    &block_record.parent_hash,
-                                    &block_record.timestamp,
-                                    &block_record.is_canonical,
-                                    &block_record.runtime_spec,
-                                ],
-                            )
-                            .await?;
-
-                        // Update progress
-                        progress.latest_block = block_number;
-                        progress.latest_block_hash = block_hash.as_bytes().to_vec();
-                        progress.latest_block_ts = timestamp;
-                        progress.blocks_indexed += 1;
-
-                        let progress_sql = format!(
-                            r#"
-                        UPDATE {schema}.index_progress
-                        SET latest_block = $2,
-                            latest_block_hash = $3,
-                            latest_block_ts = $4,
-                            blocks_indexed = $5,
-                            balance_changes_recorded = $6,
-                            updated_at = $7
-                        WHERE chain_id = $1
-                        "#,
-                            schema = schema
-                        );
-                        tx_wrapper
-                            .execute(
-                                &progress_sql,
-                                &[
-                                    &progress.chain_id,
-                                    &progress.latest_block,
-                                    &progress.latest_block_hash,
-                                    &progress.latest_block_ts,
-                                    &progress.blocks_indexed,
-                                    &progress.balance_changes_recorded,
-                                    &chrono::Utc::now(),
-                                ],
-                            )
-                            .await?;
-                    }
-
-                    tx_wrapper.commit().await?;
-                    info!("Indexed block #{} with {} balance changes", block_number, 0);
-                }
-                Err(e) => {
-                    warn!(
-                        "Failed to fetch block #{} at hash {}: {}",
-                        block_num,
-                        hex::encode(block_hash.as_bytes()),
-                        e
-                    );
-                    // Continue with next block instead of failing completely
-                    continue;
-                }
-            }
+            index_or_warn(&mut indexer, block_num, block_hash, &mut progress).await;
         }
 
         info!("Finished catching up to block {}", safe_block_number);
     }
 
     // Main indexing loop - use best blocks for PoW chains
-    let rpc_live = RpcHelper::new(rpc_client.clone());
     let mut block_sub = if follow_best {
         info!("Following best blocks (PoW mode)");
         client.blocks().subscribe_best().await?
@@ -429,16 +326,71 @@ async fn main() -> Result<()> {
         info!("Following finalized blocks (instant finality mode)");
         client.blocks().subscribe_finalized().await?
     };
-    let mut pending_blocks: std::collections::BTreeMap<i64, subxt::ext::sp_core::H256> =
+    let mut pending_blocks: std::collections::BTreeMap<i64, H256> =
         std::collections::BTreeMap::new();
+    let mut reorgs = ReorgTracker::new(finality_confirmations.max(100) * 2);
 
     while let Some(block_result) = block_sub.next().await {
         match block_result {
             Ok(block) => {
                 let block_number = block.number() as i64;
                 let block_hash = block.hash();
-                let block_header = block.header();
-                let _parent_hash = block_header.parent_hash;
+
+                // Measure how deep the best chain reorganizes
+                if follow_best {
+                    match reorgs
+                        .observe(
+                            indexer.rpc(),
+                            block_number,
+                            block_hash,
+                            block.header().parent_hash,
+                        )
+                        .await
+                    {
+                        Ok(reorg) if reorg.depth == 0 => {}
+                        Ok(reorg) => {
+                            let depth = reorg.depth;
+                            info!(
+                                "Best chain reorganized {} blocks deep at #{}",
+                                depth, block_number
+                            );
+                            progress.deepest_reorg = progress.deepest_reorg.max(depth as i32);
+                            if finality.is_near_limit(depth) {
+                                warn!(
+                                    "Reorg of {} blocks is close to the finality depth of {} ({}); consider raising it",
+                                    depth, finality_confirmations, finality.source
+                                );
+                            }
+                            let indexed_from = reorg
+                                .replaced
+                                .iter()
+                                .map(|(number, _)| *number)
+                                .filter(|number| *number <= progress.latest_block)
+                                .min();
+                            if let Some(from) = indexed_from {
+                                warn!(
+                                    "Reorg at #{} replaced blocks that were already indexed, re-indexing from #{}",
+                                    block_number, from
+                                );
+                                if let Err(e) = indexer.rewind(from, &mut progress).await {
+                                    warn!("Failed to rewind to block #{}: {}", from, e);
+                                }
+                            }
+                            // Hashes seen on the old branch are no longer to be indexed
+                            pending_blocks.retain(|&number, _| number <= reorg.fork_point);
+                            match indexer.record_orphans(&reorg.replaced).await {
+                                Ok(0) => {}
+                                Ok(recorded) => {
+                                    info!("Recorded {} orphaned blocks", recorded)
+                                }
+                                Err(e) => warn!("Failed to record orphaned blocks: {}", e),
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to check block #{} for reorgs: {}", block_number, e)
+                        }
+                    }
+                }
 
                 // Skip if we've already indexed this block
                 if block_number <= progress.latest_block {
@@ -450,7 +402,7 @@ async fn main() -> Result<()> {
                     info!(
                         "Received best block #{} ({})",
                         block_number,
-                        hex::encode(&block_hash)
+                        hex::encode(block_hash)
                     );
 
                     // Get current best block number
@@ -464,94 +416,11 @@ async fn main() -> Result<()> {
                         info!(
                             "Processing confirmed block #{} ({}) with {} confirmations",
                             block_number,
-                            hex::encode(&block_hash),
+                            hex::encode(block_hash),
                             confirmations
                         );
 
-                        // Process this confirmed block
-                        // Commit this confirmed block based on header only (no subxt block/event usage)
-                        let header = match rpc_live.get_header_by_hash(&block_hash).await {
-                            Ok(h) => h,
-                            Err(e) => {
-                                warn!(
-                                    "Failed to fetch header for confirmed block #{}: {}",
-                                    block_number, e
-                                );
-                                continue;
-                            }
-                        };
-                        let timestamp = Utc::now();
-                        let runtime_version = client.runtime_version();
-                        let runtime_spec = runtime_version.spec_version as i64;
-
-                        // Store block and update progress
-                        let mut conn = pool.get().await?;
-                        let tx = conn.transaction().await?;
-                        let tx_wrapper =
-                            chron_db::TransactionWrapper::new(tx, Some(chain_id.clone()));
-                        let schema = tx_wrapper.schema_name()?;
-
-                        let block_sql = format!(
-                            r#"
-                        INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-                        VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT (number) DO UPDATE SET
-                            hash = EXCLUDED.hash,
-                            parent_hash = EXCLUDED.parent_hash,
-                            timestamp = EXCLUDED.timestamp,
-                            is_canonical = EXCLUDED.is_canonical,
-                            runtime_spec = EXCLUDED.runtime_spec
-                        "#,
-                            schema = schema
-                        );
-                        tx_wrapper
-                            .execute(
-                                &block_sql,
-                                &[
-                                    &block_number,
-                                    &block_hash.as_bytes().to_vec(),
-                                    &header.parent_hash.as_bytes().to_vec(),
-                                    &timestamp,
-                                    &true,
-                                    &runtime_spec,
-                                ],
-                            )
-                            .await?;
-
-                        progress.latest_block = block_number;
-                        progress.latest_block_hash = block_hash.as_bytes().to_vec();
-                        progress.latest_block_ts = timestamp;
-                        progress.blocks_indexed += 1;
-
-                        let progress_sql = format!(
-                            r#"
-                        UPDATE {schema}.index_progress
-                        SET latest_block = $2,
-                            latest_block_hash = $3,
-                            latest_block_ts = $4,
-                            blocks_indexed = $5,
-                            balance_changes_recorded = $6,
-                            updated_at = $7
-                        WHERE chain_id = $1
-                        "#,
-                            schema = schema
-                        );
-                        tx_wrapper
-                            .execute(
-                                &progress_sql,
-                                &[
-                                    &progress.chain_id,
-                                    &progress.latest_block,
-                                    &progress.latest_block_hash,
-                                    &progress.latest_block_ts,
-                                    &progress.blocks_indexed,
-                                    &progress.balance_changes_recorded,
-                                    &chrono::Utc::now(),
-                                ],
-                            )
-                            .await?;
-
-                        tx_wrapper.commit().await?;
+                        index_or_warn(&mut indexer, block_number, block_hash, &mut progress).await;
                     } else {
                         debug!(
                             "Block #{} waiting for confirmations ({}/{})",
@@ -565,109 +434,39 @@ async fn main() -> Result<()> {
                         let confirmed_height =
                             latest_best.saturating_sub(finality_confirmations as i64);
 
-                        let mut to_remove = Vec::new();
-                        for (&pending_number, &pending_hash) in pending_blocks.iter() {
-                            if pending_number <= confirmed_height
-                                && pending_number > progress.latest_block
-                            {
-                                match rpc_live.get_header_by_hash(&pending_hash).await {
-                                    Ok(pending_header) => {
-                                        info!(
-                                            "Processing previously pending block #{} ({})",
-                                            pending_number,
-                                            hex::encode(pending_hash.as_ref())
-                                        );
-
-                                        let timestamp = Utc::now();
-                                        let runtime_version = client.runtime_version();
-                                        let runtime_spec = runtime_version.spec_version as i64;
-
-                                        let mut conn = pool.get().await?;
-                                        let tx = conn.transaction().await?;
-                                        let tx_wrapper = chron_db::TransactionWrapper::new(
-                                            tx,
-                                            Some(chain_id.clone()),
-                                        );
-                                        let schema = tx_wrapper.schema_name()?;
-
-                                        let block_sql = format!(
-                                            r#"
-                                    INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-                                    VALUES ($1, $2, $3, $4, $5, $6)
-                                    ON CONFLICT (number) DO UPDATE SET
-                                        hash = EXCLUDED.hash,
-                                        parent_hash = EXCLUDED.parent_hash,
-                                        timestamp = EXCLUDED.timestamp,
-                                        is_canonical = EXCLUDED.is_canonical,
-                                        runtime_spec = EXCLUDED.runtime_spec
-                                    "#,
-                                            schema = schema
-                                        );
-                                        tx_wrapper
-                                            .execute(
-                                                &block_sql,
-                                                &[
-                                                    &pending_number,
-                                                    &pending_hash.as_bytes().to_vec(),
-                                                    &pending_header.parent_hash.as_bytes().to_vec(),
-                                                    &timestamp,
-                                                    &true,
-                                                    &runtime_spec,
-                                                ],
-                                            )
-                                            .await?;
-
-                                        progress.latest_block = pending_number;
-                                        progress.latest_block_hash =
-                                            pending_hash.as_bytes().to_vec();
-                                        progress.latest_block_ts = timestamp;
-                                        progress.blocks_indexed += 1;
-
-                                        let progress_sql = format!(
-                                            r#"
-                                    UPDATE {schema}.index_progress
-                                    SET latest_block = $2,
-                                        latest_block_hash = $3,
-                                        latest_block_ts = $4,
-                                        blocks_indexed = $5,
-                                        balance_changes_recorded = $6,
-                                        updated_at = $7
-                                    WHERE chain_id = $1
-                                    "#,
-                                            schema = schema
-                                        );
-                                        tx_wrapper
-                                            .execute(
-                                                &progress_sql,
-                                                &[
-                                                    &progress.chain_id,
-                                                    &progress.latest_block,
-                                                    &progress.latest_block_hash,
-                                                    &progress.latest_block_ts,
-                                                    &progress.blocks_indexed,
-                                                    &progress.balance_changes_recorded,
-                                                    &chrono::Utc::now(),
-                                                ],
-                                            )
-                                            .await?;
-
-                                        tx_wrapper.commit().await?;
-                                        to_remove.push(pending_number);
-                                    }
+                        let mut ready: Vec<(i64, H256)> = Vec::new();
+                        for number in (progress.latest_block + 1)..=confirmed_height {
+                            match pending_blocks.get(&number) {
+                                Some(&hash) => ready.push((number, hash)),
+                                // Heights whose best block was replaced since it was seen
+                                None => match indexer
+                                    .rpc()
+                                    .get_block_hash_by_number(number as u64)
+                                    .await
+                                {
+                                    Ok(hash) => ready.push((number, hash)),
                                     Err(e) => {
-                                        warn!(
-                                            "Failed to fetch pending block header #{}: {}",
-                                            pending_number, e
-                                        );
-                                        to_remove.push(pending_number);
+                                        warn!("No block hash found for block #{}: {}", number, e);
+                                        break;
                                     }
-                                }
+                                },
                             }
                         }
 
-                        // Remove processed blocks
-                        for num in to_remove {
-                            pending_blocks.remove(&num);
+                        for (pending_number, pending_hash) in ready {
+                            info!(
+                                "Processing previously pending block #{} ({})",
+                                pending_number,
+                                hex::encode(pending_hash)
+                            );
+                            index_or_warn(
+                                &mut indexer,
+                                pending_number,
+                                pending_hash,
+                                &mut progress,
+                            )
+                            .await;
+                            pending_blocks.remove(&pending_number);
                         }
 
                         // Clean up old pending blocks that are too far behind
@@ -678,91 +477,10 @@ async fn main() -> Result<()> {
                     info!(
                         "Processing finalized block #{} ({})",
                         block_number,
-                        hex::encode(&block_hash)
+                        hex::encode(block_hash)
                     );
 
-                    // Commit finalized block based on header only (no subxt block/event usage)
-                    let header = match rpc_live.get_header_by_hash(&block_hash).await {
-                        Ok(h) => h,
-                        Err(e) => {
-                            warn!(
-                                "Failed to fetch header for finalized block #{}: {}",
-                                block_number, e
-                            );
-                            continue;
-                        }
-                    };
-                    let timestamp = Utc::now();
-                    let runtime_version = client.runtime_version();
-                    let runtime_spec = runtime_version.spec_version as i64;
-
-                    let mut conn = pool.get().await?;
-                    let tx = conn.transaction().await?;
-                    let tx_wrapper =
-                        chron_db::TransactionWrapper::new(tx, Some(chain_id.to_string()));
-                    let schema = tx_wrapper.schema_name()?;
-
-                    let block_sql = format!(
-                        r#"
-                        INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-                        VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT (number) DO UPDATE SET
-                            hash = EXCLUDED.hash,
-                            parent_hash = EXCLUDED.parent_hash,
-                            timestamp = EXCLUDED.timestamp,
-                            is_canonical = EXCLUDED.is_canonical,
-                            runtime_spec = EXCLUDED.runtime_spec
-                        "#,
-                        schema = schema
-                    );
-                    tx_wrapper
-                        .execute(
-                            &block_sql,
-                            &[
-                                &block_number,
-                                &block_hash.as_bytes().to_vec(),
-                                &header.parent_hash.as_bytes().to_vec(),
-                                &timestamp,
-                                &true,
-                                &runtime_spec,
-                            ],
-                        )
-                        .await?;
-
-                    progress.latest_block = block_number;
-                    progress.latest_block_hash = block_hash.as_bytes().to_vec();
-                    progress.latest_block_ts = timestamp;
-                    progress.blocks_indexed += 1;
-
-                    let progress_sql = format!(
-                        r#"
-                        UPDATE {schema}.index_progress
-                        SET latest_block = $2,
-                            latest_block_hash = $3,
-                            latest_block_ts = $4,
-                            blocks_indexed = $5,
-                            balance_changes_recorded = $6,
-                            updated_at = $7
-                        WHERE chain_id = $1
-                        "#,
-                        schema = schema
-                    );
-                    tx_wrapper
-                        .execute(
-                            &progress_sql,
-                            &[
-                                &progress.chain_id,
-                                &progress.latest_block,
-                                &progress.latest_block_hash,
-                                &progress.latest_block_ts,
-                                &progress.blocks_indexed,
-                                &progress.balance_changes_recorded,
-                                &chrono::Utc::now(),
-                            ],
-                        )
-                        .await?;
-
-                    tx_wrapper.commit().await?;
+                    index_or_warn(&mut indexer, block_number, block_hash, &mut progress).await;
                 }
             }
             Err(e) => {
@@ -777,486 +495,157 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Process a single confirmed block
-#[allow(dead_code)]
-/// Removed from active use; blocks are committed via chain_getBlock JSON-RPC now.
-async fn process_block(
-    client: &OnlineClient<PolkadotConfig>,
-    pool: &ConnectionPool,
-    chain_id: &str,
-    decoder: &balance_decoder::BalanceDecoder,
-    block: subxt::blocks::Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
-    progress: &mut chron_db::IndexProgress,
-    last_runtime_version: &mut Option<u32>,
-) -> Result<()> {
-    let block_number = block.number() as i64;
-    let block_hash = block.hash();
-    let block_header = block.header();
-    let parent_hash = block_header.parent_hash;
-
-    // Debug header fields to validate hashes used
-    debug!(
-        "Header fields for block #{}: hash={}, parent={}, state_root={}, extrinsics_root={}",
-        block_number,
-        hex::encode(block_hash.as_bytes()),
-        hex::encode(parent_hash.as_bytes()),
-        hex::encode(block_header.state_root.as_bytes()),
-        hex::encode(block_header.extrinsics_root.as_bytes())
-    );
-
-    // Extract timestamp from block (this is a simplified version)
-    let timestamp = Utc::now(); // TODO: Extract actual block timestamp from extrinsics
-
-    // Get runtime version and check for upgrades
-    let runtime_version = client.runtime_version();
-    let runtime_spec = runtime_version.spec_version as i64;
-    let current_spec_version = runtime_version.spec_version;
-
-    // Check if runtime has been upgraded
-    if *last_runtime_version != Some(current_spec_version) {
-        if let Some(prev_version) = *last_runtime_version {
-            info!(
-                "Runtime upgraded from v{} to v{} at block {}",
-                prev_version, current_spec_version, block_number
-            );
-
-            // Update the previous version's last_seen_block
-            let conn = pool.get().await?;
-            let metadata_repo = RuntimeMetadataRepository::new(&conn);
-            metadata_repo
-                .update_last_seen_block(prev_version as i32, block_number - 1)
-                .await?;
-        }
-
-        // Check if we need to store this new runtime version
-        let conn = pool.get().await?;
-        let metadata_repo = RuntimeMetadataRepository::new(&conn);
-
-        if !metadata_repo.exists(current_spec_version as i32).await? {
-            info!("Storing new runtime metadata for v{}", current_spec_version);
-
-            // Get the metadata bytes
-            let metadata_bytes = get_metadata_at_block(client, block_hash).await?;
-
-            let runtime_metadata = RuntimeMetadata::new(
-                current_spec_version as i32,
-                0, // impl_version not available in simplified runtime_version
-                runtime_version.transaction_version as i32,
-                0, // state_version, use 0 as default
-                block_number,
-                metadata_bytes,
-            );
-
-            metadata_repo.upsert(&runtime_metadata).await?;
-        }
-
-        *last_runtime_version = Some(current_spec_version);
-    }
-
-    // Create block record
-    let block_record = Block::new(
-        block_number,
-        block_hash.as_bytes().to_vec(),
-        parent_hash.as_bytes().to_vec(),
-        timestamp,
-        runtime_spec,
-    );
-
-    // Process events to extract balance changes (skip genesis block to avoid querying events at #0)
-    let mut all_balance_changes = Vec::new();
-    if block_number > 0 {
-        match block.events().await {
-            Ok(events) => {
-                match decoder
-                    .decode_balance_changes(events, block_number, timestamp)
-                    .await
-                {
-                    Ok(balance_changes) => {
-                        all_balance_changes.extend(balance_changes);
-                    }
-                    Err(e) => {
-                        warn!("Failed to decode events for block #{}: {}", block_number, e);
-                    }
-                }
-            }
-            Err(e) => {
-                warn!("Failed to fetch events for block #{}: {}", block_number, e);
-            }
-        }
-    } else {
-        debug!("Skipping events decoding for genesis block");
-    }
-
-    // Check for miner rewards (for PoW chains)
-    let miner_rewards = decoder
-        .decode_miner_rewards(block_hash, block_number, timestamp)
-        .await?;
-    all_balance_changes.extend(miner_rewards);
-
-    // Store block and balance changes in database within a transaction
-    let mut conn = pool.get().await?;
-    let tx = conn.transaction().await?;
-
-    // Create a transaction wrapper for cleaner API
-    let tx_wrapper = chron_db::TransactionWrapper::new(tx, Some(chain_id.to_string()));
-
-    // Use the wrapper to execute queries within the transaction
+/// Index a block, logging failures instead of stopping the indexer
+async fn index_or_warn(
+    indexer: &mut BlockIndexer,
+    block_number: i64,
+    block_hash: H256,
+    progress: &mut IndexProgress,
+) {
+    if let Err(e) = indexer
+        .index_block(block_number, block_hash, progress)
+        .await
     {
-        // Insert block
-        let schema = tx_wrapper.schema_name()?;
-        let block_sql = format!(
-            r#"
-                        INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-                        VALUES ($1, $2, $3, $4, $5, $6)
-                        ON CONFLICT (number) DO UPDATE SET
-                            hash = EXCLUDED.hash,
-                            parent_hash = EXCLUDED.parent_hash,
-                            timestamp = EXCLUDED.timestamp,
-                            is_canonical = EXCLUDED.is_canonical,
-                            runtime_spec = EXCLUDED.runtime_spec
-                        "#,
-            schema = schema
+        warn!(
+            "Failed to index block #{} ({}): {}",
+            block_number,
+            hex::encode(block_hash),
+            e
         );
-        tx_wrapper
-            .execute(
-                &block_sql,
-                &[
-                    &block_record.number,
-                    &block_record.hash,
-                    &block_record.parent_hash,
-                    &block_record.timestamp,
-                    &block_record.is_canonical,
-                    &block_record.runtime_spec,
-                ],
-            )
-            .await?;
-
-        // Insert balance changes
-        for change in &all_balance_changes {
-            let change_sql = format!(
-                r#"
-                            INSERT INTO {schema}.balance_changes
-                            (account, block_number, event_index, delta, reason, extrinsic_hash, event_pallet, event_variant, block_ts)
-                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-                            ON CONFLICT (block_number, event_index) DO NOTHING
-                            "#,
-                schema = schema
-            );
-            tx_wrapper
-                .execute(
-                    &change_sql,
-                    &[
-                        &change.account,
-                        &change.block_number,
-                        &change.event_index,
-                        &change.delta,
-                        &change.reason.as_str(),
-                        &change.extrinsic_hash,
-                        &change.event_pallet,
-                        &change.event_variant,
-                        &change.block_ts,
-                    ],
-                )
-                .await?;
-        }
-
-        // Update progress
-        progress.latest_block = block_number;
-        progress.latest_block_hash = block_hash.as_bytes().to_vec();
-        progress.latest_block_ts = timestamp;
-        progress.blocks_indexed += 1;
-        progress.balance_changes_recorded += all_balance_changes.len() as i64;
-
-        let progress_sql = format!(
-            r#"
-                        UPDATE {schema}.index_progress
-                        SET latest_block = $2,
-                            latest_block_hash = $3,
-                            latest_block_ts = $4,
-                            blocks_indexed = $5,
-                            balance_changes_recorded = $6,
-                            updated_at = $7
-                        WHERE chain_id = $1
-                        "#,
-            schema = schema
-        );
-        tx_wrapper
-            .execute(
-                &progress_sql,
-                &[
-                    &progress.chain_id,
-                    &progress.latest_block,
-                    &progress.latest_block_hash,
-                    &progress.latest_block_ts,
-                    &progress.blocks_indexed,
-                    &progress.balance_changes_recorded,
-                    &chrono::Utc::now(),
-                ],
-            )
-            .await?;
     }
-
-    // Commit the transaction
-    tx_wrapper.commit().await?;
-
-    info!(
-        "Indexed block #{} with {} balance changes",
-        block_number,
-        all_balance_changes.len()
-    );
-    Ok(())
 }
 
-/// Scan the chain from genesis to current and store all runtime versions
-async fn scan_and_store_runtime_versions(
-    client: &OnlineClient<PolkadotConfig>,
+/// Store the runtime the chain started with, unless runtimes have been recorded already.
+///
+/// Runtimes after genesis are recorded by the indexer when it reaches the block that set
+/// their code (`System::CodeUpdated`).
+async fn store_genesis_runtime(
+    rpc: &RpcHelper,
     pool: &ConnectionPool,
-    _chain_id: &str,
+    genesis_hash: H256,
 ) -> Result<usize> {
-    let mut versions_found = 0;
-
-    // Get current block height
-    let latest_block = client.blocks().at_latest().await?;
-    let latest_number = latest_block.number() as i64;
-
-    // Get connection for database operations
     let conn = pool.get().await?;
     let metadata_repo = RuntimeMetadataRepository::new(&conn);
 
-    // Check if we already have runtime versions stored
     let existing_versions = metadata_repo.get_all_versions().await?;
     if !existing_versions.is_empty() {
-        info!(
-            "Found {} existing runtime versions in database",
-            existing_versions.len()
-        );
         return Ok(existing_versions.len());
     }
 
     info!(
-        "No existing runtime versions found, scanning from genesis to block {}",
-        latest_number
+        "Fetching genesis runtime at block {}",
+        hex::encode(genesis_hash.as_ref())
     );
-
-    // Get genesis runtime
-    let genesis_hash = client.genesis_hash();
-    let genesis_metadata = get_metadata_at_block(client, genesis_hash).await?;
-    let genesis_version = client.runtime_version(); // This gets current, we'll use it as approximation
-
+    let version = rpc.get_runtime_version(&genesis_hash).await?;
+    let metadata = rpc.get_metadata_bytes(&genesis_hash).await?;
     let genesis_runtime = RuntimeMetadata::new(
-        1, // Assuming genesis starts at version 1, adjust if needed
-        0,
-        genesis_version.transaction_version as i32,
+        version.spec_version as i32,
+        version.impl_version as i32,
+        version.transaction_version as i32,
+        version.state_version as i32,
         0,
-        0, // Genesis is block 0
-        genesis_metadata,
+        metadata,
     );
-
     metadata_repo.upsert(&genesis_runtime).await?;
-    versions_found += 1;
-
-    // Get current runtime if different from genesis
-    let current_version = client.runtime_version();
-    if current_version.spec_version != 1 {
-        let current_metadata = get_current_metadata(client).await?;
-        let current_runtime = RuntimeMetadata::new(
-            current_version.spec_version as i32,
-            0,
-            current_version.transaction_version as i32,
-            0,
-            latest_number,
-            current_metadata,
-        );
-
-        metadata_repo.upsert(&current_runtime).await?;
-        versions_found += 1;
-
-        // TODO: Use binary search to find intermediate versions if there are any
-        // For now, we'll discover them as we process blocks
-    }
-
-    Ok(versions_found)
-}
-
-/// Get metadata at a specific block
-async fn get_metadata_at_block(
-    client: &OnlineClient<PolkadotConfig>,
-    block_hash: subxt::ext::sp_core::H256,
-) -> Result<Vec<u8>> {
-    // Get metadata at this block
-    use parity_scale_codec::Encode;
-
-    let hash = block_hash;
-
-    // Log exact hash used for fetching metadata
-    info!("Fetching metadata at block {}", hex::encode(hash.as_ref()));
-
-    // NOTE: No RPC here; we don't need to refetch the block to get metadata
+    info!(
+        "Genesis runtime is {} v{}",
+        version.spec_name, version.spec_version
+    );
 
-    // Get metadata from the block's runtime
-    // For now we use the client's current metadata as subxt doesn't expose historical metadata easily
-    // In production, you'd use RPC calls to get metadata at specific blocks
-    let metadata = client.metadata();
-    Ok(metadata.encode())
+    Ok(1)
 }
 
-/// Get current metadata
-async fn get_current_metadata(client: &OnlineClient<PolkadotConfig>) -> Result<Vec<u8>> {
-    use parity_scale_codec::Encode;
-
-    let metadata = client.metadata();
-    Ok(metadata.encode())
+/// The checkpoint signer configured by `LEDGER_SIGNING_KEY_FILE`, if any
+fn ledger_signer(interval: i64) -> Result<Option<LedgerSigner>> {
+    match std::env::var("LEDGER_SIGNING_KEY_FILE") {
+        Ok(path) => Ok(Some(LedgerSigner::from_seed_file(
+            Path::new(&path),
+            interval,
+        )?)),
+        Err(_) => Ok(None),
+    }
 }
 
-/// Discover all available constants in the runtime
-async fn discover_runtime_constants(client: &OnlineClient<PolkadotConfig>) -> Result<()> {
-    info!("Discovering runtime constants...");
-
-    let metadata = client.metadata();
-
-    // Iterate through all pallets
-    for pallet in metadata.pallets() {
-        let pallet_name = pallet.name();
-        let constants = pallet.constants();
-
-        if constants.len() > 0 {
-            info!(
-                "Pallet '{}' has {} constants:",
-                pallet_name,
-                constants.len()
-            );
-            for constant in constants {
-                info!("  - {}: {:?}", constant.name(), constant.ty());
-
-                // Try to decode specific interesting constants
-                if constant.name().contains("Reorg")
-                    || constant.name().contains("Depth")
-                    || constant.name().contains("Finality")
-                    || constant.name().contains("Confirmations")
-                {
-                    info!(
-                        "    Found potentially relevant constant: {}",
-                        constant.name()
-                    );
-                }
-            }
+/// Recompute a chain's ledger hashes from its balance changes and check its signed
+/// checkpoints, failing when anything does not match.
+///
+/// Checkpoints must be signed by the key in `LEDGER_PUBLIC_KEY_FILE` (hex), or by the key
+/// of `LEDGER_SIGNING_KEY_FILE` when only that is set.
+async fn verify_ledger(chain_id: &str) -> Result<()> {
+    let trusted_key = match std::env::var("LEDGER_PUBLIC_KEY_FILE") {
+        Ok(path) => {
+            let contents = std::fs::read_to_string(&path)?;
+            Some(hex::decode(contents.trim().trim_start_matches("0x"))?)
         }
+        Err(_) => ledger_signer(0)?.map(|signer| signer.public_key()),
+    };
+    if trusted_key.is_none() {
+        println!("No operator key configured; checkpoints are checked against the keys they carry");
     }
 
-    Ok(())
-}
+    let mut pool = ConnectionPool::new(&DbConfig::from_env()).await?;
+    pool.set_chain_id(chain_id.to_string());
+    let conn = pool.get().await?;
+    let report = ledger::verify_ledger(&conn, chain_id, trusted_key.as_deref()).await?;
 
-/// Query the chain for finality depth from runtime constants
-async fn query_finality_depth(client: &OnlineClient<PolkadotConfig>) -> Result<u32> {
-    // First, discover what constants are available (only in debug mode)
-    if std::env::var("RUST_LOG")
-        .unwrap_or_default()
-        .contains("debug")
-    {
-        let _ = discover_runtime_constants(client).await;
+    for number in &report.mismatched_blocks {
+        println!("Block #{} does not match its balance changes", number);
     }
-
-    // Try different possible constant locations for max reorg depth
-    // Different chains might expose this in different pallets
-
-    // Try Resonance-specific constant
-    let resonance_addr = subxt::dynamic::constant("Resonance", "MaxReorgDepth");
-    if let Ok(max_reorg_depth) = client.constants().at(&resonance_addr) {
-        if let Ok(value) = max_reorg_depth.to_value() {
-            if let Some(depth) = value.as_u128() {
-                let depth = depth as u32;
-                info!("Found MaxReorgDepth in Resonance pallet: {}", depth);
-                return Ok(depth.saturating_sub(1)); // finality at (max_reorg_depth - 1)
-            }
-        }
+    for number in &report.gaps {
+        println!(
+            "Block #{} does not chain from a hashed block #{}; the ledger has a gap",
+            number,
+            number - 1
+        );
     }
-
-    // Try PoW pallet constants
-    let pow_addr = subxt::dynamic::constant("PoW", "MaxReorgDepth");
-    if let Ok(max_reorg_depth) = client.constants().at(&pow_addr) {
-        if let Ok(value) = max_reorg_depth.to_value() {
-            if let Some(depth) = value.as_u128() {
-                let depth = depth as u32;
-                info!("Found MaxReorgDepth in PoW pallet: {}", depth);
-                return Ok(depth.saturating_sub(1));
-            }
-        }
+    if report.unanchored_changes > 0 {
+        println!(
+            "{} balance changes are not part of any indexed block",
+            report.unanchored_changes
+        );
     }
-
-    // Try Difficulty pallet (for PoW chains)
-    let difficulty_addr = subxt::dynamic::constant("Difficulty", "MaxReorgDepth");
-    if let Ok(max_reorg_depth) = client.constants().at(&difficulty_addr) {
-        if let Ok(value) = max_reorg_depth.to_value() {
-            if let Some(depth) = value.as_u128() {
-                let depth = depth as u32;
-                info!("Found MaxReorgDepth in Difficulty pallet: {}", depth);
-                return Ok(depth.saturating_sub(1));
-            }
-        }
+    for (number, failure) in &report.bad_checkpoints {
+        println!("Checkpoint at block #{}: {}", number, failure);
     }
+    println!(
+        "Checked {} blocks ({} indexed without a ledger hash) and {} checkpoints",
+        report.blocks_checked, report.unhashed_blocks, report.checkpoints_checked
+    );
 
-    // Try System pallet
-    let system_addr = subxt::dynamic::constant("System", "MaxReorgDepth");
-    if let Ok(max_reorg_depth) = client.constants().at(&system_addr) {
-        if let Ok(value) = max_reorg_depth.to_value() {
-            if let Some(depth) = value.as_u128() {
-                let depth = depth as u32;
-                info!("Found MaxReorgDepth in System pallet: {}", depth);
-                return Ok(depth.saturating_sub(1));
-            }
-        }
+    if !report.is_clean() {
+        anyhow::bail!(
+            "Ledger verification failed: {} blocks, {} unanchored changes and {} checkpoints \
+             do not match",
+            report.mismatched_blocks.len(),
+            report.unanchored_changes,
+            report.bad_checkpoints.len()
+        );
     }
+    println!("Ledger verified");
+    Ok(())
+}
 
-    // Try BABE pallet (for chains that use BABE)
-    let babe_addr = subxt::dynamic::constant("Babe", "EpochDuration");
-    if let Ok(epoch_duration) = client.constants().at(&babe_addr) {
-        if let Ok(value) = epoch_duration.to_value() {
-            if let Some(duration) = value.as_u128() {
-                // For BABE chains, use epoch duration as a proxy for finality
-                let finality_depth = ((duration as u64) / 4) as u32; // Conservative estimate
-                info!(
-                    "Using BABE epoch duration to estimate finality: {}",
-                    finality_depth
-                );
-                return Ok(finality_depth);
-            }
+/// Query the chain's SS58 address prefix.
+///
+/// Prefers the `System::SS58Prefix` constant and falls back to the `ss58Format`
+/// reported by `system_properties`.
+async fn query_ss58_prefix(client: &OnlineClient<PolkadotConfig>, rpc: &RpcHelper) -> u16 {
+    let prefix_addr = subxt::dynamic::constant("System", "SS58Prefix");
+    if let Ok(prefix) = client.constants().at(&prefix_addr) {
+        if let Some(prefix) = prefix.to_value().ok().and_then(|v| v.as_u128()) {
+            return prefix as u16;
         }
     }
 
-    // Try Grandpa pallet
-    let grandpa_addr = subxt::dynamic::constant("Grandpa", "MaxAuthorities");
-    if let Ok(_) = client.constants().at(&grandpa_addr) {
-        // If GRANDPA exists, this chain has instant finality
-        info!("Found GRANDPA pallet - using instant finality");
-        return Ok(0);
-    }
-
-    // Try to infer from block production rate
-    // Check if there's a MinimumPeriod constant (usually in Timestamp pallet)
-    let timestamp_addr = subxt::dynamic::constant("Timestamp", "MinimumPeriod");
-    if let Ok(min_period) = client.constants().at(&timestamp_addr) {
-        if let Ok(value) = min_period.to_value() {
-            if let Some(p) = value.as_u128() {
-                let period_ms = p as u64;
-                // Estimate based on block time
-                // For PoW chains, assume finality after ~30 minutes worth of blocks
-                let blocks_per_30_min = (30 * 60 * 1000) / period_ms;
-                let finality_depth = (blocks_per_30_min.min(180)) as u32; // Cap at 180
-                info!(
-                    "Estimated finality depth from block time ({}ms): {}",
-                    period_ms, finality_depth
-                );
-                return Ok(finality_depth);
+    match rpc.system_properties().await {
+        Ok(properties) => {
+            if let Some(prefix) = properties.get("ss58Format").and_then(|v| v.as_u64()) {
+                return prefix as u16;
             }
+            warn!("Chain properties do not declare an SS58 format");
         }
+        Err(e) => warn!("Failed to query system properties: {}", e),
     }
 
-    // If we still couldn't find it, try to discover all constants in debug mode
-    warn!("Could not find finality depth in known locations");
-    warn!("Set RUST_LOG=debug to see all available constants");
-    warn!("You can also set FINALITY_CONFIRMATIONS environment variable as override");
-
-    Err(anyhow::anyhow!(
-        "Could not determine finality depth from chain constants. Use FINALITY_CONFIRMATIONS env var to override."
-    ))
+    DEFAULT_SS58_PREFIX
 }
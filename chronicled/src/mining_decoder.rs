@@ -0,0 +1,213 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{BlockMining, DistanceThresholdAdjustment};
+use chrono::{DateTime, Utc};
+use parity_scale_codec::Decode;
+use subxt::{
+    config::substrate::DigestItem,
+    events::Events,
+    ext::{
+        scale_value::{Composite, Primitive, Value, ValueDef},
+        sp_core::{H256, U512},
+    },
+    Metadata, PolkadotConfig,
+};
+
+const QPOW_PALLET: &str = "QPoW";
+
+/// Consensus engine id of the QPoW pre-runtime (miner) and seal (nonce) digests
+const POW_ENGINE_ID: [u8; 4] = *b"pow_";
+
+/// QPoW activity decoded from one block
+#[derive(Debug)]
+pub struct MiningEvents {
+    pub block: BlockMining,
+    pub adjustments: Vec<DistanceThresholdAdjustment>,
+}
+
+/// Decoder for QPoW mining data: miner, nonce, difficulty and block time
+pub struct MiningDecoder;
+
+impl MiningDecoder {
+    /// Create a new mining decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract the miner, nonce, reward and difficulty adjustments of a block.
+    ///
+    /// Returns `None` on runtimes without the QPoW pallet. The miner comes from the `pow_`
+    /// pre-runtime digest, falling back to the `MiningRewards::MinerRewarded` recipient.
+    pub fn decode_block(
+        &self,
+        events: &Events<PolkadotConfig>,
+        digest_logs: &[DigestItem],
+        metadata: &Metadata,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<Option<MiningEvents>> {
+        if metadata.pallet_by_name(QPOW_PALLET).is_none() {
+            return Ok(None);
+        }
+
+        let mut block = BlockMining::new(block_number, block_timestamp);
+        block.miner = digest_miner(digest_logs);
+        let mut adjustments = Vec::new();
+        let mut rewarded = None;
+
+        for event in events.iter() {
+            let event = event?;
+            let fields = event.field_values()?;
+            match (event.pallet_name(), event.variant_name()) {
+                // ProofSubmitted { nonce }
+                (QPOW_PALLET, "ProofSubmitted") => {
+                    block.nonce = value_ext::field(&fields, "nonce").and_then(value_ext::as_bytes);
+                }
+                // DistanceThresholdAdjusted { old_distance_threshold, new_distance_threshold,
+                //                             observed_block_time }
+                (QPOW_PALLET, "DistanceThresholdAdjusted") => {
+                    let threshold = |name| value_ext::field(&fields, name).and_then(as_u512);
+                    let (Some(old), Some(new)) = (
+                        threshold("old_distance_threshold"),
+                        threshold("new_distance_threshold"),
+                    ) else {
+                        continue;
+                    };
+                    adjustments.push(DistanceThresholdAdjustment {
+                        block_number,
+                        event_index: event.index() as i32,
+                        old_distance_threshold: old.to_string(),
+                        new_distance_threshold: new.to_string(),
+                        observed_block_time: value_ext::u128_field(&fields, "observed_block_time")
+                            .unwrap_or_default()
+                            as i64,
+                        block_ts: block_timestamp,
+                    });
+                }
+                // MinerRewarded { miner, reward }
+                ("MiningRewards", "MinerRewarded") => {
+                    block.reward = value_ext::u128_field(&fields, "reward").map(|r| r.to_string());
+                    rewarded = value_ext::account_field(&fields, "miner");
+                }
+                _ => {}
+            }
+        }
+
+        if block.miner.is_none() {
+            block.miner = rewarded;
+        }
+        if block.nonce.is_none() {
+            block.nonce = digest_logs.iter().find_map(|log| match log {
+                DigestItem::Seal(id, data) if *id == POW_ENGINE_ID => Some(data.clone()),
+                _ => None,
+            });
+        }
+
+        Ok(Some(MiningEvents { block, adjustments }))
+    }
+
+    /// Read the distance threshold the block was mined against (at the parent block), and
+    /// the block duration and total work after it
+    pub async fn fetch_state(
+        &self,
+        block: &mut BlockMining,
+        rpc: &RpcHelper,
+        block_hash: &H256,
+        parent_hash: &H256,
+    ) -> Result<()> {
+        let threshold = fetch_u512(rpc, "CurrentDistanceThreshold", parent_hash).await?;
+        block.distance_threshold = threshold.map(|t| t.to_string());
+        block.difficulty = threshold.and_then(difficulty).map(|d| d.to_string());
+        block.total_work = fetch_u512(rpc, "TotalWork", block_hash)
+            .await?
+            .map(|w| w.to_string());
+
+        let key = rpc::storage_key(QPOW_PALLET, "LastBlockDuration");
+        if let Some(bytes) = rpc.get_storage(&key, block_hash).await? {
+            block.block_duration_ms = Some(u64::decode(&mut &bytes[..])? as i64);
+        }
+        Ok(())
+    }
+}
+
+impl Default for MiningDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Miner account carried by the `pow_` pre-runtime digest
+pub fn digest_miner(digest_logs: &[DigestItem]) -> Option<Vec<u8>> {
+    digest_logs.iter().find_map(|log| match log {
+        DigestItem::PreRuntime(id, data) if *id == POW_ENGINE_ID && data.len() >= 32 => {
+            Some(data[..32].to_vec())
+        }
+        _ => None,
+    })
+}
+
+/// Expected number of hashes to find a nonce below the distance threshold
+fn difficulty(threshold: U512) -> Option<U512> {
+    (!threshold.is_zero()).then(|| U512::MAX / threshold)
+}
+
+/// Read a plain `U512` storage value of the QPoW pallet
+async fn fetch_u512(rpc: &RpcHelper, item: &str, at: &H256) -> Result<Option<U512>> {
+    let key = rpc::storage_key(QPOW_PALLET, item);
+    match rpc.get_storage(&key, at).await? {
+        Some(bytes) => Ok(Some(U512::decode(&mut &bytes[..])?)),
+        None => Ok(None),
+    }
+}
+
+/// Decode a `U512` value (a newtype over `[u64; 8]`, least significant limb first)
+fn as_u512<T>(value: &Value<T>) -> Option<U512> {
+    let mut limbs = Vec::new();
+    if !collect_limbs(value, &mut limbs) {
+        return None;
+    }
+    let limbs: [u64; 8] = limbs.try_into().ok()?;
+    Some(U512(limbs))
+}
+
+fn collect_limbs<T>(value: &Value<T>, out: &mut Vec<u64>) -> bool {
+    match &value.value {
+        ValueDef::Primitive(Primitive::U128(limb)) => match u64::try_from(*limb) {
+            Ok(limb) => {
+                out.push(limb);
+                true
+            }
+            Err(_) => false,
+        },
+        ValueDef::Composite(Composite::Unnamed(vals)) => vals.iter().all(|v| collect_limbs(v, out)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_u512() {
+        let limbs = (0..8).map(|i| Value::u128(if i == 1 { 2 } else { 0 }));
+        let value = Value::unnamed_composite([Value::unnamed_composite(limbs)]);
+        assert_eq!(as_u512(&value), Some(U512::from(2u128 << 64)));
+        assert_eq!(as_u512(&Value::u128(1)), None);
+    }
+
+    #[test]
+    fn test_digest_miner_and_difficulty() {
+        let logs = vec![
+            DigestItem::Seal(POW_ENGINE_ID, vec![9; 64]),
+            DigestItem::PreRuntime(*b"aura", vec![1; 32]),
+            DigestItem::PreRuntime(POW_ENGINE_ID, vec![7; 32]),
+        ];
+        assert_eq!(digest_miner(&logs), Some(vec![7; 32]));
+        assert_eq!(digest_miner(&logs[..2]), None);
+
+        assert_eq!(difficulty(U512::zero()), None);
+        assert_eq!(difficulty(U512::MAX), Some(U512::one()));
+        assert_eq!(difficulty(U512::MAX / 1000), Some(U512::from(1000)));
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use sp_trie::{LayoutV1, StorageProof};
+use subxt::ext::sp_core::{Blake2Hasher, H256};
+
+/// Read `keys` out of a `state_getReadProof` proof, checking every trie node on the way
+/// against `state_root`. A key the proof shows to be absent reads as `None`; a proof that
+/// does not reach a key, or does not hash up to the root, is an error.
+pub fn verify_read_proof(
+    state_root: &H256,
+    proof: impl IntoIterator<Item = Vec<u8>>,
+    keys: &[&[u8]],
+) -> Result<Vec<Option<Vec<u8>>>> {
+    let db = StorageProof::new(proof).into_memory_db::<Blake2Hasher>();
+
+    keys.iter()
+        .map(|key| {
+            // V1 reads V0 tries too; the layout only changes how values are inserted
+            sp_trie::read_trie_value::<LayoutV1<Blake2Hasher>, _>(&db, state_root, key, None, None)
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "Read proof for key 0x{} does not match state root {}: {}",
+                        hex::encode(key),
+                        hex::encode(state_root),
+                        e
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_trie::{MemoryDB, TrieDBMutBuilder, TrieMut};
+
+    /// A state trie and a proof made of all of its nodes
+    fn trie(entries: &[(&[u8], Vec<u8>)]) -> (H256, Vec<Vec<u8>>) {
+        let mut db = MemoryDB::<Blake2Hasher>::default();
+        let mut root = H256::zero();
+        {
+            let mut trie =
+                TrieDBMutBuilder::<LayoutV1<Blake2Hasher>>::new(&mut db, &mut root).build();
+            for (key, value) in entries {
+                trie.insert(key, value).unwrap();
+            }
+        }
+        let nodes = db.drain().into_values().map(|(node, _)| node).collect();
+        (root, nodes)
+    }
+
+    #[test]
+    fn test_verify_read_proof() {
+        let (root, proof) = trie(&[(b"alice", vec![1; 40]), (b"bob", vec![2; 8])]);
+
+        let values = verify_read_proof(&root, proof.clone(), &[b"alice", b"bob", b"carol"]);
+        assert_eq!(
+            values.unwrap(),
+            vec![Some(vec![1; 40]), Some(vec![2; 8]), None]
+        );
+
+        // A proof for another state
+        assert!(verify_read_proof(&H256::repeat_byte(1), proof.clone(), &[b"alice"]).is_err());
+
+        // A proof with the value node swapped out
+        let (_, forged) = trie(&[(b"alice", vec![9; 40]), (b"bob", vec![2; 8])]);
+        let mixed: Vec<Vec<u8>> = forged.into_iter().filter(|n| !proof.contains(n)).collect();
+        assert!(verify_read_proof(&root, mixed, &[b"alice"]).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    DbExecutor, RecoveredAccount, Recovery, RecoveryConfig, RecoveryRepository, RecoveryStatus,
+    RecoveryVouch,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Composite, Value, ValueDef},
+        sp_core::{twox_64, H256},
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::warn;
+
+const RECOVERY_PALLET: &str = "Recovery";
+
+/// A recovery configuration created (`RecoveryCreated`) or removed (`RecoveryRemoved`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigUpdate {
+    pub account: Vec<u8>,
+    pub removed: bool,
+    pub block_number: i64,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+/// A lifecycle transition of a recovery attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryChange {
+    /// `RecoveryInitiated`
+    Initiated,
+    /// `RecoveryVouched`
+    Vouched,
+    /// `AccountRecovered`
+    Recovered,
+    /// `RecoveryClosed`
+    Closed,
+}
+
+/// A transition of one recovery attempt
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryUpdate {
+    pub lost_account: Vec<u8>,
+    pub rescuer_account: Vec<u8>,
+    pub change: RecoveryChange,
+    pub block_number: i64,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+impl RecoveryUpdate {
+    /// Record the transition on the recovery
+    pub fn apply(&self, recovery: &mut Recovery) {
+        match self.change {
+            RecoveryChange::Initiated => {}
+            RecoveryChange::Vouched => recovery.vouch_count += 1,
+            RecoveryChange::Recovered => {
+                recovery.status = RecoveryStatus::Recovered;
+                recovery.recovered_block = Some(self.block_number);
+            }
+            RecoveryChange::Closed => {
+                recovery.status = RecoveryStatus::Closed;
+                recovery.closed_block = Some(self.block_number);
+            }
+        }
+        recovery.updated_block = self.block_number;
+    }
+
+    fn new_recovery(&self) -> Recovery {
+        Recovery {
+            lost_account: self.lost_account.clone(),
+            rescuer_account: self.rescuer_account.clone(),
+            initiated_block: self.block_number,
+            initiated_extrinsic_hash: self.extrinsic_hash.clone(),
+            updated_block: self.block_number,
+            ..Default::default()
+        }
+    }
+}
+
+/// Recovery pallet activity decoded from one block
+#[derive(Debug, Default)]
+pub struct RecoveryEvents {
+    pub configs: Vec<ConfigUpdate>,
+    pub updates: Vec<RecoveryUpdate>,
+    pub vouches: Vec<RecoveryVouch>,
+    pub recovered: Vec<RecoveredAccount>,
+}
+
+impl RecoveryEvents {
+    pub fn is_empty(&self) -> bool {
+        self.configs.is_empty() && self.updates.is_empty()
+    }
+}
+
+/// Decoder for the Recovery pallet: configurations, recovery attempts and vouches
+pub struct RecoveryDecoder;
+
+impl RecoveryDecoder {
+    /// Create a new recovery decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract configuration changes, recovery transitions, vouches and lost to rescuer
+    /// links from a block's events
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<RecoveryEvents> {
+        let mut decoded = RecoveryEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != RECOVERY_PALLET {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let event_index = event.index() as i32;
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+            let config = |account: Option<Vec<u8>>, removed| {
+                account.map(|account| ConfigUpdate {
+                    account,
+                    removed,
+                    block_number,
+                    extrinsic_hash: extrinsic_hash.clone(),
+                })
+            };
+
+            let change = match event.variant_name() {
+                // RecoveryCreated { account }
+                "RecoveryCreated" => {
+                    decoded
+                        .configs
+                        .extend(config(value_ext::account_field(&fields, "account"), false));
+                    continue;
+                }
+                // RecoveryRemoved { lost_account }
+                "RecoveryRemoved" => {
+                    decoded.configs.extend(config(
+                        value_ext::account_field(&fields, "lost_account"),
+                        true,
+                    ));
+                    continue;
+                }
+                // RecoveryInitiated { lost_account, rescuer_account }
+                "RecoveryInitiated" => RecoveryChange::Initiated,
+                // RecoveryVouched { lost_account, rescuer_account, sender }
+                "RecoveryVouched" => RecoveryChange::Vouched,
+                // AccountRecovered { lost_account, rescuer_account }
+                "AccountRecovered" => RecoveryChange::Recovered,
+                // RecoveryClosed { lost_account, rescuer_account }
+                "RecoveryClosed" => RecoveryChange::Closed,
+                _ => continue,
+            };
+
+            let (Some(lost_account), Some(rescuer_account)) = (
+                value_ext::account_field(&fields, "lost_account"),
+                value_ext::account_field(&fields, "rescuer_account"),
+            ) else {
+                warn!(
+                    "Undecodable Recovery::{} at block {}",
+                    event.variant_name(),
+                    block_number
+                );
+                continue;
+            };
+
+            match change {
+                RecoveryChange::Vouched => {
+                    if let Some(voucher) = value_ext::account_field(&fields, "sender") {
+                        decoded.vouches.push(RecoveryVouch {
+                            block_number,
+                            event_index,
+                            lost_account: lost_account.clone(),
+                            rescuer_account: rescuer_account.clone(),
+                            voucher,
+                            extrinsic_hash: extrinsic_hash.clone(),
+                            block_ts: block_timestamp,
+                        });
+                    }
+                }
+                RecoveryChange::Recovered => decoded.recovered.push(RecoveredAccount {
+                    block_number,
+                    event_index,
+                    lost_account: lost_account.clone(),
+                    rescuer_account: rescuer_account.clone(),
+                    extrinsic_hash: extrinsic_hash.clone(),
+                    block_ts: block_timestamp,
+                }),
+                _ => {}
+            }
+
+            decoded.updates.push(RecoveryUpdate {
+                lost_account,
+                rescuer_account,
+                change,
+                block_number,
+                extrinsic_hash,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Build the configurations created or removed in a block.
+    ///
+    /// New configurations are read from `Recoverable` storage at the block; removed ones
+    /// come from the database or, if created before indexing began, from the parent block.
+    pub async fn resolve_configs(
+        &self,
+        configs: &[ConfigUpdate],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        parent_hash: &H256,
+    ) -> Result<Vec<RecoveryConfig>> {
+        let repo = RecoveryRepository::new(conn);
+        let mut resolved: BTreeMap<Vec<u8>, RecoveryConfig> = BTreeMap::new();
+
+        for update in configs {
+            if !update.removed {
+                let Some(mut config) =
+                    fetch_config(rpc, metadata, block_hash, &update.account).await?
+                else {
+                    warn!(
+                        "Recovery config created at block {} missing from storage",
+                        update.block_number
+                    );
+                    continue;
+                };
+                config.created_block = Some(update.block_number);
+                config.created_extrinsic_hash = update.extrinsic_hash.clone();
+                config.updated_block = update.block_number;
+                resolved.insert(update.account.clone(), config);
+                continue;
+            }
+
+            let known = match resolved.remove(&update.account) {
+                Some(config) => Some(config),
+                None => match repo.get_config(&update.account).await? {
+                    Some(config) => Some(config),
+                    None => fetch_config(rpc, metadata, parent_hash, &update.account).await?,
+                },
+            };
+            let Some(mut config) = known else {
+                warn!(
+                    "Unknown recovery config removed at block {}",
+                    update.block_number
+                );
+                continue;
+            };
+            config.removed_block = Some(update.block_number);
+            config.updated_block = update.block_number;
+            resolved.insert(update.account.clone(), config);
+        }
+
+        Ok(resolved.into_values().collect())
+    }
+
+    /// Apply recovery transitions on top of the stored records.
+    ///
+    /// Recoveries initiated before indexing began are read from `ActiveRecoveries` at the
+    /// parent block. Deposits and vouch counts of open recoveries are then refreshed from
+    /// storage at the block. `AccountRecovered` without a recovery attempt (root's
+    /// `set_recovered`) is only recorded as a lost to rescuer link.
+    pub async fn resolve_recoveries(
+        &self,
+        updates: &[RecoveryUpdate],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        parent_hash: &H256,
+    ) -> Result<Vec<Recovery>> {
+        let repo = RecoveryRepository::new(conn);
+        let mut recoveries: BTreeMap<(Vec<u8>, Vec<u8>), Recovery> = BTreeMap::new();
+
+        for update in updates {
+            let key = (update.lost_account.clone(), update.rescuer_account.clone());
+            if update.change == RecoveryChange::Initiated {
+                recoveries.insert(key, update.new_recovery());
+                continue;
+            }
+            let recovery = match recoveries.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let known = match repo
+                        .get_open_recovery(&update.lost_account, &update.rescuer_account)
+                        .await?
+                    {
+                        Some(recovery) => Some(recovery),
+                        None => fetch_recovery(rpc, metadata, parent_hash, update).await?,
+                    };
+                    let Some(known) = known else {
+                        if update.change != RecoveryChange::Recovered {
+                            warn!("Unknown recovery changed at block {}", update.block_number);
+                        }
+                        continue;
+                    };
+                    entry.insert(known)
+                }
+            };
+            update.apply(recovery);
+        }
+
+        for recovery in recoveries.values_mut() {
+            if recovery.status == RecoveryStatus::Closed {
+                continue;
+            }
+            let key = active_recovery_key(&recovery.lost_account, &recovery.rescuer_account);
+            if let Some(fields) =
+                fetch_fields(rpc, metadata, block_hash, "ActiveRecoveries", &key).await?
+            {
+                apply_active_recovery(recovery, &fields);
+            }
+        }
+
+        Ok(recoveries.into_values().collect())
+    }
+}
+
+impl Default for RecoveryDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Storage key of an `ActiveRecoveries` entry (`Twox64Concat` lost, `Twox64Concat` rescuer)
+fn active_recovery_key(lost_account: &[u8], rescuer_account: &[u8]) -> Vec<u8> {
+    let mut key = rpc::storage_twox64_map_key(RECOVERY_PALLET, "ActiveRecoveries", lost_account);
+    key.extend_from_slice(&twox_64(rescuer_account));
+    key.extend_from_slice(rescuer_account);
+    key
+}
+
+/// Read and decode a composite storage value of the Recovery pallet
+async fn fetch_fields(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    item: &str,
+    key: &[u8],
+) -> Result<Option<Composite<u32>>> {
+    if metadata.pallet_by_name(RECOVERY_PALLET).is_none() {
+        return Ok(None);
+    }
+    let Some(bytes) = rpc.get_storage(key, at).await? else {
+        return Ok(None);
+    };
+    let value = rpc::decode_storage_value(metadata, RECOVERY_PALLET, item, &bytes)?;
+    match value.value {
+        ValueDef::Composite(fields) => Ok(Some(fields)),
+        _ => Ok(None),
+    }
+}
+
+/// Read an account's configuration from `Recoverable` storage
+async fn fetch_config(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    account: &[u8],
+) -> Result<Option<RecoveryConfig>> {
+    let key = rpc::storage_twox64_map_key(RECOVERY_PALLET, "Recoverable", account);
+    let Some(fields) = fetch_fields(rpc, metadata, at, "Recoverable", &key).await? else {
+        return Ok(None);
+    };
+    Ok(Some(RecoveryConfig {
+        account: account.to_vec(),
+        friends: accounts_field(&fields, "friends"),
+        threshold: value_ext::u128_field(&fields, "threshold").unwrap_or_default() as i32,
+        delay_period: value_ext::u128_field(&fields, "delay_period").unwrap_or_default() as i64,
+        deposit: value_ext::u128_field(&fields, "deposit")
+            .unwrap_or_default()
+            .to_string(),
+        created_block: None,
+        created_extrinsic_hash: None,
+        removed_block: None,
+        updated_block: 0,
+    }))
+}
+
+/// Read a recovery attempt from `ActiveRecoveries` storage
+async fn fetch_recovery(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    update: &RecoveryUpdate,
+) -> Result<Option<Recovery>> {
+    let key = active_recovery_key(&update.lost_account, &update.rescuer_account);
+    let Some(fields) = fetch_fields(rpc, metadata, at, "ActiveRecoveries", &key).await? else {
+        return Ok(None);
+    };
+    let mut recovery = update.new_recovery();
+    recovery.initiated_extrinsic_hash = None;
+    if let Some(created) = value_ext::u128_field(&fields, "created") {
+        recovery.initiated_block = created as i64;
+    }
+    apply_active_recovery(&mut recovery, &fields);
+    Ok(Some(recovery))
+}
+
+/// Fill a recovery from its `ActiveRecovery` storage value
+fn apply_active_recovery<T>(recovery: &mut Recovery, fields: &Composite<T>) {
+    recovery.deposit = value_ext::u128_field(fields, "deposit").map(|d| d.to_string());
+    recovery.vouch_count = accounts_field(fields, "friends").len() as i32;
+}
+
+/// Decode a `BoundedVec<AccountId32>` field into account ids
+fn accounts_field<T>(fields: &Composite<T>, name: &str) -> Vec<Vec<u8>> {
+    value_ext::field(fields, name)
+        .and_then(accounts)
+        .unwrap_or_default()
+}
+
+fn accounts<T>(value: &Value<T>) -> Option<Vec<Vec<u8>>> {
+    let bytes = value_ext::as_bytes(value)?;
+    Some(bytes.chunks_exact(32).map(<[u8]>::to_vec).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accounts_field() {
+        let account =
+            |b: u8| Value::unnamed_composite((0..32).map(move |_| Value::u128(b as u128)));
+        let fields = Composite::named([
+            (
+                "friends".to_string(),
+                Value::unnamed_composite([account(1), account(2)]),
+            ),
+            ("threshold".to_string(), Value::u128(2)),
+        ]);
+        assert_eq!(
+            accounts_field(&fields, "friends"),
+            vec![vec![1; 32], vec![2; 32]]
+        );
+        assert!(accounts_field(&fields, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_recovery_lifecycle() {
+        let update = |change, block_number| RecoveryUpdate {
+            lost_account: vec![1; 32],
+            rescuer_account: vec![2; 32],
+            change,
+            block_number,
+            extrinsic_hash: None,
+        };
+        let initiated = update(RecoveryChange::Initiated, 10);
+        let mut recovery = initiated.new_recovery();
+        assert_eq!(recovery.status, RecoveryStatus::Active);
+        assert_eq!(recovery.initiated_block, 10);
+
+        update(RecoveryChange::Vouched, 11).apply(&mut recovery);
+        update(RecoveryChange::Vouched, 12).apply(&mut recovery);
+        assert_eq!(recovery.vouch_count, 2);
+
+        update(RecoveryChange::Recovered, 20).apply(&mut recovery);
+        assert_eq!(recovery.status, RecoveryStatus::Recovered);
+        assert_eq!(recovery.recovered_block, Some(20));
+
+        update(RecoveryChange::Closed, 25).apply(&mut recovery);
+        assert_eq!(recovery.status, RecoveryStatus::Closed);
+        assert_eq!(recovery.closed_block, Some(25));
+        assert_eq!(recovery.recovered_block, Some(20));
+        assert_eq!(recovery.updated_block, 25);
+    }
+}
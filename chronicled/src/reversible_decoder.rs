@@ -0,0 +1,540 @@
+use crate::{extrinsic_decoder, rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    BalanceChange, BalanceChangeReason, DbExecutor, HighSecurityAccount, PendingTransfer,
+    PendingTransferStatus, ReversibleTransferRepository,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// ReversibleTransfers activity found in a block's events
+#[derive(Debug, Default)]
+pub struct ReversibleTransferEvents {
+    /// Transfers scheduled in the block (`TransactionScheduled`)
+    pub scheduled: Vec<PendingTransfer>,
+    /// Cancellations and executions, in event order
+    pub resolutions: Vec<TransferResolution>,
+    /// Accounts that enabled high security (`HighSecuritySet`)
+    pub high_security: Vec<HighSecurityAccount>,
+}
+
+impl ReversibleTransferEvents {
+    /// Whether the block touched the pallet at all
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty() && self.resolutions.is_empty() && self.high_security.is_empty()
+    }
+}
+
+/// A pending transfer leaving the pending state
+#[derive(Debug, Clone)]
+pub struct TransferResolution {
+    pub tx_id: Vec<u8>,
+    pub status: PendingTransferStatus,
+    pub block_number: i64,
+    pub event_index: i32,
+    pub extrinsic_hash: Option<Vec<u8>>,
+    pub cancelled_by: Option<Vec<u8>>,
+    pub dispatch_error: Option<String>,
+}
+
+impl TransferResolution {
+    /// Record the transition on the transfer
+    pub fn apply(&self, transfer: &mut PendingTransfer) {
+        transfer.status = self.status;
+        transfer.resolved_block = Some(self.block_number);
+        transfer.resolved_event_index = Some(self.event_index);
+        transfer.resolved_extrinsic_hash = self.extrinsic_hash.clone();
+        transfer.cancelled_by = self.cancelled_by.clone();
+        transfer.dispatch_error = self.dispatch_error.clone();
+    }
+}
+
+/// Decoder for the ReversibleTransfers pallet (delayed, cancellable transfers)
+pub struct ReversibleTransferDecoder;
+
+impl ReversibleTransferDecoder {
+    /// Create a new reversible transfer decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract scheduled, cancelled and executed transfers and high-security settings
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        metadata: &Metadata,
+        block_number: i64,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<ReversibleTransferEvents> {
+        let mut decoded = ReversibleTransferEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != "ReversibleTransfers" {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let event_index = event.index() as i32;
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+            let account = |name| value_ext::account_field(&fields, name);
+            let tx_id = || value_ext::field(&fields, "tx_id").and_then(value_ext::as_bytes);
+
+            match event.variant_name() {
+                // TransactionScheduled { from, to, interceptor, amount, tx_id, execute_at }
+                "TransactionScheduled" => {
+                    let execute_at = value_ext::field(&fields, "execute_at");
+                    let (
+                        Some(tx_id),
+                        Some(from_account),
+                        Some(to_account),
+                        Some(interceptor),
+                        Some(amount),
+                    ) = (
+                        tx_id(),
+                        account("from"),
+                        account("to"),
+                        account("interceptor"),
+                        value_ext::u128_field(&fields, "amount"),
+                    )
+                    else {
+                        warn!("Undecodable TransactionScheduled at block {}", block_number);
+                        continue;
+                    };
+
+                    debug!(
+                        "Transfer {} scheduled at block {}",
+                        hex::encode(&tx_id),
+                        block_number
+                    );
+                    decoded.scheduled.push(PendingTransfer {
+                        tx_id,
+                        from_account,
+                        to_account,
+                        interceptor,
+                        amount: amount.to_string(),
+                        status: PendingTransferStatus::Pending,
+                        execute_at: execute_at.map(value_ext::to_json).unwrap_or_default(),
+                        execute_at_block: execute_at.and_then(dispatch_block),
+                        scheduled_block: Some(block_number),
+                        scheduled_extrinsic_hash: extrinsic_hash,
+                        resolved_block: None,
+                        resolved_event_index: None,
+                        resolved_extrinsic_hash: None,
+                        cancelled_by: None,
+                        dispatch_error: None,
+                    });
+                }
+                // TransactionCancelled { who, tx_id }
+                "TransactionCancelled" => {
+                    let Some(tx_id) = tx_id() else { continue };
+                    decoded.resolutions.push(TransferResolution {
+                        tx_id,
+                        status: PendingTransferStatus::Cancelled,
+                        block_number,
+                        event_index,
+                        extrinsic_hash,
+                        cancelled_by: account("who"),
+                        dispatch_error: None,
+                    });
+                }
+                // TransactionExecuted { tx_id, result }
+                "TransactionExecuted" => {
+                    let Some(tx_id) = tx_id() else { continue };
+                    let dispatch_error = value_ext::field(&fields, "result")
+                        .and_then(|result| execution_error(result, metadata));
+                    decoded.resolutions.push(TransferResolution {
+                        tx_id,
+                        status: if dispatch_error.is_some() {
+                            PendingTransferStatus::Failed
+                        } else {
+                            PendingTransferStatus::Executed
+                        },
+                        block_number,
+                        event_index,
+                        extrinsic_hash,
+                        cancelled_by: None,
+                        dispatch_error,
+                    });
+                }
+                // HighSecuritySet { who, interceptor, recoverer, delay }
+                "HighSecuritySet" => {
+                    let (Some(who), Some(interceptor), Some(recoverer)) =
+                        (account("who"), account("interceptor"), account("recoverer"))
+                    else {
+                        continue;
+                    };
+                    decoded.high_security.push(HighSecurityAccount {
+                        account: who,
+                        interceptor,
+                        recoverer,
+                        delay: value_ext::field(&fields, "delay")
+                            .map(value_ext::to_json)
+                            .unwrap_or_default(),
+                        set_block: block_number,
+                        extrinsic_hash,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Build the records to store for a block: newly scheduled transfers plus every
+    /// transfer cancelled or executed in it.
+    ///
+    /// Resolved transfers are looked up among the block's own scheduled transfers, then
+    /// in the database, then in `PendingTransfers` storage at the parent block (for
+    /// transfers scheduled before indexing began).
+    pub async fn resolve_transfers(
+        &self,
+        events: &ReversibleTransferEvents,
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        parent_hash: &H256,
+    ) -> Result<Vec<PendingTransfer>> {
+        let repo = ReversibleTransferRepository::new(conn);
+        let mut transfers: BTreeMap<Vec<u8>, PendingTransfer> = events
+            .scheduled
+            .iter()
+            .map(|t| (t.tx_id.clone(), t.clone()))
+            .collect();
+
+        for resolution in &events.resolutions {
+            let transfer = match transfers.entry(resolution.tx_id.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let known = match repo.get(&resolution.tx_id).await? {
+                        Some(transfer) => Some(transfer),
+                        None => {
+                            fetch_pending_transfer(rpc, metadata, parent_hash, &resolution.tx_id)
+                                .await?
+                        }
+                    };
+                    let Some(transfer) = known else {
+                        warn!(
+                            "Unknown reversible transfer {} resolved at block {}",
+                            hex::encode(&resolution.tx_id),
+                            resolution.block_number
+                        );
+                        continue;
+                    };
+                    entry.insert(transfer)
+                }
+            };
+            resolution.apply(transfer);
+        }
+
+        Ok(transfers.into_values().collect())
+    }
+
+    /// Balance changes for transfers executed in this block.
+    ///
+    /// Funds only move when a transfer executes. When the runtime already reported the
+    /// movement through a `Balances::Transfer` emitted by the execution itself, that event is
+    /// the record and nothing is added here. The execution's transfer is the one between the
+    /// previous resolution in the block and `TransactionExecuted`, in the same extrinsic,
+    /// moving the same amount from the same sender to the same recipient.
+    pub fn execution_balance_changes(
+        &self,
+        transfers: &[PendingTransfer],
+        block_changes: &[BalanceChange],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Vec<BalanceChange> {
+        let mut changes = Vec::new();
+
+        for transfer in transfers {
+            if transfer.status != PendingTransferStatus::Executed
+                || transfer.resolved_block != Some(block_number)
+            {
+                continue;
+            }
+            let event_index = transfer.resolved_event_index.unwrap_or_default();
+            let previous_resolution = transfers
+                .iter()
+                .filter(|t| t.resolved_block == Some(block_number))
+                .filter_map(|t| t.resolved_event_index)
+                .filter(|i| *i < event_index)
+                .max()
+                .unwrap_or(-1);
+            let withdrawal = format!("-{}", transfer.amount);
+            let reports = |account: &Vec<u8>, delta: &str, index: i32| {
+                block_changes.iter().any(|c| {
+                    c.is_native()
+                        && c.event_pallet == "Balances"
+                        && c.event_variant == "Transfer"
+                        && c.event_index == index
+                        && c.extrinsic_hash == transfer.resolved_extrinsic_hash
+                        && c.account == *account
+                        && c.delta == delta
+                })
+            };
+            let already_recorded = block_changes.iter().any(|c| {
+                c.event_index > previous_resolution
+                    && c.event_index < event_index
+                    && reports(&transfer.to_account, &transfer.amount, c.event_index)
+                    && reports(&transfer.from_account, &withdrawal, c.event_index)
+            });
+            if already_recorded {
+                continue;
+            }
+
+            for (account, delta) in [
+                (&transfer.from_account, format!("-{}", transfer.amount)),
+                (&transfer.to_account, transfer.amount.clone()),
+            ] {
+                changes.push(BalanceChange::new(
+                    account.clone(),
+                    block_number,
+                    event_index,
+                    delta,
+                    BalanceChangeReason::Transfer,
+                    transfer.resolved_extrinsic_hash.clone(),
+                    "ReversibleTransfers".to_string(),
+                    "TransactionExecuted".to_string(),
+                    block_timestamp,
+                ));
+            }
+        }
+
+        changes
+    }
+}
+
+impl Default for ReversibleTransferDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a transfer from `ReversibleTransfers::PendingTransfers` storage
+async fn fetch_pending_transfer(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    tx_id: &[u8],
+) -> Result<Option<PendingTransfer>> {
+    let key = rpc::storage_map_key("ReversibleTransfers", "PendingTransfers", tx_id);
+    let Some(bytes) = rpc.get_storage(&key, at).await? else {
+        return Ok(None);
+    };
+    let value =
+        rpc::decode_storage_value(metadata, "ReversibleTransfers", "PendingTransfers", &bytes)?;
+    let ValueDef::Composite(fields) = &value.value else {
+        return Ok(None);
+    };
+
+    let account = |name| value_ext::account_field(fields, name);
+    let (Some(from_account), Some(to_account), Some(interceptor), Some(amount)) = (
+        account("from"),
+        account("to"),
+        account("interceptor"),
+        value_ext::u128_field(fields, "amount"),
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some(PendingTransfer {
+        tx_id: tx_id.to_vec(),
+        from_account,
+        to_account,
+        interceptor,
+        amount: amount.to_string(),
+        status: PendingTransferStatus::Pending,
+        execute_at: serde_json::Value::Null,
+        execute_at_block: None,
+        scheduled_block: None,
+        scheduled_extrinsic_hash: None,
+        resolved_block: None,
+        resolved_event_index: None,
+        resolved_extrinsic_hash: None,
+        cancelled_by: None,
+        dispatch_error: None,
+    }))
+}
+
+/// Block number of a `DispatchTime::At(block)`
+fn dispatch_block<T>(value: &Value<T>) -> Option<i64> {
+    let ValueDef::Variant(variant) = &value.value else {
+        return None;
+    };
+    if variant.name != "At" {
+        return None;
+    }
+    variant
+        .values
+        .values()
+        .next()
+        .and_then(value_ext::as_u128)
+        .map(|b| b as i64)
+}
+
+/// Error of a failed `DispatchResultWithPostInfo`, None if it succeeded
+fn execution_error<T>(result: &Value<T>, metadata: &Metadata) -> Option<String> {
+    let ValueDef::Variant(variant) = &result.value else {
+        return None;
+    };
+    if variant.name != "Err" {
+        return None;
+    }
+
+    // Err(DispatchErrorWithPostInfo { post_info, error })
+    let error = variant
+        .values
+        .values()
+        .next()
+        .and_then(|inner| match &inner.value {
+            ValueDef::Composite(fields) => value_ext::field(fields, "error"),
+            _ => None,
+        });
+    Some(match error {
+        Some(error) => extrinsic_decoder::describe_dispatch_error(error, metadata),
+        None => value_ext::to_json(result).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executed(amount: &str) -> PendingTransfer {
+        PendingTransfer {
+            tx_id: vec![7; 32],
+            from_account: vec![1; 32],
+            to_account: vec![2; 32],
+            interceptor: vec![3; 32],
+            amount: amount.to_string(),
+            status: PendingTransferStatus::Executed,
+            execute_at: serde_json::Value::Null,
+            execute_at_block: Some(20),
+            scheduled_block: Some(10),
+            scheduled_extrinsic_hash: None,
+            resolved_block: Some(20),
+            resolved_event_index: Some(4),
+            resolved_extrinsic_hash: None,
+            cancelled_by: None,
+            dispatch_error: None,
+        }
+    }
+
+    /// Both sides of a `Balances::Transfer` as the balance decoder records them
+    fn balances_transfer(
+        event_index: i32,
+        from: Vec<u8>,
+        to: Vec<u8>,
+        amount: &str,
+    ) -> Vec<BalanceChange> {
+        [(from, format!("-{}", amount)), (to, amount.to_string())]
+            .into_iter()
+            .map(|(account, delta)| {
+                BalanceChange::new(
+                    account,
+                    20,
+                    event_index,
+                    delta,
+                    BalanceChangeReason::Transfer,
+                    None,
+                    "Balances".into(),
+                    "Transfer".into(),
+                    Utc::now(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_execution_balance_changes() {
+        let decoder = ReversibleTransferDecoder::new();
+        let transfers = [executed("500")];
+
+        let changes = decoder.execution_balance_changes(&transfers, &[], 20, Utc::now());
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].delta, "-500");
+        assert_eq!(changes[1].account, vec![2; 32]);
+        assert_eq!(changes[1].event_index, 4);
+
+        // Already reported by the Balances pallet
+        let balances = balances_transfer(3, vec![1; 32], vec![2; 32], "500");
+        assert!(decoder
+            .execution_balance_changes(&transfers, &balances, 20, Utc::now())
+            .is_empty());
+
+        // Resolved in another block
+        assert!(decoder
+            .execution_balance_changes(&transfers, &[], 21, Utc::now())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_execution_ignores_other_transfers_of_the_same_amount() {
+        let decoder = ReversibleTransferDecoder::new();
+        let transfers = [executed("500")];
+
+        // Another sender paying the recipient the same amount
+        let other_sender = balances_transfer(3, vec![9; 32], vec![2; 32], "500");
+        assert_eq!(
+            decoder
+                .execution_balance_changes(&transfers, &other_sender, 20, Utc::now())
+                .len(),
+            2
+        );
+
+        // The same transfer made directly, after the execution
+        let later = balances_transfer(5, vec![1; 32], vec![2; 32], "500");
+        assert_eq!(
+            decoder
+                .execution_balance_changes(&transfers, &later, 20, Utc::now())
+                .len(),
+            2
+        );
+
+        // The same transfer made directly, before another transfer's resolution
+        let mut cancelled = executed("500");
+        cancelled.tx_id = vec![8; 32];
+        cancelled.status = PendingTransferStatus::Cancelled;
+        cancelled.resolved_event_index = Some(2);
+        let earlier = balances_transfer(1, vec![1; 32], vec![2; 32], "500");
+        assert_eq!(
+            decoder
+                .execution_balance_changes(&[cancelled, executed("500")], &earlier, 20, Utc::now())
+                .len(),
+            2
+        );
+
+        // The same transfer made by a signed extrinsic, while the execution was scheduled
+        let mut signed = balances_transfer(3, vec![1; 32], vec![2; 32], "500");
+        for change in &mut signed {
+            change.extrinsic_hash = Some(vec![5; 32]);
+        }
+        assert_eq!(
+            decoder
+                .execution_balance_changes(&transfers, &signed, 20, Utc::now())
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_dispatch_block() {
+        let at = Value::unnamed_variant("At", [Value::u128(42)]);
+        let after = Value::unnamed_variant("After", [Value::u128(42)]);
+        assert_eq!(dispatch_block(&at), Some(42));
+        assert_eq!(dispatch_block(&after), None);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{block_verifier, read_proof};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Mutex};
+use subxt::ext::{
+    scale_value::{scale::decode_as_type, Value},
+    sp_core::{blake2_128, blake2_256, twox_128, twox_64, H256},
+};
+use subxt::{
+    backend::rpc::RpcClient,
+    config::substrate::{Digest, DigestItem},
+    Metadata, PolkadotConfig,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHeader {
+    pub parent_hash: H256,
+    pub number: String,
+    pub state_root: H256,
+    pub extrinsics_root: H256,
+    #[serde(default)]
+    pub digest: serde_json::Value,
+}
+
+/// Runtime version reported by `state_getRuntimeVersion`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcRuntimeVersion {
+    pub spec_name: String,
+    pub spec_version: u32,
+    pub impl_version: u32,
+    pub transaction_version: u32,
+    #[serde(default)]
+    pub state_version: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RpcBlockData {
+    pub header: RpcHeader,
+    pub extrinsics: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RpcBlock {
+    pub block: RpcBlockData,
+    pub justifications: Option<serde_json::Value>,
+}
+
+impl RpcHeader {
+    /// Decode the header's digest logs (`{ "logs": ["0x..."] }`)
+    pub fn digest_logs(&self) -> Vec<DigestItem> {
+        serde_json::from_value::<Digest>(self.digest.clone())
+            .map(|digest| digest.logs)
+            .unwrap_or_default()
+    }
+}
+
+impl RpcBlockData {
+    /// Decode the hex-encoded extrinsics into raw bytes (each including its length prefix)
+    pub fn extrinsic_bytes(&self) -> anyhow::Result<Vec<Vec<u8>>> {
+        self.extrinsics
+            .iter()
+            .map(|ext| Ok(hex::decode(ext.strip_prefix("0x").unwrap_or(ext))?))
+            .collect()
+    }
+}
+
+pub struct RpcHelper {
+    client: RpcClient,
+    /// State roots of verified headers, present when storage reads are proven
+    state_roots: Option<Mutex<HashMap<H256, H256>>>,
+}
+
+/// State roots kept for proving reads; reads cluster around the block being indexed
+/// and its parent
+const STATE_ROOT_CACHE_SIZE: usize = 64;
+
+impl RpcHelper {
+    pub fn new(client: RpcClient) -> Self {
+        Self {
+            client,
+            state_roots: None,
+        }
+    }
+
+    /// Prove every storage read with `state_getReadProof` against the state root of a
+    /// header that hashes to the block hash, instead of trusting the node's answer
+    pub fn with_read_proofs(mut self) -> Self {
+        self.state_roots = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    /// Whether storage reads are proven against state roots
+    pub fn proves_reads(&self) -> bool {
+        self.state_roots.is_some()
+    }
+
+    pub async fn get_block_hash_by_number(&self, number: u64) -> anyhow::Result<H256> {
+        use subxt::backend::legacy::rpc_methods::NumberOrHex;
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let block_number = NumberOrHex::Number(number);
+        let hash = legacy_rpc
+            .chain_get_block_hash(Some(block_number))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No block hash found for block #{}", number))?;
+        Ok(hash)
+    }
+
+    pub async fn get_latest_block_hash(&self) -> anyhow::Result<H256> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let hash = legacy_rpc
+            .chain_get_block_hash(None)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No latest block hash found"))?;
+        Ok(hash)
+    }
+
+    pub async fn get_block_by_hash(&self, hash: &H256) -> anyhow::Result<RpcBlock> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let signed_block = legacy_rpc
+            .chain_get_block(Some(*hash))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No block found for hash"))?;
+
+        // Convert from legacy SignedBlock to our RpcBlock structure
+        let block = RpcBlock {
+            block: RpcBlockData {
+                header: RpcHeader {
+                    parent_hash: signed_block.block.header.parent_hash,
+                    number: format!("{:#x}", signed_block.block.header.number),
+                    state_root: signed_block.block.header.state_root,
+                    extrinsics_root: signed_block.block.header.extrinsics_root,
+                    digest: serde_json::to_value(&signed_block.block.header.digest)?,
+                },
+                extrinsics: signed_block
+                    .block
+                    .extrinsics
+                    .into_iter()
+                    .map(|ext| format!("0x{}", hex::encode(ext.0)))
+                    .collect(),
+            },
+            justifications: None,
+        };
+        Ok(block)
+    }
+
+    pub async fn get_header_by_hash(&self, hash: &H256) -> anyhow::Result<RpcHeader> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let header = legacy_rpc
+            .chain_get_header(Some(*hash))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No header found for hash"))?;
+
+        Ok(RpcHeader {
+            parent_hash: header.parent_hash,
+            number: format!("{:#x}", header.number),
+            state_root: header.state_root,
+            extrinsics_root: header.extrinsics_root,
+            digest: serde_json::to_value(&header.digest)?,
+        })
+    }
+
+    /// Fetch a raw storage value at a block
+    pub async fn get_storage(&self, key: &[u8], hash: &H256) -> anyhow::Result<Option<Vec<u8>>> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        if self.proves_reads() {
+            let mut values = self.get_proven_storage(&[key], hash).await?;
+            return Ok(values.remove(0));
+        }
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.state_get_storage(key, Some(*hash)).await?)
+    }
+
+    /// Fetch the Blake2-256 hash of a storage value at a block without downloading it
+    pub async fn get_storage_hash(&self, key: &[u8], hash: &H256) -> anyhow::Result<Option<H256>> {
+        use subxt::backend::rpc::rpc_params;
+
+        // A hash reported by the node cannot be proven; prove the value and hash it here
+        if self.proves_reads() {
+            let value = self.get_storage(key, hash).await?;
+            return Ok(value.map(|value| H256(blake2_256(&value))));
+        }
+
+        let key = format!("0x{}", hex::encode(key));
+        Ok(self
+            .client
+            .request("state_getStorageHash", rpc_params![key, hash])
+            .await?)
+    }
+
+    /// Fetch the runtime version in effect after a block
+    pub async fn get_runtime_version(&self, hash: &H256) -> anyhow::Result<RpcRuntimeVersion> {
+        use subxt::backend::rpc::rpc_params;
+
+        Ok(self
+            .client
+            .request("state_getRuntimeVersion", rpc_params![hash])
+            .await?)
+    }
+
+    /// Fetch the SCALE-encoded metadata of the runtime in effect after a block
+    pub async fn get_metadata_bytes(&self, hash: &H256) -> anyhow::Result<Vec<u8>> {
+        use parity_scale_codec::Encode;
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.state_get_metadata(Some(*hash)).await?.encode())
+    }
+
+    /// Fetch every storage key starting with `prefix` at a block, paging through the results
+    pub async fn get_keys(&self, prefix: &[u8], hash: &H256) -> anyhow::Result<Vec<Vec<u8>>> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        const PAGE_SIZE: u32 = 1000;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let mut keys: Vec<Vec<u8>> = Vec::new();
+        loop {
+            let page = legacy_rpc
+                .state_get_keys_paged(
+                    prefix,
+                    PAGE_SIZE,
+                    keys.last().map(|k| k.as_slice()),
+                    Some(*hash),
+                )
+                .await?;
+            let done = page.len() < PAGE_SIZE as usize;
+            // Each listed key is proven to exist; a read proof cannot show none were left out
+            if self.proves_reads() {
+                let page_keys: Vec<&[u8]> = page.iter().map(Vec::as_slice).collect();
+                let values = self.get_proven_storage(&page_keys, hash).await?;
+                if let Some(i) = values.iter().position(Option::is_none) {
+                    return Err(anyhow::anyhow!(
+                        "Node listed storage key 0x{} that is not in the state at {}",
+                        hex::encode(&page[i]),
+                        hex::encode(hash)
+                    ));
+                }
+            }
+            keys.extend(page);
+            if done {
+                return Ok(keys);
+            }
+        }
+    }
+
+    /// Fetch the chain's `system_properties` (token symbol, decimals, SS58 format)
+    pub async fn system_properties(
+        &self,
+    ) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.system_properties().await?)
+    }
+
+    /// Fetch the chain name reported by `system_chain`
+    pub async fn system_chain(&self) -> anyhow::Result<String> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.system_chain().await?)
+    }
+
+    /// Fetch the node implementation name reported by `system_name`
+    pub async fn system_name(&self) -> anyhow::Result<String> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.system_name().await?)
+    }
+
+    /// Fetch the node implementation version reported by `system_version`
+    pub async fn system_version(&self) -> anyhow::Result<String> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        Ok(legacy_rpc.system_version().await?)
+    }
+
+    /// Fetch storage values with a read proof and check them against the block's state root
+    pub async fn get_proven_storage(
+        &self,
+        keys: &[&[u8]],
+        hash: &H256,
+    ) -> anyhow::Result<Vec<Option<Vec<u8>>>> {
+        use subxt::backend::legacy::LegacyRpcMethods;
+
+        let state_root = self.verified_state_root(hash).await?;
+        let legacy_rpc = LegacyRpcMethods::<PolkadotConfig>::new(self.client.clone());
+        let proof = legacy_rpc
+            .state_get_read_proof(keys.iter().copied(), Some(*hash))
+            .await?;
+        read_proof::verify_read_proof(&state_root, proof.proof.into_iter().map(|n| n.0), keys)
+    }
+
+    /// State root of a block, taken from a header that hashes to the block hash
+    async fn verified_state_root(&self, hash: &H256) -> anyhow::Result<H256> {
+        let cached = self
+            .state_roots
+            .as_ref()
+            .and_then(|roots| roots.lock().ok()?.get(hash).copied());
+        if let Some(root) = cached {
+            return Ok(root);
+        }
+
+        let header = self.get_header_by_hash(hash).await?;
+        block_verifier::verify_header(&header, hash).map_err(|failure| {
+            anyhow::anyhow!("Header of block {}: {}", hex::encode(hash), failure)
+        })?;
+        if let Some(Ok(mut roots)) = self.state_roots.as_ref().map(Mutex::lock) {
+            if roots.len() >= STATE_ROOT_CACHE_SIZE {
+                roots.clear();
+            }
+            roots.insert(*hash, header.state_root);
+        }
+        Ok(header.state_root)
+    }
+
+    /// Fetch the SCALE-encoded `System::Events` storage value at a block
+    pub async fn get_events_bytes(&self, hash: &H256) -> anyhow::Result<Vec<u8>> {
+        let key = storage_key("System", "Events");
+        Ok(self.get_storage(&key, hash).await?.unwrap_or_default())
+    }
+}
+
+pub fn hex_to_h256(s: &str) -> anyhow::Result<H256> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(s)?;
+    if bytes.len() != 32 {
+        return Err(anyhow::anyhow!(
+            "expected 32 bytes for H256, got {}",
+            bytes.len()
+        ));
+    }
+    let mut arr = [0u8; 32];
+    arr.copy_from_slice(&bytes);
+    Ok(H256::from(arr))
+}
+
+/// Storage key for a plain (non-map) storage item
+pub fn storage_key(pallet: &str, item: &str) -> Vec<u8> {
+    let mut key = twox_128(pallet.as_bytes()).to_vec();
+    key.extend_from_slice(&twox_128(item.as_bytes()));
+    key
+}
+
+/// Storage key for a map entry hashed with `Blake2_128Concat`
+pub fn storage_map_key(pallet: &str, item: &str, key: &[u8]) -> Vec<u8> {
+    let mut storage_key = storage_key(pallet, item);
+    storage_key.extend_from_slice(&blake2_128(key));
+    storage_key.extend_from_slice(key);
+    storage_key
+}
+
+/// Storage key for a map entry hashed with `Twox64Concat`
+pub fn storage_twox64_map_key(pallet: &str, item: &str, key: &[u8]) -> Vec<u8> {
+    let mut storage_key = storage_key(pallet, item);
+    storage_key.extend_from_slice(&twox_64(key));
+    storage_key.extend_from_slice(key);
+    storage_key
+}
+
+/// Decode a raw storage value with the type the runtime declares for it
+pub fn decode_storage_value(
+    metadata: &Metadata,
+    pallet: &str,
+    item: &str,
+    bytes: &[u8],
+) -> anyhow::Result<Value<u32>> {
+    let entry = metadata
+        .pallet_by_name(pallet)
+        .and_then(|p| p.storage())
+        .and_then(|s| s.entry_by_name(item))
+        .ok_or_else(|| anyhow::anyhow!("runtime has no {}::{} storage", pallet, item))?;
+
+    Ok(decode_as_type(
+        &mut &bytes[..],
+        entry.entry_type().value_ty(),
+        metadata.types(),
+    )?)
+}
@@ -0,0 +1,716 @@
+use crate::{extrinsic_decoder, rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    DbExecutor, Preimage, PreimageStatus, ScheduledTask, ScheduledTaskStatus, SchedulerEvent,
+    SchedulerRepository,
+};
+use chrono::{DateTime, Utc};
+use parity_scale_codec::{Decode, Encode};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Composite, Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+const SCHEDULER_PALLET: &str = "Scheduler";
+const PREIMAGE_PALLET: &str = "Preimage";
+
+/// Agenda slot of a task: its `BlockNumberOrTimestamp` and index within the agenda
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskAddress {
+    /// `block` or `timestamp`
+    pub kind: &'static str,
+    /// Block number, or timestamp in milliseconds
+    pub at: i64,
+    pub index: i32,
+}
+
+impl TaskAddress {
+    /// SCALE encoding of the agenda's `BlockNumberOrTimestamp`, as used in `Agenda` keys
+    fn encoded_when(&self) -> Vec<u8> {
+        match self.kind {
+            "block" => (0u8, self.at as u32).encode(),
+            _ => (1u8, self.at as u64).encode(),
+        }
+    }
+}
+
+/// A lifecycle transition of a scheduled task
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskChange {
+    /// `Scheduled`
+    Scheduled,
+    /// `Dispatched`, with the dispatch error if the call failed
+    Dispatched(Option<String>),
+    /// `Canceled`
+    Canceled,
+    /// `CallUnavailable`
+    CallUnavailable,
+    /// `PermanentlyOverweight`
+    Overweight,
+}
+
+/// A transition of one scheduled task
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskUpdate {
+    pub address: TaskAddress,
+    pub change: TaskChange,
+    pub block_number: i64,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+impl TaskUpdate {
+    /// Record the transition on the task
+    pub fn apply(&self, task: &mut ScheduledTask) {
+        let status = match &self.change {
+            TaskChange::Scheduled => ScheduledTaskStatus::Scheduled,
+            TaskChange::Dispatched(None) => ScheduledTaskStatus::Dispatched,
+            TaskChange::Dispatched(Some(error)) => {
+                task.dispatch_error = Some(error.clone());
+                ScheduledTaskStatus::Failed
+            }
+            TaskChange::Canceled => ScheduledTaskStatus::Canceled,
+            TaskChange::CallUnavailable => ScheduledTaskStatus::CallUnavailable,
+            TaskChange::Overweight => ScheduledTaskStatus::Overweight,
+        };
+        if status != ScheduledTaskStatus::Scheduled {
+            task.closed_block = Some(self.block_number);
+        }
+        task.status = status;
+        task.updated_block = self.block_number;
+    }
+}
+
+/// A preimage transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreimageChange {
+    /// `Noted`
+    Noted,
+    /// `Requested`
+    Requested,
+    /// `Cleared`
+    Cleared,
+}
+
+/// A transition of one preimage
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreimageUpdate {
+    pub hash: Vec<u8>,
+    pub change: PreimageChange,
+    pub block_number: i64,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+/// Scheduler and Preimage activity decoded from one block
+#[derive(Debug, Default)]
+pub struct SchedulerEvents {
+    pub events: Vec<SchedulerEvent>,
+    pub tasks: Vec<TaskUpdate>,
+    pub preimages: Vec<PreimageUpdate>,
+}
+
+impl SchedulerEvents {
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && self.preimages.is_empty()
+    }
+}
+
+/// Decoder for the Scheduler and Preimage pallets: scheduled tasks, their dispatch results
+/// and the preimages their calls resolve to
+pub struct SchedulerDecoder;
+
+impl SchedulerDecoder {
+    /// Create a new scheduler decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract task transitions, the scheduler event log and preimage changes from a
+    /// block's events
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        metadata: &Metadata,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<SchedulerEvents> {
+        let mut decoded = SchedulerEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            let pallet = event.pallet_name();
+            if pallet != SCHEDULER_PALLET && pallet != PREIMAGE_PALLET {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let variant = event.variant_name();
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+
+            if pallet == PREIMAGE_PALLET {
+                // Noted { hash } / Requested { hash } / Cleared { hash }
+                let change = match variant {
+                    "Noted" => PreimageChange::Noted,
+                    "Requested" => PreimageChange::Requested,
+                    "Cleared" => PreimageChange::Cleared,
+                    _ => continue,
+                };
+                if let Some(hash) = value_ext::account_field(&fields, "hash") {
+                    decoded.preimages.push(PreimageUpdate {
+                        hash,
+                        change,
+                        block_number,
+                        extrinsic_hash,
+                    });
+                }
+                continue;
+            }
+
+            // Scheduled / Canceled { when, index }; the others carry { task: (when, index), id }
+            let address = match variant {
+                "Scheduled" | "Canceled" => value_ext::field(&fields, "when")
+                    .and_then(agenda_when)
+                    .zip(value_ext::u128_field(&fields, "index")),
+                _ => value_ext::field(&fields, "task").and_then(task_address),
+            };
+            let Some(((kind, at), index)) = address else {
+                warn!(
+                    "Undecodable Scheduler::{} at block {}",
+                    variant, block_number
+                );
+                continue;
+            };
+            let address = TaskAddress {
+                kind,
+                at,
+                index: index as i32,
+            };
+
+            let (success, dispatch_error) = match variant {
+                "Dispatched" => value_ext::field(&fields, "result")
+                    .map(|result| extrinsic_decoder::dispatch_result(result, metadata))
+                    .unwrap_or_default(),
+                _ => (None, None),
+            };
+            let change = match variant {
+                "Scheduled" => Some(TaskChange::Scheduled),
+                "Dispatched" => Some(TaskChange::Dispatched(dispatch_error.clone())),
+                "Canceled" => Some(TaskChange::Canceled),
+                "CallUnavailable" => Some(TaskChange::CallUnavailable),
+                "PermanentlyOverweight" => Some(TaskChange::Overweight),
+                _ => None,
+            };
+
+            decoded.events.push(SchedulerEvent {
+                block_number,
+                event_index: event.index() as i32,
+                event_variant: variant.to_string(),
+                agenda_kind: address.kind.to_string(),
+                agenda_at: address.at,
+                task_index: address.index,
+                task_id: value_ext::field(&fields, "id").and_then(option_bytes),
+                success,
+                dispatch_error,
+                extrinsic_hash: extrinsic_hash.clone(),
+                block_ts: block_timestamp,
+            });
+            if let Some(change) = change {
+                decoded.tasks.push(TaskUpdate {
+                    address,
+                    change,
+                    block_number,
+                    extrinsic_hash,
+                });
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Build the preimages touched in a block, refreshing deposits and request counts from
+    /// `RequestStatusFor` and decoding the call of newly noted preimages
+    pub async fn resolve_preimages(
+        &self,
+        updates: &[PreimageUpdate],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        block_number: i64,
+    ) -> Result<Vec<Preimage>> {
+        let repo = SchedulerRepository::new(conn);
+        let mut preimages: BTreeMap<Vec<u8>, Preimage> = BTreeMap::new();
+
+        for update in updates {
+            let preimage = match preimages.entry(update.hash.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let known = repo.get_preimage(&update.hash).await?;
+                    entry.insert(known.unwrap_or_else(|| Preimage {
+                        hash: update.hash.clone(),
+                        first_block: block_number,
+                        ..Default::default()
+                    }))
+                }
+            };
+            match update.change {
+                PreimageChange::Noted => {
+                    preimage.noted_block = Some(update.block_number);
+                    preimage.noted_extrinsic_hash = update.extrinsic_hash.clone();
+                    preimage.cleared_block = None;
+                }
+                PreimageChange::Requested => preimage.cleared_block = None,
+                PreimageChange::Cleared => {
+                    preimage.status = PreimageStatus::Cleared;
+                    preimage.cleared_block = Some(update.block_number);
+                }
+            }
+            preimage.updated_block = update.block_number;
+        }
+
+        for preimage in preimages.values_mut() {
+            if preimage.cleared_block.is_some() {
+                continue;
+            }
+            let key = preimage_status_key(&preimage.hash);
+            let Some(bytes) = rpc.get_storage(&key, block_hash).await? else {
+                continue;
+            };
+            let status =
+                rpc::decode_storage_value(metadata, PREIMAGE_PALLET, "RequestStatusFor", &bytes)?;
+            apply_request_status(preimage, &status);
+
+            if preimage.call_pallet.is_none() {
+                if let Some(length) = preimage.length {
+                    let call =
+                        fetch_preimage_call(rpc, metadata, block_hash, &preimage.hash, length)
+                            .await?;
+                    if let Some((pallet, function, args)) = call {
+                        preimage.call_pallet = Some(pallet);
+                        preimage.call_function = Some(function);
+                        preimage.call_args = Some(args);
+                    }
+                }
+            }
+        }
+
+        Ok(preimages.into_values().collect())
+    }
+
+    /// Build the tasks touched in a block.
+    ///
+    /// New tasks are read from `Agenda` after the block; tasks scheduled before indexing
+    /// began are read from the agenda at the parent block when they first show up. Calls
+    /// stored by hash are resolved against `preimages` (this block's), the `preimages`
+    /// table, then `PreimageFor` storage.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn resolve_tasks(
+        &self,
+        updates: &[TaskUpdate],
+        preimages: &[Preimage],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        parent_hash: &H256,
+    ) -> Result<Vec<ScheduledTask>> {
+        let repo = SchedulerRepository::new(conn);
+        let mut tasks: BTreeMap<TaskAddress, ScheduledTask> = BTreeMap::new();
+
+        for update in updates {
+            let address = &update.address;
+            let task = if update.change == TaskChange::Scheduled {
+                let task = fetch_task(rpc, metadata, block_hash, address, update).await?;
+                let Some(mut task) = task else {
+                    warn!(
+                        "Scheduled task {}:{}#{} missing from its agenda at block {}",
+                        address.kind, address.at, address.index, update.block_number
+                    );
+                    continue;
+                };
+                task.scheduled_extrinsic_hash = update.extrinsic_hash.clone();
+                tasks.entry(address.clone()).insert_entry(task).into_mut()
+            } else {
+                match tasks.entry(address.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let known = match repo
+                            .get_open_task(address.kind, address.at, address.index)
+                            .await?
+                        {
+                            Some(task) => Some(task),
+                            None => fetch_task(rpc, metadata, parent_hash, address, update).await?,
+                        };
+                        let Some(known) = known else {
+                            debug!(
+                                "Unknown task {}:{}#{} changed at block {}",
+                                address.kind, address.at, address.index, update.block_number
+                            );
+                            continue;
+                        };
+                        entry.insert(known)
+                    }
+                }
+            };
+            update.apply(task);
+        }
+
+        for task in tasks.values_mut() {
+            if task.call_pallet.is_some() {
+                continue;
+            }
+            let Some(hash) = task.call_hash.clone() else {
+                continue;
+            };
+            let known = match preimages.iter().find(|p| p.hash == hash) {
+                Some(preimage) => Some(preimage.clone()),
+                None => repo.get_preimage(&hash).await?,
+            };
+            let call = match known.filter(|p| p.call_pallet.is_some()) {
+                Some(preimage) => preimage
+                    .call_pallet
+                    .zip(preimage.call_function)
+                    .map(|(pallet, function)| (pallet, function, preimage.call_args)),
+                None => match task.call_len {
+                    Some(len) => fetch_preimage_call(rpc, metadata, block_hash, &hash, len)
+                        .await?
+                        .map(|(pallet, function, args)| (pallet, function, Some(args))),
+                    None => None,
+                },
+            };
+            if let Some((pallet, function, args)) = call {
+                task.call_pallet = Some(pallet);
+                task.call_function = Some(function);
+                task.call_args = args;
+            }
+        }
+
+        Ok(tasks.into_values().collect())
+    }
+}
+
+impl Default for SchedulerDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode a `BlockNumberOrTimestamp` into its kind and value
+fn agenda_when<T>(value: &Value<T>) -> Option<(&'static str, i64)> {
+    let ValueDef::Variant(variant) = &value.value else {
+        return None;
+    };
+    let kind = match variant.name.as_str() {
+        "BlockNumber" => "block",
+        "Timestamp" => "timestamp",
+        _ => return None,
+    };
+    let at = variant
+        .values
+        .values()
+        .next()
+        .and_then(value_ext::as_u128)?;
+    Some((kind, at as i64))
+}
+
+/// Decode a `TaskAddress` tuple `(when, index)`
+fn task_address<T>(value: &Value<T>) -> Option<((&'static str, i64), u128)> {
+    let ValueDef::Composite(fields) = &value.value else {
+        return None;
+    };
+    let mut values = fields.values();
+    let when = agenda_when(values.next()?)?;
+    let index = value_ext::as_u128(values.next()?)?;
+    Some((when, index))
+}
+
+/// Bytes of an `Option<[u8; N]>` value
+fn option_bytes<T>(value: &Value<T>) -> Option<Vec<u8>> {
+    match &value.value {
+        ValueDef::Variant(v) if v.name == "Some" => {
+            v.values.values().next().and_then(value_ext::as_bytes)
+        }
+        _ => None,
+    }
+}
+
+/// Read a task from its agenda slot
+async fn fetch_task(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    address: &TaskAddress,
+    update: &TaskUpdate,
+) -> Result<Option<ScheduledTask>> {
+    let key = rpc::storage_twox64_map_key(SCHEDULER_PALLET, "Agenda", &address.encoded_when());
+    let Some(bytes) = rpc.get_storage(&key, at).await? else {
+        return Ok(None);
+    };
+    let agenda = rpc::decode_storage_value(metadata, SCHEDULER_PALLET, "Agenda", &bytes)?;
+    let Some(fields) = agenda_slot(&agenda, address.index as usize) else {
+        return Ok(None);
+    };
+
+    let mut task = ScheduledTask {
+        agenda_kind: address.kind.to_string(),
+        agenda_at: address.at,
+        task_index: address.index,
+        scheduled_block: update.block_number,
+        updated_block: update.block_number,
+        ..Default::default()
+    };
+    apply_scheduled(&mut task, fields, metadata);
+    Ok(Some(task))
+}
+
+/// The `Scheduled` record in an agenda slot (`BoundedVec<Option<Scheduled>>`)
+fn agenda_slot<T>(agenda: &Value<T>, index: usize) -> Option<&Composite<T>> {
+    let mut slots = agenda;
+    while let ValueDef::Composite(Composite::Unnamed(vals)) = &slots.value {
+        match vals.as_slice() {
+            [inner] if matches!(inner.value, ValueDef::Composite(_)) => slots = inner,
+            _ => break,
+        }
+    }
+    let ValueDef::Composite(slots) = &slots.value else {
+        return None;
+    };
+    let ValueDef::Variant(slot) = &slots.values().nth(index)?.value else {
+        return None;
+    };
+    if slot.name != "Some" {
+        return None;
+    }
+    match &slot.values.values().next()?.value {
+        ValueDef::Composite(fields) => Some(fields),
+        _ => None,
+    }
+}
+
+/// Fill a task from its `Scheduled` record
+fn apply_scheduled<T>(task: &mut ScheduledTask, fields: &Composite<T>, metadata: &Metadata) {
+    task.task_id = value_ext::field(fields, "maybe_id").and_then(option_bytes);
+    task.priority = value_ext::u128_field(fields, "priority").map(|p| p as i16);
+
+    if let Some(ValueDef::Variant(call)) = value_ext::field(fields, "call").map(|v| &v.value) {
+        let call_fields = &call.values;
+        match call.name.as_str() {
+            // Inline(BoundedVec<u8>)
+            "Inline" => {
+                let data = value_ext::composite_as_bytes(call_fields);
+                if let Some((pallet, function, args)) = data
+                    .as_deref()
+                    .and_then(|data| extrinsic_decoder::decode_call(data, metadata))
+                {
+                    task.call_pallet = Some(pallet);
+                    task.call_function = Some(function);
+                    task.call_args = Some(args);
+                }
+                task.call_len = data.as_ref().map(|d| d.len() as i32);
+                task.call_data = data;
+            }
+            // Lookup { hash, len } / Legacy { hash }
+            _ => {
+                task.call_hash = value_ext::account_field(call_fields, "hash");
+                task.call_len = value_ext::u128_field(call_fields, "len").map(|l| l as i32);
+            }
+        }
+    }
+
+    if let Some(ValueDef::Variant(periodic)) =
+        value_ext::field(fields, "maybe_periodic").map(|v| &v.value)
+    {
+        if let Some(ValueDef::Composite(period)) = periodic.values.values().next().map(|v| &v.value)
+        {
+            let mut values = period.values();
+            if let Some((kind, every)) = values.next().and_then(agenda_when) {
+                task.period_kind = Some(kind.to_string());
+                task.period = Some(every);
+            }
+            task.period_count = values.next().and_then(value_ext::as_u128).map(|c| c as i32);
+        }
+    }
+
+    if let Some(origin) = value_ext::field(fields, "origin") {
+        let (name, account) = origin_caller(origin);
+        task.origin = name;
+        task.origin_account = account;
+    }
+}
+
+/// Render an `OriginCaller`: `Root` / `Signed` / `None` for system origins (with the
+/// signer), `Pallet::Variant` otherwise
+fn origin_caller<T>(value: &Value<T>) -> (Option<String>, Option<Vec<u8>>) {
+    let ValueDef::Variant(caller) = &value.value else {
+        return (None, None);
+    };
+    let Some(ValueDef::Variant(inner)) = caller.values.values().next().map(|v| &v.value) else {
+        return (Some(caller.name.clone()), None);
+    };
+    if caller.name == "system" {
+        let account = inner.values.values().next().and_then(value_ext::as_bytes);
+        (Some(inner.name.clone()), account)
+    } else {
+        (Some(format!("{}::{}", caller.name, inner.name)), None)
+    }
+}
+
+/// Fill a preimage from its `RequestStatus` (`Unrequested { ticket, len }` or
+/// `Requested { maybe_ticket, count, maybe_len }`)
+fn apply_request_status<T>(preimage: &mut Preimage, status: &Value<T>) {
+    let ValueDef::Variant(status) = &status.value else {
+        return;
+    };
+    let fields = &status.values;
+    let ticket = match status.name.as_str() {
+        "Unrequested" => {
+            preimage.status = PreimageStatus::Unrequested;
+            preimage.request_count = 0;
+            preimage.length = value_ext::u128_field(fields, "len").map(|l| l as i32);
+            value_ext::field(fields, "ticket")
+        }
+        "Requested" => {
+            preimage.status = PreimageStatus::Requested;
+            preimage.request_count =
+                value_ext::u128_field(fields, "count").unwrap_or_default() as i32;
+            if let Some(len) = value_ext::option_u128_field(fields, "maybe_len") {
+                preimage.length = Some(len as i32);
+            }
+            value_ext::field(fields, "maybe_ticket").and_then(|t| match &t.value {
+                ValueDef::Variant(v) if v.name == "Some" => v.values.values().next(),
+                _ => None,
+            })
+        }
+        _ => return,
+    };
+
+    // (AccountId, deposit)
+    let (depositor, deposit) = match ticket.map(|t| &t.value) {
+        Some(ValueDef::Composite(ticket)) => {
+            let mut values = ticket.values();
+            (
+                values.next().and_then(value_ext::as_bytes),
+                values.next().and_then(value_ext::as_u128),
+            )
+        }
+        _ => (None, None),
+    };
+    preimage.depositor = depositor;
+    preimage.deposit = deposit.map(|d| d.to_string());
+}
+
+/// Storage key of a `RequestStatusFor` entry (`Identity` hashed)
+fn preimage_status_key(hash: &[u8]) -> Vec<u8> {
+    let mut key = rpc::storage_key(PREIMAGE_PALLET, "RequestStatusFor");
+    key.extend_from_slice(hash);
+    key
+}
+
+/// Read a preimage's bytes from `PreimageFor` and decode them as a call
+async fn fetch_preimage_call(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    hash: &[u8],
+    len: i32,
+) -> Result<Option<(String, String, serde_json::Value)>> {
+    let mut key = rpc::storage_key(PREIMAGE_PALLET, "PreimageFor");
+    key.extend_from_slice(hash);
+    key.extend_from_slice(&(len as u32).encode());
+    let Some(bytes) = rpc.get_storage(&key, at).await? else {
+        return Ok(None);
+    };
+    let data = Vec::<u8>::decode(&mut &bytes[..])?;
+    Ok(extrinsic_decoder::decode_call(&data, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_address() {
+        let task = Value::unnamed_composite([
+            Value::unnamed_variant("Timestamp", [Value::u128(1_700_000_000_000)]),
+            Value::u128(3),
+        ]);
+        assert_eq!(
+            task_address(&task),
+            Some((("timestamp", 1_700_000_000_000), 3))
+        );
+
+        let address = TaskAddress {
+            kind: "block",
+            at: 42,
+            index: 0,
+        };
+        assert_eq!(address.encoded_when(), vec![0, 42, 0, 0, 0]);
+        assert_eq!(agenda_when(&Value::u128(1)), None);
+    }
+
+    #[test]
+    fn test_agenda_slot_and_origin() {
+        let scheduled = Value::named_composite([
+            ("maybe_id", Value::unnamed_variant("None", [])),
+            ("priority", Value::u128(63)),
+            (
+                "origin",
+                Value::unnamed_variant(
+                    "system",
+                    [Value::unnamed_variant(
+                        "Signed",
+                        [Value::unnamed_composite([Value::from_bytes([5u8; 32])])],
+                    )],
+                ),
+            ),
+        ]);
+        let agenda = Value::unnamed_composite([Value::unnamed_composite([
+            Value::unnamed_variant("None", []),
+            Value::unnamed_variant("Some", [scheduled]),
+        ])]);
+
+        assert!(agenda_slot(&agenda, 0).is_none());
+        assert!(agenda_slot(&agenda, 2).is_none());
+        let fields = agenda_slot(&agenda, 1).expect("slot 1 is filled");
+        let (origin, account) = origin_caller(value_ext::field(fields, "origin").unwrap());
+        assert_eq!(origin.as_deref(), Some("Signed"));
+        assert_eq!(account, Some(vec![5u8; 32]));
+
+        let treasurer = Value::unnamed_variant(
+            "Origins",
+            [Value::unnamed_variant("Treasurer", Vec::<Value>::new())],
+        );
+        assert_eq!(
+            origin_caller(&treasurer),
+            (Some("Origins::Treasurer".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn test_task_update_apply() {
+        let update = |change| TaskUpdate {
+            address: TaskAddress {
+                kind: "block",
+                at: 10,
+                index: 0,
+            },
+            change,
+            block_number: 10,
+            extrinsic_hash: None,
+        };
+        let mut task = ScheduledTask::default();
+        update(TaskChange::Dispatched(Some("BadOrigin".into()))).apply(&mut task);
+        assert_eq!(task.status, ScheduledTaskStatus::Failed);
+        assert_eq!(task.dispatch_error.as_deref(), Some("BadOrigin"));
+        assert_eq!(task.closed_block, Some(10));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::value_ext;
+use subxt::{
+    ext::{
+        scale_value::{scale::decode_as_type, ValueDef},
+        sp_core::blake2_256,
+    },
+    Metadata,
+};
+
+/// ML-DSA context Quantus runtimes sign extrinsics under
+/// (`qp_dilithium_crypto::signing_context::EXTRINSIC`)
+pub const EXTRINSIC_CONTEXT: &[u8] = b"QUANTUS_EXTRINSIC";
+
+/// ML-DSA context of extrinsic signatures under a runtime spec version.
+///
+/// Dilithium runtimes before `context_since_spec` signed with the empty context; from it on
+/// extrinsic signatures are domain-separated with [`EXTRINSIC_CONTEXT`]. Only this one
+/// context is accepted, so a signature made by the key for another purpose never verifies
+/// as an extrinsic.
+pub fn extrinsic_context(spec_version: u32, context_since_spec: u32) -> &'static [u8] {
+    if spec_version >= context_since_spec {
+        EXTRINSIC_CONTEXT
+    } else {
+        b""
+    }
+}
+
+/// ML-DSA (Dilithium) parameter sets as (name, signature length, public key length)
+const ML_DSA_PARAMS: [(&str, usize, usize); 3] = [
+    ("ML-DSA-44", 2420, 1312),
+    ("ML-DSA-65", 3309, 1952),
+    ("ML-DSA-87", 4627, 2592),
+];
+
+/// What a signed extrinsic reveals about its signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    /// Parameter set when recognised (e.g. "ML-DSA-87"), otherwise the runtime's variant name
+    pub scheme: String,
+    /// The signature itself
+    pub signature: Vec<u8>,
+    /// Public key embedded next to the signature, if the scheme carries one
+    pub public_key: Option<Vec<u8>>,
+}
+
+/// Inspect an extrinsic signature using the runtime's signature type.
+///
+/// Post-quantum runtimes wrap ML-DSA signatures together with the signer's public key
+/// (`DilithiumSignatureWithPublic`), since the account id is only a hash of the key.
+pub fn inspect_signature(bytes: &[u8], metadata: &Metadata) -> Option<SignatureInfo> {
+    let signature_ty = metadata.extrinsic().signature_ty();
+    let value = decode_as_type(&mut &bytes[..], signature_ty, metadata.types()).ok()?;
+
+    let ValueDef::Variant(variant) = &value.value else {
+        return None;
+    };
+    let payload = value_ext::composite_as_bytes(&variant.values)?;
+
+    Some(split_payload(&variant.name, payload))
+}
+
+/// Split a signature payload into signature and public key by ML-DSA sizes
+fn split_payload(variant: &str, payload: Vec<u8>) -> SignatureInfo {
+    for (name, sig_len, pk_len) in ML_DSA_PARAMS {
+        if payload.len() == sig_len + pk_len {
+            let (signature, public_key) = payload.split_at(sig_len);
+            return SignatureInfo {
+                scheme: name.to_string(),
+                signature: signature.to_vec(),
+                public_key: Some(public_key.to_vec()),
+            };
+        }
+        if payload.len() == sig_len {
+            return SignatureInfo {
+                scheme: name.to_string(),
+                signature: payload,
+                public_key: None,
+            };
+        }
+    }
+
+    SignatureInfo {
+        scheme: variant.to_string(),
+        signature: payload,
+        public_key: None,
+    }
+}
+
+/// Verify an ML-DSA signature over `message` under the given context.
+///
+/// Returns None if the scheme is not an ML-DSA parameter set or the key is malformed.
+pub fn verify_ml_dsa(
+    scheme: &str,
+    public_key: &[u8],
+    signature: &[u8],
+    message: &[u8],
+    context: &[u8],
+) -> Option<bool> {
+    use qp_rusty_crystals_dilithium::{ml_dsa_44, ml_dsa_65, ml_dsa_87};
+
+    macro_rules! verify_with {
+        ($module:ident) => {{
+            let key = $module::PublicKey::from_bytes(public_key).ok()?;
+            Some(key.verify(message, signature, Some(context)))
+        }};
+    }
+
+    match scheme {
+        "ML-DSA-44" => verify_with!(ml_dsa_44),
+        "ML-DSA-65" => verify_with!(ml_dsa_65),
+        "ML-DSA-87" => verify_with!(ml_dsa_87),
+        _ => None,
+    }
+}
+
+/// Find the hash that derives `account` from `public_key`.
+///
+/// Quantus-style runtimes use Poseidon; standard Substrate runtimes hash long keys
+/// with Blake2-256. Returns None when the account does not derive from the key.
+pub fn account_derivation(public_key: &[u8], account: &[u8]) -> Option<&'static str> {
+    if qp_poseidon_core::hash_bytes(public_key) == account {
+        return Some("poseidon");
+    }
+    if blake2_256(public_key) == account {
+        return Some("blake2_256");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_ml_dsa_87_with_public() {
+        let mut payload = vec![1u8; 4627];
+        payload.extend([2u8; 2592]);
+
+        let info = split_payload("Dilithium", payload);
+        assert_eq!(info.scheme, "ML-DSA-87");
+        assert_eq!(info.signature.len(), 4627);
+        assert_eq!(info.public_key, Some(vec![2u8; 2592]));
+
+        let info = split_payload("Ed25519", vec![0u8; 64]);
+        assert_eq!(info.scheme, "Ed25519");
+        assert_eq!(info.public_key, None);
+    }
+
+    #[test]
+    fn test_account_derivation() {
+        let key = [7u8; 2592];
+        let poseidon = qp_poseidon_core::hash_bytes(&key);
+        assert_eq!(account_derivation(&key, &poseidon), Some("poseidon"));
+        assert_eq!(
+            account_derivation(&key, &blake2_256(&key)),
+            Some("blake2_256")
+        );
+        assert_eq!(account_derivation(&key, &[0u8; 32]), None);
+    }
+
+    #[test]
+    fn test_verify_ml_dsa_87_contexts() {
+        use qp_rusty_crystals_dilithium::{ml_dsa_87::Keypair, SensitiveBytes32};
+
+        let keypair = Keypair::generate(&mut SensitiveBytes32::from(&mut [3u8; 32]));
+        let public = keypair.public().to_bytes();
+        let message = b"signing payload";
+        let signature = keypair
+            .sign(message, Some(EXTRINSIC_CONTEXT), None)
+            .unwrap();
+        let legacy = keypair.sign(message, Some(b""), None).unwrap();
+
+        let verify = |signature: &[u8], message: &[u8], context: &[u8]| {
+            verify_ml_dsa("ML-DSA-87", &public, signature, message, context)
+        };
+        assert_eq!(verify(&signature, message, EXTRINSIC_CONTEXT), Some(true));
+        assert_eq!(
+            verify(&signature, b"tampered", EXTRINSIC_CONTEXT),
+            Some(false)
+        );
+
+        // A signature only verifies under the context it was made with
+        assert_eq!(verify(&signature, message, b""), Some(false));
+        assert_eq!(verify(&legacy, message, EXTRINSIC_CONTEXT), Some(false));
+        assert_eq!(verify(&legacy, message, b""), Some(true));
+
+        assert_eq!(
+            verify_ml_dsa("Ed25519", &public, &signature, message, b""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extrinsic_context_by_spec_version() {
+        assert_eq!(extrinsic_context(0, 0), EXTRINSIC_CONTEXT);
+        assert_eq!(extrinsic_context(114, 0), EXTRINSIC_CONTEXT);
+        assert_eq!(extrinsic_context(113, 114), b"");
+        assert_eq!(extrinsic_context(114, 114), EXTRINSIC_CONTEXT);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::signature::{self, SignatureInfo};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::{PortableRegistry, TypeDef};
+use subxt::{
+    ext::{
+        sp_core::{blake2_256, H256},
+        subxt_core::blocks::ExtrinsicDetails,
+    },
+    utils::Era,
+    Metadata, PolkadotConfig,
+};
+
+/// Outcome of checking an extrinsic signature offline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// The signature verifies against the reconstructed signing payload
+    Valid,
+    /// The signature does not verify
+    Invalid,
+    /// The payload or scheme could not be reconstructed offline
+    Unsupported(String),
+}
+
+impl Verification {
+    /// Value stored in `extrinsics.signature_valid` (NULL when not checked)
+    pub fn as_db_value(&self) -> Option<bool> {
+        match self {
+            Self::Valid => Some(true),
+            Self::Invalid => Some(false),
+            Self::Unsupported(_) => None,
+        }
+    }
+}
+
+/// Data a signature commits to besides the call and the signed extensions
+#[derive(Debug, Clone, Copy)]
+pub struct SigningContext {
+    /// Runtime spec version active at the block
+    pub spec_version: u32,
+    /// Transaction version active at the block
+    pub transaction_version: u32,
+    /// Hash of the block a mortal extrinsic is anchored to (genesis hash if immortal)
+    pub birth_hash: H256,
+}
+
+/// Verifies ML-DSA extrinsic signatures against the payload rebuilt from the block body,
+/// independently of the RPC node that served the block
+pub struct SignatureVerifier {
+    genesis_hash: H256,
+    /// First spec version signing extrinsics under `signature::EXTRINSIC_CONTEXT`
+    context_since_spec: u32,
+}
+
+impl SignatureVerifier {
+    /// Create a verifier for the chain with the given genesis hash
+    pub fn new(genesis_hash: H256) -> Self {
+        Self {
+            genesis_hash,
+            context_since_spec: 0,
+        }
+    }
+
+    /// Verify extrinsics of runtimes before `spec_version` under the empty ML-DSA context
+    pub fn with_context_since_spec(mut self, spec_version: u32) -> Self {
+        self.context_since_spec = spec_version;
+        self
+    }
+
+    /// Genesis hash the verifier commits to
+    pub fn genesis_hash(&self) -> H256 {
+        self.genesis_hash
+    }
+
+    /// ML-DSA context extrinsic signatures are checked under at a spec version
+    pub fn extrinsic_context(&self, spec_version: u32) -> &'static [u8] {
+        signature::extrinsic_context(spec_version, self.context_since_spec)
+    }
+
+    /// Block number whose hash a mortal extrinsic commits to, None for immortal extrinsics
+    pub fn mortality_birth(
+        &self,
+        ext: &ExtrinsicDetails<PolkadotConfig>,
+        block_number: u64,
+    ) -> Option<u64> {
+        let extensions = ext.signed_extensions()?;
+        let mortality = extensions
+            .iter()
+            .filter_map(|e| e.ok())
+            .find(|e| matches!(e.name(), "CheckMortality" | "CheckEra"))?;
+
+        match Era::decode(&mut mortality.bytes()).ok()? {
+            Era::Immortal => None,
+            Era::Mortal { period, phase } => Some(era_birth(period, phase, block_number)),
+        }
+    }
+
+    /// Reconstruct the payload an extrinsic was signed over.
+    ///
+    /// The payload is the call, the explicit signed extension data and each extension's
+    /// additional signed data, hashed with Blake2-256 when longer than 256 bytes.
+    pub fn signing_payload(
+        &self,
+        ext: &ExtrinsicDetails<PolkadotConfig>,
+        metadata: &Metadata,
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, String> {
+        let extra = ext
+            .signed_extensions_bytes()
+            .ok_or_else(|| "extrinsic is not signed".to_string())?;
+
+        let mut payload = ext.call_bytes().to_vec();
+        payload.extend_from_slice(extra);
+
+        let extensions = ext
+            .signed_extensions()
+            .ok_or_else(|| "extrinsic is not signed".to_string())?;
+
+        for extension in metadata.extrinsic().signed_extensions() {
+            match extension.identifier() {
+                "CheckSpecVersion" => context.spec_version.encode_to(&mut payload),
+                "CheckTxVersion" => context.transaction_version.encode_to(&mut payload),
+                "CheckGenesis" => payload.extend_from_slice(self.genesis_hash.as_bytes()),
+                "CheckMortality" | "CheckEra" => {
+                    payload.extend_from_slice(context.birth_hash.as_bytes())
+                }
+                "CheckMetadataHash" => {
+                    // Mode::Disabled commits to `None`; an enabled mode commits to the
+                    // merkleized metadata hash, which we cannot rebuild offline
+                    let mode = extensions
+                        .iter()
+                        .filter_map(|e| e.ok())
+                        .find(|e| e.name() == "CheckMetadataHash")
+                        .and_then(|e| e.bytes().first().copied());
+                    if mode != Some(0) {
+                        return Err("CheckMetadataHash is enabled".to_string());
+                    }
+                    None::<[u8; 32]>.encode_to(&mut payload);
+                }
+                _ if is_unit(metadata.types(), extension.additional_ty()) => {}
+                name => return Err(format!("unknown additional signed data for {}", name)),
+            }
+        }
+
+        if payload.len() > 256 {
+            Ok(blake2_256(&payload).to_vec())
+        } else {
+            Ok(payload)
+        }
+    }
+
+    /// Verify an extrinsic's signature offline, under the single context of its runtime
+    pub fn verify(
+        &self,
+        ext: &ExtrinsicDetails<PolkadotConfig>,
+        signature: &SignatureInfo,
+        metadata: &Metadata,
+        context: &SigningContext,
+    ) -> Verification {
+        let Some(public_key) = &signature.public_key else {
+            return Verification::Unsupported("signature carries no public key".to_string());
+        };
+
+        let payload = match self.signing_payload(ext, metadata, context) {
+            Ok(payload) => payload,
+            Err(reason) => return Verification::Unsupported(reason),
+        };
+
+        match signature::verify_ml_dsa(
+            &signature.scheme,
+            public_key,
+            &signature.signature,
+            &payload,
+            self.extrinsic_context(context.spec_version),
+        ) {
+            Some(true) => Verification::Valid,
+            Some(false) => Verification::Invalid,
+            None => Verification::Unsupported(format!("unsupported scheme {}", signature.scheme)),
+        }
+    }
+}
+
+/// First block of a mortal era's validity window, as computed by `sp_runtime::generic::Era::birth`
+fn era_birth(period: u64, phase: u64, current: u64) -> u64 {
+    (current.max(phase) - phase) / period * period + phase
+}
+
+/// Whether a type encodes to no bytes (e.g. `()`), as most extensions' additional data does
+fn is_unit(types: &PortableRegistry, type_id: u32) -> bool {
+    let Some(ty) = types.resolve(type_id) else {
+        return false;
+    };
+    match &ty.type_def {
+        TypeDef::Tuple(tuple) => tuple.fields.iter().all(|f| is_unit(types, f.id)),
+        TypeDef::Composite(composite) => composite.fields.iter().all(|f| is_unit(types, f.ty.id)),
+        TypeDef::Array(array) => array.len == 0 || is_unit(types, array.type_param.id),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qp_rusty_crystals_dilithium::{ml_dsa_87::Keypair, SensitiveBytes32};
+    use subxt::{
+        config::{
+            substrate::{BlakeTwo256, SubstrateHeader},
+            Config, DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder,
+        },
+        dynamic::Value,
+        ext::subxt_core::{
+            blocks::Extrinsics,
+            client::{ClientState, RuntimeVersion},
+            tx,
+        },
+        utils::{AccountId32, MultiAddress},
+    };
+
+    /// Runtime metadata of a Quantus chain (ML-DSA-87 signatures, CheckMetadataHash and a
+    /// unit `ReversibleTransactionExtension`)
+    const METADATA: &[u8] = include_bytes!(
+        "../../metadata/FnX4ttSwm8kTZUvUkDbyPYS2txtcrW5pZ7kATWar2v1i/metadata.scale"
+    );
+
+    /// `DilithiumSignatureScheme::Dilithium` carrying the signature followed by the public key
+    #[derive(Debug)]
+    struct DilithiumSignature(Vec<u8>);
+
+    impl Encode for DilithiumSignature {
+        fn encode_to<W: parity_scale_codec::Output + ?Sized>(&self, dest: &mut W) {
+            dest.push_byte(0);
+            dest.write(&self.0);
+        }
+    }
+
+    /// Config building Quantus transactions with subxt's own extrinsic params
+    enum QuantusConfig {}
+
+    impl Config for QuantusConfig {
+        type Hash = H256;
+        type AccountId = AccountId32;
+        type Address = MultiAddress<AccountId32, ()>;
+        type Signature = DilithiumSignature;
+        type Hasher = BlakeTwo256;
+        type Header = SubstrateHeader<u32, BlakeTwo256>;
+        type ExtrinsicParams = DefaultExtrinsicParams<Self>;
+        type AssetId = u32;
+    }
+
+    #[test]
+    fn test_era_birth() {
+        // Era::mortal(64, 1000) has phase 1000 % 64 = 40
+        assert_eq!(era_birth(64, 40, 1000), 1000);
+        assert_eq!(era_birth(64, 40, 1010), 1000);
+        assert_eq!(era_birth(64, 40, 1064), 1064);
+    }
+
+    #[test]
+    fn test_signing_payload_matches_subxt() {
+        let metadata = Metadata::decode(&mut &METADATA[..]).unwrap();
+        let genesis_hash = H256::repeat_byte(0x11);
+        let birth_hash = H256::repeat_byte(0x22);
+        let client_state = ClientState::<QuantusConfig> {
+            genesis_hash,
+            runtime_version: RuntimeVersion {
+                spec_version: 114,
+                transaction_version: 2,
+            },
+            metadata: metadata.clone(),
+        };
+
+        let call = subxt::dynamic::tx(
+            "Balances",
+            "transfer_keep_alive",
+            vec![
+                Value::unnamed_variant("Id", [Value::from_bytes([7u8; 32])]),
+                Value::u128(1_000_000_000_000),
+            ],
+        );
+        let params = DefaultExtrinsicParamsBuilder::<QuantusConfig>::new()
+            .nonce(5)
+            .tip(3)
+            .mortal_unchecked(1000, birth_hash, 64)
+            .build();
+        let partial = tx::create_partial_signed(&call, &client_state, params).unwrap();
+        let expected = partial.signer_payload();
+
+        let keypair = Keypair::generate(&mut SensitiveBytes32::from(&mut [9u8; 32]));
+        let public = keypair.public().to_bytes();
+        let signature = keypair
+            .sign(&expected, Some(signature::EXTRINSIC_CONTEXT), None)
+            .unwrap();
+        let mut with_public = signature.to_vec();
+        with_public.extend_from_slice(&public);
+        let account = AccountId32(qp_poseidon_core::hash_bytes(&public));
+        let transaction = partial.sign_with_address_and_signature(
+            &MultiAddress::Id(account),
+            &DilithiumSignature(with_public),
+        );
+
+        let extrinsics = Extrinsics::<PolkadotConfig>::decode_from(
+            vec![transaction.into_encoded()],
+            metadata.clone(),
+        )
+        .unwrap();
+        let ext = extrinsics.iter().next().unwrap().unwrap();
+        let verifier = SignatureVerifier::new(genesis_hash);
+        assert_eq!(verifier.mortality_birth(&ext, 1010), Some(1000));
+
+        let context = SigningContext {
+            spec_version: 114,
+            transaction_version: 2,
+            birth_hash,
+        };
+        let payload = verifier.signing_payload(&ext, &metadata, &context).unwrap();
+        assert_eq!(payload, expected);
+
+        let info = signature::inspect_signature(ext.signature_bytes().unwrap(), &metadata).unwrap();
+        assert_eq!(info.scheme, "ML-DSA-87");
+        assert_eq!(
+            verifier.verify(&ext, &info, &metadata, &context),
+            Verification::Valid
+        );
+
+        // Any other block the era could be anchored to changes the payload
+        let context = SigningContext {
+            birth_hash: genesis_hash,
+            ..context
+        };
+        assert_eq!(
+            verifier.verify(&ext, &info, &metadata, &context),
+            Verification::Invalid
+        );
+    }
+}
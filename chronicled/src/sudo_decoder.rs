@@ -0,0 +1,363 @@
+use crate::{extrinsic_decoder, rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{Extrinsic, SudoAction};
+use chrono::{DateTime, Utc};
+use serde_json::Value as Json;
+use std::collections::{HashMap, VecDeque};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{info, warn};
+
+const SUDO_PALLET: &str = "Sudo";
+
+/// A call dispatched through `sudo`, `sudo_unchecked_weight` or `sudo_as`
+#[derive(Debug, Clone, PartialEq)]
+struct WrappedCall {
+    /// Event the dispatch emits (`Sudid` or `SudoAsDone`)
+    event: &'static str,
+    pallet: String,
+    function: String,
+    args: Json,
+    as_account: Option<Vec<u8>>,
+}
+
+/// Decoder for the `Sudo` pallet's privileged actions
+pub struct SudoDecoder;
+
+impl SudoDecoder {
+    /// Create a new sudo decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract sudo actions, attaching the wrapped call of `Sudid` / `SudoAsDone` events.
+    ///
+    /// The wrapped calls are taken from the extrinsic's decoded arguments in dispatch order,
+    /// so sudo calls nested in batches or in other sudo calls are attributed too. The sudo
+    /// key is filled in by [`SudoDecoder::resolve_keys`].
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        extrinsics: &[Extrinsic],
+        metadata: &Metadata,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<Vec<SudoAction>> {
+        let mut actions = Vec::new();
+        let mut wrapped: HashMap<u32, VecDeque<WrappedCall>> = HashMap::new();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != SUDO_PALLET {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let variant = event.variant_name();
+            let extrinsic_index = match event.phase() {
+                Phase::ApplyExtrinsic(i) => Some(i),
+                _ => None,
+            };
+            let extrinsic = extrinsic_index.and_then(|i| extrinsics.get(i as usize));
+            let mut action = SudoAction {
+                block_number,
+                event_index: event.index() as i32,
+                action: variant.to_string(),
+                sudo_key: None,
+                new_key: None,
+                as_account: None,
+                call_pallet: None,
+                call_function: None,
+                call_args: None,
+                success: None,
+                dispatch_error: None,
+                extrinsic_index: extrinsic_index.map(|i| i as i32),
+                extrinsic_hash: extrinsic.map(|e| e.hash.clone()),
+                block_ts: block_timestamp,
+            };
+
+            match variant {
+                // Sudid { sudo_result } / SudoAsDone { sudo_result }
+                "Sudid" | "SudoAsDone" => {
+                    let (success, error) = value_ext::field(&fields, "sudo_result")
+                        .map(|result| extrinsic_decoder::dispatch_result(result, metadata))
+                        .unwrap_or_default();
+                    action.success = success;
+                    action.dispatch_error = error;
+
+                    let call = extrinsic.and_then(|extrinsic| {
+                        let calls = wrapped
+                            .entry(extrinsic_index.unwrap_or_default())
+                            .or_insert_with(|| wrapped_calls(extrinsic));
+                        let position = calls.iter().position(|c| c.event == variant)?;
+                        calls.remove(position)
+                    });
+                    match call {
+                        Some(call) => {
+                            action.call_pallet = Some(call.pallet);
+                            action.call_function = Some(call.function);
+                            action.call_args = Some(call.args);
+                            action.as_account = call.as_account;
+                        }
+                        None => warn!(
+                            "No wrapped call found for Sudo::{} at block {}",
+                            variant, block_number
+                        ),
+                    }
+                }
+                // KeyChanged { old, new }
+                "KeyChanged" => {
+                    action.sudo_key = value_ext::field(&fields, "old").and_then(option_account);
+                    action.new_key = value_ext::account_field(&fields, "new");
+                }
+                "KeyRemoved" => {}
+                _ => continue,
+            }
+
+            info!(
+                "Sudo::{} at block {}{}",
+                variant,
+                block_number,
+                action
+                    .call_pallet
+                    .as_ref()
+                    .zip(action.call_function.as_ref())
+                    .map(|(p, f)| format!(" dispatching {}::{}", p, f))
+                    .unwrap_or_default()
+            );
+            actions.push(action);
+        }
+
+        Ok(actions)
+    }
+
+    /// Fill in the sudo key in effect for each action, starting from the key stored at
+    /// the parent block and following key changes within the block
+    pub async fn resolve_keys(
+        &self,
+        actions: &mut [SudoAction],
+        rpc: &RpcHelper,
+        parent_hash: &H256,
+    ) -> Result<()> {
+        let key = rpc::storage_key(SUDO_PALLET, "Key");
+        let current = rpc
+            .get_storage(&key, parent_hash)
+            .await?
+            .filter(|bytes| bytes.len() == 32);
+        apply_keys(actions, current);
+        Ok(())
+    }
+}
+
+impl Default for SudoDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Track the sudo key across a block's actions
+fn apply_keys(actions: &mut [SudoAction], mut current: Option<Vec<u8>>) {
+    for action in actions {
+        match action.action.as_str() {
+            "KeyChanged" => {
+                if action.sudo_key.is_none() {
+                    action.sudo_key = current.clone();
+                }
+                current = action.new_key.clone();
+            }
+            "KeyRemoved" => {
+                action.sudo_key = current.take();
+            }
+            _ => action.sudo_key = current.clone(),
+        }
+    }
+}
+
+/// Account of an `Option<AccountId>` value
+fn option_account<T>(value: &Value<T>) -> Option<Vec<u8>> {
+    match &value.value {
+        ValueDef::Variant(v) if v.name == "Some" => {
+            v.values.values().next().and_then(value_ext::as_bytes)
+        }
+        _ => None,
+    }
+}
+
+/// Calls dispatched through sudo by an extrinsic, in the order their events are emitted
+fn wrapped_calls(extrinsic: &Extrinsic) -> VecDeque<WrappedCall> {
+    let mut calls = Vec::new();
+    visit_call(
+        &extrinsic.call_pallet,
+        &extrinsic.call_function,
+        &extrinsic.args,
+        &mut calls,
+    );
+    calls.into()
+}
+
+/// Collect the sudo dispatches of a call. A sudo call's event follows the events of the
+/// call it wraps, so nested dispatches are collected first.
+fn visit_call(pallet: &str, function: &str, args: &Json, out: &mut Vec<WrappedCall>) {
+    let event = match (pallet, function) {
+        (SUDO_PALLET, "sudo" | "sudo_unchecked_weight") => "Sudid",
+        (SUDO_PALLET, "sudo_as") => "SudoAsDone",
+        _ => {
+            visit_args(args, out);
+            return;
+        }
+    };
+
+    let Some((inner_pallet, inner_function, inner_args)) = value_ext::split_call(&args["call"])
+    else {
+        return;
+    };
+    visit_call(inner_pallet, inner_function, inner_args, out);
+    out.push(WrappedCall {
+        event,
+        pallet: inner_pallet.to_string(),
+        function: inner_function.to_string(),
+        args: inner_args.clone(),
+        as_account: address(&args["who"]),
+    });
+}
+
+/// Look for calls nested in arguments (e.g. `Utility::batch` call lists)
+fn visit_args(args: &Json, out: &mut Vec<WrappedCall>) {
+    if let Some((pallet, function, inner)) = value_ext::split_call(args) {
+        visit_call(pallet, function, inner, out);
+        return;
+    }
+    match args {
+        Json::Array(items) => items.iter().for_each(|item| visit_args(item, out)),
+        Json::Object(map) => map.values().for_each(|value| visit_args(value, out)),
+        _ => {}
+    }
+}
+
+/// Account of a rendered `MultiAddress::Id`
+fn address(value: &Json) -> Option<Vec<u8>> {
+    let (variant, account) = value_ext::json_variant(value)?;
+    if !matches!(variant, "Id" | "Address32") {
+        return None;
+    }
+    let account = account.as_str()?;
+    hex::decode(account.strip_prefix("0x").unwrap_or(account)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extrinsic(pallet: &str, function: &str, args: Json) -> Extrinsic {
+        Extrinsic {
+            id: None,
+            block_number: 1,
+            extrinsic_index: 1,
+            hash: vec![0; 32],
+            is_signed: true,
+            signer: None,
+            nonce: None,
+            tip: None,
+            call_pallet: pallet.to_string(),
+            call_function: function.to_string(),
+            args,
+            success: true,
+            dispatch_error: None,
+            fee: None,
+            signature_scheme: None,
+            signature_len: None,
+            signer_public_key: None,
+            signature_valid: None,
+            signature_context: None,
+            block_ts: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_wrapped_calls() {
+        let remark = json!({ "System": { "remark": { "remark": "0x01" } } });
+        let ext = extrinsic(
+            "Utility",
+            "batch",
+            json!({ "calls": [
+                { "Sudo": { "sudo": { "call": {
+                    "Sudo": { "sudo_as": { "who": { "Id": "0x0202" }, "call": remark } }
+                } } } },
+                { "Balances": { "transfer_all": { "dest": { "Id": "0x03" }, "keep_alive": true } } },
+                { "Sudo": { "sudo_unchecked_weight": {
+                    "call": { "Sudo": { "remove_key": null } },
+                    "weight": { "ref_time": 1, "proof_size": 0 }
+                } } }
+            ] }),
+        );
+
+        let calls: Vec<_> = wrapped_calls(&ext).into_iter().collect();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(
+            (
+                calls[0].event,
+                calls[0].pallet.as_str(),
+                calls[0].function.as_str()
+            ),
+            ("SudoAsDone", "System", "remark")
+        );
+        assert_eq!(calls[0].as_account, Some(vec![2, 2]));
+        assert_eq!(calls[0].args, json!({ "remark": "0x01" }));
+        assert_eq!(
+            (
+                calls[1].event,
+                calls[1].pallet.as_str(),
+                calls[1].function.as_str()
+            ),
+            ("Sudid", "Sudo", "sudo_as")
+        );
+        assert_eq!(
+            (calls[2].event, calls[2].function.as_str(), &calls[2].args),
+            ("Sudid", "remove_key", &Json::Null)
+        );
+
+        let plain = extrinsic("Balances", "transfer_keep_alive", json!({ "value": 1 }));
+        assert!(wrapped_calls(&plain).is_empty());
+    }
+
+    #[test]
+    fn test_apply_keys() {
+        let action = |name: &str, old: Option<u8>, new: Option<u8>| SudoAction {
+            block_number: 1,
+            event_index: 0,
+            action: name.to_string(),
+            sudo_key: old.map(|b| vec![b; 32]),
+            new_key: new.map(|b| vec![b; 32]),
+            as_account: None,
+            call_pallet: None,
+            call_function: None,
+            call_args: None,
+            success: None,
+            dispatch_error: None,
+            extrinsic_index: None,
+            extrinsic_hash: None,
+            block_ts: Utc::now(),
+        };
+        let mut actions = vec![
+            action("Sudid", None, None),
+            action("KeyChanged", Some(1), Some(2)),
+            action("Sudid", None, None),
+            action("KeyRemoved", None, None),
+            action("Sudid", None, None),
+        ];
+        apply_keys(&mut actions, Some(vec![1; 32]));
+
+        let keys: Vec<_> = actions
+            .iter()
+            .map(|a| a.sudo_key.as_ref().map(|k| k[0]))
+            .collect();
+        assert_eq!(keys, vec![Some(1), Some(1), Some(2), Some(2), None]);
+    }
+}
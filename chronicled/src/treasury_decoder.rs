@@ -0,0 +1,588 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    BalanceChange, BalanceChangeReason, DbExecutor, TreasuryBalance, TreasuryLedgerEntry,
+    TreasuryRepository, TreasurySpend, TreasurySpendKind, TreasurySpendStatus,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{btree_map::Entry, BTreeMap};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Composite, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// Names the treasury pallet is deployed under
+const TREASURY_PALLETS: [&str; 2] = ["TreasuryPallet", "Treasury"];
+
+/// A lifecycle transition of a treasury spend
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpendChange {
+    /// `SpendApproved` / `AssetSpendApproved`
+    Approved {
+        beneficiary: Option<Vec<u8>>,
+        amount: u128,
+        asset_kind: serde_json::Value,
+        valid_from: Option<i64>,
+        expire_at: Option<i64>,
+    },
+    /// `Awarded`
+    Awarded {
+        beneficiary: Option<Vec<u8>>,
+        amount: u128,
+    },
+    /// `Paid`, with the payment id
+    Paid(serde_json::Value),
+    /// `PaymentFailed`, with the payment id
+    PaymentFailed(serde_json::Value),
+    /// `SpendProcessed`
+    Processed,
+    /// `AssetSpendVoided`
+    Voided,
+}
+
+/// A transition of one spend
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpendUpdate {
+    pub kind: TreasurySpendKind,
+    pub spend_index: i64,
+    pub change: SpendChange,
+    pub block_number: i64,
+    pub event_index: i32,
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+impl SpendUpdate {
+    /// Record the transition on the spend
+    pub fn apply(&self, spend: &mut TreasurySpend) {
+        match &self.change {
+            SpendChange::Approved {
+                beneficiary,
+                amount,
+                asset_kind,
+                valid_from,
+                expire_at,
+            } => {
+                spend.beneficiary = beneficiary.clone();
+                spend.amount = amount.to_string();
+                spend.asset_kind = asset_kind.clone();
+                spend.valid_from = *valid_from;
+                spend.expire_at = *expire_at;
+                spend.status = TreasurySpendStatus::Approved;
+                spend.approved_block = Some(self.block_number);
+                spend.approved_extrinsic_hash = self.extrinsic_hash.clone();
+                spend.resolved_block = None;
+                spend.resolved_extrinsic_hash = None;
+                return;
+            }
+            SpendChange::Awarded {
+                beneficiary,
+                amount,
+            } => {
+                spend.beneficiary = beneficiary.clone().or_else(|| spend.beneficiary.clone());
+                spend.amount = amount.to_string();
+                spend.status = TreasurySpendStatus::Awarded;
+            }
+            SpendChange::Paid(payment_id) => {
+                spend.payment_id = Some(payment_id.clone());
+                spend.status = TreasurySpendStatus::Paid;
+            }
+            SpendChange::PaymentFailed(payment_id) => {
+                spend.payment_id = Some(payment_id.clone());
+                spend.status = TreasurySpendStatus::Failed;
+            }
+            SpendChange::Processed => spend.status = TreasurySpendStatus::Processed,
+            SpendChange::Voided => spend.status = TreasurySpendStatus::Voided,
+        }
+        spend.resolved_block = Some(self.block_number);
+        spend.resolved_extrinsic_hash = self.extrinsic_hash.clone();
+    }
+
+    /// Empty record for a spend first seen through this update
+    fn new_spend(&self) -> TreasurySpend {
+        TreasurySpend {
+            kind: self.kind,
+            spend_index: self.spend_index,
+            beneficiary: None,
+            asset_kind: serde_json::Value::Null,
+            amount: "0".to_string(),
+            status: TreasurySpendStatus::Approved,
+            valid_from: None,
+            expire_at: None,
+            payment_id: None,
+            approved_block: None,
+            approved_extrinsic_hash: None,
+            resolved_block: None,
+            resolved_extrinsic_hash: None,
+        }
+    }
+}
+
+/// Treasury activity found in a block's events
+#[derive(Debug, Default)]
+pub struct TreasuryEvents {
+    /// Spend transitions, in event order
+    pub spends: Vec<SpendUpdate>,
+    /// Inflows and outflows carried by the events themselves
+    pub ledger: Vec<TreasuryLedgerEntry>,
+}
+
+/// Account id of a pallet: `modl` and the pallet id, zero padded to 32 bytes
+/// (`PalletId::into_account_truncating`)
+pub fn pallet_account(pallet_id: &[u8]) -> Vec<u8> {
+    let mut account = b"modl".to_vec();
+    account.extend_from_slice(pallet_id);
+    account.resize(32, 0);
+    account
+}
+
+/// Treasury account derived from the runtime's treasury `PalletId` constant
+pub fn treasury_account(metadata: &Metadata) -> Option<Vec<u8>> {
+    TREASURY_PALLETS.iter().find_map(|name| {
+        let pallet_id = metadata
+            .pallet_by_name(name)?
+            .constant_by_name("PalletId")?;
+        Some(pallet_account(pallet_id.value()))
+    })
+}
+
+/// Decoder for the treasury: spend lifecycle, inflows and outflows
+pub struct TreasuryDecoder;
+
+impl TreasuryDecoder {
+    /// Create a new treasury decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract spend transitions and ledger entries from a block's events.
+    ///
+    /// `MiningRewards::TreasuryRewarded` is the treasury's share of the block reward.
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<TreasuryEvents> {
+        let mut decoded = TreasuryEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            let pallet = event.pallet_name();
+            let is_treasury = TREASURY_PALLETS.contains(&pallet);
+            if !is_treasury
+                && (pallet, event.variant_name()) != ("MiningRewards", "TreasuryRewarded")
+            {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let event_index = event.index() as i32;
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+            let amount = |name| value_ext::u128_field(&fields, name);
+            let index = |name| value_ext::u128_field(&fields, name).map(|i| i as i64);
+            let entry =
+                |delta: String, account: Option<Vec<u8>>, spend: Option<i64>| TreasuryLedgerEntry {
+                    block_number,
+                    event_index,
+                    event_pallet: pallet.to_string(),
+                    event_variant: event.variant_name().to_string(),
+                    delta,
+                    account,
+                    spend_kind: spend.map(|_| TreasurySpendKind::Proposal),
+                    spend_index: spend,
+                    extrinsic_hash: extrinsic_hash.clone(),
+                    block_ts: block_timestamp,
+                };
+
+            let spend = match event.variant_name() {
+                // TreasuryRewarded { reward }
+                "TreasuryRewarded" => {
+                    if let Some(reward) = amount("reward") {
+                        decoded.ledger.push(entry(reward.to_string(), None, None));
+                    }
+                    continue;
+                }
+                // Deposit { value }
+                "Deposit" => {
+                    if let Some(value) = amount("value") {
+                        decoded.ledger.push(entry(value.to_string(), None, None));
+                    }
+                    continue;
+                }
+                // Burnt { burnt_funds }
+                "Burnt" => {
+                    if let Some(burnt) = amount("burnt_funds") {
+                        decoded
+                            .ledger
+                            .push(entry(format!("-{}", burnt), None, None));
+                    }
+                    continue;
+                }
+                // SpendApproved { proposal_index, amount, beneficiary }
+                "SpendApproved" => {
+                    index("proposal_index")
+                        .zip(amount("amount"))
+                        .map(|(i, amount)| {
+                            (
+                                TreasurySpendKind::Proposal,
+                                i,
+                                SpendChange::Approved {
+                                    beneficiary: value_ext::account_field(&fields, "beneficiary"),
+                                    amount,
+                                    asset_kind: serde_json::Value::Null,
+                                    valid_from: None,
+                                    expire_at: None,
+                                },
+                            )
+                        })
+                }
+                // Awarded { proposal_index, award, account }
+                "Awarded" => index("proposal_index")
+                    .zip(amount("award"))
+                    .map(|(i, award)| {
+                        let beneficiary = value_ext::account_field(&fields, "account");
+                        decoded.ledger.push(entry(
+                            format!("-{}", award),
+                            beneficiary.clone(),
+                            Some(i),
+                        ));
+                        (
+                            TreasurySpendKind::Proposal,
+                            i,
+                            SpendChange::Awarded {
+                                beneficiary,
+                                amount: award,
+                            },
+                        )
+                    }),
+                // AssetSpendApproved { index, asset_kind, amount, beneficiary, valid_from, expire_at }
+                "AssetSpendApproved" => index("index").zip(amount("amount")).map(|(i, amount)| {
+                    (
+                        TreasurySpendKind::Spend,
+                        i,
+                        SpendChange::Approved {
+                            beneficiary: value_ext::account_field(&fields, "beneficiary"),
+                            amount,
+                            asset_kind: value_ext::field(&fields, "asset_kind")
+                                .map(value_ext::to_json)
+                                .unwrap_or_default(),
+                            valid_from: index("valid_from"),
+                            expire_at: index("expire_at"),
+                        },
+                    )
+                }),
+                // Paid { index, payment_id } / PaymentFailed { index, payment_id }
+                "Paid" | "PaymentFailed" => index("index").map(|i| {
+                    let payment_id = value_ext::field(&fields, "payment_id")
+                        .map(value_ext::to_json)
+                        .unwrap_or_default();
+                    let change = if event.variant_name() == "Paid" {
+                        SpendChange::Paid(payment_id)
+                    } else {
+                        SpendChange::PaymentFailed(payment_id)
+                    };
+                    (TreasurySpendKind::Spend, i, change)
+                }),
+                // SpendProcessed { index }
+                "SpendProcessed" => {
+                    index("index").map(|i| (TreasurySpendKind::Spend, i, SpendChange::Processed))
+                }
+                // AssetSpendVoided { index }
+                "AssetSpendVoided" => {
+                    index("index").map(|i| (TreasurySpendKind::Spend, i, SpendChange::Voided))
+                }
+                _ => continue,
+            };
+
+            let Some((kind, spend_index, change)) = spend else {
+                warn!(
+                    "Undecodable {}::{} at block {}",
+                    pallet,
+                    event.variant_name(),
+                    block_number
+                );
+                continue;
+            };
+            debug!(
+                "Treasury {} {} changed by {} at block {}",
+                kind,
+                spend_index,
+                event.variant_name(),
+                block_number
+            );
+            decoded.spends.push(SpendUpdate {
+                kind,
+                spend_index,
+                change,
+                block_number,
+                event_index,
+                extrinsic_hash,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Apply spend transitions on top of the stored records.
+    ///
+    /// Spends approved before indexing began are read from `Spends` / `Proposals` storage
+    /// at the parent block the first time they are seen.
+    pub async fn resolve_spends(
+        &self,
+        updates: &[SpendUpdate],
+        conn: &dyn DbExecutor,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        parent_hash: &H256,
+    ) -> Result<Vec<TreasurySpend>> {
+        let repo = TreasuryRepository::new(conn);
+        let mut spends: BTreeMap<(TreasurySpendKind, i64), TreasurySpend> = BTreeMap::new();
+
+        for update in updates {
+            let spend = match spends.entry((update.kind, update.spend_index)) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let known = match repo.get_spend(update.kind, update.spend_index).await? {
+                        Some(spend) => Some(spend),
+                        None => fetch_spend(rpc, metadata, parent_hash, update).await?,
+                    };
+                    let known = match known {
+                        Some(spend) => spend,
+                        None if matches!(
+                            update.change,
+                            SpendChange::Approved { .. } | SpendChange::Awarded { .. }
+                        ) =>
+                        {
+                            update.new_spend()
+                        }
+                        None => {
+                            warn!(
+                                "Unknown treasury {} {} changed at block {}",
+                                update.kind, update.spend_index, update.block_number
+                            );
+                            continue;
+                        }
+                    };
+                    entry.insert(known)
+                }
+            };
+            update.apply(spend);
+        }
+
+        Ok(spends.into_values().collect())
+    }
+
+    /// Ledger entries for spends paid out through the paymaster (`Paid`), which carry
+    /// only the spend index
+    pub fn payout_entries(
+        &self,
+        updates: &[SpendUpdate],
+        spends: &[TreasurySpend],
+        block_timestamp: DateTime<Utc>,
+    ) -> Vec<TreasuryLedgerEntry> {
+        updates
+            .iter()
+            .filter(|u| matches!(u.change, SpendChange::Paid(_)))
+            .filter_map(|update| {
+                let spend = spends
+                    .iter()
+                    .find(|s| s.kind == update.kind && s.spend_index == update.spend_index)?;
+                Some(TreasuryLedgerEntry {
+                    block_number: update.block_number,
+                    event_index: update.event_index,
+                    event_pallet: TREASURY_PALLETS[0].to_string(),
+                    event_variant: "Paid".to_string(),
+                    delta: format!("-{}", spend.amount),
+                    account: spend.beneficiary.clone(),
+                    spend_kind: Some(update.kind),
+                    spend_index: Some(update.spend_index),
+                    extrinsic_hash: update.extrinsic_hash.clone(),
+                    block_ts: block_timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Record the treasury's share of block rewards as balance changes of the treasury account.
+    ///
+    /// A `Balances::Deposit` of the reward to the treasury in the same phase is retagged;
+    /// otherwise (rewards minted without a decoded Balances event) a credit is added.
+    pub fn reward_balance_changes(
+        &self,
+        ledger: &[TreasuryLedgerEntry],
+        treasury: &[u8],
+        changes: &mut Vec<BalanceChange>,
+    ) {
+        for entry in ledger
+            .iter()
+            .filter(|e| e.event_variant == "TreasuryRewarded")
+        {
+            let deposit = changes.iter_mut().find(|c| {
+                c.is_native()
+                    && c.reason == BalanceChangeReason::Deposit
+                    && c.account == treasury
+                    && c.delta == entry.delta
+                    && c.extrinsic_hash == entry.extrinsic_hash
+            });
+            match deposit {
+                Some(deposit) => deposit.reason = BalanceChangeReason::TreasuryReward,
+                None => changes.push(BalanceChange::new(
+                    treasury.to_vec(),
+                    entry.block_number,
+                    entry.event_index,
+                    entry.delta.clone(),
+                    BalanceChangeReason::TreasuryReward,
+                    entry.extrinsic_hash.clone(),
+                    entry.event_pallet.clone(),
+                    entry.event_variant.clone(),
+                    entry.block_ts,
+                )),
+            }
+        }
+    }
+
+    /// Read the treasury account's balance from `System::Account` storage at a block
+    pub async fn fetch_balance(
+        &self,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        treasury: &[u8],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<Option<TreasuryBalance>> {
+        let key = rpc::storage_map_key("System", "Account", treasury);
+        let Some(bytes) = rpc.get_storage(&key, block_hash).await? else {
+            return Ok(None);
+        };
+        let value = rpc::decode_storage_value(metadata, "System", "Account", &bytes)?;
+        let ValueDef::Composite(info) = &value.value else {
+            return Ok(None);
+        };
+        let Some(ValueDef::Composite(data)) = value_ext::field(info, "data").map(|d| &d.value)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(TreasuryBalance {
+            block_number,
+            account: treasury.to_vec(),
+            free: value_ext::u128_field(data, "free")
+                .unwrap_or_default()
+                .to_string(),
+            reserved: value_ext::u128_field(data, "reserved")
+                .unwrap_or_default()
+                .to_string(),
+            block_ts: block_timestamp,
+        }))
+    }
+}
+
+impl Default for TreasuryDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a spend from `Spends` or a proposal from `Proposals` storage
+async fn fetch_spend(
+    rpc: &RpcHelper,
+    metadata: &Metadata,
+    at: &H256,
+    update: &SpendUpdate,
+) -> Result<Option<TreasurySpend>> {
+    let Some(pallet) = TREASURY_PALLETS
+        .into_iter()
+        .find(|name| metadata.pallet_by_name(name).is_some())
+    else {
+        return Ok(None);
+    };
+    let item = match update.kind {
+        TreasurySpendKind::Proposal => "Proposals",
+        TreasurySpendKind::Spend => "Spends",
+    };
+    let key = rpc::storage_twox64_map_key(pallet, item, &(update.spend_index as u32).to_le_bytes());
+    let Some(bytes) = rpc.get_storage(&key, at).await? else {
+        return Ok(None);
+    };
+    let value = rpc::decode_storage_value(metadata, pallet, item, &bytes)?;
+    let ValueDef::Composite(fields) = &value.value else {
+        return Ok(None);
+    };
+
+    let mut spend = update.new_spend();
+    apply_stored_spend(&mut spend, fields);
+    Ok(Some(spend))
+}
+
+/// Fill a spend from its `Proposal` or `SpendStatus` storage value
+fn apply_stored_spend<T>(spend: &mut TreasurySpend, fields: &Composite<T>) {
+    spend.beneficiary = value_ext::account_field(fields, "beneficiary");
+    spend.amount = value_ext::u128_field(fields, "amount")
+        .or_else(|| value_ext::u128_field(fields, "value"))
+        .unwrap_or_default()
+        .to_string();
+    if let Some(asset_kind) = value_ext::field(fields, "asset_kind") {
+        spend.asset_kind = value_ext::to_json(asset_kind);
+    }
+    spend.valid_from = value_ext::u128_field(fields, "valid_from").map(|b| b as i64);
+    spend.expire_at = value_ext::u128_field(fields, "expire_at").map(|b| b as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pallet_account() {
+        let account = pallet_account(b"py/trsry");
+        assert_eq!(&account[..12], b"modlpy/trsry");
+        assert_eq!(account.len(), 32);
+        assert!(account[12..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_spend_lifecycle() {
+        let update = |change| SpendUpdate {
+            kind: TreasurySpendKind::Spend,
+            spend_index: 4,
+            change,
+            block_number: 10,
+            event_index: 1,
+            extrinsic_hash: None,
+        };
+        let approved = update(SpendChange::Approved {
+            beneficiary: Some(vec![2; 32]),
+            amount: 900,
+            asset_kind: serde_json::Value::Null,
+            valid_from: Some(10),
+            expire_at: Some(100),
+        });
+        let mut spend = approved.new_spend();
+        approved.apply(&mut spend);
+        assert_eq!(spend.status, TreasurySpendStatus::Approved);
+        assert_eq!(spend.approved_block, Some(10));
+        assert_eq!(spend.resolved_block, None);
+
+        let mut paid = update(SpendChange::Paid(serde_json::json!(7)));
+        paid.block_number = 12;
+        paid.apply(&mut spend);
+        assert_eq!(spend.status, TreasurySpendStatus::Paid);
+        assert_eq!(spend.payment_id, Some(serde_json::json!(7)));
+        assert_eq!(spend.resolved_block, Some(12));
+
+        let entries = TreasuryDecoder::new().payout_entries(&[paid], &[spend], Utc::now());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].delta, "-900");
+        assert_eq!(entries[0].account, Some(vec![2; 32]));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{Extrinsic, RuntimeMetadata, RuntimeUpgrade, RuntimeUpgradeStatus};
+use chrono::{DateTime, Utc};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Primitive, Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::{info, warn};
+
+const SYSTEM_PALLET: &str = "System";
+
+/// Well-known storage key holding the runtime code
+const CODE_KEY: &[u8] = b":code";
+
+/// Runtime upgrade events of a block
+#[derive(Debug, Default)]
+pub struct UpgradeEvents {
+    /// `UpgradeAuthorized` events
+    pub authorizations: Vec<RuntimeUpgrade>,
+    /// `CodeUpdated` event, completed by [`UpgradeDecoder::resolve_upgrade`]
+    pub upgrade: Option<RuntimeUpgrade>,
+}
+
+impl UpgradeEvents {
+    pub fn is_empty(&self) -> bool {
+        self.authorizations.is_empty() && self.upgrade.is_none()
+    }
+}
+
+/// Decoder for runtime upgrades (`System::UpgradeAuthorized` and `System::CodeUpdated`)
+pub struct UpgradeDecoder;
+
+impl UpgradeDecoder {
+    /// Create a new upgrade decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract upgrade authorizations and the code update of a block, attributed to the
+    /// extrinsic that emitted them
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        extrinsics: &[Extrinsic],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<UpgradeEvents> {
+        let mut upgrades = UpgradeEvents::default();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != SYSTEM_PALLET {
+                continue;
+            }
+            let variant = event.variant_name();
+            if !matches!(variant, "UpgradeAuthorized" | "CodeUpdated") {
+                continue;
+            }
+
+            let phase = event.phase();
+            let extrinsic = match phase {
+                Phase::ApplyExtrinsic(i) => extrinsics.get(i as usize),
+                _ => None,
+            };
+            let (signer, call) = source(&phase, extrinsic);
+            let mut upgrade = RuntimeUpgrade {
+                authorized_block: block_number,
+                authorized_event_index: event.index() as i32,
+                authorizer: signer.clone(),
+                authorized_via: call.clone(),
+                authorization_extrinsic_hash: extrinsic.map(|e| e.hash.clone()),
+                authorized_ts: block_timestamp,
+                ..Default::default()
+            };
+
+            match variant {
+                // UpgradeAuthorized { code_hash, check_version }
+                "UpgradeAuthorized" => {
+                    let fields = event.field_values()?;
+                    upgrade.code_hash = value_ext::field(&fields, "code_hash")
+                        .and_then(value_ext::as_bytes)
+                        .unwrap_or_default();
+                    upgrade.check_version = value_ext::bool_field(&fields, "check_version");
+                    info!(
+                        "Runtime upgrade 0x{} authorized at block {}",
+                        hex::encode(&upgrade.code_hash),
+                        block_number
+                    );
+                    upgrades.authorizations.push(upgrade);
+                }
+                // CodeUpdated
+                _ => {
+                    upgrade.status = RuntimeUpgradeStatus::Applied;
+                    upgrade.block_number = Some(block_number);
+                    upgrade.event_index = Some(event.index() as i32);
+                    upgrade.extrinsic_index = extrinsic.map(|e| e.extrinsic_index);
+                    upgrade.extrinsic_hash = extrinsic.map(|e| e.hash.clone());
+                    upgrade.submitter = signer;
+                    upgrade.upgrade_call = call;
+                    upgrade.applied_ts = Some(block_timestamp);
+                    if upgrades.upgrade.is_some() {
+                        warn!(
+                            "Several CodeUpdated events at block {}, keeping the last",
+                            block_number
+                        );
+                    }
+                    upgrades.upgrade = Some(upgrade);
+                }
+            }
+        }
+
+        Ok(upgrades)
+    }
+
+    /// Complete a code update with the hash of the new code, the runtime it replaced
+    /// (`System::LastRuntimeUpgrade`, which is only rewritten once the new runtime starts)
+    /// and the new runtime's version. Returns the new runtime's metadata, which takes
+    /// effect from the next block.
+    pub async fn resolve_upgrade(
+        &self,
+        upgrade: &mut RuntimeUpgrade,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+    ) -> Result<RuntimeMetadata> {
+        upgrade.code_hash = rpc
+            .get_storage_hash(CODE_KEY, block_hash)
+            .await?
+            .map(|hash| hash.as_bytes().to_vec())
+            .unwrap_or_default();
+
+        let key = rpc::storage_key(SYSTEM_PALLET, "LastRuntimeUpgrade");
+        if let Some(bytes) = rpc.get_storage(&key, block_hash).await? {
+            match rpc::decode_storage_value(metadata, SYSTEM_PALLET, "LastRuntimeUpgrade", &bytes) {
+                Ok(value) => {
+                    (upgrade.previous_spec_name, upgrade.previous_spec_version) =
+                        last_runtime_upgrade(&value);
+                }
+                Err(e) => warn!("Failed to decode System::LastRuntimeUpgrade: {}", e),
+            }
+        }
+
+        let version = rpc.get_runtime_version(block_hash).await?;
+        upgrade.spec_name = Some(version.spec_name.clone());
+        upgrade.spec_version = Some(version.spec_version as i32);
+        upgrade.impl_version = Some(version.impl_version as i32);
+        upgrade.transaction_version = Some(version.transaction_version as i32);
+        upgrade.state_version = Some(version.state_version as i32);
+
+        let block_number = upgrade.block_number.unwrap_or(upgrade.authorized_block);
+        info!(
+            "Runtime upgraded at block {}: {} v{} -> {} v{}",
+            block_number,
+            upgrade.previous_spec_name.as_deref().unwrap_or("?"),
+            upgrade
+                .previous_spec_version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            version.spec_name,
+            version.spec_version
+        );
+
+        let metadata_bytes = rpc.get_metadata_bytes(block_hash).await?;
+        Ok(RuntimeMetadata::new(
+            version.spec_version as i32,
+            version.impl_version as i32,
+            version.transaction_version as i32,
+            version.state_version as i32,
+            block_number + 1,
+            metadata_bytes,
+        ))
+    }
+}
+
+impl Default for UpgradeDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Signer and `Pallet::function` of the extrinsic an event was emitted by; events of
+/// block initialization (e.g. enacted referenda) are attributed to `Initialization`
+fn source(phase: &Phase, extrinsic: Option<&Extrinsic>) -> (Option<Vec<u8>>, Option<String>) {
+    match (phase, extrinsic) {
+        (_, Some(extrinsic)) => (
+            extrinsic.signer.clone(),
+            Some(format!(
+                "{}::{}",
+                extrinsic.call_pallet, extrinsic.call_function
+            )),
+        ),
+        (Phase::Initialization, None) => (None, Some("Initialization".to_string())),
+        (Phase::Finalization, None) => (None, Some("Finalization".to_string())),
+        _ => (None, None),
+    }
+}
+
+/// Spec name and version of a `LastRuntimeUpgradeInfo { spec_version, spec_name }`
+fn last_runtime_upgrade<T>(value: &Value<T>) -> (Option<String>, Option<i32>) {
+    let ValueDef::Composite(fields) = &value.value else {
+        return (None, None);
+    };
+    let spec_name = value_ext::field(fields, "spec_name").and_then(|name| match &name.value {
+        ValueDef::Primitive(Primitive::String(name)) => Some(name.clone()),
+        _ => value_ext::text_field(fields, "spec_name"),
+    });
+    let spec_version = value_ext::u128_field(fields, "spec_version").map(|v| v as i32);
+    (spec_name, spec_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_last_runtime_upgrade() {
+        let value = Value::named_composite([
+            ("spec_version", Value::u128(104)),
+            ("spec_name", Value::string("quantus-runtime")),
+        ]);
+        assert_eq!(
+            last_runtime_upgrade(&value),
+            (Some("quantus-runtime".to_string()), Some(104))
+        );
+        assert_eq!(last_runtime_upgrade(&Value::u128(1)), (None, None));
+    }
+
+    #[test]
+    fn test_source() {
+        let extrinsic = Extrinsic {
+            id: None,
+            block_number: 1,
+            extrinsic_index: 2,
+            hash: vec![0; 32],
+            is_signed: true,
+            signer: Some(vec![7; 32]),
+            nonce: None,
+            tip: None,
+            call_pallet: "Sudo".to_string(),
+            call_function: "sudo".to_string(),
+            args: json!({}),
+            success: true,
+            dispatch_error: None,
+            fee: None,
+            signature_scheme: None,
+            signature_len: None,
+            signer_public_key: None,
+            signature_valid: None,
+            signature_context: None,
+            block_ts: Utc::now(),
+        };
+
+        assert_eq!(
+            source(&Phase::ApplyExtrinsic(2), Some(&extrinsic)),
+            (Some(vec![7; 32]), Some("Sudo::sudo".to_string()))
+        );
+        assert_eq!(
+            source(&Phase::Initialization, None),
+            (None, Some("Initialization".to_string()))
+        );
+    }
+}
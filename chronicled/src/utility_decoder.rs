@@ -0,0 +1,557 @@
+use crate::{extrinsic_decoder, value_ext};
+use anyhow::Result;
+use chron_db::{BalanceChange, BatchItem, Extrinsic};
+use chrono::{DateTime, Utc};
+use serde_json::Value as Json;
+use std::collections::{BTreeMap, HashMap};
+use subxt::{
+    events::{Events, Phase},
+    Metadata, PolkadotConfig,
+};
+use tracing::debug;
+
+const UTILITY_PALLET: &str = "Utility";
+
+/// An event of an extrinsic, reduced to what is needed to walk its call tree
+#[derive(Debug, Clone, PartialEq)]
+struct CallEvent {
+    index: i32,
+    pallet: String,
+    variant: String,
+    /// Item a `BatchInterrupted` event reports
+    item: Option<u32>,
+    /// Outcome carried by `ItemFailed`, `BatchInterrupted` and `DispatchedAs`
+    success: Option<bool>,
+    error: Option<String>,
+}
+
+/// How a batch item ended
+#[derive(Debug, Default)]
+struct Outcome {
+    success: Option<bool>,
+    error: Option<String>,
+    /// Event closing the item
+    last_event: Option<i32>,
+    /// No further items of the batch were dispatched
+    stop: bool,
+}
+
+/// Batch items of a block and the position in the call tree each event was emitted at
+#[derive(Debug, Default)]
+pub struct BatchAttribution {
+    pub items: Vec<BatchItem>,
+    /// Event index -> (outermost batch item, call path)
+    paths: HashMap<i32, (i32, String)>,
+}
+
+impl BatchAttribution {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Attribute balance changes emitted inside batches to their batch item
+    pub fn tag_balance_changes(&self, changes: &mut [BalanceChange]) {
+        for change in changes {
+            if let Some((batch_index, call_path)) = self.paths.get(&change.event_index) {
+                change.batch_index = Some(*batch_index);
+                change.call_path = Some(call_path.clone());
+            }
+        }
+    }
+}
+
+/// Decoder attributing events to the items of `Utility` batches and `dispatch_as` calls
+pub struct UtilityDecoder;
+
+impl UtilityDecoder {
+    /// Create a new utility decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Walk the call tree of every extrinsic that emitted `Utility` events.
+    ///
+    /// Batch items are matched to their events in dispatch order: an item's events are
+    /// followed by `ItemCompleted` / `ItemFailed`, or `BatchInterrupted` when a `batch`
+    /// stops, and a `dispatch_as` call's events by `DispatchedAs`. Nested utility calls are
+    /// walked first, so every event is attributed to the innermost item it was emitted in.
+    pub fn decode_events(
+        &self,
+        events: &Events<PolkadotConfig>,
+        extrinsics: &[Extrinsic],
+        metadata: &Metadata,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<BatchAttribution> {
+        let mut by_extrinsic: BTreeMap<u32, Vec<CallEvent>> = BTreeMap::new();
+        let mut has_utility = false;
+
+        for event in events.iter() {
+            let event = event?;
+            let Phase::ApplyExtrinsic(extrinsic_index) = event.phase() else {
+                continue;
+            };
+
+            let mut call_event = CallEvent {
+                index: event.index() as i32,
+                pallet: event.pallet_name().to_string(),
+                variant: event.variant_name().to_string(),
+                item: None,
+                success: None,
+                error: None,
+            };
+            if call_event.pallet == UTILITY_PALLET {
+                has_utility = true;
+                let fields = event.field_values()?;
+                match call_event.variant.as_str() {
+                    // ItemFailed { error }
+                    "ItemFailed" => {
+                        call_event.success = Some(false);
+                        call_event.error = value_ext::field(&fields, "error")
+                            .map(|e| extrinsic_decoder::describe_dispatch_error(e, metadata));
+                    }
+                    // BatchInterrupted { index, error }
+                    "BatchInterrupted" => {
+                        call_event.item = value_ext::u128_field(&fields, "index").map(|i| i as u32);
+                        call_event.success = Some(false);
+                        call_event.error = value_ext::field(&fields, "error")
+                            .map(|e| extrinsic_decoder::describe_dispatch_error(e, metadata));
+                    }
+                    // DispatchedAs { result }
+                    "DispatchedAs" => {
+                        let (success, error) = value_ext::field(&fields, "result")
+                            .map(|r| extrinsic_decoder::dispatch_result(r, metadata))
+                            .unwrap_or_default();
+                        call_event.success = success;
+                        call_event.error = error;
+                    }
+                    _ => {}
+                }
+            }
+            by_extrinsic
+                .entry(extrinsic_index)
+                .or_default()
+                .push(call_event);
+        }
+
+        let mut attribution = BatchAttribution::default();
+        if !has_utility {
+            return Ok(attribution);
+        }
+
+        for (extrinsic_index, events) in by_extrinsic {
+            if !events.iter().any(|e| e.pallet == UTILITY_PALLET) {
+                continue;
+            }
+            let Some(extrinsic) = extrinsics.get(extrinsic_index as usize) else {
+                continue;
+            };
+
+            let walker = walk_extrinsic(extrinsic, &events);
+            for (event_index, path) in walker.paths {
+                attribution
+                    .paths
+                    .insert(event_index, (path[0] as i32, format_path(&path)));
+            }
+            for mut item in walker.items {
+                item.block_number = block_number;
+                item.extrinsic_index = extrinsic_index as i32;
+                item.extrinsic_hash = Some(extrinsic.hash.clone());
+                item.block_ts = block_timestamp;
+                attribution.items.push(item);
+            }
+        }
+
+        if !attribution.is_empty() {
+            debug!(
+                "Attributed {} events to {} batch items at block {}",
+                attribution.paths.len(),
+                attribution.items.len(),
+                block_number
+            );
+        }
+        Ok(attribution)
+    }
+}
+
+impl Default for UtilityDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Walk an extrinsic's call tree against its events
+fn walk_extrinsic<'a>(extrinsic: &Extrinsic, events: &'a [CallEvent]) -> Walker<'a> {
+    // The transaction fee is withdrawn before the call is dispatched
+    let fee_paid = extrinsic.is_signed
+        && events
+            .first()
+            .is_some_and(|e| e.pallet == "Balances" && e.variant == "Withdraw");
+    let mut walker = Walker {
+        events,
+        cursor: usize::from(fee_paid),
+        items: Vec::new(),
+        paths: Vec::new(),
+    };
+    walker.visit_call(
+        &extrinsic.call_pallet,
+        &extrinsic.call_function,
+        &extrinsic.args,
+        &[],
+    );
+    walker
+}
+
+/// Cursor over an extrinsic's events while its call tree is walked
+struct Walker<'a> {
+    events: &'a [CallEvent],
+    cursor: usize,
+    /// Items in call-tree order (a batch item precedes the items nested in it)
+    items: Vec<BatchItem>,
+    /// Event index -> path of the innermost item it was emitted in
+    paths: Vec<(i32, Vec<u32>)>,
+}
+
+impl Walker<'_> {
+    /// Walk a call, looking for utility calls nested in its arguments (e.g. in `sudo`)
+    fn visit_call(&mut self, pallet: &str, function: &str, args: &Json, path: &[u32]) {
+        match (pallet, function) {
+            (UTILITY_PALLET, "batch" | "batch_all" | "force_batch") => {
+                let calls = args["calls"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+                for (index, call) in calls.iter().enumerate() {
+                    if self.visit_item(function, index as u32, call, None, path) {
+                        break;
+                    }
+                }
+            }
+            (UTILITY_PALLET, "dispatch_as") => {
+                let origin = origin_caller(&args["as_origin"]);
+                self.visit_item(function, 0, &args["call"], Some(origin), path);
+            }
+            // Arguments are a field map; a single `call` field must not be mistaken for a call
+            _ => args
+                .as_object()
+                .into_iter()
+                .flat_map(|fields| fields.values())
+                .for_each(|value| self.visit_args(value, path)),
+        }
+    }
+
+    fn visit_args(&mut self, args: &Json, path: &[u32]) {
+        if let Some((pallet, function, inner)) = value_ext::split_call(args) {
+            self.visit_call(pallet, function, inner, path);
+            return;
+        }
+        match args {
+            Json::Array(items) => items.iter().for_each(|item| self.visit_args(item, path)),
+            Json::Object(map) => map.values().for_each(|value| self.visit_args(value, path)),
+            _ => {}
+        }
+    }
+
+    /// Walk one item of a utility call and consume its events. Returns whether the
+    /// enclosing batch stopped dispatching items.
+    fn visit_item(
+        &mut self,
+        batch_call: &str,
+        item_index: u32,
+        call: &Json,
+        origin: Option<(Option<String>, Option<Vec<u8>>)>,
+        path: &[u32],
+    ) -> bool {
+        let mut item_path = path.to_vec();
+        item_path.push(item_index);
+        let call = value_ext::split_call(call);
+        let (origin, origin_account) = origin.unwrap_or_default();
+
+        let slot = self.items.len();
+        self.items.push(BatchItem {
+            call_path: format_path(&item_path),
+            batch_index: item_path[0] as i32,
+            item_index: item_index as i32,
+            depth: item_path.len() as i32,
+            batch_call: batch_call.to_string(),
+            call_pallet: call.map(|(pallet, _, _)| pallet.to_string()),
+            call_function: call.map(|(_, function, _)| function.to_string()),
+            origin,
+            origin_account,
+            ..Default::default()
+        });
+
+        let start = self.cursor;
+        if let Some((pallet, function, args)) = call {
+            self.visit_call(pallet, function, args, &item_path);
+        }
+        let outcome = self.close_item(batch_call, item_index, &item_path);
+
+        let item = &mut self.items[slot];
+        item.first_event_index = outcome
+            .last_event
+            .and_then(|_| self.events.get(start))
+            .map(|e| e.index);
+        item.last_event_index = outcome.last_event;
+        item.success = outcome.success;
+        item.dispatch_error = outcome.error;
+        outcome.stop
+    }
+
+    /// Consume an item's events up to the one closing it. An outcome event that does not
+    /// belong to this item means the item's events were rolled back with an enclosing
+    /// call; it is left for the enclosing item.
+    fn close_item(&mut self, batch_call: &str, item_index: u32, path: &[u32]) -> Outcome {
+        while let Some(event) = self.events.get(self.cursor) {
+            let closes = if event.pallet != UTILITY_PALLET {
+                None
+            } else {
+                match (batch_call, event.variant.as_str()) {
+                    ("batch" | "batch_all" | "force_batch", "ItemCompleted") => Some(false),
+                    ("force_batch", "ItemFailed") => Some(false),
+                    ("batch", "BatchInterrupted") if event.item == Some(item_index) => Some(true),
+                    ("dispatch_as", "DispatchedAs") => Some(false),
+                    (_, "ItemCompleted" | "ItemFailed" | "BatchInterrupted" | "DispatchedAs") => {
+                        return Outcome {
+                            stop: true,
+                            ..Default::default()
+                        };
+                    }
+                    _ => None,
+                }
+            };
+
+            self.cursor += 1;
+            match closes {
+                Some(stop) => {
+                    return Outcome {
+                        success: event.success.or(Some(true)),
+                        error: event.error.clone(),
+                        last_event: Some(event.index),
+                        stop,
+                    }
+                }
+                None => self.paths.push((event.index, path.to_vec())),
+            }
+        }
+
+        Outcome {
+            stop: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Dot-separated item indices (e.g. `3.1`)
+fn format_path(path: &[u32]) -> String {
+    path.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Describe a rendered `OriginCaller`: `Root` / `Signed` / `None` for system origins (with
+/// the signer), `Pallet::Variant` otherwise
+fn origin_caller(value: &Json) -> (Option<String>, Option<Vec<u8>>) {
+    let Some((caller, inner)) = value_ext::json_variant(value) else {
+        return (None, None);
+    };
+    let Some((variant, payload)) = value_ext::json_variant(inner) else {
+        return (Some(caller.to_string()), None);
+    };
+    if caller == "system" {
+        let account = payload
+            .as_str()
+            .and_then(|hex| ::hex::decode(hex.strip_prefix("0x").unwrap_or(hex)).ok());
+        (Some(variant.to_string()), account)
+    } else {
+        (Some(format!("{}::{}", caller, variant)), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extrinsic(pallet: &str, function: &str, args: Json) -> Extrinsic {
+        Extrinsic {
+            id: None,
+            block_number: 1,
+            extrinsic_index: 1,
+            hash: vec![0; 32],
+            is_signed: true,
+            signer: None,
+            nonce: None,
+            tip: None,
+            call_pallet: pallet.to_string(),
+            call_function: function.to_string(),
+            args,
+            success: true,
+            dispatch_error: None,
+            fee: None,
+            signature_scheme: None,
+            signature_len: None,
+            signer_public_key: None,
+            signature_valid: None,
+            signature_context: None,
+            block_ts: Utc::now(),
+        }
+    }
+
+    /// Events numbered from 0, given as `Pallet::Variant`
+    fn events(names: &[&str]) -> Vec<CallEvent> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let (pallet, variant) = name.split_once("::").unwrap();
+                CallEvent {
+                    index: index as i32,
+                    pallet: pallet.to_string(),
+                    variant: variant.to_string(),
+                    item: None,
+                    success: None,
+                    error: None,
+                }
+            })
+            .collect()
+    }
+
+    fn transfer() -> Json {
+        json!({ "Balances": { "transfer_keep_alive": { "dest": { "Id": "0x01" }, "value": 1 } } })
+    }
+
+    /// Call path, success, first and last event of an item
+    type Outline = (String, Option<bool>, Option<i32>, Option<i32>);
+
+    fn outline(walker: &Walker) -> Vec<Outline> {
+        walker
+            .items
+            .iter()
+            .map(|i| {
+                (
+                    i.call_path.clone(),
+                    i.success,
+                    i.first_event_index,
+                    i.last_event_index,
+                )
+            })
+            .collect()
+    }
+
+    fn path_of(walker: &Walker, event: i32) -> Option<String> {
+        walker
+            .paths
+            .iter()
+            .find(|(index, _)| *index == event)
+            .map(|(_, path)| format_path(path))
+    }
+
+    #[test]
+    fn test_nested_batches_and_dispatch_as() {
+        let ext = extrinsic(
+            "Utility",
+            "batch_all",
+            json!({ "calls": [
+                transfer(),
+                { "Utility": { "force_batch": { "calls": [transfer(), transfer()] } } },
+                { "Utility": { "dispatch_as": {
+                    "as_origin": { "system": { "Signed": "0x0202" } },
+                    "call": transfer()
+                } } }
+            ] }),
+        );
+        let mut events = events(&[
+            "Balances::Withdraw", // fee
+            "Balances::Transfer",
+            "Utility::ItemCompleted",
+            "Balances::Transfer",
+            "Utility::ItemCompleted",
+            "Utility::ItemFailed",
+            "Utility::BatchCompletedWithErrors",
+            "Utility::ItemCompleted",
+            "Balances::Transfer",
+            "Utility::DispatchedAs",
+            "Utility::ItemCompleted",
+            "Utility::BatchCompleted",
+            "TransactionPayment::TransactionFeePaid",
+        ]);
+        events[5].success = Some(false);
+        events[5].error = Some("Balances::InsufficientBalance".into());
+        events[9].success = Some(true);
+
+        let walker = walk_extrinsic(&ext, &events);
+        assert_eq!(
+            outline(&walker),
+            vec![
+                ("0".into(), Some(true), Some(1), Some(2)),
+                ("1".into(), Some(true), Some(3), Some(7)),
+                ("1.0".into(), Some(true), Some(3), Some(4)),
+                ("1.1".into(), Some(false), Some(5), Some(5)),
+                ("2".into(), Some(true), Some(8), Some(10)),
+                ("2.0".into(), Some(true), Some(8), Some(9)),
+            ]
+        );
+        assert_eq!(
+            walker.items[3].dispatch_error.as_deref(),
+            Some("Balances::InsufficientBalance")
+        );
+        assert_eq!(walker.items[5].batch_call, "dispatch_as");
+        assert_eq!(walker.items[5].origin.as_deref(), Some("Signed"));
+        assert_eq!(walker.items[5].origin_account, Some(vec![2, 2]));
+
+        assert_eq!(path_of(&walker, 0), None);
+        assert_eq!(path_of(&walker, 1).as_deref(), Some("0"));
+        assert_eq!(path_of(&walker, 3).as_deref(), Some("1.0"));
+        assert_eq!(path_of(&walker, 6).as_deref(), Some("1"));
+        assert_eq!(path_of(&walker, 8).as_deref(), Some("2.0"));
+        assert_eq!(path_of(&walker, 12), None);
+    }
+
+    #[test]
+    fn test_interrupted_batch() {
+        let ext = extrinsic(
+            "Sudo",
+            "sudo",
+            json!({ "call": { "Utility": { "batch": { "calls": [
+                transfer(),
+                { "Utility": { "batch_all": { "calls": [transfer(), transfer()] } } },
+                transfer()
+            ] } } } }),
+        );
+        // The nested batch_all failed on its second transfer, rolling back its events
+        let mut events = events(&[
+            "Balances::Withdraw",
+            "Balances::Transfer",
+            "Utility::ItemCompleted",
+            "Utility::BatchInterrupted",
+            "Sudo::Sudid",
+        ]);
+        events[3].item = Some(1);
+        events[3].success = Some(false);
+        events[3].error = Some("Balances::InsufficientBalance".into());
+
+        let walker = walk_extrinsic(&ext, &events);
+        assert_eq!(
+            outline(&walker),
+            vec![
+                ("0".into(), Some(true), Some(1), Some(2)),
+                ("1".into(), Some(false), Some(3), Some(3)),
+                ("1.0".into(), None, None, None),
+            ]
+        );
+        assert_eq!(path_of(&walker, 1).as_deref(), Some("0"));
+        assert_eq!(path_of(&walker, 4), None);
+    }
+
+    #[test]
+    fn test_origin_caller() {
+        assert_eq!(
+            origin_caller(&json!({ "system": { "Root": null } })),
+            (Some("Root".into()), None)
+        );
+        assert_eq!(
+            origin_caller(&json!({ "Origins": { "Treasurer": null } })),
+            (Some("Origins::Treasurer".into()), None)
+        );
+        assert_eq!(origin_caller(&Json::Null), (None, None));
+    }
+}
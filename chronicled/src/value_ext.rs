@@ -0,0 +1,248 @@
+use subxt::ext::scale_value::{At, Composite, Primitive, Value, ValueDef};
+
+/// Get a named field from a decoded composite (event or call fields)
+pub fn field<'a, T>(fields: &'a Composite<T>, name: &str) -> Option<&'a Value<T>> {
+    fields.at(name)
+}
+
+/// Flatten a value made of (possibly nested) unnamed composites of u8 into bytes.
+///
+/// AccountId32, H256 and `[u8; N]` all decode to this shape.
+pub fn as_bytes<T>(value: &Value<T>) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    collect_bytes(value, &mut out).then_some(out)
+}
+
+/// Flatten the fields of a composite (e.g. an enum variant's payload) into bytes
+pub fn composite_as_bytes<T>(composite: &Composite<T>) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    composite
+        .values()
+        .all(|v| collect_bytes(v, &mut out))
+        .then_some(out)
+}
+
+fn collect_bytes<T>(value: &Value<T>, out: &mut Vec<u8>) -> bool {
+    match &value.value {
+        ValueDef::Primitive(Primitive::U128(b)) if *b <= u8::MAX as u128 => {
+            out.push(*b as u8);
+            true
+        }
+        ValueDef::Composite(Composite::Unnamed(vals)) => vals.iter().all(|v| collect_bytes(v, out)),
+        // Newtype wrappers with a single named field, e.g. `{ bytes: [u8; N] }`
+        ValueDef::Composite(Composite::Named(vals)) if vals.len() == 1 => {
+            collect_bytes(&vals[0].1, out)
+        }
+        _ => false,
+    }
+}
+
+/// Decode an account id field
+pub fn account_field<T>(fields: &Composite<T>, name: &str) -> Option<Vec<u8>> {
+    field(fields, name).and_then(as_bytes)
+}
+
+/// Decode an unsigned integer field (balances, block numbers, ids)
+pub fn u128_field<T>(fields: &Composite<T>, name: &str) -> Option<u128> {
+    field(fields, name).and_then(as_u128)
+}
+
+/// Decode a boolean field
+pub fn bool_field<T>(fields: &Composite<T>, name: &str) -> Option<bool> {
+    match &field(fields, name)?.value {
+        ValueDef::Primitive(Primitive::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+/// Decode a byte string field (e.g. `BoundedVec<u8>` names) as UTF-8, replacing invalid sequences
+pub fn text_field<T>(fields: &Composite<T>, name: &str) -> Option<String> {
+    let bytes = field(fields, name).and_then(as_bytes)?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Decode an `Option<_>` unsigned integer field, None when absent or `None`
+pub fn option_u128_field<T>(fields: &Composite<T>, name: &str) -> Option<u128> {
+    let value = field(fields, name)?;
+    match &value.value {
+        ValueDef::Variant(v) if v.name == "Some" => v.values.values().next().and_then(as_u128),
+        ValueDef::Variant(_) => None,
+        _ => as_u128(value),
+    }
+}
+
+/// Unwrap single-field newtypes and return the inner unsigned integer
+pub fn as_u128<T>(value: &Value<T>) -> Option<u128> {
+    match &value.value {
+        ValueDef::Primitive(p) => p.as_u128(),
+        ValueDef::Composite(c) if c.len() == 1 => c.values().next().and_then(as_u128),
+        _ => None,
+    }
+}
+
+/// Entries of a decoded sequence. `BoundedVec` and `WeakBoundedVec` decode as a one-field
+/// unnamed composite around the real sequence, so single-element wrappers are unwrapped first.
+pub fn sequence<T>(value: &Value<T>) -> Option<&Composite<T>> {
+    let mut value = value;
+    while let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value {
+        match vals.as_slice() {
+            [inner] if matches!(inner.value, ValueDef::Composite(Composite::Unnamed(_))) => {
+                value = inner
+            }
+            _ => break,
+        }
+    }
+    match &value.value {
+        ValueDef::Composite(entries) => Some(entries),
+        _ => None,
+    }
+}
+
+/// Name of the variant if the value is an enum
+pub fn variant_name<T>(value: &Value<T>) -> Option<&str> {
+    match &value.value {
+        ValueDef::Variant(v) => Some(v.name.as_str()),
+        _ => None,
+    }
+}
+
+/// Render a decoded value as JSON.
+///
+/// Byte sequences (account ids, hashes, raw data) are rendered as `0x`-prefixed hex,
+/// large integers as strings so they survive JSON number precision limits, and enum
+/// variants as `{ "Variant": fields }` objects.
+pub fn to_json<T>(value: &Value<T>) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    match &value.value {
+        ValueDef::Composite(composite) => composite_to_json(composite),
+        ValueDef::Variant(variant) => {
+            let mut map = serde_json::Map::new();
+            let inner = if variant.values.is_empty() {
+                Json::Null
+            } else {
+                composite_to_json(&variant.values)
+            };
+            map.insert(variant.name.clone(), inner);
+            Json::Object(map)
+        }
+        ValueDef::Primitive(p) => match p {
+            Primitive::Bool(b) => Json::Bool(*b),
+            Primitive::Char(c) => Json::String(c.to_string()),
+            Primitive::String(s) => Json::String(s.clone()),
+            Primitive::U128(n) if *n <= u32::MAX as u128 => Json::from(*n as u64),
+            Primitive::I128(n) if n.unsigned_abs() <= u32::MAX as u128 => Json::from(*n as i64),
+            Primitive::U128(n) => Json::String(n.to_string()),
+            Primitive::I128(n) => Json::String(n.to_string()),
+            Primitive::U256(b) | Primitive::I256(b) => {
+                Json::String(format!("0x{}", hex::encode(b)))
+            }
+        },
+        ValueDef::BitSequence(bits) => Json::String(
+            bits.iter()
+                .map(|b| if b { '1' } else { '0' })
+                .collect::<String>(),
+        ),
+    }
+}
+
+/// Render decoded fields as JSON (object for named fields, array otherwise)
+pub fn composite_to_json<T>(composite: &Composite<T>) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    match composite {
+        Composite::Named(fields) => Json::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), to_json(v)))
+                .collect(),
+        ),
+        Composite::Unnamed(vals) => {
+            if !vals.is_empty() {
+                let mut bytes = Vec::with_capacity(vals.len());
+                if vals.iter().all(|v| collect_bytes(v, &mut bytes)) && bytes.len() == vals.len() {
+                    return Json::String(format!("0x{}", hex::encode(bytes)));
+                }
+            }
+            // Newtype wrappers collapse to their inner value
+            if vals.len() == 1 {
+                return to_json(&vals[0]);
+            }
+            Json::Array(vals.iter().map(to_json).collect())
+        }
+    }
+}
+
+/// Variant name and payload of an enum rendered by [`to_json`] (`{ "Variant": fields }`)
+pub fn json_variant(value: &serde_json::Value) -> Option<(&str, &serde_json::Value)> {
+    match value {
+        serde_json::Value::Object(map) if map.len() == 1 => {
+            map.iter().next().map(|(k, v)| (k.as_str(), v))
+        }
+        _ => None,
+    }
+}
+
+/// Split a rendered `RuntimeCall` (`{ "Pallet": { "function": args } }`) into its parts
+pub fn split_call(value: &serde_json::Value) -> Option<(&str, &str, &serde_json::Value)> {
+    let (pallet, call) = json_variant(value)?;
+    let (function, args) = json_variant(call)?;
+    matches!(args, serde_json::Value::Object(_) | serde_json::Value::Null)
+        .then_some((pallet, function, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_bytes_nested_account() {
+        let account = Value::unnamed_composite([Value::from_bytes([7u8; 32])]);
+        assert_eq!(as_bytes(&account), Some(vec![7u8; 32]));
+        assert_eq!(as_bytes(&Value::string("x")), None);
+    }
+
+    #[test]
+    fn test_json_rendering() {
+        let fields = Composite::named([
+            (
+                "dest",
+                Value::unnamed_composite([Value::from_bytes([1u8, 2, 3])]),
+            ),
+            ("value", Value::u128(10u128.pow(20))),
+            ("keep_alive", Value::bool(true)),
+        ]);
+        let json = composite_to_json(&fields);
+        assert_eq!(json["dest"], "0x010203");
+        assert_eq!(json["value"], "100000000000000000000");
+        assert_eq!(json["keep_alive"], true);
+    }
+
+    #[test]
+    fn test_text_and_bool_fields() {
+        let fields = Composite::named([
+            ("symbol", Value::from_bytes(b"QU")),
+            ("is_frozen", Value::bool(false)),
+        ]);
+        assert_eq!(text_field(&fields, "symbol").as_deref(), Some("QU"));
+        assert_eq!(bool_field(&fields, "is_frozen"), Some(false));
+        assert_eq!(bool_field(&fields, "symbol"), None);
+    }
+
+    #[test]
+    fn test_u128_field_unwraps_newtypes() {
+        let fields = Composite::named([("id", Value::unnamed_composite([Value::u128(5)]))]);
+        assert_eq!(u128_field(&fields, "id"), Some(5));
+    }
+
+    #[test]
+    fn test_option_u128_field() {
+        let fields = Composite::named([
+            ("some", Value::unnamed_variant("Some", [Value::u128(9)])),
+            ("none", Value::unnamed_variant("None", [])),
+        ]);
+        assert_eq!(option_u128_field(&fields, "some"), Some(9));
+        assert_eq!(option_u128_field(&fields, "none"), None);
+        assert_eq!(option_u128_field(&fields, "missing"), None);
+    }
+}
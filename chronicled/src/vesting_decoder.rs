@@ -0,0 +1,163 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{VestingSchedule, VestingUpdate};
+use chrono::{DateTime, Utc};
+use subxt::{
+    events::{Events, Phase},
+    ext::{
+        scale_value::{Value, ValueDef},
+        sp_core::H256,
+    },
+    Metadata, PolkadotConfig,
+};
+use tracing::debug;
+
+/// Decoder for the Vesting pallet: unvested amounts from events and schedules from storage
+pub struct VestingDecoder;
+
+impl VestingDecoder {
+    /// Create a new vesting decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract `VestingUpdated` / `VestingCompleted` events from a block
+    pub fn decode_vesting_updates(
+        &self,
+        events: &Events<PolkadotConfig>,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        extrinsic_hashes: &[Vec<u8>],
+    ) -> Result<Vec<VestingUpdate>> {
+        let mut updates = Vec::new();
+
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() != "Vesting" {
+                continue;
+            }
+
+            let fields = event.field_values()?;
+            let (unvested, completed) = match event.variant_name() {
+                // VestingUpdated { account, unvested }
+                "VestingUpdated" => (value_ext::u128_field(&fields, "unvested"), false),
+                // VestingCompleted { account }
+                "VestingCompleted" => (Some(0), true),
+                _ => continue,
+            };
+            let (Some(account), Some(unvested)) =
+                (value_ext::account_field(&fields, "account"), unvested)
+            else {
+                continue;
+            };
+
+            let extrinsic_hash = match event.phase() {
+                Phase::ApplyExtrinsic(i) => extrinsic_hashes.get(i as usize).cloned(),
+                _ => None,
+            };
+
+            debug!(
+                "Vesting of {} at block {}: {} unvested",
+                hex::encode(&account),
+                block_number,
+                unvested
+            );
+            updates.push(VestingUpdate {
+                account,
+                block_number,
+                event_index: event.index() as i32,
+                unvested: unvested.to_string(),
+                completed,
+                extrinsic_hash,
+                block_ts: block_timestamp,
+            });
+        }
+
+        Ok(updates)
+    }
+
+    /// Read an account's `Vesting::Vesting` schedules at a block
+    pub async fn fetch_schedules(
+        &self,
+        rpc: &RpcHelper,
+        metadata: &Metadata,
+        block_hash: &H256,
+        block_number: i64,
+        account: &[u8],
+    ) -> Result<Vec<VestingSchedule>> {
+        let key = rpc::storage_map_key("Vesting", "Vesting", account);
+        let Some(bytes) = rpc.get_storage(&key, block_hash).await? else {
+            return Ok(vec![]);
+        };
+
+        let value = rpc::decode_storage_value(metadata, "Vesting", "Vesting", &bytes)?;
+        Ok(schedules_from_value(&value, account, block_number))
+    }
+}
+
+impl Default for VestingDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build schedule records from a decoded `BoundedVec<VestingInfo>`
+fn schedules_from_value<T>(
+    value: &Value<T>,
+    account: &[u8],
+    block_number: i64,
+) -> Vec<VestingSchedule> {
+    let Some(schedules) = value_ext::sequence(value) else {
+        return vec![];
+    };
+
+    schedules
+        .values()
+        .enumerate()
+        .filter_map(|(index, schedule)| {
+            let ValueDef::Composite(info) = &schedule.value else {
+                return None;
+            };
+            Some(VestingSchedule {
+                account: account.to_vec(),
+                block_number,
+                schedule_index: index as i32,
+                locked: value_ext::u128_field(info, "locked")?.to_string(),
+                per_block: value_ext::u128_field(info, "per_block")?.to_string(),
+                starting_block: value_ext::u128_field(info, "starting_block")? as i64,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules_from_value() {
+        let info = |locked: u128, per_block: u128, start: u128| {
+            Value::named_composite([
+                ("locked", Value::u128(locked)),
+                ("per_block", Value::u128(per_block)),
+                ("starting_block", Value::u128(start)),
+            ])
+        };
+        let value = Value::unnamed_composite([Value::unnamed_composite([
+            info(1000, 10, 100),
+            info(500, 5, 200),
+        ])]);
+
+        let schedules = schedules_from_value(&value, &[1; 32], 42);
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules[1].schedule_index, 1);
+        assert_eq!(schedules[1].locked, "500");
+        assert_eq!(schedules[1].starting_block, 200);
+        assert_eq!(schedules[0].locked_at(150), 500);
+
+        let value = Value::unnamed_composite([Value::unnamed_composite([info(1000, 10, 100)])]);
+        let schedules = schedules_from_value(&value, &[1; 32], 42);
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].per_block, "10");
+    }
+}
@@ -0,0 +1,402 @@
+use crate::{rpc, rpc::RpcHelper, value_ext};
+use anyhow::Result;
+use chron_db::{
+    BalanceChange, BalanceChangeReason, Extrinsic, TransferProof, WormholeNullifier, WormholeProof,
+};
+use chrono::{DateTime, Utc};
+use parity_scale_codec::Decode;
+use std::collections::BTreeSet;
+use subxt::{
+    events::{Events, Phase},
+    ext::sp_core::{blake2_256, H256},
+    PolkadotConfig,
+};
+use tracing::{debug, warn};
+
+/// Wormhole activity found in a block's events
+#[derive(Debug, Default)]
+pub struct WormholeEvents {
+    /// Proofs accepted in the block (`ProofVerified`)
+    pub proofs: Vec<WormholeProof>,
+    /// Credits minted to the proofs' exit accounts
+    pub credits: Vec<BalanceChange>,
+}
+
+/// Largest public input vector looked for at the end of a wormhole proof
+const MAX_PUBLIC_INPUTS: usize = 64;
+
+/// A `Balances::Minted` not yet matched to a proof
+struct Mint {
+    phase: Phase,
+    event_index: i32,
+    who: Vec<u8>,
+    amount: u128,
+}
+
+/// Decoder for the Wormhole pallet and the `Balances::TransferProof` records it proves against
+pub struct WormholeDecoder;
+
+impl WormholeDecoder {
+    /// Create a new wormhole decoder
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract verified proofs and the credits they minted.
+    ///
+    /// `ProofVerified` only carries the exit amount; the exit account comes from the
+    /// `Balances::Minted` emitted in the same extrinsic, and the proof's anchor block
+    /// and hash from the `verify_wormhole_proof` call when it was submitted directly.
+    pub fn decode_proofs(
+        &self,
+        events: &Events<PolkadotConfig>,
+        extrinsics: &[Extrinsic],
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+    ) -> Result<WormholeEvents> {
+        let mut decoded = WormholeEvents::default();
+        let mut mints: Vec<Mint> = Vec::new();
+
+        for event in events.iter() {
+            let event = event?;
+            let event_index = event.index() as i32;
+
+            match (event.pallet_name(), event.variant_name()) {
+                // Minted { who, amount }
+                ("Balances", "Minted") => {
+                    let fields = event.field_values()?;
+                    if let (Some(who), Some(amount)) = (
+                        value_ext::account_field(&fields, "who"),
+                        value_ext::u128_field(&fields, "amount"),
+                    ) {
+                        mints.push(Mint {
+                            phase: event.phase(),
+                            event_index,
+                            who,
+                            amount,
+                        });
+                    }
+                }
+                // ProofVerified { exit_amount }
+                ("Wormhole", "ProofVerified") => {
+                    let fields = event.field_values()?;
+                    let Some(exit_amount) = value_ext::u128_field(&fields, "exit_amount") else {
+                        warn!("Undecodable ProofVerified at block {}", block_number);
+                        continue;
+                    };
+
+                    let phase = event.phase();
+                    let mint = mints
+                        .iter()
+                        .rposition(|m| m.phase == phase && m.amount == exit_amount)
+                        .or_else(|| mints.iter().rposition(|m| m.phase == phase))
+                        .map(|i| mints.remove(i));
+
+                    let extrinsic = match phase {
+                        Phase::ApplyExtrinsic(i) => extrinsics.get(i as usize),
+                        _ => None,
+                    };
+                    let extrinsic_hash = extrinsic.map(|e| e.hash.clone());
+                    let (proof_block_number, proof_hash) = extrinsic
+                        .filter(|e| {
+                            e.call_pallet == "Wormhole"
+                                && e.call_function == "verify_wormhole_proof"
+                        })
+                        .map(|e| proof_args(&e.args))
+                        .unwrap_or_default();
+
+                    debug!(
+                        "Wormhole proof verified at block {}: {} exited",
+                        block_number, exit_amount
+                    );
+
+                    if let Some(mint) = &mint {
+                        decoded.credits.push(BalanceChange::new(
+                            mint.who.clone(),
+                            block_number,
+                            mint.event_index,
+                            mint.amount.to_string(),
+                            BalanceChangeReason::Wormhole,
+                            extrinsic_hash.clone(),
+                            "Balances".to_string(),
+                            "Minted".to_string(),
+                            block_timestamp,
+                        ));
+                    }
+                    decoded.proofs.push(WormholeProof {
+                        block_number,
+                        event_index,
+                        extrinsic_hash,
+                        exit_account: mint.map(|m| m.who),
+                        exit_amount: exit_amount.to_string(),
+                        proof_block_number,
+                        proof_hash,
+                        block_ts: block_timestamp,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Nullifiers added to `Wormhole::UsedNullifiers` by a block's proofs.
+    ///
+    /// The pallet emits no event for them; they are public inputs of the proofs submitted
+    /// by the extrinsics that verified them. Every digest among those inputs is looked up
+    /// in `UsedNullifiers`, and the ones set by the block but not by its parent are new.
+    pub async fn fetch_new_nullifiers(
+        &self,
+        rpc: &RpcHelper,
+        block_hash: &H256,
+        parent_hash: &H256,
+        proofs: &[WormholeProof],
+        extrinsics: &[Extrinsic],
+    ) -> Result<Vec<WormholeNullifier>> {
+        let mut seen = BTreeSet::new();
+        let mut nullifiers = Vec::new();
+
+        for proof in proofs {
+            let Some(extrinsic_hash) = &proof.extrinsic_hash else {
+                continue;
+            };
+            if !seen.insert(extrinsic_hash.clone()) {
+                continue;
+            }
+            let Some(extrinsic) = extrinsics.iter().find(|e| &e.hash == extrinsic_hash) else {
+                continue;
+            };
+
+            let mut candidates = BTreeSet::new();
+            for proof_bytes in proof_bytes(&extrinsic.args) {
+                candidates.extend(public_input_digests(&proof_bytes));
+            }
+            for candidate in candidates {
+                let key = rpc::storage_map_key("Wormhole", "UsedNullifiers", &candidate);
+                if rpc.get_storage(&key, block_hash).await?.is_none()
+                    || rpc.get_storage(&key, parent_hash).await?.is_some()
+                {
+                    continue;
+                }
+                nullifiers.push(WormholeNullifier {
+                    nullifier: candidate.to_vec(),
+                    block_number: proof.block_number,
+                    extrinsic_hash: Some(extrinsic_hash.clone()),
+                    block_ts: proof.block_ts,
+                });
+            }
+        }
+
+        if nullifiers.is_empty() && !proofs.is_empty() {
+            warn!(
+                "No new nullifier found for {} wormhole proofs at block {}",
+                proofs.len(),
+                proofs[0].block_number
+            );
+        }
+        Ok(nullifiers)
+    }
+
+    /// `Balances::TransferProof` records created by a block.
+    ///
+    /// Each proof is stored under the current `TransferCount` before the counter is
+    /// bumped, and the storage key holds the record itself (Identity hasher), so the
+    /// entries for the counter values the block consumed are found by key prefix.
+    /// Records are attributed to the matching `Balances::Transfer` in `balance_changes`.
+    pub async fn fetch_transfer_proofs(
+        &self,
+        rpc: &RpcHelper,
+        block_hash: &H256,
+        parent_hash: &H256,
+        block_number: i64,
+        block_timestamp: DateTime<Utc>,
+        balance_changes: &[BalanceChange],
+    ) -> Result<Vec<TransferProof>> {
+        let count_key = rpc::storage_key("Balances", "TransferCount");
+        let read_count = |bytes: Option<Vec<u8>>| {
+            bytes
+                .and_then(|b| u64::decode(&mut &b[..]).ok())
+                .unwrap_or_default()
+        };
+        let before = read_count(rpc.get_storage(&count_key, parent_hash).await?);
+        let after = read_count(rpc.get_storage(&count_key, block_hash).await?);
+
+        let prefix = rpc::storage_key("Balances", "TransferProof");
+        let mut credits: Vec<&BalanceChange> = balance_changes
+            .iter()
+            .filter(|c| {
+                c.is_native() && c.event_pallet == "Balances" && c.event_variant == "Transfer"
+            })
+            .filter(|c| !c.delta.starts_with('-'))
+            .collect();
+        let mut proofs = Vec::new();
+
+        for count in before..after {
+            let mut count_prefix = prefix.clone();
+            count_prefix.extend_from_slice(&count.to_le_bytes());
+
+            for key in rpc.get_keys(&count_prefix, block_hash).await? {
+                let Some((transfer_count, from_account, to_account, amount)) =
+                    decode_transfer_proof_key(&key[prefix.len()..])
+                else {
+                    warn!(
+                        "Undecodable TransferProof key {} at block {}",
+                        hex::encode(&key),
+                        block_number
+                    );
+                    continue;
+                };
+
+                let amount = amount.to_string();
+                let extrinsic_hash = credits
+                    .iter()
+                    .position(|c| c.account == to_account && c.delta == amount)
+                    .and_then(|i| credits.remove(i).extrinsic_hash.clone());
+                proofs.push(TransferProof {
+                    transfer_count: transfer_count as i64,
+                    from_account,
+                    to_account,
+                    amount,
+                    block_number,
+                    extrinsic_hash,
+                    block_ts: block_timestamp,
+                });
+            }
+        }
+
+        Ok(proofs)
+    }
+}
+
+impl Default for WormholeDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Proof anchor block and Blake2-256 proof hash from `verify_wormhole_proof` arguments
+fn proof_args(args: &serde_json::Value) -> (Option<i64>, Option<Vec<u8>>) {
+    let block_number = match &args["block_number"] {
+        serde_json::Value::String(s) => s.parse().ok(),
+        value => value.as_i64(),
+    };
+    let proof_hash = args["proof_bytes"]
+        .as_str()
+        .and_then(|s| hex::decode(s.strip_prefix("0x").unwrap_or(s)).ok())
+        .map(|bytes| blake2_256(&bytes).to_vec());
+    (block_number, proof_hash)
+}
+
+/// Every `proof_bytes` argument in a decoded call, including calls nested in batches or
+/// dispatched through other pallets
+fn proof_bytes(args: &serde_json::Value) -> Vec<Vec<u8>> {
+    match args {
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .flat_map(|(name, value)| match (name.as_str(), value.as_str()) {
+                ("proof_bytes", Some(s)) => hex::decode(s.strip_prefix("0x").unwrap_or(s))
+                    .ok()
+                    .into_iter()
+                    .collect(),
+                _ => proof_bytes(value),
+            })
+            .collect(),
+        serde_json::Value::Array(values) => values.iter().flat_map(proof_bytes).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 32-byte digests among a serialized plonky2 proof's public inputs.
+///
+/// The inputs close the proof as a length-prefixed vector of Goldilocks elements (u64 LE
+/// each); a digest is four consecutive elements.
+fn public_input_digests(proof_bytes: &[u8]) -> Vec<[u8; 32]> {
+    let read = |at: usize| {
+        proof_bytes
+            .get(at..at + 8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    };
+    // An input can look like the length of a shorter vector; the longest match holds it
+    let Some(inputs) = (1..=MAX_PUBLIC_INPUTS).rev().find_map(|count| {
+        let start = proof_bytes.len().checked_sub(8 * count)?;
+        (read(start.checked_sub(8)?)? == count as u64).then_some(&proof_bytes[start..])
+    }) else {
+        return Vec::new();
+    };
+
+    inputs
+        .chunks_exact(8)
+        .collect::<Vec<_>>()
+        .windows(4)
+        .map(|felts| {
+            let mut digest = [0u8; 32];
+            for (i, felt) in felts.iter().enumerate() {
+                digest[i * 8..i * 8 + 8].copy_from_slice(felt);
+            }
+            digest
+        })
+        .collect()
+}
+
+/// Decode the `(count, from, to, amount)` record held in a `TransferProof` storage key
+fn decode_transfer_proof_key(bytes: &[u8]) -> Option<(u64, Vec<u8>, Vec<u8>, u128)> {
+    let (count, from, to, amount) =
+        <(u64, [u8; 32], [u8; 32], u128)>::decode(&mut &bytes[..]).ok()?;
+    Some((count, from.to_vec(), to.to_vec(), amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Encode;
+
+    #[test]
+    fn test_decode_transfer_proof_key() {
+        let encoded = (7u64, [1u8; 32], [2u8; 32], 500u128).encode();
+        assert_eq!(
+            decode_transfer_proof_key(&encoded),
+            Some((7, vec![1; 32], vec![2; 32], 500))
+        );
+        assert_eq!(decode_transfer_proof_key(&encoded[..40]), None);
+    }
+
+    #[test]
+    fn test_public_input_digests() {
+        let inputs: Vec<u64> = (1..=6).collect();
+        let mut proof = vec![0xab; 100];
+        proof.extend_from_slice(&(inputs.len() as u64).to_le_bytes());
+        for input in &inputs {
+            proof.extend_from_slice(&input.to_le_bytes());
+        }
+
+        let digests = public_input_digests(&proof);
+        assert_eq!(digests.len(), 3);
+        assert_eq!(&digests[0][..8], &1u64.to_le_bytes());
+        assert_eq!(&digests[2][24..], &6u64.to_le_bytes());
+        assert!(public_input_digests(&[0xab; 20]).is_empty());
+    }
+
+    #[test]
+    fn test_proof_bytes() {
+        let direct = serde_json::json!({ "proof_bytes": "0x0102", "block_number": 42 });
+        assert_eq!(proof_bytes(&direct), vec![vec![1, 2]]);
+
+        let batch = serde_json::json!({ "calls": [
+            { "Wormhole": { "verify_wormhole_proof": { "proof_bytes": "0x03", "block_number": 1 } } },
+            { "Balances": { "transfer_keep_alive": { "dest": "0x00", "value": "5" } } },
+            { "Wormhole": { "verify_wormhole_proof": { "proof_bytes": "0x04", "block_number": 2 } } },
+        ] });
+        assert_eq!(proof_bytes(&batch), vec![vec![3], vec![4]]);
+    }
+
+    #[test]
+    fn test_proof_args() {
+        let args = serde_json::json!({ "proof_bytes": "0x0102", "block_number": 42 });
+        assert_eq!(
+            proof_args(&args),
+            (Some(42), Some(blake2_256(&[1, 2]).to_vec()))
+        );
+        assert_eq!(proof_args(&serde_json::Value::Null), (None, None));
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     config::DbConfig,
     error::{DbError, Result},
 };
+use async_trait::async_trait;
 use deadpool_postgres::{Client, Manager, ManagerConfig, Pool, RecyclingMethod};
 
 use tokio_postgres::NoTls;
@@ -98,6 +99,50 @@ impl ConnectionPool {
     }
 }
 
+/// Common query interface shared by pooled connections and open transactions,
+/// so repositories can be used both standalone and inside a block transaction
+#[async_trait]
+pub trait DbExecutor: Send + Sync {
+    /// Get the chain ID for this executor
+    fn chain_id(&self) -> Option<&str>;
+
+    /// Get the schema name for the current chain
+    fn schema_name(&self) -> Result<String> {
+        match self.chain_id() {
+            Some(id) => Ok(format!("\"{}\"", id)),
+            None => Err(DbError::Configuration("Chain ID not set".into())),
+        }
+    }
+
+    /// Execute a statement
+    async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<u64>;
+
+    /// Query and return rows
+    async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Vec<tokio_postgres::Row>>;
+
+    /// Query and return a single row
+    async fn query_one(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<tokio_postgres::Row>;
+
+    /// Query and return an optional single row
+    async fn query_opt(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Option<tokio_postgres::Row>>;
+}
+
 /// Wrapper around a pooled database connection
 pub struct DbConnection {
     client: Client,
@@ -283,6 +328,84 @@ impl<'a> TransactionWrapper<'a> {
     }
 }
 
+#[async_trait]
+impl DbExecutor for DbConnection {
+    fn chain_id(&self) -> Option<&str> {
+        self.chain_id.as_deref()
+    }
+
+    async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<u64> {
+        DbConnection::execute(self, statement, params).await
+    }
+
+    async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Vec<tokio_postgres::Row>> {
+        DbConnection::query(self, statement, params).await
+    }
+
+    async fn query_one(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<tokio_postgres::Row> {
+        DbConnection::query_one(self, statement, params).await
+    }
+
+    async fn query_opt(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Option<tokio_postgres::Row>> {
+        DbConnection::query_opt(self, statement, params).await
+    }
+}
+
+#[async_trait]
+impl DbExecutor for TransactionWrapper<'_> {
+    fn chain_id(&self) -> Option<&str> {
+        self.chain_id.as_deref()
+    }
+
+    async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<u64> {
+        TransactionWrapper::execute(self, statement, params).await
+    }
+
+    async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Vec<tokio_postgres::Row>> {
+        TransactionWrapper::query(self, statement, params).await
+    }
+
+    async fn query_one(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<tokio_postgres::Row> {
+        TransactionWrapper::query_one(self, statement, params).await
+    }
+
+    async fn query_opt(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Option<tokio_postgres::Row>> {
+        TransactionWrapper::query_opt(self, statement, params).await
+    }
+}
+
 /// Pool status information
 #[derive(Debug, Clone)]
 pub struct PoolStatus {
@@ -28,6 +28,9 @@ pub enum DbError {
 
     #[error("Configuration error: {0}")]
     Configuration(String),
+
+    #[error("Invalid account: {0}")]
+    InvalidAccount(String),
 }
 
 pub type Result<T> = std::result::Result<T, DbError>;
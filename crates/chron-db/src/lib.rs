@@ -6,13 +6,28 @@ mod repository;
 mod schema;
 
 pub use config::DbConfig;
-pub use connection::{ConnectionPool, DbConnection, TransactionWrapper};
+pub use connection::{ConnectionPool, DbConnection, DbExecutor, TransactionWrapper};
 pub use error::{DbError, Result};
 pub use models::{
-    AccountStats, BalanceChange, BalanceChangeReason, Block, IndexProgress, RuntimeMetadata,
+    account_to_ss58, Account, AccountBalance, AccountId, AccountStats, Airdrop, AirdropClaim,
+    Asset, BalanceChange, BalanceChangeReason, BalanceLock, BalanceLockKind, BatchItem, Block,
+    BlockHeader, BlockMining, ChainInfo, CollectiveMember, CollectiveRankChange, DigestLog,
+    DigestLogKind, DistanceThresholdAdjustment, Extrinsic, ForkBlock, HighSecurityAccount,
+    IndexProgress, LedgerCheckpoint, LineageHop, PendingTransfer, PendingTransferStatus, Preimage,
+    PreimageStatus, PublicKey, RecoveredAccount, RecoveredOutflow, Recovery, RecoveryConfig,
+    RecoveryStatus, RecoveryVouch, Referendum, ReferendumStatus, ReferendumStatusChange,
+    ReferendumVote, RuntimeMetadata, RuntimeUpgrade, RuntimeUpgradeStatus, ScheduledTask,
+    ScheduledTaskStatus, SchedulerEvent, SudoAction, TransferProof, TreasuryBalance,
+    TreasuryLedgerEntry, TreasurySpend, TreasurySpendKind, TreasurySpendStatus, VestingSchedule,
+    VestingUpdate, VoteDelegation, WormholeNullifier, WormholeProof, DEFAULT_SS58_PREFIX,
 };
 pub use repository::{
-    BalanceChangeRepository, BlockRepository, ChainRepository, RuntimeMetadataRepository,
+    AccountBalanceRepository, AccountRepository, AirdropRepository, AssetRepository,
+    BalanceChangeRepository, BlockHeaderRepository, BlockRepository, ChainInfoRepository,
+    ChainRepository, ExtrinsicRepository, GovernanceRepository, LedgerRepository, MiningRepository,
+    PublicKeyRepository, RecoveryRepository, ReversibleTransferRepository,
+    RuntimeMetadataRepository, RuntimeUpgradeRepository, SchedulerRepository, SudoRepository,
+    TreasuryRepository, UtilityRepository, VestingRepository, WormholeRepository,
 };
 pub use schema::SchemaManager;
 
@@ -1,5 +1,144 @@
+use crate::error::{DbError, Result};
+use blake2::{Blake2b512, Digest};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// SS58 prefix used when a chain does not declare one (generic Substrate)
+pub const DEFAULT_SS58_PREFIX: u16 = 42;
+
+/// A 32-byte account id, renderable as hex or SS58
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AccountId(pub [u8; 32]);
+
+impl AccountId {
+    /// Create an account id from raw bytes (must be 32 bytes)
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Self)
+    }
+
+    /// Raw account bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Raw account bytes as a vector, as stored in `BYTEA` columns
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Get account as hex string
+    pub fn to_hex(&self) -> String {
+        ::hex::encode(self.0)
+    }
+
+    /// Encode the account as an SS58 address with the given network prefix
+    pub fn to_ss58(&self, prefix: u16) -> String {
+        let mut data = ss58_prefix_bytes(prefix);
+        data.extend_from_slice(&self.0);
+        let checksum = ss58_checksum(&data);
+        data.extend_from_slice(&checksum[..2]);
+        bs58::encode(data).into_string()
+    }
+
+    /// Decode an SS58 address, returning the account and its network prefix
+    pub fn from_ss58(address: &str) -> Result<(Self, u16)> {
+        let invalid = |reason: &str| DbError::InvalidAccount(format!("{}: {}", address, reason));
+
+        let data = bs58::decode(address)
+            .into_vec()
+            .map_err(|_| invalid("not base58"))?;
+
+        let (prefix, prefix_len) = match data.first() {
+            Some(&b) if b < 64 => (b as u16, 1),
+            Some(&b) if b < 128 && data.len() > 1 => {
+                // Two-byte prefix, see the SS58 specification
+                let lower = (b << 2) | (data[1] >> 6);
+                let upper = data[1] & 0b0011_1111;
+                ((lower as u16) | ((upper as u16) << 8), 2)
+            }
+            _ => return Err(invalid("unsupported address format")),
+        };
+
+        if data.len() != prefix_len + 32 + 2 {
+            return Err(invalid("not a 32-byte account"));
+        }
+
+        let (body, checksum) = data.split_at(prefix_len + 32);
+        if ss58_checksum(body)[..2] != *checksum {
+            return Err(invalid("bad checksum"));
+        }
+
+        let account = Self::from_slice(&body[prefix_len..]).ok_or_else(|| invalid("bad length"))?;
+        Ok((account, prefix))
+    }
+
+    /// Parse an account from an SS58 address or a (0x-prefixed) hex string
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let hex_input = input.strip_prefix("0x").unwrap_or(input);
+        if hex_input.len() == 64 {
+            if let Ok(bytes) = ::hex::decode(hex_input) {
+                return Self::from_slice(&bytes)
+                    .ok_or_else(|| DbError::InvalidAccount(input.to_string()));
+            }
+        }
+        Self::from_ss58(input).map(|(account, _)| account)
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl From<[u8; 32]> for AccountId {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for AccountId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for AccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{}", self.to_hex())
+    }
+}
+
+fn ss58_prefix_bytes(prefix: u16) -> Vec<u8> {
+    match prefix {
+        0..=63 => vec![prefix as u8],
+        _ => {
+            // Two-byte prefix (64..16383), see the SS58 specification
+            let first = ((prefix & 0b0000_0000_1111_1100) as u8) >> 2;
+            let second = ((prefix >> 8) as u8) | (((prefix & 0b0000_0000_0000_0011) as u8) << 6);
+            vec![first | 0b0100_0000, second]
+        }
+    }
+}
+
+fn ss58_checksum(data: &[u8]) -> [u8; 64] {
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"SS58PRE");
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Render raw account bytes as SS58, falling back to hex for non-32-byte accounts
+pub fn account_to_ss58(account: &[u8], prefix: u16) -> String {
+    match AccountId::from_slice(account) {
+        Some(account) => account.to_ss58(prefix),
+        None => format!("0x{}", ::hex::encode(account)),
+    }
+}
 
 /// Represents a block in the blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +155,10 @@ pub struct Block {
     pub is_canonical: bool,
     /// Runtime specification version
     pub runtime_spec: i64,
+    /// Account that produced the block (the QPoW miner), when known
+    pub author: Option<Vec<u8>>,
+    /// Running hash over the canonical chain's blocks and balance changes up to this block
+    pub ledger_hash: Option<Vec<u8>>,
 }
 
 impl Block {
@@ -34,9 +177,23 @@ impl Block {
             timestamp,
             is_canonical: true, // Default to canonical
             runtime_spec,
+            author: None,
+            ledger_hash: None,
         }
     }
 
+    /// Record the account that produced the block
+    pub fn with_author(mut self, author: Option<Vec<u8>>) -> Self {
+        self.author = author;
+        self
+    }
+
+    /// Mark the block as not part of the canonical chain (the losing side of a fork)
+    pub fn orphaned(mut self) -> Self {
+        self.is_canonical = false;
+        self
+    }
+
     /// Get block hash as hex string
     pub fn hash_hex(&self) -> String {
         ::hex::encode(&self.hash)
@@ -48,6 +205,134 @@ impl Block {
     }
 }
 
+/// A block off the canonical chain, placed in the fork it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkBlock {
+    /// Block number (height)
+    pub number: i64,
+    /// Block hash
+    pub hash: Vec<u8>,
+    /// Parent block hash
+    pub parent_hash: Vec<u8>,
+    /// Miner of the block, when known
+    pub author: Option<Vec<u8>>,
+    /// Block timestamp
+    pub timestamp: DateTime<Utc>,
+    /// Height of the canonical block the fork branched from
+    pub fork_point: i64,
+    /// First block of the fork
+    pub fork_root: Vec<u8>,
+    /// Position of the block in the fork, 1 for its first block
+    pub depth: i32,
+    /// Canonical block at the same height
+    pub canonical_hash: Option<Vec<u8>>,
+    /// Miner of the canonical block at the same height
+    pub canonical_author: Option<Vec<u8>>,
+}
+
+/// Header roots and body sizes of a block, canonical or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    /// Block hash
+    pub block_hash: Vec<u8>,
+    /// Block number (height)
+    pub block_number: i64,
+    /// Root of the state trie after the block
+    pub state_root: Vec<u8>,
+    /// Root of the extrinsics trie
+    pub extrinsics_root: Vec<u8>,
+    /// Number of extrinsics in the body
+    pub extrinsic_count: i32,
+    /// Number of events deposited by the block, when its events could be read
+    pub event_count: Option<i32>,
+    /// SCALE-encoded size of the block (header and body) in bytes
+    pub block_size: i32,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// An operator-signed commitment to the ledger hash at a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerCheckpoint {
+    /// Block the checkpoint was taken at
+    pub block_number: i64,
+    /// Canonical block hash at that height
+    pub block_hash: Vec<u8>,
+    /// Ledger hash of the block
+    pub ledger_hash: Vec<u8>,
+    /// Signature scheme (e.g. `ML-DSA-87`)
+    pub scheme: String,
+    /// Operator public key the signature verifies under
+    pub public_key: Vec<u8>,
+    /// Signature over the chain id, block number, block hash and ledger hash
+    pub signature: Vec<u8>,
+    /// When the checkpoint was signed
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Kind of a header digest item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DigestLogKind {
+    /// Message from the consensus engine to the runtime (e.g. the QPoW miner)
+    PreRuntime,
+    /// Message from the runtime to the consensus engine
+    Consensus,
+    /// Seal added by the block author (e.g. the QPoW nonce)
+    Seal,
+    /// Any other data
+    Other,
+    /// The runtime code or heap pages changed in this block
+    RuntimeEnvironmentUpdated,
+}
+
+impl DigestLogKind {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreRuntime => "pre_runtime",
+            Self::Consensus => "consensus",
+            Self::Seal => "seal",
+            Self::Other => "other",
+            Self::RuntimeEnvironmentUpdated => "runtime_environment_updated",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "pre_runtime" => Self::PreRuntime,
+            "consensus" => Self::Consensus,
+            "seal" => Self::Seal,
+            "runtime_environment_updated" => Self::RuntimeEnvironmentUpdated,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for DigestLogKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A digest item of a block header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestLog {
+    /// Block hash
+    pub block_hash: Vec<u8>,
+    /// Block number (height)
+    pub block_number: i64,
+    /// Position of the item in the digest
+    pub log_index: i32,
+    /// Kind of digest item
+    pub kind: DigestLogKind,
+    /// Consensus engine id (e.g. `pow_`), for pre-runtime, consensus and seal items
+    pub engine_id: Option<String>,
+    /// Raw item payload; empty for `RuntimeEnvironmentUpdated`
+    pub data: Vec<u8>,
+}
+
 /// Reasons for balance changes
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BalanceChangeReason {
@@ -69,6 +354,20 @@ pub enum BalanceChangeReason {
     Slash,
     /// Staking reward
     StakingReward,
+    /// New asset units minted
+    Issue,
+    /// Asset units destroyed
+    Burn,
+    /// Funds minted by a verified wormhole proof
+    Wormhole,
+    /// Funds claimed from a MerkleAirdrop campaign
+    AirdropClaim,
+    /// Treasury share of the block reward
+    TreasuryReward,
+    /// Referendum deposit slashed (`DepositSlashed`)
+    DepositSlash,
+    /// Referendum deposit refunded (`DecisionDepositRefunded`, `SubmissionDepositRefunded`)
+    DepositRefund,
     /// Other reason (with description)
     Other(String),
 }
@@ -86,11 +385,19 @@ impl BalanceChangeReason {
             Self::Withdrawal => "withdrawal",
             Self::Slash => "slash",
             Self::StakingReward => "staking_reward",
+            Self::Issue => "issue",
+            Self::Burn => "burn",
+            Self::Wormhole => "wormhole",
+            Self::AirdropClaim => "airdrop_claim",
+            Self::TreasuryReward => "treasury_reward",
+            Self::DepositSlash => "deposit_slash",
+            Self::DepositRefund => "deposit_refund",
             Self::Other(reason) => reason,
         }
     }
 
     /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "endowment" => Self::Endowment,
@@ -102,6 +409,13 @@ impl BalanceChangeReason {
             "withdrawal" => Self::Withdrawal,
             "slash" => Self::Slash,
             "staking_reward" => Self::StakingReward,
+            "issue" => Self::Issue,
+            "burn" => Self::Burn,
+            "wormhole" => Self::Wormhole,
+            "airdrop_claim" => Self::AirdropClaim,
+            "treasury_reward" => Self::TreasuryReward,
+            "deposit_slash" => Self::DepositSlash,
+            "deposit_refund" => Self::DepositRefund,
             other => Self::Other(other.to_string()),
         }
     }
@@ -120,6 +434,8 @@ pub struct BalanceChange {
     pub id: Option<i64>,
     /// Account address (32 bytes typically)
     pub account: Vec<u8>,
+    /// Asset the change applies to (None for the native token)
+    pub asset_id: Option<i64>,
     /// Block number where change occurred
     pub block_number: i64,
     /// Event index within the block
@@ -135,6 +451,14 @@ pub struct BalanceChange {
     pub event_pallet: String,
     /// Event variant name
     pub event_variant: String,
+    /// Index of the outermost batch item the change happened in
+    pub batch_index: Option<i32>,
+    /// Position in the call tree for changes inside batches (e.g. `3.1`)
+    pub call_path: Option<String>,
+    /// Hash of the block the change was made in
+    pub block_hash: Option<Vec<u8>>,
+    /// False once the block lost a fork; such changes are kept but no longer count
+    pub is_canonical: bool,
     /// Block timestamp
     pub block_ts: DateTime<Utc>,
 }
@@ -156,6 +480,7 @@ impl BalanceChange {
         Self {
             id: None,
             account,
+            asset_id: None,
             block_number,
             event_index,
             delta,
@@ -163,18 +488,38 @@ impl BalanceChange {
             extrinsic_hash,
             event_pallet,
             event_variant,
+            batch_index: None,
+            call_path: None,
+            block_hash: None,
+            is_canonical: true,
             block_ts,
         }
     }
 
+    /// Attribute the change to an asset instead of the native token
+    pub fn with_asset(mut self, asset_id: i64) -> Self {
+        self.asset_id = Some(asset_id);
+        self
+    }
+
+    /// Whether the change moves the native token
+    pub fn is_native(&self) -> bool {
+        self.asset_id.is_none()
+    }
+
     /// Get account as hex string
     pub fn account_hex(&self) -> String {
         ::hex::encode(&self.account)
     }
 
+    /// Get account as an SS58 address
+    pub fn account_ss58(&self, prefix: u16) -> String {
+        account_to_ss58(&self.account, prefix)
+    }
+
     /// Get extrinsic hash as hex string
     pub fn extrinsic_hash_hex(&self) -> Option<String> {
-        self.extrinsic_hash.as_ref().map(|h| ::hex::encode(h))
+        self.extrinsic_hash.as_ref().map(::hex::encode)
     }
 
     /// Check if this is a credit (positive balance change)
@@ -188,114 +533,1707 @@ impl BalanceChange {
     }
 }
 
-/// Statistics for an account
+/// Represents a decoded extrinsic together with its dispatch outcome
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AccountStats {
-    /// Account address
+pub struct Extrinsic {
+    /// Auto-incrementing ID (None for new records)
+    pub id: Option<i64>,
+    /// Block number containing the extrinsic
+    pub block_number: i64,
+    /// Position of the extrinsic within the block
+    pub extrinsic_index: i32,
+    /// Blake2-256 hash of the encoded extrinsic
+    pub hash: Vec<u8>,
+    /// Whether the extrinsic carries a signature
+    pub is_signed: bool,
+    /// Signer account (None for unsigned extrinsics)
+    pub signer: Option<Vec<u8>>,
+    /// Account nonce from the signed extensions
+    pub nonce: Option<i64>,
+    /// Tip from the signed extensions
+    /// Using string to handle arbitrary precision
+    pub tip: Option<String>,
+    /// Pallet of the dispatched call
+    pub call_pallet: String,
+    /// Function name of the dispatched call
+    pub call_function: String,
+    /// Decoded call arguments
+    pub args: serde_json::Value,
+    /// Whether the extrinsic dispatched successfully
+    pub success: bool,
+    /// Dispatch error for failed extrinsics (e.g. "Balances::InsufficientBalance")
+    pub dispatch_error: Option<String>,
+    /// Fee actually paid for the extrinsic
+    pub fee: Option<String>,
+    /// Signature scheme (e.g. "ML-DSA-87"), None for unsigned extrinsics
+    pub signature_scheme: Option<String>,
+    /// Length of the signature itself, excluding any embedded public key
+    pub signature_len: Option<i32>,
+    /// Public key carried alongside the signature
+    pub signer_public_key: Option<Vec<u8>>,
+    /// Result of offline signature verification (None when not checked)
+    pub signature_valid: Option<bool>,
+    /// ML-DSA context the signature was verified under (empty for the empty context)
+    pub signature_context: Option<String>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+impl Extrinsic {
+    /// Get extrinsic hash as hex string
+    pub fn hash_hex(&self) -> String {
+        ::hex::encode(&self.hash)
+    }
+
+    /// Get signer as hex string
+    pub fn signer_hex(&self) -> Option<String> {
+        self.signer.as_ref().map(::hex::encode)
+    }
+
+    /// Get signer as an SS58 address
+    pub fn signer_ss58(&self, prefix: u16) -> Option<String> {
+        self.signer.as_ref().map(|s| account_to_ss58(s, prefix))
+    }
+
+    /// Get the call as "Pallet::function"
+    pub fn call_name(&self) -> String {
+        format!("{}::{}", self.call_pallet, self.call_function)
+    }
+}
+
+/// A public key revealed on-chain by a signed extrinsic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKey {
+    /// Account the key signed for
     pub account: Vec<u8>,
-    /// Current balance (at latest indexed block)
-    pub balance: String,
-    /// First seen at block number
+    /// Full public key
+    pub public_key: Vec<u8>,
+    /// Signature scheme the key belongs to
+    pub signature_scheme: String,
+    /// Hash that derives the account from the key ("poseidon", "blake2_256"), if any
+    pub derivation: Option<String>,
+    /// Whether the account derives from the public key
+    pub account_matches: bool,
+    /// Block where the key was first seen
     pub first_seen_block: i64,
-    /// Last activity at block number
-    pub last_activity_block: i64,
-    /// Total number of balance changes
-    pub total_changes: i64,
+    /// Index of the first extrinsic carrying the key
+    pub first_extrinsic_index: i32,
+    /// Hash of the first extrinsic carrying the key
+    pub first_extrinsic_hash: Vec<u8>,
 }
 
-/// Chain indexing progress
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct IndexProgress {
-    /// Chain ID (base58 encoded genesis hash)
-    pub chain_id: String,
-    /// Latest indexed block number
-    pub latest_block: i64,
-    /// Latest indexed block hash
-    pub latest_block_hash: Vec<u8>,
-    /// Timestamp of latest indexed block
-    pub latest_block_ts: DateTime<Utc>,
-    /// Total blocks indexed
-    pub blocks_indexed: i64,
-    /// Total balance changes recorded
-    pub balance_changes_recorded: i64,
-    /// Indexing started at
-    pub started_at: DateTime<Utc>,
-    /// Last updated at
-    pub updated_at: DateTime<Utc>,
+impl PublicKey {
+    /// Get account as hex string
+    pub fn account_hex(&self) -> String {
+        ::hex::encode(&self.account)
+    }
+
+    /// Get account as an SS58 address
+    pub fn account_ss58(&self, prefix: u16) -> String {
+        account_to_ss58(&self.account, prefix)
+    }
+
+    /// Get public key as hex string
+    pub fn public_key_hex(&self) -> String {
+        ::hex::encode(&self.public_key)
+    }
 }
 
-/// Runtime metadata information
+/// An account's SS58 address, kept so SQL and GraphQL clients can filter by address
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RuntimeMetadata {
-    /// Spec version of the runtime
-    pub spec_version: i32,
-    /// Implementation version of the runtime
-    pub impl_version: i32,
-    /// Transaction version of the runtime
-    pub transaction_version: i32,
-    /// State version of the runtime
-    pub state_version: i32,
-    /// First block where this runtime was seen
+pub struct Account {
+    /// Raw account bytes
+    pub account: Vec<u8>,
+    /// SS58 address with the chain's prefix
+    pub ss58: String,
+    /// Block where the account was first seen
     pub first_seen_block: i64,
-    /// Last block where this runtime was seen (None if still active)
-    pub last_seen_block: Option<i64>,
-    /// The actual metadata bytes (SCALE encoded)
-    pub metadata_bytes: Vec<u8>,
-    /// Hash of the metadata bytes for quick comparison
-    pub metadata_hash: Vec<u8>,
-    /// When this record was created
-    pub created_at: DateTime<Utc>,
-    /// When this record was last updated
-    pub updated_at: DateTime<Utc>,
 }
 
-impl RuntimeMetadata {
-    /// Create a new runtime metadata record
-    pub fn new(
-        spec_version: i32,
-        impl_version: i32,
-        transaction_version: i32,
-        state_version: i32,
-        first_seen_block: i64,
-        metadata_bytes: Vec<u8>,
-    ) -> Self {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(&metadata_bytes);
-        let metadata_hash = hasher.finalize().to_vec();
-
+impl Account {
+    /// Create an account record, encoding the address with the chain's prefix
+    pub fn new(account: &AccountId, prefix: u16, first_seen_block: i64) -> Self {
         Self {
-            spec_version,
-            impl_version,
-            transaction_version,
-            state_version,
+            account: account.to_vec(),
+            ss58: account.to_ss58(prefix),
             first_seen_block,
-            last_seen_block: None,
-            metadata_bytes,
-            metadata_hash,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
         }
     }
+}
 
-    /// Get the metadata size in bytes
-    pub fn size(&self) -> usize {
-        self.metadata_bytes.len()
+/// A fungible asset registered with the Assets pallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Asset {
+    /// Asset ID
+    pub asset_id: i64,
+    /// Account that created the asset and paid its deposit
+    pub creator: Option<Vec<u8>>,
+    /// Current owner
+    pub owner: Option<Vec<u8>>,
+    /// Account allowed to mint
+    pub issuer: Option<Vec<u8>>,
+    /// Account allowed to burn and transfer on behalf of holders
+    pub admin: Option<Vec<u8>>,
+    /// Account allowed to freeze holders
+    pub freezer: Option<Vec<u8>>,
+    /// Display name from the asset metadata
+    pub name: Option<String>,
+    /// Ticker symbol from the asset metadata
+    pub symbol: Option<String>,
+    /// Decimals from the asset metadata
+    pub decimals: Option<i32>,
+    /// Whether transfers of the asset are frozen
+    pub is_frozen: bool,
+    /// Block where the asset was created (None if created before indexing began)
+    pub created_block: Option<i64>,
+    /// Block of the last change to this record
+    pub updated_block: i64,
+    /// Block where the asset was destroyed
+    pub destroyed_block: Option<i64>,
+}
+
+impl Asset {
+    /// Create an empty registry record, filled in from the asset's events
+    pub fn new(asset_id: i64, block_number: i64) -> Self {
+        Self {
+            asset_id,
+            updated_block: block_number,
+            ..Default::default()
+        }
     }
+}
 
-    /// Get metadata hash as hex string
-    pub fn metadata_hash_hex(&self) -> String {
-        ::hex::encode(&self.metadata_hash)
+/// A change to an account's unvested amount reported by the Vesting pallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VestingUpdate {
+    /// Account whose vesting changed
+    pub account: Vec<u8>,
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Amount still locked after the event (as string for arbitrary precision)
+    pub unvested: String,
+    /// Whether all of the account's schedules have completed (`VestingCompleted`)
+    pub completed: bool,
+    /// Extrinsic that triggered the event, if any
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// One of an account's vesting schedules as stored at a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VestingSchedule {
+    /// Account the schedule belongs to
+    pub account: Vec<u8>,
+    /// Block at which the schedules were read
+    pub block_number: i64,
+    /// Position of the schedule in the account's schedule list
+    pub schedule_index: i32,
+    /// Amount locked when the schedule started
+    pub locked: String,
+    /// Amount unlocked per block
+    pub per_block: String,
+    /// Block from which unlocking starts
+    pub starting_block: i64,
+}
+
+impl VestingSchedule {
+    /// Amount of this schedule still locked at a block
+    pub fn locked_at(&self, block_number: i64) -> u128 {
+        let locked: u128 = self.locked.parse().unwrap_or(0);
+        let per_block: u128 = self.per_block.parse().unwrap_or(0);
+        let elapsed = block_number.saturating_sub(self.starting_block).max(0) as u128;
+        locked.saturating_sub(per_block.saturating_mul(elapsed))
     }
+}
 
-    /// Check if this runtime version is still active
-    pub fn is_active(&self) -> bool {
-        self.last_seen_block.is_none()
+/// An account's native balance as stored in `System::Account` at a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountBalance {
+    /// Account address
+    pub account: Vec<u8>,
+    /// Block at which the balance was read
+    pub block_number: i64,
+    /// Free balance (as string for arbitrary precision)
+    pub free: String,
+    /// Reserved balance, including holds
+    pub reserved: String,
+    /// Largest lock or freeze on the account, applying to free and reserved together
+    pub frozen: String,
+    /// Free balance that can be transferred: `free - max(frozen - reserved, 0)`
+    pub transferable: String,
+    /// Whether the account's locks were read at this block too
+    pub locks_refreshed: bool,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+impl AccountBalance {
+    /// Balance from the raw `AccountData` amounts
+    pub fn new(
+        account: Vec<u8>,
+        block_number: i64,
+        free: u128,
+        reserved: u128,
+        frozen: u128,
+        block_ts: DateTime<Utc>,
+    ) -> Self {
+        let untouchable = frozen.saturating_sub(reserved);
+        Self {
+            account,
+            block_number,
+            free: free.to_string(),
+            reserved: reserved.to_string(),
+            frozen: frozen.to_string(),
+            transferable: free.saturating_sub(untouchable).to_string(),
+            locks_refreshed: false,
+            block_ts,
+        }
     }
+}
 
-    /// Update the last seen block
+/// Storage a balance lock was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BalanceLockKind {
+    /// `Balances::Locks`, identified by an 8-byte `LockIdentifier`
+    Lock,
+    /// `Balances::Reserves`, named reserves
+    Reserve,
+    /// `Balances::Holds`, identified by a `RuntimeHoldReason`
+    Hold,
+    /// `Balances::Freezes`, identified by a `RuntimeFreezeReason`
+    Freeze,
+}
+
+impl BalanceLockKind {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lock => "lock",
+            Self::Reserve => "reserve",
+            Self::Hold => "hold",
+            Self::Freeze => "freeze",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "reserve" => Self::Reserve,
+            "hold" => Self::Hold,
+            "freeze" => Self::Freeze,
+            _ => Self::Lock,
+        }
+    }
+}
+
+impl std::fmt::Display for BalanceLockKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A lock, named reserve, hold or freeze on an account as stored at a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceLock {
+    /// Account the lock applies to
+    pub account: Vec<u8>,
+    /// Block at which the locks were read
+    pub block_number: i64,
+    /// Storage the lock was read from
+    pub kind: BalanceLockKind,
+    /// Lock identifier: the 8-byte id as text (e.g. `vesting `) or hex when not printable,
+    /// `Pallet::Reason` for holds and freezes
+    pub lock_id: String,
+    /// Amount locked (as string for arbitrary precision)
+    pub amount: String,
+    /// Balance uses a lock applies to (`Fee`, `Misc` or `All`), locks only
+    pub reasons: Option<String>,
+}
+
+/// Lifecycle state of a reversible transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingTransferStatus {
+    /// Scheduled and waiting for its delay to pass
+    Pending,
+    /// Cancelled by the interceptor before execution
+    Cancelled,
+    /// Executed successfully
+    Executed,
+    /// Executed but the transfer call failed
+    Failed,
+}
+
+impl PendingTransferStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Cancelled => "cancelled",
+            Self::Executed => "executed",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "cancelled" => Self::Cancelled,
+            "executed" => Self::Executed,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+impl std::fmt::Display for PendingTransferStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A transfer scheduled through the ReversibleTransfers pallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransfer {
+    /// Transaction ID assigned by the pallet
+    pub tx_id: Vec<u8>,
+    /// Sender
+    pub from_account: Vec<u8>,
+    /// Recipient
+    pub to_account: Vec<u8>,
+    /// Account allowed to cancel the transfer
+    pub interceptor: Vec<u8>,
+    /// Amount (as string for arbitrary precision)
+    pub amount: String,
+    /// Current lifecycle state
+    pub status: PendingTransferStatus,
+    /// When the transfer becomes executable, as the runtime's `DispatchTime`
+    pub execute_at: serde_json::Value,
+    /// Execution block when `execute_at` is a block number
+    pub execute_at_block: Option<i64>,
+    /// Block where the transfer was scheduled (None if scheduled before indexing began)
+    pub scheduled_block: Option<i64>,
+    /// Extrinsic that scheduled the transfer
+    pub scheduled_extrinsic_hash: Option<Vec<u8>>,
+    /// Block where the transfer was cancelled or executed
+    pub resolved_block: Option<i64>,
+    /// Index of the cancelling or executing event
+    pub resolved_event_index: Option<i32>,
+    /// Extrinsic that cancelled or executed the transfer (None when run by the scheduler)
+    pub resolved_extrinsic_hash: Option<Vec<u8>>,
+    /// Account that cancelled the transfer
+    pub cancelled_by: Option<Vec<u8>>,
+    /// Dispatch error of a failed execution
+    pub dispatch_error: Option<String>,
+}
+
+/// An account that enabled high-security mode (reversible outgoing transfers)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighSecurityAccount {
+    /// Protected account
+    pub account: Vec<u8>,
+    /// Account allowed to cancel outgoing transfers
+    pub interceptor: Vec<u8>,
+    /// Account allowed to recover funds
+    pub recoverer: Vec<u8>,
+    /// Delay applied to outgoing transfers, as the runtime's `BlockNumberOrTimestamp`
+    pub delay: serde_json::Value,
+    /// Block where high security was set
+    pub set_block: i64,
+    /// Extrinsic that set high security
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+/// A wormhole proof accepted by the Wormhole pallet (`ProofVerified`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WormholeProof {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Extrinsic that submitted the proof
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Account credited with the exit amount, from the accompanying `Balances::Minted`
+    pub exit_account: Option<Vec<u8>>,
+    /// Amount minted on exit (as string for arbitrary precision)
+    pub exit_amount: String,
+    /// Block the proof was generated against (`block_number` call argument)
+    pub proof_block_number: Option<i64>,
+    /// Blake2-256 hash of the submitted proof bytes
+    pub proof_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A nullifier marked as used in `Wormhole::UsedNullifiers`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WormholeNullifier {
+    /// The 32-byte nullifier
+    pub nullifier: Vec<u8>,
+    /// Block where the nullifier was first used
+    pub block_number: i64,
+    /// Extrinsic that used it, when the block verified a single proof
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A `Balances::TransferProof` record created by a transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferProof {
+    /// Transfer counter value the proof is keyed by
+    pub transfer_count: i64,
+    /// Sending account
+    pub from_account: Vec<u8>,
+    /// Receiving account
+    pub to_account: Vec<u8>,
+    /// Amount transferred (as string for arbitrary precision)
+    pub amount: String,
+    /// Block where the transfer happened
+    pub block_number: i64,
+    /// Extrinsic that made the transfer, if matched to a `Balances::Transfer` event
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A MerkleAirdrop campaign
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Airdrop {
+    /// Airdrop ID
+    pub airdrop_id: i64,
+    /// Merkle root claims are proven against
+    pub merkle_root: Option<Vec<u8>>,
+    /// Account that created the campaign
+    pub creator: Option<Vec<u8>>,
+    /// Blocks over which claimed funds vest, if claims vest
+    pub vesting_period: Option<i64>,
+    /// Blocks before vesting of claimed funds starts
+    pub vesting_delay: Option<i64>,
+    /// Total funded through `AirdropFunded` (as string for arbitrary precision)
+    pub funded: String,
+    /// Total claimed
+    pub claimed: String,
+    /// Number of claims
+    pub claim_count: i64,
+    /// Balance left to claim, as held in `AirdropInfo`
+    pub balance: String,
+    /// Block where the campaign was created (None if created before indexing began)
+    pub created_block: Option<i64>,
+    /// Extrinsic that created the campaign
+    pub created_extrinsic_hash: Option<Vec<u8>>,
+    /// Block of the last change to this record
+    pub updated_block: i64,
+    /// Block where the campaign was deleted
+    pub deleted_block: Option<i64>,
+}
+
+impl Airdrop {
+    /// Create an empty campaign record, filled in from the campaign's events
+    pub fn new(airdrop_id: i64, block_number: i64) -> Self {
+        Self {
+            airdrop_id,
+            funded: "0".to_string(),
+            claimed: "0".to_string(),
+            balance: "0".to_string(),
+            updated_block: block_number,
+            ..Default::default()
+        }
+    }
+}
+
+/// A claim paid out by a MerkleAirdrop campaign (`Claimed`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirdropClaim {
+    /// Campaign the claim was made against
+    pub airdrop_id: i64,
+    /// Account that received the funds
+    pub account: Vec<u8>,
+    /// Amount claimed (as string for arbitrary precision)
+    pub amount: String,
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Claim extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Kind of treasury spend: a legacy proposal (`ProposalIndex`) or a spend (`SpendIndex`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TreasurySpendKind {
+    /// Approved through `spend_local`, paid out by `Awarded` at the next spend period
+    Proposal,
+    /// Approved through `spend`, paid out by `payout`
+    Spend,
+}
+
+impl TreasurySpendKind {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Proposal => "proposal",
+            Self::Spend => "spend",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "proposal" => Self::Proposal,
+            _ => Self::Spend,
+        }
+    }
+}
+
+impl std::fmt::Display for TreasurySpendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Lifecycle state of a treasury spend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TreasurySpendStatus {
+    /// Approved and waiting to be paid
+    Approved,
+    /// Proposal paid out at a spend period (`Awarded`)
+    Awarded,
+    /// Payment made (`Paid`)
+    Paid,
+    /// Payment failed (`PaymentFailed`) and may be retried
+    Failed,
+    /// Payment confirmed and the spend removed (`SpendProcessed`)
+    Processed,
+    /// Spend cancelled before payout (`AssetSpendVoided`)
+    Voided,
+}
+
+impl TreasurySpendStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Approved => "approved",
+            Self::Awarded => "awarded",
+            Self::Paid => "paid",
+            Self::Failed => "failed",
+            Self::Processed => "processed",
+            Self::Voided => "voided",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "awarded" => Self::Awarded,
+            "paid" => Self::Paid,
+            "failed" => Self::Failed,
+            "processed" => Self::Processed,
+            "voided" => Self::Voided,
+            _ => Self::Approved,
+        }
+    }
+}
+
+impl std::fmt::Display for TreasurySpendStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A treasury spend and its lifecycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasurySpend {
+    /// Proposal or spend
+    pub kind: TreasurySpendKind,
+    /// `ProposalIndex` or `SpendIndex`, depending on the kind
+    pub spend_index: i64,
+    /// Account receiving the funds
+    pub beneficiary: Option<Vec<u8>>,
+    /// Asset paid out, as the runtime's `AssetKind` (null for the native token)
+    pub asset_kind: serde_json::Value,
+    /// Amount (as string for arbitrary precision)
+    pub amount: String,
+    /// Current lifecycle state
+    pub status: TreasurySpendStatus,
+    /// First block the spend can be paid out
+    pub valid_from: Option<i64>,
+    /// Block after which the spend expires
+    pub expire_at: Option<i64>,
+    /// Paymaster payment id of the latest payment attempt
+    pub payment_id: Option<serde_json::Value>,
+    /// Block where the spend was approved (None if approved before indexing began)
+    pub approved_block: Option<i64>,
+    /// Extrinsic that approved the spend
+    pub approved_extrinsic_hash: Option<Vec<u8>>,
+    /// Block of the latest lifecycle transition after approval
+    pub resolved_block: Option<i64>,
+    /// Extrinsic of the latest lifecycle transition
+    pub resolved_extrinsic_hash: Option<Vec<u8>>,
+}
+
+/// A movement of funds into (positive delta) or out of (negative delta) the treasury
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryLedgerEntry {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Pallet that emitted the event
+    pub event_pallet: String,
+    /// Event variant (e.g. "Deposit", "Awarded")
+    pub event_variant: String,
+    /// Signed amount (as string for arbitrary precision)
+    pub delta: String,
+    /// Counterparty, e.g. the beneficiary of a payout
+    pub account: Option<Vec<u8>>,
+    /// Spend kind and index for payouts
+    pub spend_kind: Option<TreasurySpendKind>,
+    pub spend_index: Option<i64>,
+    /// Extrinsic that triggered the event, if any
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// The treasury account's balance at a block where it changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryBalance {
+    /// Block number
+    pub block_number: i64,
+    /// Treasury account, derived from the pallet id
+    pub account: Vec<u8>,
+    /// Free balance (as string for arbitrary precision)
+    pub free: String,
+    /// Reserved balance
+    pub reserved: String,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Lifecycle state of a referendum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferendumStatus {
+    /// Submitted and waiting for a decision deposit or a deciding slot
+    #[default]
+    Preparing,
+    /// In its decision period
+    Deciding,
+    /// Passing and in its confirmation period
+    Confirming,
+    /// Confirmed and approved (`Approved`)
+    Approved,
+    /// Decision period ended without approval (`Rejected`)
+    Rejected,
+    /// Cancelled by the cancel origin (`Cancelled`)
+    Cancelled,
+    /// Never got a decision deposit or deciding slot in time (`TimedOut`)
+    TimedOut,
+    /// Killed by the kill origin, deposits slashed (`Killed`)
+    Killed,
+}
+
+impl ReferendumStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Preparing => "preparing",
+            Self::Deciding => "deciding",
+            Self::Confirming => "confirming",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::Cancelled => "cancelled",
+            Self::TimedOut => "timed_out",
+            Self::Killed => "killed",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "deciding" => Self::Deciding,
+            "confirming" => Self::Confirming,
+            "approved" => Self::Approved,
+            "rejected" => Self::Rejected,
+            "cancelled" => Self::Cancelled,
+            "timed_out" => Self::TimedOut,
+            "killed" => Self::Killed,
+            _ => Self::Preparing,
+        }
+    }
+
+    /// Whether the referendum has concluded
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Preparing | Self::Deciding | Self::Confirming)
+    }
+}
+
+impl std::fmt::Display for ReferendumStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A referendum of a `Referenda` pallet instance and its current state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Referendum {
+    /// Referenda pallet instance (e.g. "Referenda", "TechReferenda")
+    pub pallet: String,
+    /// `ReferendumIndex`
+    pub referendum_index: i64,
+    /// Track the referendum is on
+    pub track: Option<i32>,
+    /// Origin the proposal is dispatched with
+    pub origin: Option<serde_json::Value>,
+    /// Bounded proposal call (`Legacy`/`Inline`/`Lookup`)
+    pub proposal: Option<serde_json::Value>,
+    /// Enactment moment (`At`/`After`)
+    pub enactment: Option<serde_json::Value>,
+    /// Current lifecycle state
+    pub status: ReferendumStatus,
+    /// Block the referendum was submitted at
+    pub submitted_block: Option<i64>,
+    /// Submission deposit and its depositor
+    pub submission_deposit_account: Option<Vec<u8>>,
+    pub submission_deposit: Option<String>,
+    /// Block where the submission deposit was refunded
+    pub submission_deposit_refunded_block: Option<i64>,
+    /// Decision deposit and its depositor
+    pub decision_deposit_account: Option<Vec<u8>>,
+    pub decision_deposit: Option<String>,
+    /// Block where the decision deposit was refunded
+    pub decision_deposit_refunded_block: Option<i64>,
+    /// Block the decision period started
+    pub decision_started_block: Option<i64>,
+    /// Block the current confirmation period started
+    pub confirm_started_block: Option<i64>,
+    /// Block the current confirmation period ends, as held in `DecidingStatus`
+    pub confirm_end_block: Option<i64>,
+    /// Block the referendum concluded
+    pub concluded_block: Option<i64>,
+    /// Latest tally: ayes, nays and support (bare ayes for ranked collectives)
+    pub ayes: String,
+    pub nays: String,
+    pub support: String,
+    /// Extrinsic that submitted the referendum
+    pub submitted_extrinsic_hash: Option<Vec<u8>>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+impl Referendum {
+    /// Create an empty record for a referendum first seen at `block_number`
+    pub fn new(pallet: &str, referendum_index: i64, block_number: i64) -> Self {
+        Self {
+            pallet: pallet.to_string(),
+            referendum_index,
+            ayes: "0".to_string(),
+            nays: "0".to_string(),
+            support: "0".to_string(),
+            updated_block: block_number,
+            ..Default::default()
+        }
+    }
+}
+
+/// A status transition of a referendum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferendumStatusChange {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Referenda pallet instance
+    pub pallet: String,
+    /// `ReferendumIndex`
+    pub referendum_index: i64,
+    /// Event variant (e.g. "DecisionStarted", "Approved")
+    pub event_variant: String,
+    /// Status after the event
+    pub status: ReferendumStatus,
+    /// Tally carried by the event
+    pub ayes: Option<String>,
+    pub nays: Option<String>,
+    pub support: Option<String>,
+    /// Extrinsic that triggered the transition, if any
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A vote cast (or removed) on a referendum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferendumVote {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Referenda pallet instance the poll belongs to
+    pub pallet: String,
+    /// Poll index (None when the vote was not cast by a top-level call)
+    pub referendum_index: Option<i64>,
+    /// Voter
+    pub account: Vec<u8>,
+    /// `standard`, `split`, `split_abstain` (ConvictionVoting) or `aye`, `nay` (ranked collective)
+    pub vote_kind: String,
+    /// Direction of a standard or collective vote
+    pub aye: Option<bool>,
+    /// Conviction multiplier of a standard vote (0 = none, 1-6 = Locked1x-Locked6x)
+    pub conviction: Option<i16>,
+    /// Amounts per side (as string for arbitrary precision); vote weight for collectives
+    pub aye_amount: String,
+    pub nay_amount: String,
+    pub abstain_amount: String,
+    /// Balance locked by the vote
+    pub lock_amount: String,
+    /// Whether this is a `VoteRemoved`
+    pub removed: bool,
+    /// Voting extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A ConvictionVoting delegation or undelegation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteDelegation {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Delegating account
+    pub account: Vec<u8>,
+    /// Delegate (None for an undelegation)
+    pub target: Option<Vec<u8>>,
+    /// Voting class (track), when known from the call
+    pub class: Option<i32>,
+    /// Conviction multiplier (0 = none, 1-6 = Locked1x-Locked6x)
+    pub conviction: Option<i16>,
+    /// Delegated balance, locked while delegating
+    pub balance: Option<String>,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A membership or rank change in a ranked collective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectiveRankChange {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Collective pallet instance (e.g. "TechCollective")
+    pub collective: String,
+    /// Member
+    pub account: Vec<u8>,
+    /// `added`, `rank_changed`, `removed`, `exchanged_out` or `exchanged_in`
+    pub change: String,
+    /// Rank after the change (rank held when removed)
+    pub rank: i32,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Current membership of a ranked collective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectiveMember {
+    /// Collective pallet instance
+    pub collective: String,
+    /// Member
+    pub account: Vec<u8>,
+    /// Current (or last) rank
+    pub rank: i32,
+    /// Block the member joined, if seen
+    pub joined_block: Option<i64>,
+    /// Block the member left (None while a member)
+    pub removed_block: Option<i64>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+/// Recovery configuration of an account (`Recoverable`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryConfig {
+    /// Recoverable account
+    pub account: Vec<u8>,
+    /// Friends who can vouch for a rescuer
+    pub friends: Vec<Vec<u8>>,
+    /// Vouches needed to recover the account
+    pub threshold: i32,
+    /// Blocks between initiating and claiming a recovery
+    pub delay_period: i64,
+    /// Deposit reserved for the configuration (as string for arbitrary precision)
+    pub deposit: String,
+    /// Block the configuration was created (None if created before indexing began)
+    pub created_block: Option<i64>,
+    /// Extrinsic that created the configuration
+    pub created_extrinsic_hash: Option<Vec<u8>>,
+    /// Block the configuration was removed
+    pub removed_block: Option<i64>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+/// Lifecycle state of a recovery attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecoveryStatus {
+    /// Initiated and collecting vouches
+    #[default]
+    Active,
+    /// Claimed by the rescuer (`AccountRecovered`), still open
+    Recovered,
+    /// Closed by the lost account (`RecoveryClosed`), deposit paid to the lost account
+    Closed,
+}
+
+impl RecoveryStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Recovered => "recovered",
+            Self::Closed => "closed",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "recovered" => Self::Recovered,
+            "closed" => Self::Closed,
+            _ => Self::Active,
+        }
+    }
+}
+
+impl std::fmt::Display for RecoveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A recovery attempt of a lost account by a rescuer (`ActiveRecoveries`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recovery {
+    /// Account being recovered
+    pub lost_account: Vec<u8>,
+    /// Account taking over
+    pub rescuer_account: Vec<u8>,
+    /// Block the recovery was initiated
+    pub initiated_block: i64,
+    /// Extrinsic that initiated the recovery
+    pub initiated_extrinsic_hash: Option<Vec<u8>>,
+    /// Deposit reserved from the rescuer
+    pub deposit: Option<String>,
+    /// Friends who vouched so far
+    pub vouch_count: i32,
+    /// Current lifecycle state
+    pub status: RecoveryStatus,
+    /// Block the rescuer claimed the account
+    pub recovered_block: Option<i64>,
+    /// Block the recovery was closed
+    pub closed_block: Option<i64>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+/// A friend's vouch for a rescuer (`RecoveryVouched`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryVouch {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Account being recovered
+    pub lost_account: Vec<u8>,
+    /// Rescuer vouched for
+    pub rescuer_account: Vec<u8>,
+    /// Vouching friend
+    pub voucher: Vec<u8>,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A lost account handed to a rescuer (`AccountRecovered`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredAccount {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Account recovered
+    pub lost_account: Vec<u8>,
+    /// Account now controlling it through `as_recovered`
+    pub rescuer_account: Vec<u8>,
+    /// Extrinsic (`claim_recovery` or `set_recovered`)
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// One hop of an account's recovery lineage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageHop {
+    /// Distance from the starting account (1 for its own recovery)
+    pub depth: i32,
+    /// Account recovered
+    pub lost_account: Vec<u8>,
+    /// Rescuer it was handed to
+    pub rescuer_account: Vec<u8>,
+    /// Block of the recovery
+    pub recovered_block: i64,
+}
+
+/// Funds moved out of a recovered account after its recovery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredOutflow {
+    /// Recovered account the funds left
+    pub lost_account: Vec<u8>,
+    /// Block number of the transfer
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Receiving account, if the change had a counterparty
+    pub recipient: Option<Vec<u8>>,
+    /// Amount moved (as string for arbitrary precision)
+    pub amount: String,
+    /// Balance change reason
+    pub reason: BalanceChangeReason,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+}
+
+/// QPoW mining data of a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockMining {
+    /// Block number
+    pub block_number: i64,
+    /// Miner from the `pow_` pre-runtime digest (else the `MinerRewarded` recipient)
+    pub miner: Option<Vec<u8>>,
+    /// Winning nonce (`ProofSubmitted`)
+    pub nonce: Option<Vec<u8>>,
+    /// Distance threshold the block was mined against (U512, as string)
+    pub distance_threshold: Option<String>,
+    /// Expected hashes to find the block, `U512::MAX / distance_threshold` (as string)
+    pub difficulty: Option<String>,
+    /// Milliseconds since the previous block (`LastBlockDuration`)
+    pub block_duration_ms: Option<i64>,
+    /// Accumulated chain work after the block (U512, as string)
+    pub total_work: Option<String>,
+    /// Reward paid to the miner (as string for arbitrary precision)
+    pub reward: Option<String>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+impl BlockMining {
+    /// Create an empty record for a block
+    pub fn new(block_number: i64, block_ts: DateTime<Utc>) -> Self {
+        Self {
+            block_number,
+            miner: None,
+            nonce: None,
+            distance_threshold: None,
+            difficulty: None,
+            block_duration_ms: None,
+            total_work: None,
+            reward: None,
+            block_ts,
+        }
+    }
+}
+
+/// A difficulty retarget (`DistanceThresholdAdjusted`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistanceThresholdAdjustment {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Threshold before the adjustment (as string)
+    pub old_distance_threshold: String,
+    /// Threshold after the adjustment (as string)
+    pub new_distance_threshold: String,
+    /// Block time observed over the adjustment period, in milliseconds
+    pub observed_block_time: i64,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// A privileged action of the `Sudo` pallet (`Sudid`, `SudoAsDone`, `KeyChanged`, `KeyRemoved`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SudoAction {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Event variant
+    pub action: String,
+    /// Sudo key in effect when the action was dispatched
+    pub sudo_key: Option<Vec<u8>>,
+    /// New sudo key (`KeyChanged`)
+    pub new_key: Option<Vec<u8>>,
+    /// Account the call was dispatched as (`sudo_as`)
+    pub as_account: Option<Vec<u8>>,
+    /// Pallet of the wrapped call
+    pub call_pallet: Option<String>,
+    /// Function of the wrapped call
+    pub call_function: Option<String>,
+    /// Decoded arguments of the wrapped call
+    pub call_args: Option<serde_json::Value>,
+    /// Whether the wrapped call succeeded (`None` for key changes)
+    pub success: Option<bool>,
+    /// Dispatch error of the wrapped call
+    pub dispatch_error: Option<String>,
+    /// Extrinsic index within the block
+    pub extrinsic_index: Option<i32>,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Lifecycle state of a scheduled task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduledTaskStatus {
+    /// Waiting in its agenda
+    #[default]
+    Scheduled,
+    /// Dispatched successfully (`Dispatched` with `Ok`)
+    Dispatched,
+    /// Dispatched with an error (`Dispatched` with `Err`)
+    Failed,
+    /// Cancelled before it ran (`Canceled`)
+    Canceled,
+    /// Dropped because its preimage was missing (`CallUnavailable`)
+    CallUnavailable,
+    /// Dropped because it can never fit in a block (`PermanentlyOverweight`)
+    Overweight,
+}
+
+impl ScheduledTaskStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Dispatched => "dispatched",
+            Self::Failed => "failed",
+            Self::Canceled => "canceled",
+            Self::CallUnavailable => "call_unavailable",
+            Self::Overweight => "overweight",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "dispatched" => Self::Dispatched,
+            "failed" => Self::Failed,
+            "canceled" => Self::Canceled,
+            "call_unavailable" => Self::CallUnavailable,
+            "overweight" => Self::Overweight,
+            _ => Self::Scheduled,
+        }
+    }
+}
+
+impl std::fmt::Display for ScheduledTaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A task in the `Scheduler` agenda
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    /// Agenda kind: `block` or `timestamp`
+    pub agenda_kind: String,
+    /// Agenda block number, or timestamp in milliseconds
+    pub agenda_at: i64,
+    /// Index of the task within its agenda
+    pub task_index: i32,
+    /// Block the task was scheduled (the block it was first seen if scheduled before indexing)
+    pub scheduled_block: i64,
+    /// Extrinsic that scheduled the task
+    pub scheduled_extrinsic_hash: Option<Vec<u8>>,
+    /// Name of a named task
+    pub task_id: Option<Vec<u8>>,
+    /// Dispatch priority (lower runs first)
+    pub priority: Option<i16>,
+    /// Origin the call is dispatched with (`Root`, `Signed`, `Origins::Treasurer`, ...)
+    pub origin: Option<String>,
+    /// Account of a signed origin
+    pub origin_account: Option<Vec<u8>>,
+    /// Preimage hash of a call stored by hash (`Lookup` / `Legacy`)
+    pub call_hash: Option<Vec<u8>>,
+    /// Encoded call length
+    pub call_len: Option<i32>,
+    /// Encoded call (inline, or resolved from its preimage)
+    pub call_data: Option<Vec<u8>>,
+    /// Pallet of the call
+    pub call_pallet: Option<String>,
+    /// Function of the call
+    pub call_function: Option<String>,
+    /// Decoded arguments of the call
+    pub call_args: Option<serde_json::Value>,
+    /// Period kind of a periodic task (`block` or `timestamp`)
+    pub period_kind: Option<String>,
+    /// Period between runs of a periodic task
+    pub period: Option<i64>,
+    /// Remaining runs of a periodic task
+    pub period_count: Option<i32>,
+    /// Current lifecycle state
+    pub status: ScheduledTaskStatus,
+    /// Dispatch error of a failed task
+    pub dispatch_error: Option<String>,
+    /// Block the task was dispatched, cancelled or dropped
+    pub closed_block: Option<i64>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+/// A `Scheduler` event, kept as an audit log of every scheduling decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerEvent {
+    /// Block number of the event
+    pub block_number: i64,
+    /// Event index within the block
+    pub event_index: i32,
+    /// Event variant
+    pub event_variant: String,
+    /// Agenda kind of the task (`block` or `timestamp`)
+    pub agenda_kind: String,
+    /// Agenda block number or timestamp of the task
+    pub agenda_at: i64,
+    /// Index of the task within its agenda
+    pub task_index: i32,
+    /// Name of a named task
+    pub task_id: Option<Vec<u8>>,
+    /// Dispatch success (`Dispatched` only)
+    pub success: Option<bool>,
+    /// Dispatch error (`Dispatched` only)
+    pub dispatch_error: Option<String>,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Request state of a preimage (`RequestStatusFor`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreimageStatus {
+    /// Noted with a deposit, not requested
+    #[default]
+    Unrequested,
+    /// Requested (by governance or the scheduler), possibly not noted yet
+    Requested,
+    /// Removed from storage (`Cleared`)
+    Cleared,
+}
+
+impl PreimageStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unrequested => "unrequested",
+            Self::Requested => "requested",
+            Self::Cleared => "cleared",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "requested" => Self::Requested,
+            "cleared" => Self::Cleared,
+            _ => Self::Unrequested,
+        }
+    }
+}
+
+impl std::fmt::Display for PreimageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A preimage of the `Preimage` pallet, with the call it encodes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Preimage {
+    /// Preimage hash
+    pub hash: Vec<u8>,
+    /// Preimage length, once known
+    pub length: Option<i32>,
+    /// Account holding the deposit
+    pub depositor: Option<Vec<u8>>,
+    /// Deposit held for the preimage (as string for arbitrary precision)
+    pub deposit: Option<String>,
+    /// Current request state
+    pub status: PreimageStatus,
+    /// Outstanding requests
+    pub request_count: i32,
+    /// Pallet of the encoded call
+    pub call_pallet: Option<String>,
+    /// Function of the encoded call
+    pub call_function: Option<String>,
+    /// Decoded arguments of the encoded call
+    pub call_args: Option<serde_json::Value>,
+    /// Block the preimage was first seen
+    pub first_block: i64,
+    /// Block the preimage bytes were noted (`Noted`)
+    pub noted_block: Option<i64>,
+    /// Extrinsic that noted the preimage
+    pub noted_extrinsic_hash: Option<Vec<u8>>,
+    /// Block the preimage was cleared
+    pub cleared_block: Option<i64>,
+    /// Block of the latest change
+    pub updated_block: i64,
+}
+
+/// A call dispatched from a `Utility` batch (`batch`, `batch_all`, `force_batch`) or
+/// through `dispatch_as`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchItem {
+    /// Block number of the extrinsic
+    pub block_number: i64,
+    /// Extrinsic index within the block
+    pub extrinsic_index: i32,
+    /// Position in the call tree, item indices joined by dots (e.g. `3.1`)
+    pub call_path: String,
+    /// Index of the outermost batch item
+    pub batch_index: i32,
+    /// Index of the item within its batch (0 for `dispatch_as`)
+    pub item_index: i32,
+    /// Nesting depth, 1 for items of the outermost batch
+    pub depth: i32,
+    /// Utility call the item belongs to (e.g. `batch_all`, `dispatch_as`)
+    pub batch_call: String,
+    /// Pallet of the dispatched call
+    pub call_pallet: Option<String>,
+    /// Function of the dispatched call
+    pub call_function: Option<String>,
+    /// Origin the call was dispatched with (`dispatch_as`)
+    pub origin: Option<String>,
+    /// Account of a signed `dispatch_as` origin
+    pub origin_account: Option<Vec<u8>>,
+    /// Whether the call succeeded (`None` when its outcome was rolled back with the batch)
+    pub success: Option<bool>,
+    /// Dispatch error of the call
+    pub dispatch_error: Option<String>,
+    /// First event emitted while the item was dispatched
+    pub first_event_index: Option<i32>,
+    /// Event closing the item (`ItemCompleted`, `ItemFailed`, `BatchInterrupted`, `DispatchedAs`)
+    pub last_event_index: Option<i32>,
+    /// Extrinsic
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Block timestamp
+    pub block_ts: DateTime<Utc>,
+}
+
+/// Statistics for an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStats {
+    /// Account address
+    pub account: Vec<u8>,
+    /// Free plus reserved balance at the latest `account_balances` snapshot
+    pub balance: String,
+    /// First seen at block number
+    pub first_seen_block: i64,
+    /// Last activity at block number
+    pub last_activity_block: i64,
+    /// Total number of balance changes
+    pub total_changes: i64,
+}
+
+/// Identity and display properties of an indexed chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainInfo {
+    /// Chain ID (base58 encoded genesis hash)
+    pub chain_id: String,
+    /// Genesis block hash
+    pub genesis_hash: Vec<u8>,
+    /// Chain name from `system_chain`
+    pub chain_name: String,
+    /// Node implementation name from `system_name`
+    pub node_name: String,
+    /// Node implementation version from `system_version`
+    pub node_version: String,
+    /// Native token symbol
+    pub token_symbol: Option<String>,
+    /// Native token decimals
+    pub token_decimals: Option<i32>,
+    /// SS58 address prefix
+    pub ss58_prefix: i32,
+    /// Raw `system_properties` object
+    pub properties: serde_json::Value,
+    /// Runtime spec version when the info was last refreshed
+    pub spec_version: i32,
+    /// Node endpoints the indexer has connected through
+    pub endpoints: Vec<String>,
+    /// Last refreshed at
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Chain indexing progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexProgress {
+    /// Chain ID (base58 encoded genesis hash)
+    pub chain_id: String,
+    /// Latest indexed block number
+    pub latest_block: i64,
+    /// Latest indexed block hash
+    pub latest_block_hash: Vec<u8>,
+    /// Timestamp of latest indexed block
+    pub latest_block_ts: DateTime<Utc>,
+    /// Total blocks indexed
+    pub blocks_indexed: i64,
+    /// Total balance changes recorded
+    pub balance_changes_recorded: i64,
+    /// Confirmations a best block needs before it is indexed
+    pub finality_depth: Option<i32>,
+    /// Where the finality depth came from (e.g. `QPoW::MaxReorgDepth`, `override`)
+    pub finality_source: Option<String>,
+    /// Deepest best-chain reorg observed, in blocks
+    pub deepest_reorg: i32,
+    /// Indexing started at
+    pub started_at: DateTime<Utc>,
+    /// Last updated at
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lifecycle state of a runtime upgrade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuntimeUpgradeStatus {
+    /// Authorized (`UpgradeAuthorized`), waiting for the code to be applied
+    #[default]
+    Authorized,
+    /// Replaced by a later authorization before it was applied
+    Superseded,
+    /// New code set (`CodeUpdated`)
+    Applied,
+}
+
+impl RuntimeUpgradeStatus {
+    /// Convert to string representation for database storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Authorized => "authorized",
+            Self::Superseded => "superseded",
+            Self::Applied => "applied",
+        }
+    }
+
+    /// Parse from string representation
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "superseded" => Self::Superseded,
+            "applied" => Self::Applied,
+            _ => Self::Authorized,
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeUpgradeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A runtime upgrade, from its authorization to the block its code was set in.
+///
+/// Upgrades applied with `set_code` have no separate authorization; the `CodeUpdated`
+/// event stands in for it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeUpgrade {
+    /// Block of the authorizing event
+    pub authorized_block: i64,
+    /// Event index of the authorizing event
+    pub authorized_event_index: i32,
+    /// Blake2-256 hash of the runtime code
+    pub code_hash: Vec<u8>,
+    /// Whether the new code's version is checked when applied (`UpgradeAuthorized`)
+    pub check_version: Option<bool>,
+    /// Signer of the authorizing extrinsic
+    pub authorizer: Option<Vec<u8>>,
+    /// Call that authorized the upgrade (e.g. `Sudo::sudo`), `Initialization` for
+    /// scheduled enactments
+    pub authorized_via: Option<String>,
+    /// Authorizing extrinsic
+    pub authorization_extrinsic_hash: Option<Vec<u8>>,
+    /// Current state
+    pub status: RuntimeUpgradeStatus,
+    /// Block the code was set in (`CodeUpdated`); the new runtime runs from the next block
+    pub block_number: Option<i64>,
+    /// Event index of `CodeUpdated`
+    pub event_index: Option<i32>,
+    /// Index of the extrinsic that set the code
+    pub extrinsic_index: Option<i32>,
+    /// Extrinsic that set the code
+    pub extrinsic_hash: Option<Vec<u8>>,
+    /// Signer of the extrinsic that set the code (None for `apply_authorized_upgrade`)
+    pub submitter: Option<Vec<u8>>,
+    /// Call of the extrinsic that set the code (e.g. `System::apply_authorized_upgrade`)
+    pub upgrade_call: Option<String>,
+    /// Spec name of the runtime replaced by the upgrade (`System::LastRuntimeUpgrade`)
+    pub previous_spec_name: Option<String>,
+    /// Spec version of the runtime replaced by the upgrade
+    pub previous_spec_version: Option<i32>,
+    /// Spec name of the new runtime
+    pub spec_name: Option<String>,
+    /// Spec version of the new runtime
+    pub spec_version: Option<i32>,
+    /// Implementation version of the new runtime
+    pub impl_version: Option<i32>,
+    /// Transaction version of the new runtime
+    pub transaction_version: Option<i32>,
+    /// State trie version of the new runtime
+    pub state_version: Option<i32>,
+    /// Block a later authorization replaced this one in
+    pub superseded_block: Option<i64>,
+    /// Timestamp of the authorizing block
+    pub authorized_ts: DateTime<Utc>,
+    /// Timestamp of the block the code was set in
+    pub applied_ts: Option<DateTime<Utc>>,
+}
+
+/// Runtime metadata information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetadata {
+    /// Spec version of the runtime
+    pub spec_version: i32,
+    /// Implementation version of the runtime
+    pub impl_version: i32,
+    /// Transaction version of the runtime
+    pub transaction_version: i32,
+    /// State version of the runtime
+    pub state_version: i32,
+    /// First block where this runtime was seen
+    pub first_seen_block: i64,
+    /// Last block where this runtime was seen (None if still active)
+    pub last_seen_block: Option<i64>,
+    /// The actual metadata bytes (SCALE encoded)
+    pub metadata_bytes: Vec<u8>,
+    /// Hash of the metadata bytes for quick comparison
+    pub metadata_hash: Vec<u8>,
+    /// When this record was created
+    pub created_at: DateTime<Utc>,
+    /// When this record was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RuntimeMetadata {
+    /// Create a new runtime metadata record
+    pub fn new(
+        spec_version: i32,
+        impl_version: i32,
+        transaction_version: i32,
+        state_version: i32,
+        first_seen_block: i64,
+        metadata_bytes: Vec<u8>,
+    ) -> Self {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(&metadata_bytes);
+        let metadata_hash = hasher.finalize().to_vec();
+
+        Self {
+            spec_version,
+            impl_version,
+            transaction_version,
+            state_version,
+            first_seen_block,
+            last_seen_block: None,
+            metadata_bytes,
+            metadata_hash,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Get the metadata size in bytes
+    pub fn size(&self) -> usize {
+        self.metadata_bytes.len()
+    }
+
+    /// Get metadata hash as hex string
+    pub fn metadata_hash_hex(&self) -> String {
+        ::hex::encode(&self.metadata_hash)
+    }
+
+    /// Check if this runtime version is still active
+    pub fn is_active(&self) -> bool {
+        self.last_seen_block.is_none()
+    }
+
+    /// Update the last seen block
     pub fn set_last_seen_block(&mut self, block: i64) {
         self.last_seen_block = Some(block);
         self.updated_at = Utc::now();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE_HEX: &str = "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const ALICE_SS58: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_ss58_roundtrip() {
+        let alice = AccountId::parse(ALICE_HEX).unwrap();
+        assert_eq!(alice.to_ss58(42), ALICE_SS58);
+        assert_eq!(AccountId::from_ss58(ALICE_SS58).unwrap(), (alice, 42));
+
+        // Two-byte prefixes round-trip too
+        let address = alice.to_ss58(189);
+        assert_eq!(AccountId::from_ss58(&address).unwrap(), (alice, 189));
+    }
+
+    #[test]
+    fn test_parse_accepts_hex_and_ss58() {
+        let from_hex: AccountId = format!("0x{}", ALICE_HEX).parse().unwrap();
+        let from_ss58: AccountId = ALICE_SS58.parse().unwrap();
+        assert_eq!(from_hex, from_ss58);
+
+        let mut corrupted = ALICE_SS58.to_string();
+        corrupted.replace_range(10..11, "z");
+        assert!(AccountId::parse(&corrupted).is_err());
+        assert!(AccountId::parse("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_vesting_locked_at() {
+        let schedule = VestingSchedule {
+            account: vec![1; 32],
+            block_number: 5,
+            schedule_index: 0,
+            locked: "1000".into(),
+            per_block: "10".into(),
+            starting_block: 100,
+        };
+
+        assert_eq!(schedule.locked_at(50), 1000);
+        assert_eq!(schedule.locked_at(130), 700);
+        assert_eq!(schedule.locked_at(500), 0);
+    }
+}
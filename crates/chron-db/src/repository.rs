@@ -1,8 +1,19 @@
 use crate::{
-    connection::DbConnection,
+    connection::DbExecutor,
     error::{DbError, Result},
     models::{
-        AccountStats, BalanceChange, BalanceChangeReason, Block, IndexProgress, RuntimeMetadata,
+        Account, AccountBalance, AccountId, AccountStats, Airdrop, AirdropClaim, Asset,
+        BalanceChange, BalanceChangeReason, BalanceLock, BalanceLockKind, BatchItem, Block,
+        BlockHeader, BlockMining, ChainInfo, CollectiveMember, CollectiveRankChange, DigestLog,
+        DigestLogKind, DistanceThresholdAdjustment, Extrinsic, ForkBlock, HighSecurityAccount,
+        IndexProgress, LedgerCheckpoint, LineageHop, PendingTransfer, PendingTransferStatus,
+        Preimage, PreimageStatus, PublicKey, RecoveredAccount, RecoveredOutflow, Recovery,
+        RecoveryConfig, RecoveryStatus, RecoveryVouch, Referendum, ReferendumStatus,
+        ReferendumStatusChange, ReferendumVote, RuntimeMetadata, RuntimeUpgrade,
+        RuntimeUpgradeStatus, ScheduledTask, ScheduledTaskStatus, SchedulerEvent, SudoAction,
+        TransferProof, TreasuryBalance, TreasuryLedgerEntry, TreasurySpend, TreasurySpendKind,
+        TreasurySpendStatus, VestingSchedule, VestingUpdate, VoteDelegation, WormholeNullifier,
+        WormholeProof,
     },
 };
 use chrono::Utc;
@@ -10,28 +21,47 @@ use tracing::info;
 
 /// Repository for managing blocks
 pub struct BlockRepository<'a> {
-    conn: &'a DbConnection,
+    conn: &'a dyn DbExecutor,
 }
 
 impl<'a> BlockRepository<'a> {
     /// Create a new block repository
-    pub fn new(conn: &'a DbConnection) -> Self {
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
         Self { conn }
     }
 
-    /// Insert a new block
+    /// Insert a block. A canonical block takes over its height: any other canonical block
+    /// at that height, and the balance changes it made, are flagged non-canonical.
     pub async fn insert(&self, block: &Block) -> Result<()> {
         let schema = self.conn.schema_name()?;
+        if block.is_canonical {
+            let sql = format!(
+                r#"
+                WITH replaced AS (
+                    UPDATE {schema}.blocks SET is_canonical = false
+                    WHERE number = $1 AND hash <> $2 AND is_canonical
+                    RETURNING hash
+                )
+                UPDATE {schema}.balance_changes SET is_canonical = false
+                WHERE block_hash IN (SELECT hash FROM replaced) AND is_canonical
+                "#,
+                schema = schema
+            );
+            self.conn
+                .execute(&sql, &[&block.number, &block.hash])
+                .await?;
+        }
+
         let sql = format!(
             r#"
-            INSERT INTO {schema}.blocks (number, hash, parent_hash, timestamp, is_canonical, runtime_spec)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            ON CONFLICT (number) DO UPDATE SET
-                hash = EXCLUDED.hash,
-                parent_hash = EXCLUDED.parent_hash,
+            INSERT INTO {schema}.blocks
+            (hash, number, parent_hash, timestamp, is_canonical, runtime_spec, author)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (hash) DO UPDATE SET
                 timestamp = EXCLUDED.timestamp,
                 is_canonical = EXCLUDED.is_canonical,
-                runtime_spec = EXCLUDED.runtime_spec
+                runtime_spec = EXCLUDED.runtime_spec,
+                author = COALESCE(EXCLUDED.author, blocks.author)
             "#,
             schema = schema
         );
@@ -40,12 +70,13 @@ impl<'a> BlockRepository<'a> {
             .execute(
                 &sql,
                 &[
-                    &block.number,
                     &block.hash,
+                    &block.number,
                     &block.parent_hash,
                     &block.timestamp,
                     &block.is_canonical,
                     &block.runtime_spec,
+                    &block.author,
                 ],
             )
             .await?;
@@ -53,6 +84,35 @@ impl<'a> BlockRepository<'a> {
         Ok(())
     }
 
+    /// Record a block that lost a fork without having been indexed. Blocks already known,
+    /// canonical or not, are left alone.
+    pub async fn insert_orphan(&self, block: &Block) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.blocks
+            (hash, number, parent_hash, timestamp, is_canonical, runtime_spec, author)
+            VALUES ($1, $2, $3, $4, false, $5, $6)
+            ON CONFLICT (hash) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &block.hash,
+                    &block.number,
+                    &block.parent_hash,
+                    &block.timestamp,
+                    &block.runtime_spec,
+                    &block.author,
+                ],
+            )
+            .await
+    }
+
     /// Batch insert multiple blocks
     pub async fn insert_batch(&self, blocks: &[Block]) -> Result<u64> {
         if blocks.is_empty() {
@@ -71,29 +131,37 @@ impl<'a> BlockRepository<'a> {
         Ok(inserted)
     }
 
-    /// Get a block by number
+    /// Get the canonical block at a height
     pub async fn get_by_number(&self, number: i64) -> Result<Option<Block>> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
-            SELECT number, hash, parent_hash, timestamp, is_canonical, runtime_spec
+            SELECT {BLOCK_COLUMNS}
+            FROM {schema}.blocks
+            WHERE number = $1 AND is_canonical
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&number]).await?;
+        Ok(row.as_ref().map(block_from_row))
+    }
+
+    /// Get every block seen at a height, the canonical one first
+    pub async fn get_at_height(&self, number: i64) -> Result<Vec<Block>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {BLOCK_COLUMNS}
             FROM {schema}.blocks
             WHERE number = $1
+            ORDER BY is_canonical DESC, created_at
             "#,
             schema = schema
         );
 
-        match self.conn.query_opt(&sql, &[&number]).await? {
-            Some(row) => Ok(Some(Block {
-                number: row.get(0),
-                hash: row.get(1),
-                parent_hash: row.get(2),
-                timestamp: row.get(3),
-                is_canonical: row.get(4),
-                runtime_spec: row.get(5),
-            })),
-            None => Ok(None),
-        }
+        let rows = self.conn.query(&sql, &[&number]).await?;
+        Ok(rows.iter().map(block_from_row).collect())
     }
 
     /// Get a block by hash
@@ -101,24 +169,15 @@ impl<'a> BlockRepository<'a> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
-            SELECT number, hash, parent_hash, timestamp, is_canonical, runtime_spec
+            SELECT {BLOCK_COLUMNS}
             FROM {schema}.blocks
             WHERE hash = $1
             "#,
             schema = schema
         );
 
-        match self.conn.query_opt(&sql, &[&hash]).await? {
-            Some(row) => Ok(Some(Block {
-                number: row.get(0),
-                hash: row.get(1),
-                parent_hash: row.get(2),
-                timestamp: row.get(3),
-                is_canonical: row.get(4),
-                runtime_spec: row.get(5),
-            })),
-            None => Ok(None),
-        }
+        let row = self.conn.query_opt(&sql, &[&hash]).await?;
+        Ok(row.as_ref().map(block_from_row))
     }
 
     /// Get the latest canonical block
@@ -126,7 +185,7 @@ impl<'a> BlockRepository<'a> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
-            SELECT number, hash, parent_hash, timestamp, is_canonical, runtime_spec
+            SELECT {BLOCK_COLUMNS}
             FROM {schema}.blocks
             WHERE is_canonical = true
             ORDER BY number DESC
@@ -135,17 +194,59 @@ impl<'a> BlockRepository<'a> {
             schema = schema
         );
 
-        match self.conn.query_opt(&sql, &[]).await? {
-            Some(row) => Ok(Some(Block {
+        let row = self.conn.query_opt(&sql, &[]).await?;
+        Ok(row.as_ref().map(block_from_row))
+    }
+
+    /// Get the non-canonical blocks seen since a height, most recent first
+    pub async fn get_fork_blocks(&self, from_block: i64, limit: i64) -> Result<Vec<ForkBlock>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT number, hash, parent_hash, author, timestamp, fork_point, fork_root, depth,
+                   canonical_hash, canonical_author
+            FROM {schema}.fork_blocks
+            WHERE number >= $1
+            ORDER BY number DESC, depth DESC
+            LIMIT $2
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&from_block, &limit]).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ForkBlock {
                 number: row.get(0),
                 hash: row.get(1),
                 parent_hash: row.get(2),
-                timestamp: row.get(3),
-                is_canonical: row.get(4),
-                runtime_spec: row.get(5),
-            })),
-            None => Ok(None),
-        }
+                author: row.get(3),
+                timestamp: row.get(4),
+                fork_point: row.get(5),
+                fork_root: row.get(6),
+                depth: row.get(7),
+                canonical_hash: row.get(8),
+                canonical_author: row.get(9),
+            })
+            .collect())
+    }
+
+    /// Share of the blocks seen since a height that ended up off the canonical chain
+    pub async fn get_orphan_rate(&self, from_block: i64) -> Result<f64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT COALESCE(
+                COUNT(*) FILTER (WHERE NOT is_canonical)::FLOAT8 / NULLIF(COUNT(*), 0), 0
+            )
+            FROM {schema}.blocks
+            WHERE number >= $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_one(&sql, &[&from_block]).await?;
+        Ok(row.get(0))
     }
 
     /// Mark blocks as non-canonical starting from a specific height
@@ -163,11 +264,11 @@ impl<'a> BlockRepository<'a> {
         self.conn.execute(&sql, &[&from_height]).await
     }
 
-    /// Check if a block exists
+    /// Check if a canonical block exists at a height
     pub async fn exists(&self, number: i64) -> Result<bool> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
-            "SELECT EXISTS (SELECT 1 FROM {schema}.blocks WHERE number = $1)",
+            "SELECT EXISTS (SELECT 1 FROM {schema}.blocks WHERE number = $1 AND is_canonical)",
             schema = schema
         );
 
@@ -176,14 +277,190 @@ impl<'a> BlockRepository<'a> {
     }
 }
 
+const BLOCK_COLUMNS: &str =
+    "number, hash, parent_hash, timestamp, is_canonical, runtime_spec, author, ledger_hash";
+
+fn block_from_row(row: &tokio_postgres::Row) -> Block {
+    Block {
+        number: row.get(0),
+        hash: row.get(1),
+        parent_hash: row.get(2),
+        timestamp: row.get(3),
+        is_canonical: row.get(4),
+        runtime_spec: row.get(5),
+        author: row.get(6),
+        ledger_hash: row.get(7),
+    }
+}
+
+/// Repository for block headers and their digest logs
+pub struct BlockHeaderRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> BlockHeaderRepository<'a> {
+    /// Create a new block header repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert a block's header, replacing an earlier record of the same block
+    pub async fn insert(&self, header: &BlockHeader) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.block_headers
+            (block_hash, block_number, state_root, extrinsics_root, extrinsic_count, event_count,
+             block_size, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (block_hash) DO UPDATE SET
+                event_count = COALESCE(EXCLUDED.event_count, {schema}.block_headers.event_count),
+                block_ts = EXCLUDED.block_ts
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &header.block_hash,
+                    &header.block_number,
+                    &header.state_root,
+                    &header.extrinsics_root,
+                    &header.extrinsic_count,
+                    &header.event_count,
+                    &header.block_size,
+                    &header.block_ts,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Insert digest logs, skipping logs already stored
+    pub async fn insert_digest_logs(&self, logs: &[DigestLog]) -> Result<u64> {
+        if logs.is_empty() {
+            return Ok(0);
+        }
+
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.digest_logs
+            (block_hash, block_number, log_index, kind, engine_id, data)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (block_hash, log_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for log in logs {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &log.block_hash,
+                        &log.block_number,
+                        &log.log_index,
+                        &log.kind.as_str(),
+                        &log.engine_id,
+                        &log.data,
+                    ],
+                )
+                .await?;
+        }
+
+        Ok(inserted)
+    }
+
+    /// Get the header of a block by hash
+    pub async fn get_by_hash(&self, block_hash: &[u8]) -> Result<Option<BlockHeader>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.block_headers WHERE block_hash = $1",
+            columns = BLOCK_HEADER_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&block_hash]).await?;
+        Ok(row.as_ref().map(block_header_from_row))
+    }
+
+    /// Get the header of the canonical block at a height
+    pub async fn get_by_number(&self, block_number: i64) -> Result<Option<BlockHeader>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.block_headers
+            WHERE block_hash = (
+                SELECT hash FROM {schema}.blocks WHERE number = $1 AND is_canonical
+            )
+            "#,
+            columns = BLOCK_HEADER_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&block_number]).await?;
+        Ok(row.as_ref().map(block_header_from_row))
+    }
+
+    /// Get the digest logs of a block, in digest order
+    pub async fn get_digest_logs(&self, block_hash: &[u8]) -> Result<Vec<DigestLog>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_hash, block_number, log_index, kind, engine_id, data
+            FROM {schema}.digest_logs
+            WHERE block_hash = $1
+            ORDER BY log_index
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&block_hash]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| DigestLog {
+                block_hash: row.get(0),
+                block_number: row.get(1),
+                log_index: row.get(2),
+                kind: DigestLogKind::from_str(row.get(3)),
+                engine_id: row.get(4),
+                data: row.get(5),
+            })
+            .collect())
+    }
+}
+
+const BLOCK_HEADER_COLUMNS: &str = "block_hash, block_number, state_root, extrinsics_root, \
+     extrinsic_count, event_count, block_size, block_ts";
+
+fn block_header_from_row(row: &tokio_postgres::Row) -> BlockHeader {
+    BlockHeader {
+        block_hash: row.get(0),
+        block_number: row.get(1),
+        state_root: row.get(2),
+        extrinsics_root: row.get(3),
+        extrinsic_count: row.get(4),
+        event_count: row.get(5),
+        block_size: row.get(6),
+        block_ts: row.get(7),
+    }
+}
+
 /// Repository for managing balance changes
 pub struct BalanceChangeRepository<'a> {
-    conn: &'a DbConnection,
+    conn: &'a dyn DbExecutor,
 }
 
 impl<'a> BalanceChangeRepository<'a> {
     /// Create a new balance change repository
-    pub fn new(conn: &'a DbConnection) -> Self {
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
         Self { conn }
     }
 
@@ -193,8 +470,8 @@ impl<'a> BalanceChangeRepository<'a> {
         let sql = format!(
             r#"
             INSERT INTO {schema}.balance_changes
-            (account, block_number, event_index, delta, reason, extrinsic_hash, event_pallet, event_variant, block_ts)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            (account, asset_id, block_number, event_index, delta, reason, extrinsic_hash, event_pallet, event_variant, batch_index, call_path, block_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING id
             "#,
             schema = schema
@@ -206,6 +483,7 @@ impl<'a> BalanceChangeRepository<'a> {
                 &sql,
                 &[
                     &change.account,
+                    &change.asset_id,
                     &change.block_number,
                     &change.event_index,
                     &change.delta,
@@ -213,6 +491,9 @@ impl<'a> BalanceChangeRepository<'a> {
                     &change.extrinsic_hash,
                     &change.event_pallet,
                     &change.event_variant,
+                    &change.batch_index,
+                    &change.call_path,
+                    &change.block_hash,
                     &change.block_ts,
                 ],
             )
@@ -227,137 +508,4252 @@ impl<'a> BalanceChangeRepository<'a> {
             return Ok(0);
         }
 
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.balance_changes
+            (account, asset_id, block_number, event_index, delta, reason, extrinsic_hash, event_pallet, event_variant, batch_index, call_path, block_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (block_number, event_index, account) WHERE is_canonical DO NOTHING
+            "#,
+            schema = schema
+        );
+
         let mut inserted = 0;
 
         // TODO: Use COPY for better performance with large batches
         for change in changes {
-            self.insert(change).await?;
-            inserted += 1;
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &change.account,
+                        &change.asset_id,
+                        &change.block_number,
+                        &change.event_index,
+                        &change.delta,
+                        &change.reason.as_str(),
+                        &change.extrinsic_hash,
+                        &change.event_pallet,
+                        &change.event_variant,
+                        &change.batch_index,
+                        &change.call_path,
+                        &change.block_hash,
+                        &change.block_ts,
+                    ],
+                )
+                .await?;
+        }
+
+        Ok(inserted)
+    }
+
+    /// Get the canonical balance changes for an account
+    pub async fn get_by_account(
+        &self,
+        account: &AccountId,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<BalanceChange>> {
+        let account = account.as_bytes();
+        let schema = self.conn.schema_name()?;
+        let mut sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.balance_changes
+            WHERE account = $1 AND is_canonical
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            columns = BALANCE_CHANGE_COLUMNS,
+            schema = schema
+        );
+
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        let rows = self.conn.query(&sql, &[&account]).await?;
+
+        Ok(rows.iter().map(balance_change_from_row).collect())
+    }
+
+    /// Get the canonical balance changes for a block
+    pub async fn get_by_block(&self, block_number: i64) -> Result<Vec<BalanceChange>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.balance_changes
+            WHERE block_number = $1 AND is_canonical
+            ORDER BY event_index
+            "#,
+            columns = BALANCE_CHANGE_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&block_number]).await?;
+
+        Ok(rows.iter().map(balance_change_from_row).collect())
+    }
+
+    /// Get balance changes of an asset, most recent first
+    pub async fn get_by_asset(
+        &self,
+        asset_id: i64,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<BalanceChange>> {
+        let schema = self.conn.schema_name()?;
+        let mut sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.balance_changes
+            WHERE asset_id = $1 AND is_canonical
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            columns = BALANCE_CHANGE_COLUMNS,
+            schema = schema
+        );
+
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
         }
 
+        let rows = self.conn.query(&sql, &[&asset_id]).await?;
+        Ok(rows.iter().map(balance_change_from_row).collect())
+    }
+
+    /// Get native token balance at a specific block for an account
+    pub async fn get_balance_at_block(
+        &self,
+        account: &AccountId,
+        block_number: i64,
+    ) -> Result<String> {
+        let account = account.as_bytes();
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(delta::NUMERIC), 0)::TEXT
+            FROM {schema}.balance_changes
+            WHERE account = $1 AND asset_id IS NULL AND block_number <= $2 AND is_canonical
+            "#,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_one(&sql, &[&account, &block_number])
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Get an account's balance of an asset at a specific block
+    pub async fn get_asset_balance_at_block(
+        &self,
+        account: &AccountId,
+        asset_id: i64,
+        block_number: i64,
+    ) -> Result<String> {
+        let account = account.as_bytes();
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(delta::NUMERIC), 0)::TEXT
+            FROM {schema}.balance_changes
+            WHERE account = $1 AND asset_id = $2 AND block_number <= $3 AND is_canonical
+            "#,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_one(&sql, &[&account, &asset_id, &block_number])
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Get the current holders of an asset with their balances, largest first
+    pub async fn get_asset_holders(
+        &self,
+        asset_id: i64,
+        limit: i64,
+    ) -> Result<Vec<(Vec<u8>, String)>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, SUM(delta)::TEXT
+            FROM {schema}.balance_changes
+            WHERE asset_id = $1 AND is_canonical
+            GROUP BY account
+            HAVING SUM(delta) > 0
+            ORDER BY SUM(delta) DESC
+            LIMIT $2
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&asset_id, &limit]).await?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    /// Get the balance changes a block made, canonical or not
+    pub async fn get_by_block_hash(&self, block_hash: &[u8]) -> Result<Vec<BalanceChange>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.balance_changes
+            WHERE block_hash = $1
+            ORDER BY event_index
+            "#,
+            columns = BALANCE_CHANGE_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&block_hash]).await?;
+        Ok(rows.iter().map(balance_change_from_row).collect())
+    }
+
+    /// Flag the balance changes of blocks at or after a specific height as non-canonical,
+    /// keeping them for fork analysis
+    pub async fn mark_non_canonical_from(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            UPDATE {schema}.balance_changes
+            SET is_canonical = false
+            WHERE block_number >= $1 AND is_canonical
+            "#,
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+
+    /// Delete the non-canonical changes of a block that is being indexed again, as it
+    /// rejoined the canonical chain
+    pub async fn delete_non_canonical(&self, block_hash: &[u8]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.balance_changes
+            WHERE block_hash = $1 AND NOT is_canonical
+            "#,
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&block_hash]).await
+    }
+
+    /// Delete balance changes for blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.balance_changes
+            WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const BALANCE_CHANGE_COLUMNS: &str =
+    "id, account, asset_id, block_number, event_index, delta::TEXT, reason, \
+     extrinsic_hash, event_pallet, event_variant, batch_index, call_path, block_hash, \
+     is_canonical, block_ts";
+
+fn balance_change_from_row(row: &tokio_postgres::Row) -> BalanceChange {
+    BalanceChange {
+        id: Some(row.get(0)),
+        account: row.get(1),
+        asset_id: row.get(2),
+        block_number: row.get(3),
+        event_index: row.get(4),
+        delta: row.get(5),
+        reason: BalanceChangeReason::from_str(row.get(6)),
+        extrinsic_hash: row.get(7),
+        event_pallet: row.get(8),
+        event_variant: row.get(9),
+        batch_index: row.get(10),
+        call_path: row.get(11),
+        block_hash: row.get(12),
+        is_canonical: row.get(13),
+        block_ts: row.get(14),
+    }
+}
+
+/// Repository for managing decoded extrinsics
+pub struct ExtrinsicRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> ExtrinsicRepository<'a> {
+    /// Create a new extrinsic repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace an extrinsic
+    pub async fn upsert(&self, extrinsic: &Extrinsic) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.extrinsics
+            (block_number, extrinsic_index, hash, is_signed, signer, nonce, tip,
+             call_pallet, call_function, args, success, dispatch_error, fee,
+             signature_scheme, signature_len, signer_public_key, signature_valid, signature_context,
+             block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7::TEXT::NUMERIC, $8, $9, $10, $11, $12, $13::TEXT::NUMERIC,
+                    $14, $15, $16, $17, $18, $19)
+            ON CONFLICT (block_number, extrinsic_index) DO UPDATE SET
+                hash = EXCLUDED.hash,
+                is_signed = EXCLUDED.is_signed,
+                signer = EXCLUDED.signer,
+                nonce = EXCLUDED.nonce,
+                tip = EXCLUDED.tip,
+                call_pallet = EXCLUDED.call_pallet,
+                call_function = EXCLUDED.call_function,
+                args = EXCLUDED.args,
+                success = EXCLUDED.success,
+                dispatch_error = EXCLUDED.dispatch_error,
+                fee = EXCLUDED.fee,
+                signature_scheme = EXCLUDED.signature_scheme,
+                signature_len = EXCLUDED.signature_len,
+                signer_public_key = EXCLUDED.signer_public_key,
+                signature_valid = EXCLUDED.signature_valid,
+                signature_context = EXCLUDED.signature_context,
+                block_ts = EXCLUDED.block_ts
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &extrinsic.block_number,
+                    &extrinsic.extrinsic_index,
+                    &extrinsic.hash,
+                    &extrinsic.is_signed,
+                    &extrinsic.signer,
+                    &extrinsic.nonce,
+                    &extrinsic.tip,
+                    &extrinsic.call_pallet,
+                    &extrinsic.call_function,
+                    &extrinsic.args,
+                    &extrinsic.success,
+                    &extrinsic.dispatch_error,
+                    &extrinsic.fee,
+                    &extrinsic.signature_scheme,
+                    &extrinsic.signature_len,
+                    &extrinsic.signer_public_key,
+                    &extrinsic.signature_valid,
+                    &extrinsic.signature_context,
+                    &extrinsic.block_ts,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Batch insert multiple extrinsics
+    pub async fn insert_batch(&self, extrinsics: &[Extrinsic]) -> Result<u64> {
+        let mut inserted = 0;
+        for extrinsic in extrinsics {
+            self.upsert(extrinsic).await?;
+            inserted += 1;
+        }
+        Ok(inserted)
+    }
+
+    /// Get extrinsics by hash (the same hash may appear in several blocks)
+    pub async fn get_by_hash(&self, hash: &[u8]) -> Result<Vec<Extrinsic>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.extrinsics
+            WHERE hash = $1
+            ORDER BY block_number DESC
+            "#,
+            columns = EXTRINSIC_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&hash]).await?;
+        Ok(rows.iter().map(extrinsic_from_row).collect())
+    }
+
+    /// Get all extrinsics of a block
+    pub async fn get_by_block(&self, block_number: i64) -> Result<Vec<Extrinsic>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.extrinsics
+            WHERE block_number = $1
+            ORDER BY extrinsic_index
+            "#,
+            columns = EXTRINSIC_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&block_number]).await?;
+        Ok(rows.iter().map(extrinsic_from_row).collect())
+    }
+
+    /// Get extrinsics signed by an account
+    pub async fn get_by_signer(
+        &self,
+        signer: &AccountId,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<Extrinsic>> {
+        let signer = signer.as_bytes();
+        let schema = self.conn.schema_name()?;
+        let mut sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.extrinsics
+            WHERE signer = $1
+            ORDER BY block_number DESC, extrinsic_index DESC
+            "#,
+            columns = EXTRINSIC_COLUMNS,
+            schema = schema
+        );
+
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        let rows = self.conn.query(&sql, &[&signer]).await?;
+        Ok(rows.iter().map(extrinsic_from_row).collect())
+    }
+
+    /// Get extrinsics whose signature failed offline verification
+    pub async fn get_invalid_signatures(&self, limit: i64) -> Result<Vec<Extrinsic>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.extrinsics
+            WHERE signature_valid = false
+            ORDER BY block_number DESC, extrinsic_index DESC
+            LIMIT $1
+            "#,
+            columns = EXTRINSIC_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&limit]).await?;
+        Ok(rows.iter().map(extrinsic_from_row).collect())
+    }
+
+    /// Delete extrinsics for blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.extrinsics WHERE block_number >= $1",
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const EXTRINSIC_COLUMNS: &str =
+    "id, block_number, extrinsic_index, hash, is_signed, signer, nonce, \
+     tip::TEXT, call_pallet, call_function, args, success, dispatch_error, fee::TEXT, \
+     signature_scheme, signature_len, signer_public_key, signature_valid, signature_context, \
+     block_ts";
+
+fn extrinsic_from_row(row: &tokio_postgres::Row) -> Extrinsic {
+    Extrinsic {
+        id: Some(row.get(0)),
+        block_number: row.get(1),
+        extrinsic_index: row.get(2),
+        hash: row.get(3),
+        is_signed: row.get(4),
+        signer: row.get(5),
+        nonce: row.get(6),
+        tip: row.get(7),
+        call_pallet: row.get(8),
+        call_function: row.get(9),
+        args: row.get(10),
+        success: row.get(11),
+        dispatch_error: row.get(12),
+        fee: row.get(13),
+        signature_scheme: row.get(14),
+        signature_len: row.get(15),
+        signer_public_key: row.get(16),
+        signature_valid: row.get(17),
+        signature_context: row.get(18),
+        block_ts: row.get(19),
+    }
+}
+
+/// Repository for account addresses
+pub struct AccountRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> AccountRepository<'a> {
+    /// Create a new account repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Record accounts that have not been seen before, returning how many were new
+    pub async fn insert_batch(&self, accounts: &[Account]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.accounts (account, ss58, first_seen_block)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (account) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for account in accounts {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[&account.account, &account.ss58, &account.first_seen_block],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get an account record
+    pub async fn get(&self, account: &AccountId) -> Result<Option<Account>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT account, ss58, first_seen_block FROM {schema}.accounts WHERE account = $1",
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&account.as_bytes()]).await?;
+        Ok(row.map(|row| Account {
+            account: row.get(0),
+            ss58: row.get(1),
+            first_seen_block: row.get(2),
+        }))
+    }
+
+    /// Look up an account by its SS58 address
+    pub async fn get_by_ss58(&self, address: &str) -> Result<Option<Account>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT account, ss58, first_seen_block FROM {schema}.accounts WHERE ss58 = $1",
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&address]).await?;
+        Ok(row.map(|row| Account {
+            account: row.get(0),
+            ss58: row.get(1),
+            first_seen_block: row.get(2),
+        }))
+    }
+
+    /// Delete accounts first seen at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.accounts WHERE first_seen_block >= $1",
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+/// Repository for the Assets pallet registry
+pub struct AssetRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> AssetRepository<'a> {
+    /// Create a new asset repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace an asset record
+    pub async fn upsert(&self, asset: &Asset) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.assets
+            (asset_id, creator, owner, issuer, admin, freezer, name, symbol, decimals,
+             is_frozen, created_block, updated_block, destroyed_block)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (asset_id) DO UPDATE SET
+                creator = EXCLUDED.creator,
+                owner = EXCLUDED.owner,
+                issuer = EXCLUDED.issuer,
+                admin = EXCLUDED.admin,
+                freezer = EXCLUDED.freezer,
+                name = EXCLUDED.name,
+                symbol = EXCLUDED.symbol,
+                decimals = EXCLUDED.decimals,
+                is_frozen = EXCLUDED.is_frozen,
+                created_block = EXCLUDED.created_block,
+                updated_block = EXCLUDED.updated_block,
+                destroyed_block = EXCLUDED.destroyed_block
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &asset.asset_id,
+                    &asset.creator,
+                    &asset.owner,
+                    &asset.issuer,
+                    &asset.admin,
+                    &asset.freezer,
+                    &asset.name,
+                    &asset.symbol,
+                    &asset.decimals,
+                    &asset.is_frozen,
+                    &asset.created_block,
+                    &asset.updated_block,
+                    &asset.destroyed_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get an asset by ID
+    pub async fn get(&self, asset_id: i64) -> Result<Option<Asset>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.assets WHERE asset_id = $1",
+            columns = ASSET_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&asset_id]).await?;
+        Ok(row.as_ref().map(asset_from_row))
+    }
+
+    /// Get the assets owned by an account
+    pub async fn get_by_owner(&self, owner: &AccountId) -> Result<Vec<Asset>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.assets WHERE owner = $1 ORDER BY asset_id",
+            columns = ASSET_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&owner.as_bytes()]).await?;
+        Ok(rows.iter().map(asset_from_row).collect())
+    }
+
+    /// Delete assets created at or after a specific height.
+    ///
+    /// Changes to older assets made in those blocks are not rolled back; they are
+    /// overwritten as the replacement blocks are indexed.
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.assets WHERE created_block >= $1",
+            schema = schema
+        );
+
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const ASSET_COLUMNS: &str =
+    "asset_id, creator, owner, issuer, admin, freezer, name, symbol, decimals, \
+     is_frozen, created_block, updated_block, destroyed_block";
+
+fn asset_from_row(row: &tokio_postgres::Row) -> Asset {
+    Asset {
+        asset_id: row.get(0),
+        creator: row.get(1),
+        owner: row.get(2),
+        issuer: row.get(3),
+        admin: row.get(4),
+        freezer: row.get(5),
+        name: row.get(6),
+        symbol: row.get(7),
+        decimals: row.get(8),
+        is_frozen: row.get(9),
+        created_block: row.get(10),
+        updated_block: row.get(11),
+        destroyed_block: row.get(12),
+    }
+}
+
+/// Repository for Vesting pallet updates and schedule snapshots
+pub struct VestingRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> VestingRepository<'a> {
+    /// Create a new vesting repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert vesting updates, skipping ones already recorded
+    pub async fn insert_updates(&self, updates: &[VestingUpdate]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.vesting_updates
+            (account, block_number, event_index, unvested, completed, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5, $6, $7)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for update in updates {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &update.account,
+                        &update.block_number,
+                        &update.event_index,
+                        &update.unvested,
+                        &update.completed,
+                        &update.extrinsic_hash,
+                        &update.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert schedule snapshots, replacing any recorded for the same block
+    pub async fn insert_schedules(&self, schedules: &[VestingSchedule]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.vesting_schedules
+            (account, block_number, schedule_index, locked, per_block, starting_block)
+            VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5::TEXT::NUMERIC, $6)
+            ON CONFLICT (account, block_number, schedule_index) DO UPDATE SET
+                locked = EXCLUDED.locked,
+                per_block = EXCLUDED.per_block,
+                starting_block = EXCLUDED.starting_block
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for schedule in schedules {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &schedule.account,
+                        &schedule.block_number,
+                        &schedule.schedule_index,
+                        &schedule.locked,
+                        &schedule.per_block,
+                        &schedule.starting_block,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get an account's vesting updates, most recent first
+    pub async fn get_updates(&self, account: &AccountId) -> Result<Vec<VestingUpdate>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, block_number, event_index, unvested::TEXT, completed,
+                   extrinsic_hash, block_ts
+            FROM {schema}.vesting_updates
+            WHERE account = $1
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account.as_bytes()]).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| VestingUpdate {
+                account: row.get(0),
+                block_number: row.get(1),
+                event_index: row.get(2),
+                unvested: row.get(3),
+                completed: row.get(4),
+                extrinsic_hash: row.get(5),
+                block_ts: row.get(6),
+            })
+            .collect())
+    }
+
+    /// Get the schedules in force for an account at a block
+    pub async fn get_schedules_at_block(
+        &self,
+        account: &AccountId,
+        block_number: i64,
+    ) -> Result<Vec<VestingSchedule>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, block_number, schedule_index, locked::TEXT, per_block::TEXT,
+                   starting_block
+            FROM {schema}.vesting_schedules
+            WHERE account = $1
+              AND block_number = (
+                  SELECT MAX(block_number) FROM {schema}.vesting_updates
+                  WHERE account = $1 AND block_number <= $2
+              )
+            ORDER BY schedule_index
+            "#,
+            schema = schema
+        );
+
+        let rows = self
+            .conn
+            .query(&sql, &[&account.as_bytes(), &block_number])
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| VestingSchedule {
+                account: row.get(0),
+                block_number: row.get(1),
+                schedule_index: row.get(2),
+                locked: row.get(3),
+                per_block: row.get(4),
+                starting_block: row.get(5),
+            })
+            .collect())
+    }
+
+    /// Get the amount still locked by vesting and the vested balance of an account at a block
+    pub async fn get_vesting_at_block(
+        &self,
+        account: &AccountId,
+        block_number: i64,
+    ) -> Result<(String, String)> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {schema}.vesting_locked($1, $2)::TEXT,
+                   {schema}.vested_balance($1, $2)::TEXT
+            "#,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_one(&sql, &[&account.as_bytes(), &block_number])
+            .await?;
+        Ok((row.get(0), row.get(1)))
+    }
+
+    /// Delete vesting updates and schedules for blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.vesting_schedules WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.vesting_updates WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const ACCOUNT_BALANCE_COLUMNS: &str = "account, block_number, free::TEXT, reserved::TEXT, \
+     frozen::TEXT, transferable::TEXT, locks_refreshed, block_ts";
+
+fn account_balance_from_row(row: &tokio_postgres::Row) -> AccountBalance {
+    AccountBalance {
+        account: row.get(0),
+        block_number: row.get(1),
+        free: row.get(2),
+        reserved: row.get(3),
+        frozen: row.get(4),
+        transferable: row.get(5),
+        locks_refreshed: row.get(6),
+        block_ts: row.get(7),
+    }
+}
+
+/// Repository for account balance snapshots and the locks, reserves, holds and freezes on them
+pub struct AccountBalanceRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> AccountBalanceRepository<'a> {
+    /// Create a new account balance repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert balance snapshots, replacing any recorded for the same block, and bring the
+    /// accounts' `account_stats` up to the snapshot
+    pub async fn insert_balances(&self, balances: &[AccountBalance]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.account_balances
+            (account, block_number, free, reserved, frozen, transferable, locks_refreshed, block_ts)
+            VALUES ($1, $2, $3::TEXT::NUMERIC, $4::TEXT::NUMERIC, $5::TEXT::NUMERIC,
+                    $6::TEXT::NUMERIC, $7, $8)
+            ON CONFLICT (account, block_number) DO UPDATE SET
+                free = EXCLUDED.free,
+                reserved = EXCLUDED.reserved,
+                frozen = EXCLUDED.frozen,
+                transferable = EXCLUDED.transferable,
+                locks_refreshed = account_balances.locks_refreshed OR EXCLUDED.locks_refreshed
+            "#,
+            schema = schema
+        );
+        // Native balance changes are counted from the last activity up to the snapshot, so a
+        // block indexed twice is not counted twice
+        let stats_sql = format!(
+            r#"
+            INSERT INTO {schema}.account_stats
+            (account, balance, first_seen_block, last_activity_block, total_changes, updated_at)
+            SELECT $1, $2::TEXT::NUMERIC + $3::TEXT::NUMERIC,
+                   COALESCE(MIN(c.block_number), $4), $4, COUNT(*), NOW()
+            FROM {schema}.balance_changes c
+            WHERE c.account = $1 AND c.asset_id IS NULL AND c.block_number <= $4 AND c.is_canonical
+            ON CONFLICT (account) DO UPDATE SET
+                balance = EXCLUDED.balance,
+                first_seen_block = LEAST(account_stats.first_seen_block, EXCLUDED.first_seen_block),
+                last_activity_block = EXCLUDED.last_activity_block,
+                total_changes = account_stats.total_changes + (
+                    SELECT COUNT(*) FROM {schema}.balance_changes c
+                    WHERE c.account = EXCLUDED.account AND c.asset_id IS NULL AND c.is_canonical
+                      AND c.block_number > account_stats.last_activity_block
+                      AND c.block_number <= EXCLUDED.last_activity_block
+                ),
+                updated_at = EXCLUDED.updated_at
+            WHERE account_stats.last_activity_block < EXCLUDED.last_activity_block
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for balance in balances {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &balance.account,
+                        &balance.block_number,
+                        &balance.free,
+                        &balance.reserved,
+                        &balance.frozen,
+                        &balance.transferable,
+                        &balance.locks_refreshed,
+                        &balance.block_ts,
+                    ],
+                )
+                .await?;
+            self.conn
+                .execute(
+                    &stats_sql,
+                    &[
+                        &balance.account,
+                        &balance.free,
+                        &balance.reserved,
+                        &balance.block_number,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert lock snapshots, replacing any recorded for the same block
+    pub async fn insert_locks(&self, locks: &[BalanceLock]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.balance_locks
+            (account, block_number, kind, lock_id, amount, reasons)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6)
+            ON CONFLICT (account, block_number, kind, lock_id) DO UPDATE SET
+                amount = EXCLUDED.amount,
+                reasons = EXCLUDED.reasons
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for lock in locks {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &lock.account,
+                        &lock.block_number,
+                        &lock.kind.as_str(),
+                        &lock.lock_id,
+                        &lock.amount,
+                        &lock.reasons,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get an account's balance as of a block, from the latest snapshot at or before it
+    pub async fn get_balance_at_block(
+        &self,
+        account: &AccountId,
+        block_number: i64,
+    ) -> Result<Option<AccountBalance>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {ACCOUNT_BALANCE_COLUMNS}
+            FROM {schema}.account_balances
+            WHERE account = $1 AND block_number <= $2
+            ORDER BY block_number DESC
+            LIMIT 1
+            "#,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_opt(&sql, &[&account.as_bytes(), &block_number])
+            .await?;
+        Ok(row.as_ref().map(account_balance_from_row))
+    }
+
+    /// Get an account's latest balance
+    pub async fn get_balance(&self, account: &AccountId) -> Result<Option<AccountBalance>> {
+        self.get_balance_at_block(account, i64::MAX).await
+    }
+
+    /// Get the locks in force on an account at a block, from the latest snapshot whose
+    /// locks were read at or before it
+    pub async fn get_locks_at_block(
+        &self,
+        account: &AccountId,
+        block_number: i64,
+    ) -> Result<Vec<BalanceLock>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, block_number, kind, lock_id, amount::TEXT, reasons
+            FROM {schema}.balance_locks
+            WHERE account = $1
+              AND block_number = (
+                  SELECT MAX(block_number) FROM {schema}.account_balances
+                  WHERE account = $1 AND locks_refreshed AND block_number <= $2
+              )
+            ORDER BY kind, lock_id
+            "#,
+            schema = schema
+        );
+
+        let rows = self
+            .conn
+            .query(&sql, &[&account.as_bytes(), &block_number])
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BalanceLock {
+                account: row.get(0),
+                block_number: row.get(1),
+                kind: BalanceLockKind::from_str(row.get(2)),
+                lock_id: row.get(3),
+                amount: row.get(4),
+                reasons: row.get(5),
+            })
+            .collect())
+    }
+
+    /// Delete balance and lock snapshots for blocks at or after a specific height, and
+    /// rewind `account_stats` to the latest remaining snapshot
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.balance_locks WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.account_balances WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.account_stats s SET
+                balance = b.free + b.reserved,
+                last_activity_block = b.block_number,
+                total_changes = (
+                    SELECT COUNT(*) FROM {schema}.balance_changes c
+                    WHERE c.account = s.account AND c.asset_id IS NULL AND c.is_canonical
+                      AND c.block_number <= b.block_number
+                ),
+                updated_at = NOW()
+            FROM (
+                SELECT DISTINCT ON (account) account, block_number, free, reserved
+                FROM {schema}.account_balances
+                WHERE account IN (
+                    SELECT account FROM {schema}.account_stats WHERE last_activity_block >= $1
+                )
+                ORDER BY account, block_number DESC
+            ) b
+            WHERE s.account = b.account AND s.last_activity_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.account_stats WHERE last_activity_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+        Ok(deleted)
+    }
+}
+
+/// Repository for ReversibleTransfers pending transfers and high-security accounts
+pub struct ReversibleTransferRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> ReversibleTransferRepository<'a> {
+    /// Create a new reversible transfer repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace a pending transfer record
+    pub async fn upsert(&self, transfer: &PendingTransfer) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.pending_transfers
+            (tx_id, from_account, to_account, interceptor, amount, status, execute_at,
+             execute_at_block, scheduled_block, scheduled_extrinsic_hash, resolved_block,
+             resolved_event_index, resolved_extrinsic_hash, cancelled_by, dispatch_error)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (tx_id) DO UPDATE SET
+                from_account = EXCLUDED.from_account,
+                to_account = EXCLUDED.to_account,
+                interceptor = EXCLUDED.interceptor,
+                amount = EXCLUDED.amount,
+                status = EXCLUDED.status,
+                execute_at = EXCLUDED.execute_at,
+                execute_at_block = EXCLUDED.execute_at_block,
+                scheduled_block = EXCLUDED.scheduled_block,
+                scheduled_extrinsic_hash = EXCLUDED.scheduled_extrinsic_hash,
+                resolved_block = EXCLUDED.resolved_block,
+                resolved_event_index = EXCLUDED.resolved_event_index,
+                resolved_extrinsic_hash = EXCLUDED.resolved_extrinsic_hash,
+                cancelled_by = EXCLUDED.cancelled_by,
+                dispatch_error = EXCLUDED.dispatch_error,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &transfer.tx_id,
+                    &transfer.from_account,
+                    &transfer.to_account,
+                    &transfer.interceptor,
+                    &transfer.amount,
+                    &transfer.status.as_str(),
+                    &transfer.execute_at,
+                    &transfer.execute_at_block,
+                    &transfer.scheduled_block,
+                    &transfer.scheduled_extrinsic_hash,
+                    &transfer.resolved_block,
+                    &transfer.resolved_event_index,
+                    &transfer.resolved_extrinsic_hash,
+                    &transfer.cancelled_by,
+                    &transfer.dispatch_error,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a pending transfer by transaction ID
+    pub async fn get(&self, tx_id: &[u8]) -> Result<Option<PendingTransfer>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.pending_transfers WHERE tx_id = $1",
+            columns = PENDING_TRANSFER_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&tx_id]).await?;
+        Ok(row.as_ref().map(pending_transfer_from_row))
+    }
+
+    /// Get transfers still pending for an account, outgoing and incoming
+    pub async fn get_pending_for_account(
+        &self,
+        account: &AccountId,
+    ) -> Result<Vec<PendingTransfer>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.pending_transfers
+            WHERE (from_account = $1 OR to_account = $1) AND status = 'pending'
+            ORDER BY scheduled_block DESC NULLS LAST
+            "#,
+            columns = PENDING_TRANSFER_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account.as_bytes()]).await?;
+        Ok(rows.iter().map(pending_transfer_from_row).collect())
+    }
+
+    /// Record that an account enabled high security
+    pub async fn upsert_high_security(&self, account: &HighSecurityAccount) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.high_security_accounts
+            (account, interceptor, recoverer, delay, set_block, extrinsic_hash)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (account) DO UPDATE SET
+                interceptor = EXCLUDED.interceptor,
+                recoverer = EXCLUDED.recoverer,
+                delay = EXCLUDED.delay,
+                set_block = EXCLUDED.set_block,
+                extrinsic_hash = EXCLUDED.extrinsic_hash
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &account.account,
+                    &account.interceptor,
+                    &account.recoverer,
+                    &account.delay,
+                    &account.set_block,
+                    &account.extrinsic_hash,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get an account's high-security settings
+    pub async fn get_high_security(
+        &self,
+        account: &AccountId,
+    ) -> Result<Option<HighSecurityAccount>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, interceptor, recoverer, delay, set_block, extrinsic_hash
+            FROM {schema}.high_security_accounts
+            WHERE account = $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&account.as_bytes()]).await?;
+        Ok(row.map(|row| HighSecurityAccount {
+            account: row.get(0),
+            interceptor: row.get(1),
+            recoverer: row.get(2),
+            delay: row.get(3),
+            set_block: row.get(4),
+            extrinsic_hash: row.get(5),
+        }))
+    }
+
+    /// Undo transfer lifecycle changes and high-security settings from blocks at or after
+    /// a specific height: transfers scheduled there are deleted, transfers resolved there
+    /// are pending again
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.pending_transfers WHERE scheduled_block >= $1
+            "#,
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.pending_transfers
+            SET status = 'pending', resolved_block = NULL, resolved_event_index = NULL,
+                resolved_extrinsic_hash = NULL, cancelled_by = NULL, dispatch_error = NULL,
+                updated_at = NOW()
+            WHERE resolved_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.high_security_accounts WHERE set_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        Ok(deleted)
+    }
+}
+
+const PENDING_TRANSFER_COLUMNS: &str =
+    "tx_id, from_account, to_account, interceptor, amount::TEXT, status, execute_at, \
+     execute_at_block, scheduled_block, scheduled_extrinsic_hash, resolved_block, \
+     resolved_event_index, resolved_extrinsic_hash, cancelled_by, dispatch_error";
+
+fn pending_transfer_from_row(row: &tokio_postgres::Row) -> PendingTransfer {
+    PendingTransfer {
+        tx_id: row.get(0),
+        from_account: row.get(1),
+        to_account: row.get(2),
+        interceptor: row.get(3),
+        amount: row.get(4),
+        status: PendingTransferStatus::from_str(row.get(5)),
+        execute_at: row.get(6),
+        execute_at_block: row.get(7),
+        scheduled_block: row.get(8),
+        scheduled_extrinsic_hash: row.get(9),
+        resolved_block: row.get(10),
+        resolved_event_index: row.get(11),
+        resolved_extrinsic_hash: row.get(12),
+        cancelled_by: row.get(13),
+        dispatch_error: row.get(14),
+    }
+}
+
+/// Repository for MerkleAirdrop campaigns and claims
+pub struct AirdropRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> AirdropRepository<'a> {
+    /// Create a new airdrop repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace a campaign record
+    pub async fn upsert(&self, airdrop: &Airdrop) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.airdrops
+            (airdrop_id, merkle_root, creator, vesting_period, vesting_delay, funded, claimed,
+             claim_count, balance, created_block, created_extrinsic_hash, updated_block,
+             deleted_block)
+            VALUES ($1, $2, $3, $4, $5, $6::TEXT::NUMERIC, $7::TEXT::NUMERIC, $8,
+                    $9::TEXT::NUMERIC, $10, $11, $12, $13)
+            ON CONFLICT (airdrop_id) DO UPDATE SET
+                merkle_root = EXCLUDED.merkle_root,
+                creator = EXCLUDED.creator,
+                vesting_period = EXCLUDED.vesting_period,
+                vesting_delay = EXCLUDED.vesting_delay,
+                funded = EXCLUDED.funded,
+                claimed = EXCLUDED.claimed,
+                claim_count = EXCLUDED.claim_count,
+                balance = EXCLUDED.balance,
+                created_block = EXCLUDED.created_block,
+                created_extrinsic_hash = EXCLUDED.created_extrinsic_hash,
+                updated_block = EXCLUDED.updated_block,
+                deleted_block = EXCLUDED.deleted_block
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &airdrop.airdrop_id,
+                    &airdrop.merkle_root,
+                    &airdrop.creator,
+                    &airdrop.vesting_period,
+                    &airdrop.vesting_delay,
+                    &airdrop.funded,
+                    &airdrop.claimed,
+                    &airdrop.claim_count,
+                    &airdrop.balance,
+                    &airdrop.created_block,
+                    &airdrop.created_extrinsic_hash,
+                    &airdrop.updated_block,
+                    &airdrop.deleted_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a campaign by ID
+    pub async fn get(&self, airdrop_id: i64) -> Result<Option<Airdrop>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT airdrop_id, merkle_root, creator, vesting_period, vesting_delay,
+                   funded::TEXT, claimed::TEXT, claim_count, balance::TEXT, created_block,
+                   created_extrinsic_hash, updated_block, deleted_block
+            FROM {schema}.airdrops
+            WHERE airdrop_id = $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&airdrop_id]).await?;
+        Ok(row.map(|row| Airdrop {
+            airdrop_id: row.get(0),
+            merkle_root: row.get(1),
+            creator: row.get(2),
+            vesting_period: row.get(3),
+            vesting_delay: row.get(4),
+            funded: row.get(5),
+            claimed: row.get(6),
+            claim_count: row.get(7),
+            balance: row.get(8),
+            created_block: row.get(9),
+            created_extrinsic_hash: row.get(10),
+            updated_block: row.get(11),
+            deleted_block: row.get(12),
+        }))
+    }
+
+    /// Insert claims, skipping ones already recorded
+    pub async fn insert_claims(&self, claims: &[AirdropClaim]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.airdrop_claims
+            (airdrop_id, account, amount, block_number, event_index, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3::TEXT::NUMERIC, $4, $5, $6, $7)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for claim in claims {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &claim.airdrop_id,
+                        &claim.account,
+                        &claim.amount,
+                        &claim.block_number,
+                        &claim.event_index,
+                        &claim.extrinsic_hash,
+                        &claim.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get an account's claims across campaigns, most recent first
+    pub async fn get_claims_by_account(&self, account: &AccountId) -> Result<Vec<AirdropClaim>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT airdrop_id, account, amount::TEXT, block_number, event_index,
+                   extrinsic_hash, block_ts
+            FROM {schema}.airdrop_claims
+            WHERE account = $1
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account.as_bytes()]).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| AirdropClaim {
+                airdrop_id: row.get(0),
+                account: row.get(1),
+                amount: row.get(2),
+                block_number: row.get(3),
+                event_index: row.get(4),
+                extrinsic_hash: row.get(5),
+                block_ts: row.get(6),
+            })
+            .collect())
+    }
+
+    /// Undo campaign activity from blocks at or after a specific height: claims there are
+    /// deleted and their amounts returned to the campaign balance, campaigns created there
+    /// are deleted and deletions there are reverted. Funding is not rewound.
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.airdrop_claims WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.airdrops WHERE created_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.airdrops a
+            SET claimed = c.claimed,
+                claim_count = c.claim_count,
+                balance = a.balance + a.claimed - c.claimed,
+                deleted_block = CASE WHEN a.deleted_block >= $1 THEN NULL ELSE a.deleted_block END
+            FROM (
+                SELECT d.airdrop_id,
+                       COALESCE(SUM(k.amount), 0) AS claimed,
+                       COUNT(k.amount) AS claim_count
+                FROM {schema}.airdrops d
+                LEFT JOIN {schema}.airdrop_claims k ON k.airdrop_id = d.airdrop_id
+                WHERE d.updated_block >= $1
+                GROUP BY d.airdrop_id
+            ) c
+            WHERE a.airdrop_id = c.airdrop_id
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        Ok(deleted)
+    }
+}
+
+/// Repository for treasury spends, the treasury ledger and treasury balance snapshots
+pub struct TreasuryRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> TreasuryRepository<'a> {
+    /// Create a new treasury repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace a spend record
+    pub async fn upsert_spend(&self, spend: &TreasurySpend) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.treasury_spends
+            (kind, spend_index, beneficiary, asset_kind, amount, status, valid_from, expire_at,
+             payment_id, approved_block, approved_extrinsic_hash, resolved_block,
+             resolved_extrinsic_hash)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (kind, spend_index) DO UPDATE SET
+                beneficiary = EXCLUDED.beneficiary,
+                asset_kind = EXCLUDED.asset_kind,
+                amount = EXCLUDED.amount,
+                status = EXCLUDED.status,
+                valid_from = EXCLUDED.valid_from,
+                expire_at = EXCLUDED.expire_at,
+                payment_id = EXCLUDED.payment_id,
+                approved_block = EXCLUDED.approved_block,
+                approved_extrinsic_hash = EXCLUDED.approved_extrinsic_hash,
+                resolved_block = EXCLUDED.resolved_block,
+                resolved_extrinsic_hash = EXCLUDED.resolved_extrinsic_hash,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &spend.kind.as_str(),
+                    &spend.spend_index,
+                    &spend.beneficiary,
+                    &spend.asset_kind,
+                    &spend.amount,
+                    &spend.status.as_str(),
+                    &spend.valid_from,
+                    &spend.expire_at,
+                    &spend.payment_id,
+                    &spend.approved_block,
+                    &spend.approved_extrinsic_hash,
+                    &spend.resolved_block,
+                    &spend.resolved_extrinsic_hash,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a spend by kind and index
+    pub async fn get_spend(
+        &self,
+        kind: TreasurySpendKind,
+        spend_index: i64,
+    ) -> Result<Option<TreasurySpend>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.treasury_spends WHERE kind = $1 AND spend_index = $2",
+            columns = TREASURY_SPEND_COLUMNS,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_opt(&sql, &[&kind.as_str(), &spend_index])
+            .await?;
+        Ok(row.as_ref().map(treasury_spend_from_row))
+    }
+
+    /// Get spends in a lifecycle state, most recently approved first
+    pub async fn get_spends_by_status(
+        &self,
+        status: TreasurySpendStatus,
+    ) -> Result<Vec<TreasurySpend>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.treasury_spends
+            WHERE status = $1
+            ORDER BY approved_block DESC NULLS LAST, spend_index DESC
+            "#,
+            columns = TREASURY_SPEND_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&status.as_str()]).await?;
+        Ok(rows.iter().map(treasury_spend_from_row).collect())
+    }
+
+    /// Insert ledger entries, skipping ones already recorded
+    pub async fn insert_ledger(&self, entries: &[TreasuryLedgerEntry]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.treasury_ledger
+            (block_number, event_index, event_pallet, event_variant, delta, account, spend_kind,
+             spend_index, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for entry in entries {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &entry.block_number,
+                        &entry.event_index,
+                        &entry.event_pallet,
+                        &entry.event_variant,
+                        &entry.delta,
+                        &entry.account,
+                        &entry.spend_kind.map(|k| k.as_str()),
+                        &entry.spend_index,
+                        &entry.extrinsic_hash,
+                        &entry.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get ledger entries, most recent first
+    pub async fn get_ledger(
+        &self,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<TreasuryLedgerEntry>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, event_index, event_pallet, event_variant, delta::TEXT, account,
+                   spend_kind, spend_index, extrinsic_hash, block_ts
+            FROM {schema}.treasury_ledger
+            ORDER BY block_number DESC, event_index DESC
+            LIMIT $1 OFFSET $2
+            "#,
+            schema = schema
+        );
+
+        let rows = self
+            .conn
+            .query(&sql, &[&limit.unwrap_or(100), &offset.unwrap_or(0)])
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| TreasuryLedgerEntry {
+                block_number: row.get(0),
+                event_index: row.get(1),
+                event_pallet: row.get(2),
+                event_variant: row.get(3),
+                delta: row.get(4),
+                account: row.get(5),
+                spend_kind: row
+                    .get::<_, Option<&str>>(6)
+                    .map(TreasurySpendKind::from_str),
+                spend_index: row.get(7),
+                extrinsic_hash: row.get(8),
+                block_ts: row.get(9),
+            })
+            .collect())
+    }
+
+    /// Record the treasury balance at a block
+    pub async fn insert_balance(&self, balance: &TreasuryBalance) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.treasury_balances
+            (block_number, account, free, reserved, block_ts)
+            VALUES ($1, $2, $3::TEXT::NUMERIC, $4::TEXT::NUMERIC, $5)
+            ON CONFLICT (block_number) DO UPDATE SET
+                account = EXCLUDED.account,
+                free = EXCLUDED.free,
+                reserved = EXCLUDED.reserved,
+                block_ts = EXCLUDED.block_ts
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &balance.block_number,
+                    &balance.account,
+                    &balance.free,
+                    &balance.reserved,
+                    &balance.block_ts,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the latest treasury balance at or before a block
+    pub async fn get_balance_at_block(&self, block_number: i64) -> Result<Option<TreasuryBalance>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, account, free::TEXT, reserved::TEXT, block_ts
+            FROM {schema}.treasury_balances
+            WHERE block_number <= $1
+            ORDER BY block_number DESC
+            LIMIT 1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&block_number]).await?;
+        Ok(row.map(|row| TreasuryBalance {
+            block_number: row.get(0),
+            account: row.get(1),
+            free: row.get(2),
+            reserved: row.get(3),
+            block_ts: row.get(4),
+        }))
+    }
+
+    /// Undo treasury activity from blocks at or after a specific height: spends approved
+    /// there are deleted, spends resolved there are approved again
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            DELETE FROM {schema}.treasury_spends WHERE approved_block >= $1
+            "#,
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.treasury_spends
+            SET status = 'approved', payment_id = NULL, resolved_block = NULL,
+                resolved_extrinsic_hash = NULL, updated_at = NOW()
+            WHERE resolved_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        for table in ["treasury_ledger", "treasury_balances"] {
+            let sql = format!(
+                "DELETE FROM {schema}.{table} WHERE block_number >= $1",
+                schema = schema,
+                table = table
+            );
+            self.conn.execute(&sql, &[&from_block]).await?;
+        }
+
+        Ok(deleted)
+    }
+}
+
+const TREASURY_SPEND_COLUMNS: &str =
+    "kind, spend_index, beneficiary, asset_kind, amount::TEXT, status, valid_from, expire_at, \
+     payment_id, approved_block, approved_extrinsic_hash, resolved_block, resolved_extrinsic_hash";
+
+fn treasury_spend_from_row(row: &tokio_postgres::Row) -> TreasurySpend {
+    TreasurySpend {
+        kind: TreasurySpendKind::from_str(row.get(0)),
+        spend_index: row.get(1),
+        beneficiary: row.get(2),
+        asset_kind: row.get(3),
+        amount: row.get(4),
+        status: TreasurySpendStatus::from_str(row.get(5)),
+        valid_from: row.get(6),
+        expire_at: row.get(7),
+        payment_id: row.get(8),
+        approved_block: row.get(9),
+        approved_extrinsic_hash: row.get(10),
+        resolved_block: row.get(11),
+        resolved_extrinsic_hash: row.get(12),
+    }
+}
+
+/// Repository for referenda, votes, delegations and ranked collective membership
+pub struct GovernanceRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> GovernanceRepository<'a> {
+    /// Create a new governance repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace a referendum record
+    pub async fn upsert_referendum(&self, referendum: &Referendum) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.referenda
+            (pallet, referendum_index, track, origin, proposal, enactment, status, submitted_block,
+             submission_deposit_account, submission_deposit, submission_deposit_refunded_block,
+             decision_deposit_account, decision_deposit, decision_deposit_refunded_block,
+             decision_started_block, confirm_started_block, confirm_end_block, concluded_block,
+             ayes, nays, support, submitted_extrinsic_hash, updated_block)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::TEXT::NUMERIC, $11, $12,
+                    $13::TEXT::NUMERIC, $14, $15, $16, $17, $18, $19::TEXT::NUMERIC,
+                    $20::TEXT::NUMERIC, $21::TEXT::NUMERIC, $22, $23)
+            ON CONFLICT (pallet, referendum_index) DO UPDATE SET
+                track = EXCLUDED.track,
+                origin = EXCLUDED.origin,
+                proposal = EXCLUDED.proposal,
+                enactment = EXCLUDED.enactment,
+                status = EXCLUDED.status,
+                submitted_block = EXCLUDED.submitted_block,
+                submission_deposit_account = EXCLUDED.submission_deposit_account,
+                submission_deposit = EXCLUDED.submission_deposit,
+                submission_deposit_refunded_block = EXCLUDED.submission_deposit_refunded_block,
+                decision_deposit_account = EXCLUDED.decision_deposit_account,
+                decision_deposit = EXCLUDED.decision_deposit,
+                decision_deposit_refunded_block = EXCLUDED.decision_deposit_refunded_block,
+                decision_started_block = EXCLUDED.decision_started_block,
+                confirm_started_block = EXCLUDED.confirm_started_block,
+                confirm_end_block = EXCLUDED.confirm_end_block,
+                concluded_block = EXCLUDED.concluded_block,
+                ayes = EXCLUDED.ayes,
+                nays = EXCLUDED.nays,
+                support = EXCLUDED.support,
+                submitted_extrinsic_hash = EXCLUDED.submitted_extrinsic_hash,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &referendum.pallet,
+                    &referendum.referendum_index,
+                    &referendum.track,
+                    &referendum.origin,
+                    &referendum.proposal,
+                    &referendum.enactment,
+                    &referendum.status.as_str(),
+                    &referendum.submitted_block,
+                    &referendum.submission_deposit_account,
+                    &referendum.submission_deposit,
+                    &referendum.submission_deposit_refunded_block,
+                    &referendum.decision_deposit_account,
+                    &referendum.decision_deposit,
+                    &referendum.decision_deposit_refunded_block,
+                    &referendum.decision_started_block,
+                    &referendum.confirm_started_block,
+                    &referendum.confirm_end_block,
+                    &referendum.concluded_block,
+                    &referendum.ayes,
+                    &referendum.nays,
+                    &referendum.support,
+                    &referendum.submitted_extrinsic_hash,
+                    &referendum.updated_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a referendum by pallet instance and index
+    pub async fn get_referendum(
+        &self,
+        pallet: &str,
+        referendum_index: i64,
+    ) -> Result<Option<Referendum>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.referenda WHERE pallet = $1 AND referendum_index = $2",
+            columns = REFERENDUM_COLUMNS,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_opt(&sql, &[&pallet, &referendum_index])
+            .await?;
+        Ok(row.as_ref().map(referendum_from_row))
+    }
+
+    /// Get referenda in a lifecycle state, most recent first
+    pub async fn get_referenda_by_status(
+        &self,
+        status: ReferendumStatus,
+    ) -> Result<Vec<Referendum>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.referenda
+            WHERE status = $1
+            ORDER BY pallet, referendum_index DESC
+            "#,
+            columns = REFERENDUM_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&status.as_str()]).await?;
+        Ok(rows.iter().map(referendum_from_row).collect())
+    }
+
+    /// Insert referendum status transitions, skipping ones already recorded
+    pub async fn insert_status_changes(&self, changes: &[ReferendumStatusChange]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.referendum_status_changes
+            (block_number, event_index, pallet, referendum_index, event_variant, status, ayes,
+             nays, support, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7::TEXT::NUMERIC, $8::TEXT::NUMERIC,
+                    $9::TEXT::NUMERIC, $10, $11)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for change in changes {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &change.block_number,
+                        &change.event_index,
+                        &change.pallet,
+                        &change.referendum_index,
+                        &change.event_variant,
+                        &change.status.as_str(),
+                        &change.ayes,
+                        &change.nays,
+                        &change.support,
+                        &change.extrinsic_hash,
+                        &change.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert votes, skipping ones already recorded
+    pub async fn insert_votes(&self, votes: &[ReferendumVote]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.referendum_votes
+            (block_number, event_index, pallet, referendum_index, account, vote_kind, aye,
+             conviction, aye_amount, nay_amount, abstain_amount, lock_amount, removed,
+             extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::TEXT::NUMERIC, $10::TEXT::NUMERIC,
+                    $11::TEXT::NUMERIC, $12::TEXT::NUMERIC, $13, $14, $15)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for vote in votes {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &vote.block_number,
+                        &vote.event_index,
+                        &vote.pallet,
+                        &vote.referendum_index,
+                        &vote.account,
+                        &vote.vote_kind,
+                        &vote.aye,
+                        &vote.conviction,
+                        &vote.aye_amount,
+                        &vote.nay_amount,
+                        &vote.abstain_amount,
+                        &vote.lock_amount,
+                        &vote.removed,
+                        &vote.extrinsic_hash,
+                        &vote.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get the votes cast on a referendum, in order
+    pub async fn get_votes(
+        &self,
+        pallet: &str,
+        referendum_index: i64,
+    ) -> Result<Vec<ReferendumVote>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, event_index, pallet, referendum_index, account, vote_kind, aye,
+                   conviction, aye_amount::TEXT, nay_amount::TEXT, abstain_amount::TEXT,
+                   lock_amount::TEXT, removed, extrinsic_hash, block_ts
+            FROM {schema}.referendum_votes
+            WHERE pallet = $1 AND referendum_index = $2
+            ORDER BY block_number, event_index
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&pallet, &referendum_index]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| ReferendumVote {
+                block_number: row.get(0),
+                event_index: row.get(1),
+                pallet: row.get(2),
+                referendum_index: row.get(3),
+                account: row.get(4),
+                vote_kind: row.get(5),
+                aye: row.get(6),
+                conviction: row.get(7),
+                aye_amount: row.get(8),
+                nay_amount: row.get(9),
+                abstain_amount: row.get(10),
+                lock_amount: row.get(11),
+                removed: row.get(12),
+                extrinsic_hash: row.get(13),
+                block_ts: row.get(14),
+            })
+            .collect())
+    }
+
+    /// Insert delegations and undelegations, skipping ones already recorded
+    pub async fn insert_delegations(&self, delegations: &[VoteDelegation]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.vote_delegations
+            (block_number, event_index, account, target, class, conviction, balance,
+             extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7::TEXT::NUMERIC, $8, $9)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for delegation in delegations {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &delegation.block_number,
+                        &delegation.event_index,
+                        &delegation.account,
+                        &delegation.target,
+                        &delegation.class,
+                        &delegation.conviction,
+                        &delegation.balance,
+                        &delegation.extrinsic_hash,
+                        &delegation.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get an account's delegations and undelegations, most recent first
+    pub async fn get_delegations(&self, account: &[u8]) -> Result<Vec<VoteDelegation>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, event_index, account, target, class, conviction, balance::TEXT,
+                   extrinsic_hash, block_ts
+            FROM {schema}.vote_delegations
+            WHERE account = $1
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| VoteDelegation {
+                block_number: row.get(0),
+                event_index: row.get(1),
+                account: row.get(2),
+                target: row.get(3),
+                class: row.get(4),
+                conviction: row.get(5),
+                balance: row.get(6),
+                extrinsic_hash: row.get(7),
+                block_ts: row.get(8),
+            })
+            .collect())
+    }
+
+    /// Record membership and rank changes and apply them to `collective_members`
+    pub async fn apply_rank_changes(&self, changes: &[CollectiveRankChange]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let insert_sql = format!(
+            r#"
+            INSERT INTO {schema}.collective_rank_changes
+            (block_number, event_index, collective, account, change, rank, extrinsic_hash,
+             block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (block_number, event_index, account) DO NOTHING
+            "#,
+            schema = schema
+        );
+        let member_sql = format!(
+            r#"
+            INSERT INTO {schema}.collective_members
+            (collective, account, rank, joined_block, removed_block, updated_block)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (collective, account) DO UPDATE SET
+                rank = EXCLUDED.rank,
+                joined_block = COALESCE(EXCLUDED.joined_block, {schema}.collective_members.joined_block),
+                removed_block = EXCLUDED.removed_block,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for change in changes {
+            let recorded = self
+                .conn
+                .execute(
+                    &insert_sql,
+                    &[
+                        &change.block_number,
+                        &change.event_index,
+                        &change.collective,
+                        &change.account,
+                        &change.change,
+                        &change.rank,
+                        &change.extrinsic_hash,
+                        &change.block_ts,
+                    ],
+                )
+                .await?;
+            if recorded == 0 {
+                continue;
+            }
+            inserted += recorded;
+
+            let joined = matches!(change.change.as_str(), "added" | "exchanged_in")
+                .then_some(change.block_number);
+            let removed = matches!(change.change.as_str(), "removed" | "exchanged_out")
+                .then_some(change.block_number);
+            self.conn
+                .execute(
+                    &member_sql,
+                    &[
+                        &change.collective,
+                        &change.account,
+                        &change.rank,
+                        &joined,
+                        &removed,
+                        &change.block_number,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get the current members of a collective, highest rank first
+    pub async fn get_members(&self, collective: &str) -> Result<Vec<CollectiveMember>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT collective, account, rank, joined_block, removed_block, updated_block
+            FROM {schema}.collective_members
+            WHERE collective = $1 AND removed_block IS NULL
+            ORDER BY rank DESC, account
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&collective]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| CollectiveMember {
+                collective: row.get(0),
+                account: row.get(1),
+                rank: row.get(2),
+                joined_block: row.get(3),
+                removed_block: row.get(4),
+                updated_block: row.get(5),
+            })
+            .collect())
+    }
+
+    /// Undo governance activity from blocks at or after a specific height.
+    ///
+    /// Referenda submitted there are deleted; others get the status of their last earlier
+    /// transition and lose block markers set there. Tallies and deposits are refreshed from
+    /// storage the next time the referendum changes. Collective members touched there are
+    /// rebuilt from their remaining rank history.
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.referenda WHERE submitted_block >= $1",
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.referenda r SET
+                status = COALESCE((
+                    SELECT s.status FROM {schema}.referendum_status_changes s
+                    WHERE s.pallet = r.pallet AND s.referendum_index = r.referendum_index
+                      AND s.block_number < $1
+                    ORDER BY s.block_number DESC, s.event_index DESC
+                    LIMIT 1
+                ), r.status),
+                submission_deposit_refunded_block = NULLIF(LEAST(submission_deposit_refunded_block, $1), $1),
+                decision_deposit_refunded_block = NULLIF(LEAST(decision_deposit_refunded_block, $1), $1),
+                decision_started_block = NULLIF(LEAST(decision_started_block, $1), $1),
+                confirm_started_block = NULLIF(LEAST(confirm_started_block, $1), $1),
+                concluded_block = NULLIF(LEAST(concluded_block, $1), $1),
+                updated_block = $1 - 1,
+                updated_at = NOW()
+            WHERE updated_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        for table in [
+            "referendum_status_changes",
+            "referendum_votes",
+            "vote_delegations",
+        ] {
+            let sql = format!(
+                "DELETE FROM {schema}.{table} WHERE block_number >= $1",
+                schema = schema,
+                table = table
+            );
+            self.conn.execute(&sql, &[&from_block]).await?;
+        }
+
+        let sql = format!(
+            r#"
+            SELECT DISTINCT collective, account
+            FROM {schema}.collective_rank_changes
+            WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        let members = self.conn.query(&sql, &[&from_block]).await?;
+        let sql = format!(
+            "DELETE FROM {schema}.collective_rank_changes WHERE block_number >= $1",
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let delete_sql = format!(
+            "DELETE FROM {schema}.collective_members WHERE collective = $1 AND account = $2",
+            schema = schema
+        );
+        let rebuild_sql = format!(
+            r#"
+            INSERT INTO {schema}.collective_members
+            (collective, account, rank, joined_block, removed_block, updated_block)
+            SELECT collective, account, rank,
+                   (SELECT MAX(j.block_number) FROM {schema}.collective_rank_changes j
+                    WHERE j.collective = c.collective AND j.account = c.account
+                      AND j.change IN ('added', 'exchanged_in')),
+                   CASE WHEN change IN ('removed', 'exchanged_out') THEN block_number END,
+                   block_number
+            FROM {schema}.collective_rank_changes c
+            WHERE collective = $1 AND account = $2
+            ORDER BY block_number DESC, event_index DESC
+            LIMIT 1
+            "#,
+            schema = schema
+        );
+        for row in &members {
+            let collective: String = row.get(0);
+            let account: Vec<u8> = row.get(1);
+            self.conn
+                .execute(&delete_sql, &[&collective, &account])
+                .await?;
+            self.conn
+                .execute(&rebuild_sql, &[&collective, &account])
+                .await?;
+        }
+
+        Ok(deleted)
+    }
+}
+
+const REFERENDUM_COLUMNS: &str =
+    "pallet, referendum_index, track, origin, proposal, enactment, status, submitted_block, \
+     submission_deposit_account, submission_deposit::TEXT, submission_deposit_refunded_block, \
+     decision_deposit_account, decision_deposit::TEXT, decision_deposit_refunded_block, \
+     decision_started_block, confirm_started_block, confirm_end_block, concluded_block, \
+     ayes::TEXT, nays::TEXT, support::TEXT, submitted_extrinsic_hash, updated_block";
+
+fn referendum_from_row(row: &tokio_postgres::Row) -> Referendum {
+    Referendum {
+        pallet: row.get(0),
+        referendum_index: row.get(1),
+        track: row.get(2),
+        origin: row.get(3),
+        proposal: row.get(4),
+        enactment: row.get(5),
+        status: ReferendumStatus::from_str(row.get(6)),
+        submitted_block: row.get(7),
+        submission_deposit_account: row.get(8),
+        submission_deposit: row.get(9),
+        submission_deposit_refunded_block: row.get(10),
+        decision_deposit_account: row.get(11),
+        decision_deposit: row.get(12),
+        decision_deposit_refunded_block: row.get(13),
+        decision_started_block: row.get(14),
+        confirm_started_block: row.get(15),
+        confirm_end_block: row.get(16),
+        concluded_block: row.get(17),
+        ayes: row.get(18),
+        nays: row.get(19),
+        support: row.get(20),
+        submitted_extrinsic_hash: row.get(21),
+        updated_block: row.get(22),
+    }
+}
+
+/// Repository for recovery configurations, recovery attempts, vouches and recovered accounts
+pub struct RecoveryRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> RecoveryRepository<'a> {
+    /// Create a new recovery repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or replace a recovery configuration
+    pub async fn upsert_config(&self, config: &RecoveryConfig) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.recovery_configs
+            (account, friends, threshold, delay_period, deposit, created_block,
+             created_extrinsic_hash, removed_block, updated_block)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9)
+            ON CONFLICT (account) DO UPDATE SET
+                friends = EXCLUDED.friends,
+                threshold = EXCLUDED.threshold,
+                delay_period = EXCLUDED.delay_period,
+                deposit = EXCLUDED.deposit,
+                created_block = EXCLUDED.created_block,
+                created_extrinsic_hash = EXCLUDED.created_extrinsic_hash,
+                removed_block = EXCLUDED.removed_block,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &config.account,
+                    &config.friends,
+                    &config.threshold,
+                    &config.delay_period,
+                    &config.deposit,
+                    &config.created_block,
+                    &config.created_extrinsic_hash,
+                    &config.removed_block,
+                    &config.updated_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the recovery configuration of an account, including removed ones
+    pub async fn get_config(&self, account: &[u8]) -> Result<Option<RecoveryConfig>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT account, friends, threshold, delay_period, deposit::TEXT, created_block,
+                   created_extrinsic_hash, removed_block, updated_block
+            FROM {schema}.recovery_configs
+            WHERE account = $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&account]).await?;
+        Ok(row.map(|row| RecoveryConfig {
+            account: row.get(0),
+            friends: row.get(1),
+            threshold: row.get(2),
+            delay_period: row.get(3),
+            deposit: row.get(4),
+            created_block: row.get(5),
+            created_extrinsic_hash: row.get(6),
+            removed_block: row.get(7),
+            updated_block: row.get(8),
+        }))
+    }
+
+    /// Insert or replace a recovery attempt
+    pub async fn upsert_recovery(&self, recovery: &Recovery) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.recoveries
+            (lost_account, rescuer_account, initiated_block, initiated_extrinsic_hash, deposit,
+             vouch_count, status, recovered_block, closed_block, updated_block)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8, $9, $10)
+            ON CONFLICT (lost_account, rescuer_account, initiated_block) DO UPDATE SET
+                initiated_extrinsic_hash = EXCLUDED.initiated_extrinsic_hash,
+                deposit = EXCLUDED.deposit,
+                vouch_count = EXCLUDED.vouch_count,
+                status = EXCLUDED.status,
+                recovered_block = EXCLUDED.recovered_block,
+                closed_block = EXCLUDED.closed_block,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &recovery.lost_account,
+                    &recovery.rescuer_account,
+                    &recovery.initiated_block,
+                    &recovery.initiated_extrinsic_hash,
+                    &recovery.deposit,
+                    &recovery.vouch_count,
+                    &recovery.status.as_str(),
+                    &recovery.recovered_block,
+                    &recovery.closed_block,
+                    &recovery.updated_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the latest recovery of a lost account by a rescuer that has not been closed
+    pub async fn get_open_recovery(
+        &self,
+        lost_account: &[u8],
+        rescuer_account: &[u8],
+    ) -> Result<Option<Recovery>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.recoveries
+            WHERE lost_account = $1 AND rescuer_account = $2 AND status <> 'closed'
+            ORDER BY initiated_block DESC
+            LIMIT 1
+            "#,
+            columns = RECOVERY_COLUMNS,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_opt(&sql, &[&lost_account, &rescuer_account])
+            .await?;
+        Ok(row.as_ref().map(recovery_from_row))
+    }
+
+    /// Get every recovery attempt on a lost account, most recent first
+    pub async fn get_recoveries(&self, lost_account: &[u8]) -> Result<Vec<Recovery>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.recoveries
+            WHERE lost_account = $1
+            ORDER BY initiated_block DESC, rescuer_account
+            "#,
+            columns = RECOVERY_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&lost_account]).await?;
+        Ok(rows.iter().map(recovery_from_row).collect())
+    }
+
+    /// Insert vouches, skipping ones already recorded
+    pub async fn insert_vouches(&self, vouches: &[RecoveryVouch]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.recovery_vouches
+            (block_number, event_index, lost_account, rescuer_account, voucher, extrinsic_hash,
+             block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for vouch in vouches {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &vouch.block_number,
+                        &vouch.event_index,
+                        &vouch.lost_account,
+                        &vouch.rescuer_account,
+                        &vouch.voucher,
+                        &vouch.extrinsic_hash,
+                        &vouch.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert lost to rescuer links, skipping ones already recorded
+    pub async fn insert_recovered(&self, recovered: &[RecoveredAccount]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.recovered_accounts
+            (block_number, event_index, lost_account, rescuer_account, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for link in recovered {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &link.block_number,
+                        &link.event_index,
+                        &link.lost_account,
+                        &link.rescuer_account,
+                        &link.extrinsic_hash,
+                        &link.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Follow an account through its recovery and any later recoveries of its rescuers
+    pub async fn get_lineage(&self, account: &[u8]) -> Result<Vec<LineageHop>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT depth, lost_account, rescuer_account, recovered_block
+            FROM {schema}.account_lineage($1)
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| LineageHop {
+                depth: row.get(0),
+                lost_account: row.get(1),
+                rescuer_account: row.get(2),
+                recovered_block: row.get(3),
+            })
+            .collect())
+    }
+
+    /// Get native funds that left the recovered accounts of an account's lineage
+    pub async fn get_outflows(&self, account: &[u8]) -> Result<Vec<RecoveredOutflow>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT lost_account, block_number, event_index, recipient, amount::TEXT, reason,
+                   extrinsic_hash
+            FROM {schema}.recovered_outflows($1)
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| RecoveredOutflow {
+                lost_account: row.get(0),
+                block_number: row.get(1),
+                event_index: row.get(2),
+                recipient: row.get(3),
+                amount: row.get(4),
+                reason: BalanceChangeReason::from_str(row.get(5)),
+                extrinsic_hash: row.get(6),
+            })
+            .collect())
+    }
+
+    /// Undo recovery activity from blocks at or after a specific height.
+    ///
+    /// Configurations and recoveries created there are deleted; others lose removal, claim
+    /// and close markers set there. Vouch counts are refreshed from storage the next time the
+    /// recovery changes.
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.recovery_configs WHERE created_block >= $1",
+            schema = schema
+        );
+        let mut deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.recovery_configs SET
+                removed_block = NULLIF(LEAST(removed_block, $1), $1),
+                updated_block = $1 - 1,
+                updated_at = NOW()
+            WHERE updated_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            "DELETE FROM {schema}.recoveries WHERE initiated_block >= $1",
+            schema = schema
+        );
+        deleted += self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.recoveries SET
+                recovered_block = NULLIF(LEAST(recovered_block, $1), $1),
+                closed_block = NULLIF(LEAST(closed_block, $1), $1),
+                status = CASE
+                    WHEN closed_block < $1 THEN 'closed'
+                    WHEN recovered_block < $1 THEN 'recovered'
+                    ELSE 'active'
+                END,
+                updated_block = $1 - 1,
+                updated_at = NOW()
+            WHERE updated_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        for table in ["recovery_vouches", "recovered_accounts"] {
+            let sql = format!(
+                "DELETE FROM {schema}.{table} WHERE block_number >= $1",
+                schema = schema,
+                table = table
+            );
+            self.conn.execute(&sql, &[&from_block]).await?;
+        }
+
+        Ok(deleted)
+    }
+}
+
+const RECOVERY_COLUMNS: &str =
+    "lost_account, rescuer_account, initiated_block, initiated_extrinsic_hash, deposit::TEXT, \
+     vouch_count, status, recovered_block, closed_block, updated_block";
+
+fn recovery_from_row(row: &tokio_postgres::Row) -> Recovery {
+    Recovery {
+        lost_account: row.get(0),
+        rescuer_account: row.get(1),
+        initiated_block: row.get(2),
+        initiated_extrinsic_hash: row.get(3),
+        deposit: row.get(4),
+        vouch_count: row.get(5),
+        status: RecoveryStatus::from_str(row.get(6)),
+        recovered_block: row.get(7),
+        closed_block: row.get(8),
+        updated_block: row.get(9),
+    }
+}
+
+/// Repository for QPoW mining data and difficulty adjustments
+pub struct MiningRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> MiningRepository<'a> {
+    /// Create a new mining repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert a block's mining data, replacing an earlier record of the block
+    pub async fn insert_block(&self, mining: &BlockMining) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.block_mining
+            (block_number, miner, nonce, distance_threshold, difficulty, block_duration_ms,
+             total_work, reward, block_ts)
+            VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5::TEXT::NUMERIC, $6, $7::TEXT::NUMERIC,
+                    $8::TEXT::NUMERIC, $9)
+            ON CONFLICT (block_number) DO UPDATE SET
+                miner = EXCLUDED.miner,
+                nonce = EXCLUDED.nonce,
+                distance_threshold = EXCLUDED.distance_threshold,
+                difficulty = EXCLUDED.difficulty,
+                block_duration_ms = EXCLUDED.block_duration_ms,
+                total_work = EXCLUDED.total_work,
+                reward = EXCLUDED.reward,
+                block_ts = EXCLUDED.block_ts
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &mining.block_number,
+                    &mining.miner,
+                    &mining.nonce,
+                    &mining.distance_threshold,
+                    &mining.difficulty,
+                    &mining.block_duration_ms,
+                    &mining.total_work,
+                    &mining.reward,
+                    &mining.block_ts,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the mining data of a block
+    pub async fn get_block(&self, block_number: i64) -> Result<Option<BlockMining>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.block_mining WHERE block_number = $1",
+            columns = BLOCK_MINING_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&block_number]).await?;
+        Ok(row.as_ref().map(block_mining_from_row))
+    }
+
+    /// Get the latest blocks mined by an account
+    pub async fn get_blocks_by_miner(&self, miner: &[u8], limit: i64) -> Result<Vec<BlockMining>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.block_mining
+            WHERE miner = $1
+            ORDER BY block_number DESC
+            LIMIT $2
+            "#,
+            columns = BLOCK_MINING_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&miner, &limit]).await?;
+        Ok(rows.iter().map(block_mining_from_row).collect())
+    }
+
+    /// Insert difficulty adjustments, skipping ones already recorded
+    pub async fn insert_adjustments(
+        &self,
+        adjustments: &[DistanceThresholdAdjustment],
+    ) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.distance_threshold_adjustments
+            (block_number, event_index, old_distance_threshold, new_distance_threshold,
+             observed_block_time, block_ts)
+            VALUES ($1, $2, $3::TEXT::NUMERIC, $4::TEXT::NUMERIC, $5, $6)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for adjustment in adjustments {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &adjustment.block_number,
+                        &adjustment.event_index,
+                        &adjustment.old_distance_threshold,
+                        &adjustment.new_distance_threshold,
+                        &adjustment.observed_block_time,
+                        &adjustment.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get the latest difficulty adjustments
+    pub async fn get_adjustments(&self, limit: i64) -> Result<Vec<DistanceThresholdAdjustment>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, event_index, old_distance_threshold::TEXT,
+                   new_distance_threshold::TEXT, observed_block_time, block_ts
+            FROM {schema}.distance_threshold_adjustments
+            ORDER BY block_number DESC, event_index DESC
+            LIMIT $1
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&limit]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| DistanceThresholdAdjustment {
+                block_number: row.get(0),
+                event_index: row.get(1),
+                old_distance_threshold: row.get(2),
+                new_distance_threshold: row.get(3),
+                observed_block_time: row.get(4),
+                block_ts: row.get(5),
+            })
+            .collect())
+    }
+
+    /// Delete mining data and adjustments from blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let mut deleted = 0;
+        for table in ["block_mining", "distance_threshold_adjustments"] {
+            let sql = format!(
+                "DELETE FROM {schema}.{table} WHERE block_number >= $1",
+                schema = schema,
+                table = table
+            );
+            deleted += self.conn.execute(&sql, &[&from_block]).await?;
+        }
+        Ok(deleted)
+    }
+}
+
+const BLOCK_MINING_COLUMNS: &str =
+    "block_number, miner, nonce, distance_threshold::TEXT, difficulty::TEXT, block_duration_ms, \
+     total_work::TEXT, reward::TEXT, block_ts";
+
+fn block_mining_from_row(row: &tokio_postgres::Row) -> BlockMining {
+    BlockMining {
+        block_number: row.get(0),
+        miner: row.get(1),
+        nonce: row.get(2),
+        distance_threshold: row.get(3),
+        difficulty: row.get(4),
+        block_duration_ms: row.get(5),
+        total_work: row.get(6),
+        reward: row.get(7),
+        block_ts: row.get(8),
+    }
+}
+
+/// Repository for the sudo audit log
+pub struct SudoRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> SudoRepository<'a> {
+    /// Create a new sudo repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert sudo actions, skipping ones already recorded
+    pub async fn insert_actions(&self, actions: &[SudoAction]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.sudo_actions
+            (block_number, event_index, action, sudo_key, new_key, as_account, call_pallet,
+             call_function, call_args, success, dispatch_error, extrinsic_index, extrinsic_hash,
+             block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for action in actions {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &action.block_number,
+                        &action.event_index,
+                        &action.action,
+                        &action.sudo_key,
+                        &action.new_key,
+                        &action.as_account,
+                        &action.call_pallet,
+                        &action.call_function,
+                        &action.call_args,
+                        &action.success,
+                        &action.dispatch_error,
+                        &action.extrinsic_index,
+                        &action.extrinsic_hash,
+                        &action.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get the latest sudo actions, newest first
+    pub async fn get_actions(&self, limit: i64) -> Result<Vec<SudoAction>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.sudo_actions
+            ORDER BY block_number DESC, event_index DESC
+            LIMIT $1
+            "#,
+            columns = SUDO_ACTION_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&limit]).await?;
+        Ok(rows.iter().map(sudo_action_from_row).collect())
+    }
+
+    /// Get the sudo actions performed with a key
+    pub async fn get_actions_by_key(&self, sudo_key: &[u8]) -> Result<Vec<SudoAction>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.sudo_actions
+            WHERE sudo_key = $1
+            ORDER BY block_number, event_index
+            "#,
+            columns = SUDO_ACTION_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&sudo_key]).await?;
+        Ok(rows.iter().map(sudo_action_from_row).collect())
+    }
+
+    /// Delete sudo actions from blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.sudo_actions WHERE block_number >= $1",
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const SUDO_ACTION_COLUMNS: &str =
+    "block_number, event_index, action, sudo_key, new_key, as_account, call_pallet, \
+     call_function, call_args, success, dispatch_error, extrinsic_index, extrinsic_hash, block_ts";
+
+fn sudo_action_from_row(row: &tokio_postgres::Row) -> SudoAction {
+    SudoAction {
+        block_number: row.get(0),
+        event_index: row.get(1),
+        action: row.get(2),
+        sudo_key: row.get(3),
+        new_key: row.get(4),
+        as_account: row.get(5),
+        call_pallet: row.get(6),
+        call_function: row.get(7),
+        call_args: row.get(8),
+        success: row.get(9),
+        dispatch_error: row.get(10),
+        extrinsic_index: row.get(11),
+        extrinsic_hash: row.get(12),
+        block_ts: row.get(13),
+    }
+}
+
+/// Repository for scheduled tasks, scheduler events and preimages
+pub struct SchedulerRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> SchedulerRepository<'a> {
+    /// Create a new scheduler repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert or update a scheduled task
+    pub async fn upsert_task(&self, task: &ScheduledTask) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.scheduled_tasks
+            (agenda_kind, agenda_at, task_index, scheduled_block, scheduled_extrinsic_hash,
+             task_id, priority, origin, origin_account, call_hash, call_len, call_data,
+             call_pallet, call_function, call_args, period_kind, period, period_count, status,
+             dispatch_error, closed_block, updated_block)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                    $18, $19, $20, $21, $22)
+            ON CONFLICT (agenda_kind, agenda_at, task_index, scheduled_block) DO UPDATE SET
+                scheduled_extrinsic_hash = EXCLUDED.scheduled_extrinsic_hash,
+                task_id = EXCLUDED.task_id,
+                priority = EXCLUDED.priority,
+                origin = EXCLUDED.origin,
+                origin_account = EXCLUDED.origin_account,
+                call_hash = EXCLUDED.call_hash,
+                call_len = EXCLUDED.call_len,
+                call_data = EXCLUDED.call_data,
+                call_pallet = EXCLUDED.call_pallet,
+                call_function = EXCLUDED.call_function,
+                call_args = EXCLUDED.call_args,
+                period_kind = EXCLUDED.period_kind,
+                period = EXCLUDED.period,
+                period_count = EXCLUDED.period_count,
+                status = EXCLUDED.status,
+                dispatch_error = EXCLUDED.dispatch_error,
+                closed_block = EXCLUDED.closed_block,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &task.agenda_kind,
+                    &task.agenda_at,
+                    &task.task_index,
+                    &task.scheduled_block,
+                    &task.scheduled_extrinsic_hash,
+                    &task.task_id,
+                    &task.priority,
+                    &task.origin,
+                    &task.origin_account,
+                    &task.call_hash,
+                    &task.call_len,
+                    &task.call_data,
+                    &task.call_pallet,
+                    &task.call_function,
+                    &task.call_args,
+                    &task.period_kind,
+                    &task.period,
+                    &task.period_count,
+                    &task.status.as_str(),
+                    &task.dispatch_error,
+                    &task.closed_block,
+                    &task.updated_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the latest task at an agenda slot that is still waiting to run
+    pub async fn get_open_task(
+        &self,
+        agenda_kind: &str,
+        agenda_at: i64,
+        task_index: i32,
+    ) -> Result<Option<ScheduledTask>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.scheduled_tasks
+            WHERE agenda_kind = $1 AND agenda_at = $2 AND task_index = $3
+              AND status = 'scheduled'
+            ORDER BY scheduled_block DESC
+            LIMIT 1
+            "#,
+            columns = SCHEDULED_TASK_COLUMNS,
+            schema = schema
+        );
+
+        let row = self
+            .conn
+            .query_opt(&sql, &[&agenda_kind, &agenda_at, &task_index])
+            .await?;
+        Ok(row.as_ref().map(scheduled_task_from_row))
+    }
+
+    /// Get tasks in a lifecycle state, by agenda order
+    pub async fn get_tasks_by_status(
+        &self,
+        status: ScheduledTaskStatus,
+        limit: i64,
+    ) -> Result<Vec<ScheduledTask>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.scheduled_tasks
+            WHERE status = $1
+            ORDER BY agenda_kind, agenda_at, task_index
+            LIMIT $2
+            "#,
+            columns = SCHEDULED_TASK_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&status.as_str(), &limit]).await?;
+        Ok(rows.iter().map(scheduled_task_from_row).collect())
+    }
+
+    /// Insert scheduler events, skipping ones already recorded
+    pub async fn insert_events(&self, events: &[SchedulerEvent]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.scheduler_events
+            (block_number, event_index, event_variant, agenda_kind, agenda_at, task_index,
+             task_id, success, dispatch_error, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for event in events {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &event.block_number,
+                        &event.event_index,
+                        &event.event_variant,
+                        &event.agenda_kind,
+                        &event.agenda_at,
+                        &event.task_index,
+                        &event.task_id,
+                        &event.success,
+                        &event.dispatch_error,
+                        &event.extrinsic_hash,
+                        &event.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert or update a preimage
+    pub async fn upsert_preimage(&self, preimage: &Preimage) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.preimages
+            (hash, length, depositor, deposit, status, request_count, call_pallet, call_function,
+             call_args, first_block, noted_block, noted_extrinsic_hash, cleared_block,
+             updated_block)
+            VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (hash) DO UPDATE SET
+                length = EXCLUDED.length,
+                depositor = EXCLUDED.depositor,
+                deposit = EXCLUDED.deposit,
+                status = EXCLUDED.status,
+                request_count = EXCLUDED.request_count,
+                call_pallet = EXCLUDED.call_pallet,
+                call_function = EXCLUDED.call_function,
+                call_args = EXCLUDED.call_args,
+                noted_block = EXCLUDED.noted_block,
+                noted_extrinsic_hash = EXCLUDED.noted_extrinsic_hash,
+                cleared_block = EXCLUDED.cleared_block,
+                updated_block = EXCLUDED.updated_block,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &preimage.hash,
+                    &preimage.length,
+                    &preimage.depositor,
+                    &preimage.deposit,
+                    &preimage.status.as_str(),
+                    &preimage.request_count,
+                    &preimage.call_pallet,
+                    &preimage.call_function,
+                    &preimage.call_args,
+                    &preimage.first_block,
+                    &preimage.noted_block,
+                    &preimage.noted_extrinsic_hash,
+                    &preimage.cleared_block,
+                    &preimage.updated_block,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a preimage by hash
+    pub async fn get_preimage(&self, hash: &[u8]) -> Result<Option<Preimage>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT hash, length, depositor, deposit::TEXT, status, request_count, call_pallet,
+                   call_function, call_args, first_block, noted_block, noted_extrinsic_hash,
+                   cleared_block, updated_block
+            FROM {schema}.preimages
+            WHERE hash = $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&hash]).await?;
+        Ok(row.map(|row| Preimage {
+            hash: row.get(0),
+            length: row.get(1),
+            depositor: row.get(2),
+            deposit: row.get(3),
+            status: PreimageStatus::from_str(row.get(4)),
+            request_count: row.get(5),
+            call_pallet: row.get(6),
+            call_function: row.get(7),
+            call_args: row.get(8),
+            first_block: row.get(9),
+            noted_block: row.get(10),
+            noted_extrinsic_hash: row.get(11),
+            cleared_block: row.get(12),
+            updated_block: row.get(13),
+        }))
+    }
+
+    /// Rewind tasks and preimages to before a block and delete the scheduler events from it.
+    ///
+    /// Tasks and preimages first seen at or after the block are deleted; ones closed or
+    /// cleared at or after it are reopened.
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.scheduled_tasks WHERE scheduled_block >= $1",
+            schema = schema
+        );
+        let mut deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.scheduled_tasks SET
+                status = CASE WHEN closed_block >= $1 THEN 'scheduled' ELSE status END,
+                dispatch_error = CASE WHEN closed_block >= $1 THEN NULL ELSE dispatch_error END,
+                closed_block = NULLIF(LEAST(closed_block, $1), $1),
+                updated_block = $1 - 1,
+                updated_at = NOW()
+            WHERE updated_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            "DELETE FROM {schema}.preimages WHERE first_block >= $1",
+            schema = schema
+        );
+        deleted += self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.preimages SET
+                status = CASE WHEN cleared_block >= $1 THEN 'requested' ELSE status END,
+                cleared_block = NULLIF(LEAST(cleared_block, $1), $1),
+                noted_block = NULLIF(LEAST(noted_block, $1), $1),
+                updated_block = $1 - 1,
+                updated_at = NOW()
+            WHERE updated_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            "DELETE FROM {schema}.scheduler_events WHERE block_number >= $1",
+            schema = schema
+        );
+        deleted += self.conn.execute(&sql, &[&from_block]).await?;
+
+        Ok(deleted)
+    }
+}
+
+const SCHEDULED_TASK_COLUMNS: &str =
+    "agenda_kind, agenda_at, task_index, scheduled_block, scheduled_extrinsic_hash, task_id, \
+     priority, origin, origin_account, call_hash, call_len, call_data, call_pallet, \
+     call_function, call_args, period_kind, period, period_count, status, dispatch_error, \
+     closed_block, updated_block";
+
+fn scheduled_task_from_row(row: &tokio_postgres::Row) -> ScheduledTask {
+    ScheduledTask {
+        agenda_kind: row.get(0),
+        agenda_at: row.get(1),
+        task_index: row.get(2),
+        scheduled_block: row.get(3),
+        scheduled_extrinsic_hash: row.get(4),
+        task_id: row.get(5),
+        priority: row.get(6),
+        origin: row.get(7),
+        origin_account: row.get(8),
+        call_hash: row.get(9),
+        call_len: row.get(10),
+        call_data: row.get(11),
+        call_pallet: row.get(12),
+        call_function: row.get(13),
+        call_args: row.get(14),
+        period_kind: row.get(15),
+        period: row.get(16),
+        period_count: row.get(17),
+        status: ScheduledTaskStatus::from_str(row.get(18)),
+        dispatch_error: row.get(19),
+        closed_block: row.get(20),
+        updated_block: row.get(21),
+    }
+}
+
+/// Repository for calls dispatched from Utility batches
+pub struct UtilityRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> UtilityRepository<'a> {
+    /// Create a new utility repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert batch items, skipping ones already recorded
+    pub async fn insert_items(&self, items: &[BatchItem]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.batch_items
+            (block_number, extrinsic_index, call_path, batch_index, item_index, depth, batch_call,
+             call_pallet, call_function, origin, origin_account, success, dispatch_error,
+             first_event_index, last_event_index, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            ON CONFLICT (block_number, extrinsic_index, call_path) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for item in items {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &item.block_number,
+                        &item.extrinsic_index,
+                        &item.call_path,
+                        &item.batch_index,
+                        &item.item_index,
+                        &item.depth,
+                        &item.batch_call,
+                        &item.call_pallet,
+                        &item.call_function,
+                        &item.origin,
+                        &item.origin_account,
+                        &item.success,
+                        &item.dispatch_error,
+                        &item.first_event_index,
+                        &item.last_event_index,
+                        &item.extrinsic_hash,
+                        &item.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get the batch items of an extrinsic in call-tree order
+    pub async fn get_items(
+        &self,
+        block_number: i64,
+        extrinsic_index: i32,
+    ) -> Result<Vec<BatchItem>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.batch_items
+            WHERE block_number = $1 AND extrinsic_index = $2
+            ORDER BY first_event_index NULLS LAST, depth
+            "#,
+            columns = BATCH_ITEM_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self
+            .conn
+            .query(&sql, &[&block_number, &extrinsic_index])
+            .await?;
+        Ok(rows.iter().map(batch_item_from_row).collect())
+    }
+
+    /// Get the calls dispatched as an account through `dispatch_as`
+    pub async fn get_items_by_origin(&self, account: &[u8]) -> Result<Vec<BatchItem>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.batch_items
+            WHERE origin_account = $1
+            ORDER BY block_number, extrinsic_index, first_event_index
+            "#,
+            columns = BATCH_ITEM_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account]).await?;
+        Ok(rows.iter().map(batch_item_from_row).collect())
+    }
+
+    /// Delete batch items from blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.batch_items WHERE block_number >= $1",
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+const BATCH_ITEM_COLUMNS: &str =
+    "block_number, extrinsic_index, call_path, batch_index, item_index, depth, batch_call, \
+     call_pallet, call_function, origin, origin_account, success, dispatch_error, \
+     first_event_index, last_event_index, extrinsic_hash, block_ts";
+
+fn batch_item_from_row(row: &tokio_postgres::Row) -> BatchItem {
+    BatchItem {
+        block_number: row.get(0),
+        extrinsic_index: row.get(1),
+        call_path: row.get(2),
+        batch_index: row.get(3),
+        item_index: row.get(4),
+        depth: row.get(5),
+        batch_call: row.get(6),
+        call_pallet: row.get(7),
+        call_function: row.get(8),
+        origin: row.get(9),
+        origin_account: row.get(10),
+        success: row.get(11),
+        dispatch_error: row.get(12),
+        first_event_index: row.get(13),
+        last_event_index: row.get(14),
+        extrinsic_hash: row.get(15),
+        block_ts: row.get(16),
+    }
+}
+
+/// Repository for the running ledger hash of each block and its signed checkpoints
+pub struct LedgerRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> LedgerRepository<'a> {
+    /// Create a new ledger repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Record the ledger hash of a block
+    pub async fn set_ledger_hash(&self, block_hash: &[u8], ledger_hash: &[u8]) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "UPDATE {schema}.blocks SET ledger_hash = $2 WHERE hash = $1",
+            schema = schema
+        );
+        self.conn
+            .execute(&sql, &[&block_hash, &ledger_hash])
+            .await?;
+        Ok(())
+    }
+
+    /// Get the ledger hash of the canonical block at a height
+    pub async fn get_ledger_hash(&self, block_number: i64) -> Result<Option<Vec<u8>>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "SELECT ledger_hash FROM {schema}.blocks WHERE number = $1 AND is_canonical",
+            schema = schema
+        );
+        let row = self.conn.query_opt(&sql, &[&block_number]).await?;
+        Ok(row.and_then(|row| row.get(0)))
+    }
+
+    /// Get up to `limit` canonical blocks from a height on, in chain order
+    pub async fn get_blocks(&self, from_block: i64, limit: i64) -> Result<Vec<Block>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.blocks
+            WHERE number >= $1 AND is_canonical
+            ORDER BY number
+            LIMIT $2
+            "#,
+            columns = BLOCK_COLUMNS,
+            schema = schema
+        );
+        let rows = self.conn.query(&sql, &[&from_block, &limit]).await?;
+        Ok(rows.iter().map(block_from_row).collect())
+    }
+
+    /// Get the canonical balance changes of a block range in the order the ledger hashes them
+    pub async fn get_changes(&self, from_block: i64, to_block: i64) -> Result<Vec<BalanceChange>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.balance_changes
+            WHERE block_number BETWEEN $1 AND $2 AND is_canonical
+            ORDER BY block_number, event_index, account, asset_id NULLS FIRST
+            "#,
+            columns = BALANCE_CHANGE_COLUMNS,
+            schema = schema
+        );
+        let rows = self.conn.query(&sql, &[&from_block, &to_block]).await?;
+        Ok(rows.iter().map(balance_change_from_row).collect())
+    }
+
+    /// Count canonical balance changes at heights without a canonical block, which no
+    /// ledger hash covers
+    pub async fn count_unanchored_changes(&self) -> Result<i64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT COUNT(*)
+            FROM {schema}.balance_changes c
+            WHERE c.is_canonical AND NOT EXISTS (
+                SELECT 1 FROM {schema}.blocks b WHERE b.number = c.block_number AND b.is_canonical
+            )
+            "#,
+            schema = schema
+        );
+        let row = self.conn.query_one(&sql, &[]).await?;
+        Ok(row.get(0))
+    }
+
+    /// Store a signed checkpoint, replacing an earlier one at the same height
+    pub async fn insert_checkpoint(&self, checkpoint: &LedgerCheckpoint) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.ledger_checkpoints
+            (block_number, block_hash, ledger_hash, scheme, public_key, signature, signed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (block_number) DO UPDATE SET
+                block_hash = EXCLUDED.block_hash,
+                ledger_hash = EXCLUDED.ledger_hash,
+                scheme = EXCLUDED.scheme,
+                public_key = EXCLUDED.public_key,
+                signature = EXCLUDED.signature,
+                signed_at = EXCLUDED.signed_at
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &checkpoint.block_number,
+                    &checkpoint.block_hash,
+                    &checkpoint.ledger_hash,
+                    &checkpoint.scheme,
+                    &checkpoint.public_key,
+                    &checkpoint.signature,
+                    &checkpoint.signed_at,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Get all checkpoints, oldest first
+    pub async fn get_checkpoints(&self) -> Result<Vec<LedgerCheckpoint>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, block_hash, ledger_hash, scheme, public_key, signature, signed_at
+            FROM {schema}.ledger_checkpoints
+            ORDER BY block_number
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| LedgerCheckpoint {
+                block_number: row.get(0),
+                block_hash: row.get(1),
+                ledger_hash: row.get(2),
+                scheme: row.get(3),
+                public_key: row.get(4),
+                signature: row.get(5),
+                signed_at: row.get(6),
+            })
+            .collect())
+    }
+
+    /// Delete checkpoints at or after a specific height; the blocks' own ledger hashes stay
+    /// with them when they are demoted
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.ledger_checkpoints WHERE block_number >= $1",
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
+
+/// Repository for wormhole proofs, used nullifiers and balance transfer proofs
+pub struct WormholeRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> WormholeRepository<'a> {
+    /// Create a new wormhole repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Insert verified proofs, skipping ones already recorded
+    pub async fn insert_proofs(&self, proofs: &[WormholeProof]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.wormhole_proofs
+            (block_number, event_index, extrinsic_hash, exit_account, exit_amount,
+             proof_block_number, proof_hash, block_ts)
+            VALUES ($1, $2, $3, $4, $5::TEXT::NUMERIC, $6, $7, $8)
+            ON CONFLICT (block_number, event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for proof in proofs {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &proof.block_number,
+                        &proof.event_index,
+                        &proof.extrinsic_hash,
+                        &proof.exit_account,
+                        &proof.exit_amount,
+                        &proof.proof_block_number,
+                        &proof.proof_hash,
+                        &proof.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert used nullifiers, keeping the block where each was first seen
+    pub async fn insert_nullifiers(&self, nullifiers: &[WormholeNullifier]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.wormhole_nullifiers
+            (nullifier, block_number, extrinsic_hash, block_ts)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (nullifier) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for nullifier in nullifiers {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &nullifier.nullifier,
+                        &nullifier.block_number,
+                        &nullifier.extrinsic_hash,
+                        &nullifier.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert transfer proofs, replacing any recorded under the same counter value
+    pub async fn insert_transfer_proofs(&self, proofs: &[TransferProof]) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.transfer_proofs
+            (transfer_count, from_account, to_account, amount, block_number, extrinsic_hash,
+             block_ts)
+            VALUES ($1, $2, $3, $4::TEXT::NUMERIC, $5, $6, $7)
+            ON CONFLICT (transfer_count) DO UPDATE SET
+                from_account = EXCLUDED.from_account,
+                to_account = EXCLUDED.to_account,
+                amount = EXCLUDED.amount,
+                block_number = EXCLUDED.block_number,
+                extrinsic_hash = EXCLUDED.extrinsic_hash,
+                block_ts = EXCLUDED.block_ts
+            "#,
+            schema = schema
+        );
+
+        let mut inserted = 0;
+        for proof in proofs {
+            inserted += self
+                .conn
+                .execute(
+                    &sql,
+                    &[
+                        &proof.transfer_count,
+                        &proof.from_account,
+                        &proof.to_account,
+                        &proof.amount,
+                        &proof.block_number,
+                        &proof.extrinsic_hash,
+                        &proof.block_ts,
+                    ],
+                )
+                .await?;
+        }
+        Ok(inserted)
+    }
+
+    /// Get proofs that exited to an account, most recent first
+    pub async fn get_proofs_by_exit_account(
+        &self,
+        account: &AccountId,
+    ) -> Result<Vec<WormholeProof>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT block_number, event_index, extrinsic_hash, exit_account, exit_amount::TEXT,
+                   proof_block_number, proof_hash, block_ts
+            FROM {schema}.wormhole_proofs
+            WHERE exit_account = $1
+            ORDER BY block_number DESC, event_index DESC
+            "#,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[&account.as_bytes()]).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| WormholeProof {
+                block_number: row.get(0),
+                event_index: row.get(1),
+                extrinsic_hash: row.get(2),
+                exit_account: row.get(3),
+                exit_amount: row.get(4),
+                proof_block_number: row.get(5),
+                proof_hash: row.get(6),
+                block_ts: row.get(7),
+            })
+            .collect())
+    }
+
+    /// Whether a nullifier has been used, and in which block
+    pub async fn get_nullifier(&self, nullifier: &[u8]) -> Result<Option<WormholeNullifier>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT nullifier, block_number, extrinsic_hash, block_ts
+            FROM {schema}.wormhole_nullifiers
+            WHERE nullifier = $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&nullifier]).await?;
+        Ok(row.map(|row| WormholeNullifier {
+            nullifier: row.get(0),
+            block_number: row.get(1),
+            extrinsic_hash: row.get(2),
+            block_ts: row.get(3),
+        }))
+    }
+
+    /// Get the transfer proofs an account sent or received, most recent first
+    pub async fn get_transfer_proofs(
+        &self,
+        account: &AccountId,
+        limit: i64,
+    ) -> Result<Vec<TransferProof>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT transfer_count, from_account, to_account, amount::TEXT, block_number,
+                   extrinsic_hash, block_ts
+            FROM {schema}.transfer_proofs
+            WHERE from_account = $1 OR to_account = $1
+            ORDER BY transfer_count DESC
+            LIMIT $2
+            "#,
+            schema = schema
+        );
+
+        let rows = self
+            .conn
+            .query(&sql, &[&account.as_bytes(), &limit])
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| TransferProof {
+                transfer_count: row.get(0),
+                from_account: row.get(1),
+                to_account: row.get(2),
+                amount: row.get(3),
+                block_number: row.get(4),
+                extrinsic_hash: row.get(5),
+                block_ts: row.get(6),
+            })
+            .collect())
+    }
+
+    /// Total minted by wormhole exits up to and including a block
+    pub async fn get_exit_total(&self, block_number: i64) -> Result<String> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(exit_amount), 0)::TEXT
+            FROM {schema}.wormhole_proofs
+            WHERE block_number <= $1
+            "#,
+            schema = schema
+        );
+
+        let row = self.conn.query_one(&sql, &[&block_number]).await?;
+        Ok(row.get(0))
+    }
+
+    /// Delete proofs, nullifiers and transfer proofs for blocks at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let mut deleted = 0;
+        for table in ["wormhole_proofs", "wormhole_nullifiers", "transfer_proofs"] {
+            let sql = format!(
+                "DELETE FROM {schema}.{table} WHERE block_number >= $1",
+                schema = schema,
+                table = table
+            );
+            deleted += self.conn.execute(&sql, &[&from_block]).await?;
+        }
+        Ok(deleted)
+    }
+}
+
+/// Repository for public keys revealed by signed extrinsics
+pub struct PublicKeyRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> PublicKeyRepository<'a> {
+    /// Create a new public key repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Record a public key unless the account already revealed one.
+    ///
+    /// A key that derives to the account replaces one that does not, so a bogus key seen
+    /// first cannot shadow the account's real key. Returns true if the key was recorded.
+    pub async fn insert_if_new(&self, key: &PublicKey) -> Result<bool> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.public_keys
+            (account, public_key, signature_scheme, derivation, account_matches,
+             first_seen_block, first_extrinsic_index, first_extrinsic_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (account) DO UPDATE SET
+                public_key = EXCLUDED.public_key,
+                signature_scheme = EXCLUDED.signature_scheme,
+                derivation = EXCLUDED.derivation,
+                account_matches = EXCLUDED.account_matches,
+                first_seen_block = EXCLUDED.first_seen_block,
+                first_extrinsic_index = EXCLUDED.first_extrinsic_index,
+                first_extrinsic_hash = EXCLUDED.first_extrinsic_hash
+            WHERE EXCLUDED.account_matches AND NOT {schema}.public_keys.account_matches
+            "#,
+            schema = schema
+        );
+
+        let inserted = self
+            .conn
+            .execute(
+                &sql,
+                &[
+                    &key.account,
+                    &key.public_key,
+                    &key.signature_scheme,
+                    &key.derivation,
+                    &key.account_matches,
+                    &key.first_seen_block,
+                    &key.first_extrinsic_index,
+                    &key.first_extrinsic_hash,
+                ],
+            )
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Record multiple public keys, returning how many were new
+    pub async fn insert_batch(&self, keys: &[PublicKey]) -> Result<u64> {
+        let mut inserted = 0;
+        for key in keys {
+            if self.insert_if_new(key).await? {
+                inserted += 1;
+            }
+        }
         Ok(inserted)
     }
 
-    /// Get balance changes for an account
-    pub async fn get_by_account(
-        &self,
-        account: &[u8],
-        limit: Option<i64>,
-        offset: Option<i64>,
-    ) -> Result<Vec<BalanceChange>> {
+    /// Get the public key revealed by an account
+    pub async fn get_by_account(&self, account: &AccountId) -> Result<Option<PublicKey>> {
+        let account = account.as_bytes();
         let schema = self.conn.schema_name()?;
-        let mut sql = format!(
+        let sql = format!(
+            "SELECT {columns} FROM {schema}.public_keys WHERE account = $1",
+            columns = PUBLIC_KEY_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&account]).await?;
+        Ok(row.as_ref().map(public_key_from_row))
+    }
+
+    /// Get keys whose account does not derive from the public key
+    pub async fn get_mismatched(&self, limit: i64) -> Result<Vec<PublicKey>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
             r#"
-            SELECT id, account, block_number, event_index, delta, reason,
-                   extrinsic_hash, event_pallet, event_variant, block_ts
-            FROM {schema}.balance_changes
-            WHERE account = $1
-            ORDER BY block_number DESC, event_index DESC
+            SELECT {columns}
+            FROM {schema}.public_keys
+            WHERE account_matches = false
+            ORDER BY first_seen_block DESC
+            LIMIT $1
             "#,
+            columns = PUBLIC_KEY_COLUMNS,
             schema = schema
         );
 
-        if let Some(limit) = limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
-        }
-        if let Some(offset) = offset {
-            sql.push_str(&format!(" OFFSET {}", offset));
-        }
+        let rows = self.conn.query(&sql, &[&limit]).await?;
+        Ok(rows.iter().map(public_key_from_row).collect())
+    }
 
-        let rows = self.conn.query(&sql, &[&account]).await?;
+    /// Count accounts that have revealed their public key
+    pub async fn count(&self) -> Result<i64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!("SELECT COUNT(*) FROM {schema}.public_keys", schema = schema);
 
-        Ok(rows
-            .into_iter()
-            .map(|row| BalanceChange {
-                id: Some(row.get(0)),
-                account: row.get(1),
-                block_number: row.get(2),
-                event_index: row.get(3),
-                delta: row.get(4),
-                reason: BalanceChangeReason::from_str(row.get(5)),
-                extrinsic_hash: row.get(6),
-                event_pallet: row.get(7),
-                event_variant: row.get(8),
-                block_ts: row.get(9),
-            })
-            .collect())
+        let row = self.conn.query_one(&sql, &[]).await?;
+        Ok(row.get(0))
     }
 
-    /// Get balance changes for a block
-    pub async fn get_by_block(&self, block_number: i64) -> Result<Vec<BalanceChange>> {
+    /// Delete keys first seen at or after a specific height
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
-            r#"
-            SELECT id, account, block_number, event_index, delta, reason,
-                   extrinsic_hash, event_pallet, event_variant, block_ts
-            FROM {schema}.balance_changes
-            WHERE block_number = $1
-            ORDER BY event_index
-            "#,
+            "DELETE FROM {schema}.public_keys WHERE first_seen_block >= $1",
             schema = schema
         );
 
-        let rows = self.conn.query(&sql, &[&block_number]).await?;
+        self.conn.execute(&sql, &[&from_block]).await
+    }
+}
 
-        Ok(rows
-            .into_iter()
-            .map(|row| BalanceChange {
-                id: Some(row.get(0)),
-                account: row.get(1),
-                block_number: row.get(2),
-                event_index: row.get(3),
-                delta: row.get(4),
-                reason: BalanceChangeReason::from_str(row.get(5)),
-                extrinsic_hash: row.get(6),
-                event_pallet: row.get(7),
-                event_variant: row.get(8),
-                block_ts: row.get(9),
-            })
-            .collect())
+const PUBLIC_KEY_COLUMNS: &str =
+    "account, public_key, signature_scheme, derivation, account_matches, \
+     first_seen_block, first_extrinsic_index, first_extrinsic_hash";
+
+fn public_key_from_row(row: &tokio_postgres::Row) -> PublicKey {
+    PublicKey {
+        account: row.get(0),
+        public_key: row.get(1),
+        signature_scheme: row.get(2),
+        derivation: row.get(3),
+        account_matches: row.get(4),
+        first_seen_block: row.get(5),
+        first_extrinsic_index: row.get(6),
+        first_extrinsic_hash: row.get(7),
+    }
+}
+
+/// Repository for the chain identity record
+pub struct ChainInfoRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> ChainInfoRepository<'a> {
+    /// Create a new chain info repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
     }
 
-    /// Get balance at a specific block for an account
-    pub async fn get_balance_at_block(&self, account: &[u8], block_number: i64) -> Result<String> {
+    /// Insert or refresh the chain info, keeping every endpoint seen so far
+    pub async fn upsert(&self, info: &ChainInfo) -> Result<()> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
-            SELECT COALESCE(SUM(delta::NUMERIC), 0)::TEXT
-            FROM {schema}.balance_changes
-            WHERE account = $1 AND block_number <= $2
+            INSERT INTO {schema}.chain_info
+            (chain_id, genesis_hash, chain_name, node_name, node_version, token_symbol,
+             token_decimals, ss58_prefix, properties, spec_version, endpoints, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (chain_id) DO UPDATE SET
+                genesis_hash = EXCLUDED.genesis_hash,
+                chain_name = EXCLUDED.chain_name,
+                node_name = EXCLUDED.node_name,
+                node_version = EXCLUDED.node_version,
+                token_symbol = EXCLUDED.token_symbol,
+                token_decimals = EXCLUDED.token_decimals,
+                ss58_prefix = EXCLUDED.ss58_prefix,
+                properties = EXCLUDED.properties,
+                spec_version = EXCLUDED.spec_version,
+                endpoints = ARRAY(
+                    SELECT DISTINCT e
+                    FROM unnest({schema}.chain_info.endpoints || EXCLUDED.endpoints) AS e
+                    ORDER BY e
+                ),
+                updated_at = EXCLUDED.updated_at
             "#,
             schema = schema
         );
 
-        let row = self
-            .conn
-            .query_one(&sql, &[&account, &block_number])
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &info.chain_id,
+                    &info.genesis_hash,
+                    &info.chain_name,
+                    &info.node_name,
+                    &info.node_version,
+                    &info.token_symbol,
+                    &info.token_decimals,
+                    &info.ss58_prefix,
+                    &info.properties,
+                    &info.spec_version,
+                    &info.endpoints,
+                    &info.updated_at,
+                ],
+            )
             .await?;
-        Ok(row.get(0))
+
+        Ok(())
     }
 
-    /// Delete balance changes for blocks at or after a specific height
-    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+    /// Get the chain info record
+    pub async fn get(&self) -> Result<Option<ChainInfo>> {
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
-            DELETE FROM {schema}.balance_changes
-            WHERE block_number >= $1
+            SELECT chain_id, genesis_hash, chain_name, node_name, node_version, token_symbol,
+                   token_decimals, ss58_prefix, properties, spec_version, endpoints, updated_at
+            FROM {schema}.chain_info
+            LIMIT 1
             "#,
             schema = schema
         );
 
-        self.conn.execute(&sql, &[&from_block]).await
+        let row = self.conn.query_opt(&sql, &[]).await?;
+        Ok(row.map(|row| ChainInfo {
+            chain_id: row.get(0),
+            genesis_hash: row.get(1),
+            chain_name: row.get(2),
+            node_name: row.get(3),
+            node_version: row.get(4),
+            token_symbol: row.get(5),
+            token_decimals: row.get(6),
+            ss58_prefix: row.get(7),
+            properties: row.get(8),
+            spec_version: row.get(9),
+            endpoints: row.get(10),
+            updated_at: row.get(11),
+        }))
     }
 }
 
 /// Repository for managing chain-wide operations
 pub struct ChainRepository<'a> {
-    conn: &'a DbConnection,
+    conn: &'a dyn DbExecutor,
 }
 
 impl<'a> ChainRepository<'a> {
     /// Create a new chain repository
-    pub fn new(conn: &'a DbConnection) -> Self {
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
         Self { conn }
     }
 
@@ -369,7 +4765,8 @@ impl<'a> ChainRepository<'a> {
         let sql = format!(
             r#"
             SELECT chain_id, latest_block, latest_block_hash, latest_block_ts,
-                   blocks_indexed, balance_changes_recorded, started_at, updated_at
+                   blocks_indexed, balance_changes_recorded, started_at, updated_at,
+                   finality_depth, finality_source, deepest_reorg
             FROM {schema}.index_progress
             WHERE chain_id = $1
             "#,
@@ -386,6 +4783,9 @@ impl<'a> ChainRepository<'a> {
                 balance_changes_recorded: row.get(5),
                 started_at: row.get(6),
                 updated_at: row.get(7),
+                finality_depth: row.get(8),
+                finality_source: row.get(9),
+                deepest_reorg: row.get(10),
             }),
             None => {
                 // Create new progress record starting from genesis (block 0)
@@ -397,6 +4797,9 @@ impl<'a> ChainRepository<'a> {
                     latest_block_ts: now,
                     blocks_indexed: 0,
                     balance_changes_recorded: 0,
+                    finality_depth: None,
+                    finality_source: None,
+                    deepest_reorg: 0,
                     started_at: now,
                     updated_at: now,
                 };
@@ -443,7 +4846,10 @@ impl<'a> ChainRepository<'a> {
                 latest_block_ts = $4,
                 blocks_indexed = $5,
                 balance_changes_recorded = $6,
-                updated_at = $7
+                updated_at = $7,
+                finality_depth = $8,
+                finality_source = $9,
+                deepest_reorg = $10
             WHERE chain_id = $1
             "#,
             schema = schema
@@ -460,6 +4866,9 @@ impl<'a> ChainRepository<'a> {
                     &progress.blocks_indexed,
                     &progress.balance_changes_recorded,
                     &Utc::now(),
+                    &progress.finality_depth,
+                    &progress.finality_source,
+                    &progress.deepest_reorg,
                 ],
             )
             .await?;
@@ -474,7 +4883,7 @@ impl<'a> ChainRepository<'a> {
             r#"
             INSERT INTO {schema}.account_stats
             (account, balance, first_seen_block, last_activity_block, total_changes, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            VALUES ($1, $2::TEXT::NUMERIC, $3, $4, $5, $6)
             ON CONFLICT (account) DO UPDATE SET
                 balance = EXCLUDED.balance,
                 last_activity_block = EXCLUDED.last_activity_block,
@@ -502,7 +4911,8 @@ impl<'a> ChainRepository<'a> {
     }
 
     /// Get account statistics
-    pub async fn get_account_stats(&self, account: &[u8]) -> Result<Option<AccountStats>> {
+    pub async fn get_account_stats(&self, account: &AccountId) -> Result<Option<AccountStats>> {
+        let account = account.as_bytes();
         let schema = self.conn.schema_name()?;
         let sql = format!(
             r#"
@@ -533,9 +4943,83 @@ impl<'a> ChainRepository<'a> {
         let blocks_repo = BlockRepository::new(self.conn);
         blocks_repo.mark_non_canonical_from(from_block).await?;
 
-        // Delete balance changes
+        // Flag balance changes, keeping them with their non-canonical blocks
         let changes_repo = BalanceChangeRepository::new(self.conn);
-        changes_repo.delete_from_block(from_block).await?;
+        changes_repo.mark_non_canonical_from(from_block).await?;
+
+        // Forget assets created in reorged blocks
+        let assets_repo = AssetRepository::new(self.conn);
+        assets_repo.delete_from_block(from_block).await?;
+
+        // Delete vesting updates and schedule snapshots
+        let vesting_repo = VestingRepository::new(self.conn);
+        vesting_repo.delete_from_block(from_block).await?;
+
+        // Delete balance and lock snapshots, rewind account stats
+        let balances_repo = AccountBalanceRepository::new(self.conn);
+        balances_repo.delete_from_block(from_block).await?;
+
+        // Reopen or forget reversible transfers touched by reorged blocks
+        let reversible_repo = ReversibleTransferRepository::new(self.conn);
+        reversible_repo.delete_from_block(from_block).await?;
+
+        // Rewind airdrop claims and forget campaigns created in reorged blocks
+        let airdrop_repo = AirdropRepository::new(self.conn);
+        airdrop_repo.delete_from_block(from_block).await?;
+
+        // Reopen treasury spends and forget ledger entries and balances of reorged blocks
+        let treasury_repo = TreasuryRepository::new(self.conn);
+        treasury_repo.delete_from_block(from_block).await?;
+
+        // Rewind referenda and collective membership, delete votes and delegations
+        let governance_repo = GovernanceRepository::new(self.conn);
+        governance_repo.delete_from_block(from_block).await?;
+
+        // Rewind recoveries and forget vouches and recovered accounts of reorged blocks
+        let recovery_repo = RecoveryRepository::new(self.conn);
+        recovery_repo.delete_from_block(from_block).await?;
+
+        // Delete mining data and difficulty adjustments
+        let mining_repo = MiningRepository::new(self.conn);
+        mining_repo.delete_from_block(from_block).await?;
+
+        // Delete sudo actions
+        let sudo_repo = SudoRepository::new(self.conn);
+        sudo_repo.delete_from_block(from_block).await?;
+
+        // Reopen scheduled tasks and preimages, delete scheduler events
+        let scheduler_repo = SchedulerRepository::new(self.conn);
+        scheduler_repo.delete_from_block(from_block).await?;
+
+        // Delete batch items
+        let utility_repo = UtilityRepository::new(self.conn);
+        utility_repo.delete_from_block(from_block).await?;
+
+        // Delete ledger checkpoints of reorged blocks
+        let ledger_repo = LedgerRepository::new(self.conn);
+        ledger_repo.delete_from_block(from_block).await?;
+
+        // Rewind runtime upgrades and the runtimes they started
+        let upgrade_repo = RuntimeUpgradeRepository::new(self.conn);
+        upgrade_repo.delete_from_block(from_block).await?;
+        let metadata_repo = RuntimeMetadataRepository::new(self.conn);
+        metadata_repo.delete_from_block(from_block).await?;
+
+        // Delete wormhole proofs, nullifiers and transfer proofs
+        let wormhole_repo = WormholeRepository::new(self.conn);
+        wormhole_repo.delete_from_block(from_block).await?;
+
+        // Delete extrinsics
+        let extrinsics_repo = ExtrinsicRepository::new(self.conn);
+        extrinsics_repo.delete_from_block(from_block).await?;
+
+        // Forget public keys first revealed in reorged blocks
+        let keys_repo = PublicKeyRepository::new(self.conn);
+        keys_repo.delete_from_block(from_block).await?;
+
+        // Forget accounts first seen in reorged blocks
+        let accounts_repo = AccountRepository::new(self.conn);
+        accounts_repo.delete_from_block(from_block).await?;
 
         // Update progress to reflect the reorg
         let chain_id = self
@@ -543,7 +5027,7 @@ impl<'a> ChainRepository<'a> {
             .chain_id()
             .ok_or_else(|| DbError::Configuration("Chain ID not set".into()))?;
 
-        let mut progress = self.get_or_create_progress(&chain_id).await?;
+        let mut progress = self.get_or_create_progress(chain_id).await?;
         progress.latest_block = from_block - 1;
         self.update_progress(&progress).await?;
 
@@ -553,12 +5037,12 @@ impl<'a> ChainRepository<'a> {
 
 /// Repository for managing runtime metadata
 pub struct RuntimeMetadataRepository<'a> {
-    conn: &'a DbConnection,
+    conn: &'a dyn DbExecutor,
 }
 
 impl<'a> RuntimeMetadataRepository<'a> {
     /// Create a new runtime metadata repository
-    pub fn new(conn: &'a DbConnection) -> Self {
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
         Self { conn }
     }
 
@@ -662,6 +5146,79 @@ impl<'a> RuntimeMetadataRepository<'a> {
         }
     }
 
+    /// Record the runtime a `CodeUpdated` event switched to, closing the runtime it
+    /// replaces. `metadata.first_seen_block` is the first block the new runtime runs.
+    pub async fn record_upgrade(&self, metadata: &RuntimeMetadata) -> Result<()> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            UPDATE {schema}.metadata
+            SET last_seen_block = $2 - 1, updated_at = NOW()
+            WHERE last_seen_block IS NULL AND first_seen_block < $2 AND spec_version <> $1
+            "#,
+            schema = schema
+        );
+        self.conn
+            .execute(&sql, &[&metadata.spec_version, &metadata.first_seen_block])
+            .await?;
+
+        // An upgrade keeping its spec version keeps the blocks it already covers
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.metadata
+            (spec_version, impl_version, transaction_version, state_version,
+             first_seen_block, last_seen_block, metadata_bytes, metadata_hash, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, NULL, $6, $7, NOW(), NOW())
+            ON CONFLICT (spec_version) DO UPDATE SET
+                impl_version = EXCLUDED.impl_version,
+                transaction_version = EXCLUDED.transaction_version,
+                state_version = EXCLUDED.state_version,
+                first_seen_block = LEAST({schema}.metadata.first_seen_block, EXCLUDED.first_seen_block),
+                last_seen_block = NULL,
+                metadata_bytes = EXCLUDED.metadata_bytes,
+                metadata_hash = EXCLUDED.metadata_hash,
+                updated_at = NOW()
+            "#,
+            schema = schema
+        );
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &metadata.spec_version,
+                    &metadata.impl_version,
+                    &metadata.transaction_version,
+                    &metadata.state_version,
+                    &metadata.first_seen_block,
+                    &metadata.metadata_bytes,
+                    &metadata.metadata_hash,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Forget runtimes that started after a block and reopen the ones closed at or after it
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.metadata WHERE first_seen_block > $1",
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.metadata
+            SET last_seen_block = NULL, updated_at = NOW()
+            WHERE last_seen_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+        Ok(deleted)
+    }
+
     /// Update last seen block for a runtime version
     pub async fn update_last_seen_block(
         &self,
@@ -761,3 +5318,243 @@ impl<'a> RuntimeMetadataRepository<'a> {
         }
     }
 }
+
+/// Repository for runtime upgrades
+pub struct RuntimeUpgradeRepository<'a> {
+    conn: &'a dyn DbExecutor,
+}
+
+impl<'a> RuntimeUpgradeRepository<'a> {
+    /// Create a new runtime upgrade repository
+    pub fn new(conn: &'a dyn DbExecutor) -> Self {
+        Self { conn }
+    }
+
+    /// Record an upgrade authorization, superseding the one pending before it
+    pub async fn record_authorization(&self, upgrade: &RuntimeUpgrade) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            UPDATE {schema}.runtime_upgrades
+            SET status = 'superseded', superseded_block = $1, updated_at = NOW()
+            WHERE status = 'authorized'
+              AND (authorized_block, authorized_event_index) < ($1, $2)
+            "#,
+            schema = schema
+        );
+        self.conn
+            .execute(
+                &sql,
+                &[&upgrade.authorized_block, &upgrade.authorized_event_index],
+            )
+            .await?;
+
+        self.insert(upgrade).await
+    }
+
+    /// Record the code an upgrade set, completing the pending authorization for the same
+    /// code or recording an upgrade applied without one
+    pub async fn record_applied(&self, upgrade: &RuntimeUpgrade) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            UPDATE {schema}.runtime_upgrades
+            SET status = 'applied', block_number = $2, event_index = $3, extrinsic_index = $4,
+                extrinsic_hash = $5, submitter = $6, upgrade_call = $7, previous_spec_name = $8,
+                previous_spec_version = $9, spec_name = $10, spec_version = $11,
+                impl_version = $12, transaction_version = $13, state_version = $14,
+                applied_ts = $15, updated_at = NOW()
+            WHERE code_hash = $1 AND status = 'authorized' AND authorized_block <= $2
+            "#,
+            schema = schema
+        );
+        let updated = self
+            .conn
+            .execute(
+                &sql,
+                &[
+                    &upgrade.code_hash,
+                    &upgrade.block_number,
+                    &upgrade.event_index,
+                    &upgrade.extrinsic_index,
+                    &upgrade.extrinsic_hash,
+                    &upgrade.submitter,
+                    &upgrade.upgrade_call,
+                    &upgrade.previous_spec_name,
+                    &upgrade.previous_spec_version,
+                    &upgrade.spec_name,
+                    &upgrade.spec_version,
+                    &upgrade.impl_version,
+                    &upgrade.transaction_version,
+                    &upgrade.state_version,
+                    &upgrade.applied_ts,
+                ],
+            )
+            .await?;
+        if updated > 0 {
+            return Ok(updated);
+        }
+
+        self.insert(upgrade).await
+    }
+
+    async fn insert(&self, upgrade: &RuntimeUpgrade) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            INSERT INTO {schema}.runtime_upgrades
+            (authorized_block, authorized_event_index, code_hash, check_version, authorizer,
+             authorized_via, authorization_extrinsic_hash, status, block_number, event_index,
+             extrinsic_index, extrinsic_hash, submitter, upgrade_call, previous_spec_name,
+             previous_spec_version, spec_name, spec_version, impl_version, transaction_version,
+             state_version, superseded_block, authorized_ts, applied_ts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                    $18, $19, $20, $21, $22, $23, $24)
+            ON CONFLICT (authorized_block, authorized_event_index) DO NOTHING
+            "#,
+            schema = schema
+        );
+
+        self.conn
+            .execute(
+                &sql,
+                &[
+                    &upgrade.authorized_block,
+                    &upgrade.authorized_event_index,
+                    &upgrade.code_hash,
+                    &upgrade.check_version,
+                    &upgrade.authorizer,
+                    &upgrade.authorized_via,
+                    &upgrade.authorization_extrinsic_hash,
+                    &upgrade.status.as_str(),
+                    &upgrade.block_number,
+                    &upgrade.event_index,
+                    &upgrade.extrinsic_index,
+                    &upgrade.extrinsic_hash,
+                    &upgrade.submitter,
+                    &upgrade.upgrade_call,
+                    &upgrade.previous_spec_name,
+                    &upgrade.previous_spec_version,
+                    &upgrade.spec_name,
+                    &upgrade.spec_version,
+                    &upgrade.impl_version,
+                    &upgrade.transaction_version,
+                    &upgrade.state_version,
+                    &upgrade.superseded_block,
+                    &upgrade.authorized_ts,
+                    &upgrade.applied_ts,
+                ],
+            )
+            .await
+    }
+
+    /// Get every recorded upgrade and authorization, oldest first
+    pub async fn get_upgrades(&self) -> Result<Vec<RuntimeUpgrade>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.runtime_upgrades
+            ORDER BY authorized_block, authorized_event_index
+            "#,
+            columns = RUNTIME_UPGRADE_COLUMNS,
+            schema = schema
+        );
+
+        let rows = self.conn.query(&sql, &[]).await?;
+        Ok(rows.iter().map(runtime_upgrade_from_row).collect())
+    }
+
+    /// Get the upgrade that switched to a spec version
+    pub async fn get_by_spec_version(&self, spec_version: i32) -> Result<Option<RuntimeUpgrade>> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            r#"
+            SELECT {columns}
+            FROM {schema}.runtime_upgrades
+            WHERE spec_version = $1 AND status = 'applied'
+            ORDER BY block_number DESC
+            LIMIT 1
+            "#,
+            columns = RUNTIME_UPGRADE_COLUMNS,
+            schema = schema
+        );
+
+        let row = self.conn.query_opt(&sql, &[&spec_version]).await?;
+        Ok(row.as_ref().map(runtime_upgrade_from_row))
+    }
+
+    /// Forget upgrades authorized at or after a block and reopen the ones applied or
+    /// superseded from it
+    pub async fn delete_from_block(&self, from_block: i64) -> Result<u64> {
+        let schema = self.conn.schema_name()?;
+        let sql = format!(
+            "DELETE FROM {schema}.runtime_upgrades WHERE authorized_block >= $1",
+            schema = schema
+        );
+        let deleted = self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.runtime_upgrades
+            SET status = 'authorized', block_number = NULL, event_index = NULL,
+                extrinsic_index = NULL, extrinsic_hash = NULL, submitter = NULL,
+                upgrade_call = NULL, previous_spec_name = NULL, previous_spec_version = NULL,
+                spec_name = NULL, spec_version = NULL, impl_version = NULL,
+                transaction_version = NULL, state_version = NULL, applied_ts = NULL,
+                updated_at = NOW()
+            WHERE block_number >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        let sql = format!(
+            r#"
+            UPDATE {schema}.runtime_upgrades
+            SET status = 'authorized', superseded_block = NULL, updated_at = NOW()
+            WHERE superseded_block >= $1
+            "#,
+            schema = schema
+        );
+        self.conn.execute(&sql, &[&from_block]).await?;
+
+        Ok(deleted)
+    }
+}
+
+const RUNTIME_UPGRADE_COLUMNS: &str =
+    "authorized_block, authorized_event_index, code_hash, check_version, authorizer, \
+     authorized_via, authorization_extrinsic_hash, status, block_number, event_index, \
+     extrinsic_index, extrinsic_hash, submitter, upgrade_call, previous_spec_name, \
+     previous_spec_version, spec_name, spec_version, impl_version, transaction_version, \
+     state_version, superseded_block, authorized_ts, applied_ts";
+
+fn runtime_upgrade_from_row(row: &tokio_postgres::Row) -> RuntimeUpgrade {
+    RuntimeUpgrade {
+        authorized_block: row.get(0),
+        authorized_event_index: row.get(1),
+        code_hash: row.get(2),
+        check_version: row.get(3),
+        authorizer: row.get(4),
+        authorized_via: row.get(5),
+        authorization_extrinsic_hash: row.get(6),
+        status: RuntimeUpgradeStatus::from_str(row.get(7)),
+        block_number: row.get(8),
+        event_index: row.get(9),
+        extrinsic_index: row.get(10),
+        extrinsic_hash: row.get(11),
+        submitter: row.get(12),
+        upgrade_call: row.get(13),
+        previous_spec_name: row.get(14),
+        previous_spec_version: row.get(15),
+        spec_name: row.get(16),
+        spec_version: row.get(17),
+        impl_version: row.get(18),
+        transaction_version: row.get(19),
+        state_version: row.get(20),
+        superseded_block: row.get(21),
+        authorized_ts: row.get(22),
+        applied_ts: row.get(23),
+    }
+}
@@ -36,10 +36,31 @@ impl SchemaManager {
 
         // Create tables
         self.create_blocks_table(conn).await?;
+        self.create_block_header_tables(conn).await?;
         self.create_balance_changes_table(conn).await?;
         self.create_index_progress_table(conn).await?;
+        self.create_chain_info_table(conn).await?;
         self.create_account_stats_table(conn).await?;
         self.create_metadata_table(conn).await?;
+        self.create_runtime_upgrades_table(conn).await?;
+        self.create_extrinsics_table(conn).await?;
+        self.create_public_keys_table(conn).await?;
+        self.create_accounts_table(conn).await?;
+        self.create_assets_table(conn).await?;
+        self.create_vesting_tables(conn).await?;
+        self.create_account_balance_tables(conn).await?;
+        self.create_reversible_transfer_tables(conn).await?;
+        self.create_wormhole_tables(conn).await?;
+        self.create_airdrop_tables(conn).await?;
+        self.create_treasury_tables(conn).await?;
+        self.create_governance_tables(conn).await?;
+        self.create_recovery_tables(conn).await?;
+        self.create_mining_tables(conn).await?;
+        self.create_fork_views(conn).await?;
+        self.create_sudo_tables(conn).await?;
+        self.create_scheduler_tables(conn).await?;
+        self.create_utility_tables(conn).await?;
+        self.create_ledger_tables(conn).await?;
 
         // Create indexes
         self.create_indexes(conn).await?;
@@ -82,14 +103,37 @@ impl SchemaManager {
         let sql = format!(
             r#"
             CREATE TABLE IF NOT EXISTS {schema}.blocks (
-                number BIGINT PRIMARY KEY,
-                hash BYTEA NOT NULL UNIQUE,
+                hash BYTEA PRIMARY KEY,
+                number BIGINT NOT NULL,
                 parent_hash BYTEA NOT NULL,
                 timestamp TIMESTAMPTZ NOT NULL,
                 is_canonical BOOLEAN NOT NULL DEFAULT true,
                 runtime_spec BIGINT NOT NULL,
+                author BYTEA,
                 created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-            )
+            );
+
+            -- Blocks used to be keyed by number, which overwrote the losing side of every fork
+            DO $$
+            BEGIN
+                IF EXISTS (
+                    SELECT 1
+                    FROM pg_index i
+                    JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+                    WHERE i.indrelid = '{schema}.blocks'::regclass AND i.indisprimary
+                      AND a.attname = 'number'
+                ) THEN
+                    ALTER TABLE {schema}.blocks DROP CONSTRAINT blocks_pkey;
+                    ALTER TABLE {schema}.blocks DROP CONSTRAINT IF EXISTS blocks_hash_key;
+                    ALTER TABLE {schema}.blocks ADD PRIMARY KEY (hash);
+                END IF;
+            END $$;
+            ALTER TABLE {schema}.blocks ADD COLUMN IF NOT EXISTS author BYTEA;
+            ALTER TABLE {schema}.blocks ADD COLUMN IF NOT EXISTS ledger_hash BYTEA;
+
+            -- Any number of blocks per height, at most one of them canonical
+            CREATE UNIQUE INDEX IF NOT EXISTS blocks_canonical_number_key
+                ON {schema}.blocks (number) WHERE is_canonical;
             "#,
             schema = schema
         );
@@ -99,6 +143,112 @@ impl SchemaManager {
         Ok(())
     }
 
+    /// Create the fork views over `blocks`. Canonical blocks indexed before `blocks.author`
+    /// existed take their miner from `block_mining`, so this runs after the mining tables.
+    pub async fn create_fork_views(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            -- Non-canonical blocks with the fork they belong to: the canonical block it
+            -- branched from, the depth within the branch, and the canonical block that won
+            -- the height
+            CREATE OR REPLACE VIEW {schema}.fork_blocks AS
+            WITH RECURSIVE branch AS (
+                SELECT b.hash, b.number, b.parent_hash, b.author, b.timestamp,
+                       b.number - 1 AS fork_point, b.hash AS fork_root, 1 AS depth
+                FROM {schema}.blocks b
+                LEFT JOIN {schema}.blocks p ON p.hash = b.parent_hash
+                WHERE NOT b.is_canonical AND (p.hash IS NULL OR p.is_canonical)
+                UNION ALL
+                SELECT b.hash, b.number, b.parent_hash, b.author, b.timestamp,
+                       f.fork_point, f.fork_root, f.depth + 1
+                FROM {schema}.blocks b
+                JOIN branch f ON b.parent_hash = f.hash
+                WHERE NOT b.is_canonical
+            )
+            SELECT f.number, f.hash, f.parent_hash, f.author, f.timestamp,
+                   f.fork_point, f.fork_root, f.depth,
+                   c.hash AS canonical_hash,
+                   COALESCE(c.author, m.miner) AS canonical_author
+            FROM branch f
+            LEFT JOIN {schema}.blocks c ON c.number = f.number AND c.is_canonical
+            LEFT JOIN {schema}.block_mining m ON m.block_number = f.number;
+
+            -- One row per fork: how deep it went and which miners competed
+            CREATE OR REPLACE VIEW {schema}.fork_history AS
+            SELECT fork_root,
+                   MIN(fork_point) AS fork_point,
+                   MAX(depth) AS depth,
+                   COUNT(*) AS orphaned_blocks,
+                   MIN(timestamp) AS first_seen_ts,
+                   ARRAY_AGG(DISTINCT author) FILTER (WHERE author IS NOT NULL) AS orphaned_miners,
+                   ARRAY_AGG(DISTINCT canonical_author) FILTER (WHERE canonical_author IS NOT NULL)
+                       AS canonical_miners
+            FROM {schema}.fork_blocks
+            GROUP BY fork_root;
+
+            -- Share of the blocks seen each day that ended up off the canonical chain
+            CREATE OR REPLACE VIEW {schema}.orphan_rate_daily AS
+            SELECT date_trunc('day', timestamp) AS day,
+                   COUNT(*) FILTER (WHERE is_canonical) AS canonical_blocks,
+                   COUNT(*) FILTER (WHERE NOT is_canonical) AS orphaned_blocks,
+                   COUNT(*) FILTER (WHERE NOT is_canonical)::NUMERIC / COUNT(*) AS orphan_rate
+            FROM {schema}.blocks
+            GROUP BY 1;
+
+            -- Blocks each miner had orphaned against those that stayed canonical
+            CREATE OR REPLACE VIEW {schema}.miner_orphans AS
+            SELECT COALESCE(b.author, m.miner) AS miner,
+                   COUNT(*) FILTER (WHERE b.is_canonical) AS canonical_blocks,
+                   COUNT(*) FILTER (WHERE NOT b.is_canonical) AS orphaned_blocks,
+                   COUNT(*) FILTER (WHERE NOT b.is_canonical)::NUMERIC / COUNT(*) AS orphan_rate
+            FROM {schema}.blocks b
+            LEFT JOIN {schema}.block_mining m ON m.block_number = b.number AND b.is_canonical
+            GROUP BY 1;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating fork views");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the block header and digest log tables
+    pub async fn create_block_header_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            -- Keyed by hash like blocks, so forked blocks keep their headers too
+            CREATE TABLE IF NOT EXISTS {schema}.block_headers (
+                block_hash BYTEA PRIMARY KEY,
+                block_number BIGINT NOT NULL,
+                state_root BYTEA NOT NULL,
+                extrinsics_root BYTEA NOT NULL,
+                extrinsic_count INT NOT NULL,
+                event_count INT,
+                block_size INT NOT NULL,
+                block_ts TIMESTAMPTZ NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.digest_logs (
+                block_hash BYTEA NOT NULL,
+                block_number BIGINT NOT NULL,
+                log_index INT NOT NULL,
+                kind TEXT NOT NULL,
+                engine_id TEXT,
+                data BYTEA NOT NULL,
+                PRIMARY KEY (block_hash, log_index)
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating block header tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
     /// Create the balance_changes table
     pub async fn create_balance_changes_table(&self, conn: &DbConnection) -> Result<()> {
         let schema = self.schema_name();
@@ -107,6 +257,7 @@ impl SchemaManager {
             CREATE TABLE IF NOT EXISTS {schema}.balance_changes (
                 id BIGSERIAL PRIMARY KEY,
                 account BYTEA NOT NULL,
+                asset_id BIGINT,
                 block_number BIGINT NOT NULL,
                 event_index INT NOT NULL,
                 delta NUMERIC(78,0) NOT NULL,
@@ -115,9 +266,43 @@ impl SchemaManager {
                 event_pallet TEXT NOT NULL,
                 event_variant TEXT NOT NULL,
                 block_ts TIMESTAMPTZ NOT NULL,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-                UNIQUE(block_number, event_index)
-            )
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            -- A single event can move funds for several accounts (e.g. both sides of a transfer)
+            ALTER TABLE {schema}.balance_changes
+                DROP CONSTRAINT IF EXISTS balance_changes_block_number_event_index_key;
+
+            -- Assets pallet balances share the table; NULL is the native token
+            ALTER TABLE {schema}.balance_changes ADD COLUMN IF NOT EXISTS asset_id BIGINT;
+
+            -- Position in the call tree for changes made inside Utility batches
+            ALTER TABLE {schema}.balance_changes ADD COLUMN IF NOT EXISTS batch_index INT;
+            ALTER TABLE {schema}.balance_changes ADD COLUMN IF NOT EXISTS call_path TEXT;
+
+            -- Block the change was made in; changes of blocks that lost a fork are kept, flagged
+            -- non-canonical, so only canonical changes are unique per event and account
+            DO $$
+            BEGIN
+                IF NOT EXISTS (
+                    SELECT 1 FROM pg_attribute
+                    WHERE attrelid = '{schema}.balance_changes'::regclass
+                      AND attname = 'block_hash' AND NOT attisdropped
+                ) THEN
+                    ALTER TABLE {schema}.balance_changes ADD COLUMN block_hash BYTEA;
+                    UPDATE {schema}.balance_changes c SET block_hash = b.hash
+                    FROM {schema}.blocks b
+                    WHERE b.number = c.block_number AND b.is_canonical;
+                END IF;
+            END $$;
+            ALTER TABLE {schema}.balance_changes
+                ADD COLUMN IF NOT EXISTS is_canonical BOOLEAN NOT NULL DEFAULT true;
+            ALTER TABLE {schema}.balance_changes
+                DROP CONSTRAINT IF EXISTS balance_changes_block_number_event_index_account_key;
+            DROP INDEX IF EXISTS {schema}.balance_changes_block_number_event_index_account_key;
+            CREATE UNIQUE INDEX IF NOT EXISTS balance_changes_canonical_event_account_key
+                ON {schema}.balance_changes (block_number, event_index, account)
+                WHERE is_canonical;
             "#,
             schema = schema
         );
@@ -141,7 +326,14 @@ impl SchemaManager {
                 balance_changes_recorded BIGINT NOT NULL DEFAULT 0,
                 started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
                 updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-            )
+            );
+
+            -- Confirmations the indexer waits for, where that number came from, and the deepest
+            -- reorg of the best chain seen while following it
+            ALTER TABLE {schema}.index_progress ADD COLUMN IF NOT EXISTS finality_depth INT;
+            ALTER TABLE {schema}.index_progress ADD COLUMN IF NOT EXISTS finality_source TEXT;
+            ALTER TABLE {schema}.index_progress
+                ADD COLUMN IF NOT EXISTS deepest_reorg INT NOT NULL DEFAULT 0;
             "#,
             schema = schema
         );
@@ -151,6 +343,843 @@ impl SchemaManager {
         Ok(())
     }
 
+    /// Create the chain_info table describing the chain (name, token, SS58 prefix)
+    pub async fn create_chain_info_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.chain_info (
+                chain_id TEXT PRIMARY KEY,
+                genesis_hash BYTEA NOT NULL,
+                chain_name TEXT NOT NULL,
+                node_name TEXT NOT NULL,
+                node_version TEXT NOT NULL,
+                token_symbol TEXT,
+                token_decimals INT,
+                ss58_prefix INT NOT NULL,
+                properties JSONB NOT NULL DEFAULT '{{}}'::jsonb,
+                spec_version INT NOT NULL,
+                endpoints TEXT[] NOT NULL DEFAULT '{{}}',
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating chain_info table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the assets table registering Assets pallet tokens
+    pub async fn create_assets_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.assets (
+                asset_id BIGINT PRIMARY KEY,
+                creator BYTEA,
+                owner BYTEA,
+                issuer BYTEA,
+                admin BYTEA,
+                freezer BYTEA,
+                name TEXT,
+                symbol TEXT,
+                decimals INT,
+                is_frozen BOOLEAN NOT NULL DEFAULT FALSE,
+                created_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                destroyed_block BIGINT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating assets table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the vesting_updates and vesting_schedules tables and the vesting SQL helpers
+    pub async fn create_vesting_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.vesting_updates (
+                account BYTEA NOT NULL,
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                unvested NUMERIC(78,0) NOT NULL,
+                completed BOOLEAN NOT NULL DEFAULT FALSE,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            -- Snapshot of an account's schedules at each block with a vesting event
+            CREATE TABLE IF NOT EXISTS {schema}.vesting_schedules (
+                account BYTEA NOT NULL,
+                block_number BIGINT NOT NULL,
+                schedule_index INT NOT NULL,
+                locked NUMERIC(78,0) NOT NULL,
+                per_block NUMERIC(78,0) NOT NULL,
+                starting_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (account, block_number, schedule_index)
+            );
+
+            -- Native balance of an account at a block
+            CREATE OR REPLACE FUNCTION {schema}.balance_at(account BYTEA, at_block BIGINT)
+            RETURNS NUMERIC
+            LANGUAGE SQL STABLE AS $$
+                SELECT COALESCE(SUM(c.delta), 0)
+                FROM {schema}.balance_changes c
+                WHERE c.account = $1 AND c.asset_id IS NULL AND c.block_number <= $2
+                  AND c.is_canonical
+            $$;
+
+            -- Amount still locked by vesting at a block, from the latest schedule snapshot
+            CREATE OR REPLACE FUNCTION {schema}.vesting_locked(account BYTEA, at_block BIGINT)
+            RETURNS NUMERIC
+            LANGUAGE SQL STABLE AS $$
+                SELECT COALESCE(SUM(GREATEST(
+                    s.locked - s.per_block * GREATEST($2 - s.starting_block, 0), 0
+                )), 0)
+                FROM {schema}.vesting_schedules s
+                WHERE s.account = $1
+                  AND s.block_number = (
+                      SELECT MAX(u.block_number)
+                      FROM {schema}.vesting_updates u
+                      WHERE u.account = $1 AND u.block_number <= $2
+                  )
+            $$;
+
+            -- Native balance minus the amount still locked by vesting
+            CREATE OR REPLACE FUNCTION {schema}.vested_balance(account BYTEA, at_block BIGINT)
+            RETURNS NUMERIC
+            LANGUAGE SQL STABLE AS $$
+                SELECT GREATEST(
+                    {schema}.balance_at($1, $2) - {schema}.vesting_locked($1, $2), 0
+                )
+            $$;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating vesting tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the account_balances and balance_locks tables and the lock SQL helpers
+    pub async fn create_account_balance_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            -- Snapshot of an account's System::Account balance at each block that touched it
+            CREATE TABLE IF NOT EXISTS {schema}.account_balances (
+                account BYTEA NOT NULL,
+                block_number BIGINT NOT NULL,
+                free NUMERIC(78,0) NOT NULL,
+                reserved NUMERIC(78,0) NOT NULL,
+                frozen NUMERIC(78,0) NOT NULL,
+                transferable NUMERIC(78,0) NOT NULL,
+                locks_refreshed BOOLEAN NOT NULL DEFAULT FALSE,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (account, block_number)
+            );
+
+            -- Snapshot of an account's locks, reserves, holds and freezes at each block with
+            -- a lock event; an empty snapshot is a balance row with locks_refreshed and no locks
+            CREATE TABLE IF NOT EXISTS {schema}.balance_locks (
+                account BYTEA NOT NULL,
+                block_number BIGINT NOT NULL,
+                kind TEXT NOT NULL,
+                lock_id TEXT NOT NULL,
+                amount NUMERIC(78,0) NOT NULL,
+                reasons TEXT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (account, block_number, kind, lock_id)
+            );
+
+            -- Locks in force on an account at a block, from the latest lock snapshot
+            CREATE OR REPLACE FUNCTION {schema}.locks_at(account BYTEA, at_block BIGINT)
+            RETURNS SETOF {schema}.balance_locks
+            LANGUAGE SQL STABLE AS $$
+                SELECT l.*
+                FROM {schema}.balance_locks l
+                WHERE l.account = $1
+                  AND l.block_number = (
+                      SELECT MAX(b.block_number)
+                      FROM {schema}.account_balances b
+                      WHERE b.account = $1 AND b.locks_refreshed AND b.block_number <= $2
+                  )
+            $$;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating account balance tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the pending_transfers and high_security_accounts tables (ReversibleTransfers)
+    pub async fn create_reversible_transfer_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.pending_transfers (
+                tx_id BYTEA PRIMARY KEY,
+                from_account BYTEA NOT NULL,
+                to_account BYTEA NOT NULL,
+                interceptor BYTEA NOT NULL,
+                amount NUMERIC(78,0) NOT NULL,
+                status TEXT NOT NULL,
+                execute_at JSONB NOT NULL,
+                execute_at_block BIGINT,
+                scheduled_block BIGINT,
+                scheduled_extrinsic_hash BYTEA,
+                resolved_block BIGINT,
+                resolved_event_index INT,
+                resolved_extrinsic_hash BYTEA,
+                cancelled_by BYTEA,
+                dispatch_error TEXT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.high_security_accounts (
+                account BYTEA PRIMARY KEY,
+                interceptor BYTEA NOT NULL,
+                recoverer BYTEA NOT NULL,
+                delay JSONB NOT NULL,
+                set_block BIGINT NOT NULL,
+                extrinsic_hash BYTEA,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating reversible transfer tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the wormhole_proofs, wormhole_nullifiers and transfer_proofs tables
+    pub async fn create_wormhole_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.wormhole_proofs (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                extrinsic_hash BYTEA,
+                exit_account BYTEA,
+                exit_amount NUMERIC(78,0) NOT NULL,
+                proof_block_number BIGINT,
+                proof_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.wormhole_nullifiers (
+                nullifier BYTEA PRIMARY KEY,
+                block_number BIGINT NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            -- Balances::TransferProof entries, keyed by the runtime's transfer counter
+            CREATE TABLE IF NOT EXISTS {schema}.transfer_proofs (
+                transfer_count BIGINT PRIMARY KEY,
+                from_account BYTEA NOT NULL,
+                to_account BYTEA NOT NULL,
+                amount NUMERIC(78,0) NOT NULL,
+                block_number BIGINT NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating wormhole tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the airdrops and airdrop_claims tables (MerkleAirdrop)
+    pub async fn create_airdrop_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.airdrops (
+                airdrop_id BIGINT PRIMARY KEY,
+                merkle_root BYTEA,
+                creator BYTEA,
+                vesting_period BIGINT,
+                vesting_delay BIGINT,
+                funded NUMERIC(78,0) NOT NULL DEFAULT 0,
+                claimed NUMERIC(78,0) NOT NULL DEFAULT 0,
+                claim_count BIGINT NOT NULL DEFAULT 0,
+                balance NUMERIC(78,0) NOT NULL DEFAULT 0,
+                created_block BIGINT,
+                created_extrinsic_hash BYTEA,
+                updated_block BIGINT NOT NULL,
+                deleted_block BIGINT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.airdrop_claims (
+                airdrop_id BIGINT NOT NULL,
+                account BYTEA NOT NULL,
+                amount NUMERIC(78,0) NOT NULL,
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating airdrop tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the treasury_spends, treasury_ledger and treasury_balances tables
+    pub async fn create_treasury_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.treasury_spends (
+                kind TEXT NOT NULL,
+                spend_index BIGINT NOT NULL,
+                beneficiary BYTEA,
+                asset_kind JSONB NOT NULL,
+                amount NUMERIC(78,0) NOT NULL,
+                status TEXT NOT NULL,
+                valid_from BIGINT,
+                expire_at BIGINT,
+                payment_id JSONB,
+                approved_block BIGINT,
+                approved_extrinsic_hash BYTEA,
+                resolved_block BIGINT,
+                resolved_extrinsic_hash BYTEA,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (kind, spend_index)
+            );
+
+            -- Inflows (positive delta) and outflows (negative delta) of the treasury
+            CREATE TABLE IF NOT EXISTS {schema}.treasury_ledger (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                event_pallet TEXT NOT NULL,
+                event_variant TEXT NOT NULL,
+                delta NUMERIC(78,0) NOT NULL,
+                account BYTEA,
+                spend_kind TEXT,
+                spend_index BIGINT,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            -- Treasury account balance read from storage at each block where it changed
+            CREATE TABLE IF NOT EXISTS {schema}.treasury_balances (
+                block_number BIGINT PRIMARY KEY,
+                account BYTEA NOT NULL,
+                free NUMERIC(78,0) NOT NULL,
+                reserved NUMERIC(78,0) NOT NULL,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating treasury tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the referenda, referendum_status_changes, referendum_votes, vote_delegations,
+    /// collective_members and collective_rank_changes tables
+    pub async fn create_governance_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.referenda (
+                pallet TEXT NOT NULL,
+                referendum_index BIGINT NOT NULL,
+                track INT,
+                origin JSONB,
+                proposal JSONB,
+                enactment JSONB,
+                status TEXT NOT NULL,
+                submitted_block BIGINT,
+                submission_deposit_account BYTEA,
+                submission_deposit NUMERIC(78,0),
+                submission_deposit_refunded_block BIGINT,
+                decision_deposit_account BYTEA,
+                decision_deposit NUMERIC(78,0),
+                decision_deposit_refunded_block BIGINT,
+                decision_started_block BIGINT,
+                confirm_started_block BIGINT,
+                confirm_end_block BIGINT,
+                concluded_block BIGINT,
+                ayes NUMERIC(78,0) NOT NULL,
+                nays NUMERIC(78,0) NOT NULL,
+                support NUMERIC(78,0) NOT NULL,
+                submitted_extrinsic_hash BYTEA,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (pallet, referendum_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.referendum_status_changes (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                pallet TEXT NOT NULL,
+                referendum_index BIGINT NOT NULL,
+                event_variant TEXT NOT NULL,
+                status TEXT NOT NULL,
+                ayes NUMERIC(78,0),
+                nays NUMERIC(78,0),
+                support NUMERIC(78,0),
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.referendum_votes (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                pallet TEXT NOT NULL,
+                referendum_index BIGINT,
+                account BYTEA NOT NULL,
+                vote_kind TEXT NOT NULL,
+                aye BOOLEAN,
+                conviction SMALLINT,
+                aye_amount NUMERIC(78,0) NOT NULL,
+                nay_amount NUMERIC(78,0) NOT NULL,
+                abstain_amount NUMERIC(78,0) NOT NULL,
+                lock_amount NUMERIC(78,0) NOT NULL,
+                removed BOOLEAN NOT NULL DEFAULT FALSE,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.vote_delegations (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                account BYTEA NOT NULL,
+                target BYTEA,
+                class INT,
+                conviction SMALLINT,
+                balance NUMERIC(78,0),
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.collective_members (
+                collective TEXT NOT NULL,
+                account BYTEA NOT NULL,
+                rank INT NOT NULL,
+                joined_block BIGINT,
+                removed_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (collective, account)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.collective_rank_changes (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                collective TEXT NOT NULL,
+                account BYTEA NOT NULL,
+                change TEXT NOT NULL,
+                rank INT NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index, account)
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating governance tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the recovery_configs, recoveries, recovery_vouches and recovered_accounts tables
+    /// and the account lineage SQL helpers
+    pub async fn create_recovery_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.recovery_configs (
+                account BYTEA PRIMARY KEY,
+                friends BYTEA[] NOT NULL,
+                threshold INT NOT NULL,
+                delay_period BIGINT NOT NULL,
+                deposit NUMERIC(78,0) NOT NULL,
+                created_block BIGINT,
+                created_extrinsic_hash BYTEA,
+                removed_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.recoveries (
+                lost_account BYTEA NOT NULL,
+                rescuer_account BYTEA NOT NULL,
+                initiated_block BIGINT NOT NULL,
+                initiated_extrinsic_hash BYTEA,
+                deposit NUMERIC(78,0),
+                vouch_count INT NOT NULL DEFAULT 0,
+                status TEXT NOT NULL,
+                recovered_block BIGINT,
+                closed_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (lost_account, rescuer_account, initiated_block)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.recovery_vouches (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                lost_account BYTEA NOT NULL,
+                rescuer_account BYTEA NOT NULL,
+                voucher BYTEA NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            -- Lost -> rescuer links; the rescuer controls the lost account from then on
+            CREATE TABLE IF NOT EXISTS {schema}.recovered_accounts (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                lost_account BYTEA NOT NULL,
+                rescuer_account BYTEA NOT NULL,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            -- Recoveries starting at an account, following rescuers that were recovered later
+            CREATE OR REPLACE FUNCTION {schema}.account_lineage(account BYTEA)
+            RETURNS TABLE (depth INT, lost_account BYTEA, rescuer_account BYTEA, recovered_block BIGINT)
+            LANGUAGE SQL STABLE AS $$
+                WITH RECURSIVE lineage AS (
+                    SELECT 1 AS depth, r.lost_account, r.rescuer_account, r.block_number AS recovered_block
+                    FROM {schema}.recovered_accounts r
+                    WHERE r.lost_account = $1
+                    UNION ALL
+                    SELECT l.depth + 1, r.lost_account, r.rescuer_account, r.block_number
+                    FROM lineage l
+                    JOIN {schema}.recovered_accounts r
+                      ON r.lost_account = l.rescuer_account AND r.block_number >= l.recovered_block
+                    WHERE l.depth < 16
+                )
+                SELECT l.depth, l.lost_account, l.rescuer_account, l.recovered_block
+                FROM lineage l
+                ORDER BY l.depth, l.recovered_block
+            $$;
+
+            -- Native funds leaving the recovered accounts of a lineage after their recovery
+            CREATE OR REPLACE FUNCTION {schema}.recovered_outflows(account BYTEA)
+            RETURNS TABLE (lost_account BYTEA, block_number BIGINT, event_index INT, recipient BYTEA,
+                           amount NUMERIC, reason TEXT, extrinsic_hash BYTEA)
+            LANGUAGE SQL STABLE AS $$
+                SELECT c.account, c.block_number, c.event_index, r.account, -c.delta, c.reason,
+                       c.extrinsic_hash
+                FROM (
+                    SELECT l.lost_account, MIN(l.recovered_block) AS recovered_block
+                    FROM {schema}.account_lineage($1) l
+                    GROUP BY l.lost_account
+                ) l
+                JOIN {schema}.balance_changes c
+                  ON c.account = l.lost_account AND c.asset_id IS NULL
+                 AND c.block_number >= l.recovered_block AND c.delta < 0 AND c.is_canonical
+                LEFT JOIN {schema}.balance_changes r
+                  ON r.block_number = c.block_number AND r.event_index = c.event_index
+                 AND r.account <> c.account AND r.asset_id IS NULL AND r.delta > 0
+                 AND r.is_canonical
+                ORDER BY c.block_number, c.event_index
+            $$;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating recovery tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the block_mining and distance_threshold_adjustments tables and the hashrate and
+    /// miner share views
+    pub async fn create_mining_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            -- U512 quantities (threshold, difficulty, work) exceed NUMERIC(78,0)
+            CREATE TABLE IF NOT EXISTS {schema}.block_mining (
+                block_number BIGINT PRIMARY KEY,
+                miner BYTEA,
+                nonce BYTEA,
+                distance_threshold NUMERIC,
+                difficulty NUMERIC,
+                block_duration_ms BIGINT,
+                total_work NUMERIC,
+                reward NUMERIC(78,0),
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.distance_threshold_adjustments (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                old_distance_threshold NUMERIC NOT NULL,
+                new_distance_threshold NUMERIC NOT NULL,
+                observed_block_time BIGINT NOT NULL,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            -- Estimated hashrate: expected hashes of the blocks over the time it took to mine them
+            CREATE OR REPLACE VIEW {schema}.mining_hashrate_hourly AS
+            SELECT date_trunc('hour', block_ts) AS hour,
+                   COUNT(*) AS blocks,
+                   AVG(block_duration_ms)::BIGINT AS avg_block_duration_ms,
+                   ROUND(AVG(difficulty)) AS avg_difficulty,
+                   ROUND(SUM(difficulty) * 1000 / NULLIF(SUM(block_duration_ms), 0)) AS hashrate
+            FROM {schema}.block_mining
+            GROUP BY 1;
+
+            CREATE OR REPLACE VIEW {schema}.miner_share_daily AS
+            SELECT date_trunc('day', block_ts) AS day,
+                   miner,
+                   COUNT(*) AS blocks,
+                   SUM(reward) AS rewards,
+                   COUNT(*)::NUMERIC / SUM(COUNT(*)) OVER (PARTITION BY date_trunc('day', block_ts)) AS share
+            FROM {schema}.block_mining
+            GROUP BY 1, 2;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating mining tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the sudo_actions audit log
+    pub async fn create_sudo_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.sudo_actions (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                action TEXT NOT NULL,
+                sudo_key BYTEA,
+                new_key BYTEA,
+                as_account BYTEA,
+                call_pallet TEXT,
+                call_function TEXT,
+                call_args JSONB,
+                success BOOLEAN,
+                dispatch_error TEXT,
+                extrinsic_index INT,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating sudo tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the scheduled_tasks, scheduler_events and preimages tables
+    pub async fn create_scheduler_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.scheduled_tasks (
+                agenda_kind TEXT NOT NULL,
+                agenda_at BIGINT NOT NULL,
+                task_index INT NOT NULL,
+                scheduled_block BIGINT NOT NULL,
+                scheduled_extrinsic_hash BYTEA,
+                task_id BYTEA,
+                priority SMALLINT,
+                origin TEXT,
+                origin_account BYTEA,
+                call_hash BYTEA,
+                call_len INT,
+                call_data BYTEA,
+                call_pallet TEXT,
+                call_function TEXT,
+                call_args JSONB,
+                period_kind TEXT,
+                period BIGINT,
+                period_count INT,
+                status TEXT NOT NULL,
+                dispatch_error TEXT,
+                closed_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (agenda_kind, agenda_at, task_index, scheduled_block)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.scheduler_events (
+                block_number BIGINT NOT NULL,
+                event_index INT NOT NULL,
+                event_variant TEXT NOT NULL,
+                agenda_kind TEXT NOT NULL,
+                agenda_at BIGINT NOT NULL,
+                task_index INT NOT NULL,
+                task_id BYTEA,
+                success BOOLEAN,
+                dispatch_error TEXT,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, event_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.preimages (
+                hash BYTEA PRIMARY KEY,
+                length INT,
+                depositor BYTEA,
+                deposit NUMERIC(78,0),
+                status TEXT NOT NULL,
+                request_count INT NOT NULL DEFAULT 0,
+                call_pallet TEXT,
+                call_function TEXT,
+                call_args JSONB,
+                first_block BIGINT NOT NULL,
+                noted_block BIGINT,
+                noted_extrinsic_hash BYTEA,
+                cleared_block BIGINT,
+                updated_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating scheduler tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the batch_items table and the event_call_path helper
+    pub async fn create_utility_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.batch_items (
+                block_number BIGINT NOT NULL,
+                extrinsic_index INT NOT NULL,
+                call_path TEXT NOT NULL,
+                batch_index INT NOT NULL,
+                item_index INT NOT NULL,
+                depth INT NOT NULL,
+                batch_call TEXT NOT NULL,
+                call_pallet TEXT,
+                call_function TEXT,
+                origin TEXT,
+                origin_account BYTEA,
+                success BOOLEAN,
+                dispatch_error TEXT,
+                first_event_index INT,
+                last_event_index INT,
+                extrinsic_hash BYTEA,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (block_number, extrinsic_index, call_path)
+            );
+
+            -- Innermost batch item an event was emitted in, e.g.
+            -- SELECT {schema}.event_call_path(block_number, event_index) FROM {schema}.sudo_actions
+            CREATE OR REPLACE FUNCTION {schema}.event_call_path(at_block BIGINT, at_event INT)
+            RETURNS TEXT AS $$
+                SELECT b.call_path FROM {schema}.batch_items b
+                WHERE b.block_number = $1
+                  AND $2 BETWEEN b.first_event_index AND b.last_event_index
+                ORDER BY b.depth DESC
+                LIMIT 1
+            $$ LANGUAGE SQL STABLE;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating utility tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the ledger_checkpoints table of signed ledger hashes
+    pub async fn create_ledger_tables(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.ledger_checkpoints (
+                block_number BIGINT PRIMARY KEY,
+                block_hash BYTEA NOT NULL,
+                ledger_hash BYTEA NOT NULL,
+                scheme TEXT NOT NULL,
+                public_key BYTEA NOT NULL,
+                signature BYTEA NOT NULL,
+                signed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating ledger tables");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
     /// Create the account_stats table for aggregated account data
     pub async fn create_account_stats_table(&self, conn: &DbConnection) -> Result<()> {
         let schema = self.schema_name();
@@ -199,6 +1228,156 @@ impl SchemaManager {
         Ok(())
     }
 
+    /// Create the runtime_upgrades table
+    pub async fn create_runtime_upgrades_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.runtime_upgrades (
+                authorized_block BIGINT NOT NULL,
+                authorized_event_index INT NOT NULL,
+                code_hash BYTEA NOT NULL,
+                check_version BOOLEAN,
+                authorizer BYTEA,
+                authorized_via TEXT,
+                authorization_extrinsic_hash BYTEA,
+                status TEXT NOT NULL,
+                block_number BIGINT,
+                event_index INT,
+                extrinsic_index INT,
+                extrinsic_hash BYTEA,
+                submitter BYTEA,
+                upgrade_call TEXT,
+                previous_spec_name TEXT,
+                previous_spec_version INT,
+                spec_name TEXT,
+                spec_version INT,
+                impl_version INT,
+                transaction_version INT,
+                state_version INT,
+                superseded_block BIGINT,
+                authorized_ts TIMESTAMPTZ NOT NULL,
+                applied_ts TIMESTAMPTZ,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (authorized_block, authorized_event_index)
+            );
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating runtime_upgrades table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the extrinsics table for decoded extrinsics and their outcome
+    pub async fn create_extrinsics_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.extrinsics (
+                id BIGSERIAL PRIMARY KEY,
+                block_number BIGINT NOT NULL,
+                extrinsic_index INT NOT NULL,
+                hash BYTEA NOT NULL,
+                is_signed BOOLEAN NOT NULL,
+                signer BYTEA,
+                nonce BIGINT,
+                tip NUMERIC(78,0),
+                call_pallet TEXT NOT NULL,
+                call_function TEXT NOT NULL,
+                args JSONB NOT NULL DEFAULT '{{}}'::jsonb,
+                success BOOLEAN NOT NULL,
+                dispatch_error TEXT,
+                fee NUMERIC(78,0),
+                signature_scheme TEXT,
+                signature_len INT,
+                signer_public_key BYTEA,
+                signature_valid BOOLEAN,
+                signature_context TEXT,
+                block_ts TIMESTAMPTZ NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                UNIQUE(block_number, extrinsic_index)
+            );
+
+            ALTER TABLE {schema}.extrinsics
+                ADD COLUMN IF NOT EXISTS signature_scheme TEXT,
+                ADD COLUMN IF NOT EXISTS signature_len INT,
+                ADD COLUMN IF NOT EXISTS signer_public_key BYTEA,
+                ADD COLUMN IF NOT EXISTS signature_valid BOOLEAN,
+                ADD COLUMN IF NOT EXISTS signature_context TEXT;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating extrinsics table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the public_keys table mapping accounts to their revealed public keys
+    pub async fn create_public_keys_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.public_keys (
+                account BYTEA PRIMARY KEY,
+                public_key BYTEA NOT NULL,
+                signature_scheme TEXT NOT NULL,
+                derivation TEXT,
+                account_matches BOOLEAN NOT NULL,
+                first_seen_block BIGINT NOT NULL,
+                first_extrinsic_index INT NOT NULL,
+                first_extrinsic_hash BYTEA NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating public_keys table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Create the accounts table holding SS58 addresses, plus SQL helpers to convert
+    /// between addresses and raw account bytes
+    pub async fn create_accounts_table(&self, conn: &DbConnection) -> Result<()> {
+        let schema = self.schema_name();
+        let sql = format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.accounts (
+                account BYTEA PRIMARY KEY,
+                ss58 TEXT NOT NULL UNIQUE,
+                first_seen_block BIGINT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
+            -- SS58 address of an account, e.g. SELECT {schema}.ss58(account) FROM ...
+            CREATE OR REPLACE FUNCTION {schema}.ss58(account BYTEA) RETURNS TEXT
+            LANGUAGE SQL STABLE AS $$
+                SELECT a.ss58 FROM {schema}.accounts a WHERE a.account = $1
+            $$;
+
+            -- Raw account bytes from an SS58 address or hex string,
+            -- e.g. WHERE account = {schema}.account_id('qz...')
+            CREATE OR REPLACE FUNCTION {schema}.account_id(address TEXT) RETURNS BYTEA
+            LANGUAGE SQL STABLE AS $$
+                SELECT CASE
+                    WHEN $1 ~ '^(0x)?[0-9a-fA-F]{{64}}$' THEN decode(right($1, 64), 'hex')
+                    ELSE (SELECT a.account FROM {schema}.accounts a WHERE a.ss58 = $1)
+                END
+            $$;
+            "#,
+            schema = schema
+        );
+
+        debug!("Creating accounts table");
+        conn.batch_execute(&sql).await?;
+        Ok(())
+    }
+
     /// Create indexes for better query performance
     pub async fn create_indexes(&self, conn: &DbConnection) -> Result<()> {
         let schema = self.schema_name();
@@ -206,6 +1385,11 @@ impl SchemaManager {
             // Blocks indexes
             format!("CREATE INDEX IF NOT EXISTS idx_{}_blocks_timestamp ON {schema}.blocks (timestamp DESC)", self.chain_id),
             format!("CREATE INDEX IF NOT EXISTS idx_{}_blocks_canonical ON {schema}.blocks (is_canonical) WHERE is_canonical = true", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_blocks_number ON {schema}.blocks (number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_blocks_parent ON {schema}.blocks (parent_hash)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_block_hash ON {schema}.balance_changes (block_hash)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_block_headers_number ON {schema}.block_headers (block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_digest_logs_engine ON {schema}.digest_logs (engine_id, kind, block_number DESC)", self.chain_id),
 
             // Balance changes indexes
             format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_account ON {schema}.balance_changes (account)", self.chain_id),
@@ -214,6 +1398,44 @@ impl SchemaManager {
             format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_ts ON {schema}.balance_changes (block_ts DESC)", self.chain_id),
             format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_reason ON {schema}.balance_changes (reason)", self.chain_id),
             format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_extrinsic ON {schema}.balance_changes (extrinsic_hash) WHERE extrinsic_hash IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_changes_asset_account ON {schema}.balance_changes (asset_id, account, block_number DESC) WHERE asset_id IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_assets_owner ON {schema}.assets (owner)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_account_balances_block ON {schema}.account_balances (block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_account_balances_locks ON {schema}.account_balances (account, block_number DESC) WHERE locks_refreshed", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_balance_locks_block ON {schema}.balance_locks (block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_vesting_updates_account ON {schema}.vesting_updates (account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_pending_transfers_from ON {schema}.pending_transfers (from_account, status)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_pending_transfers_to ON {schema}.pending_transfers (to_account, status)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_pending_transfers_interceptor ON {schema}.pending_transfers (interceptor, status)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_wormhole_proofs_exit ON {schema}.wormhole_proofs (exit_account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_wormhole_nullifiers_block ON {schema}.wormhole_nullifiers (block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_transfer_proofs_from ON {schema}.transfer_proofs (from_account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_transfer_proofs_to ON {schema}.transfer_proofs (to_account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_transfer_proofs_block ON {schema}.transfer_proofs (block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_airdrop_claims_airdrop ON {schema}.airdrop_claims (airdrop_id, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_airdrop_claims_account ON {schema}.airdrop_claims (account)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_treasury_spends_beneficiary ON {schema}.treasury_spends (beneficiary, status)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_treasury_spends_status ON {schema}.treasury_spends (status)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_treasury_ledger_ts ON {schema}.treasury_ledger (block_ts DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_referenda_status ON {schema}.referenda (status, track)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_referendum_status_changes_referendum ON {schema}.referendum_status_changes (pallet, referendum_index, block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_referendum_votes_referendum ON {schema}.referendum_votes (pallet, referendum_index)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_referendum_votes_account ON {schema}.referendum_votes (account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_vote_delegations_account ON {schema}.vote_delegations (account, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_vote_delegations_target ON {schema}.vote_delegations (target)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_block_mining_miner ON {schema}.block_mining (miner, block_number DESC)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_block_mining_ts ON {schema}.block_mining (block_ts)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_recoveries_rescuer ON {schema}.recoveries (rescuer_account)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_recovery_vouches_recovery ON {schema}.recovery_vouches (lost_account, rescuer_account)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_recovered_accounts_lost ON {schema}.recovered_accounts (lost_account, block_number)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_recovered_accounts_rescuer ON {schema}.recovered_accounts (rescuer_account)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_scheduled_tasks_status ON {schema}.scheduled_tasks (status, agenda_at)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_scheduled_tasks_call_hash ON {schema}.scheduled_tasks (call_hash)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_scheduler_events_task ON {schema}.scheduler_events (agenda_kind, agenda_at, task_index)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_sudo_actions_call ON {schema}.sudo_actions (call_pallet, call_function)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_batch_items_call ON {schema}.batch_items (call_pallet, call_function)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_batch_items_origin ON {schema}.batch_items (origin_account) WHERE origin_account IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_collective_rank_changes_account ON {schema}.collective_rank_changes (collective, account, block_number DESC)", self.chain_id),
 
             // Account stats indexes
             format!("CREATE INDEX IF NOT EXISTS idx_{}_account_stats_balance ON {schema}.account_stats (balance DESC)", self.chain_id),
@@ -222,6 +1444,22 @@ impl SchemaManager {
             // Metadata indexes
             format!("CREATE INDEX IF NOT EXISTS idx_{}_metadata_block_range ON {schema}.metadata (first_seen_block, last_seen_block)", self.chain_id),
             format!("CREATE INDEX IF NOT EXISTS idx_{}_metadata_hash ON {schema}.metadata (metadata_hash)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_runtime_upgrades_block ON {schema}.runtime_upgrades (block_number) WHERE block_number IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_runtime_upgrades_code_hash ON {schema}.runtime_upgrades (code_hash, status)", self.chain_id),
+
+            // Extrinsics indexes
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_extrinsics_hash ON {schema}.extrinsics (hash)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_extrinsics_signer ON {schema}.extrinsics (signer, block_number DESC) WHERE signer IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_extrinsics_call ON {schema}.extrinsics (call_pallet, call_function)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_extrinsics_scheme ON {schema}.extrinsics (signature_scheme) WHERE signature_scheme IS NOT NULL", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_extrinsics_invalid_signature ON {schema}.extrinsics (block_number DESC) WHERE signature_valid = false", self.chain_id),
+
+            // Accounts indexes
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_accounts_first_seen ON {schema}.accounts (first_seen_block)", self.chain_id),
+
+            // Public keys indexes
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_public_keys_block ON {schema}.public_keys (first_seen_block)", self.chain_id),
+            format!("CREATE INDEX IF NOT EXISTS idx_{}_public_keys_mismatch ON {schema}.public_keys (account) WHERE account_matches = false", self.chain_id),
         ];
 
         debug!("Creating {} indexes", indexes.len());
@@ -287,19 +1525,22 @@ impl SchemaManager {
         let schema = self.schema_name();
 
         // Count blocks
-        let blocks_sql = format!("SELECT COUNT(*) FROM {schema}.blocks");
+        let blocks_sql = format!("SELECT COUNT(*) FROM {schema}.blocks WHERE is_canonical");
         let blocks_count: i64 = conn.query_one(&blocks_sql, &[]).await?.get(0);
 
         // Count balance changes
-        let changes_sql = format!("SELECT COUNT(*) FROM {schema}.balance_changes");
+        let changes_sql =
+            format!("SELECT COUNT(*) FROM {schema}.balance_changes WHERE is_canonical");
         let changes_count: i64 = conn.query_one(&changes_sql, &[]).await?.get(0);
 
         // Count unique accounts
-        let accounts_sql = format!("SELECT COUNT(DISTINCT account) FROM {schema}.balance_changes");
+        let accounts_sql = format!(
+            "SELECT COUNT(DISTINCT account) FROM {schema}.balance_changes WHERE is_canonical"
+        );
         let accounts_count: i64 = conn.query_one(&accounts_sql, &[]).await?.get(0);
 
         // Get latest block
-        let latest_sql = format!("SELECT MAX(number) FROM {schema}.blocks");
+        let latest_sql = format!("SELECT MAX(number) FROM {schema}.blocks WHERE is_canonical");
         let latest_block: Option<i64> = conn.query_one(&latest_sql, &[]).await?.get(0);
 
         Ok(TableStats {
@@ -317,7 +1558,7 @@ impl SchemaManager {
         info!("Running VACUUM ANALYZE on schema {}", schema);
 
         // Note: VACUUM cannot be run inside a transaction block
-        let tables = vec!["blocks", "balance_changes", "account_stats"];
+        let tables = vec!["blocks", "balance_changes", "account_stats", "extrinsics"];
 
         for table in tables {
             let sql = format!("VACUUM ANALYZE {schema}.{table}");
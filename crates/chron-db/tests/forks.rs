@@ -0,0 +1,394 @@
+//! Fork handling against a live database: canonical flags, orphan recording, rewinds, the
+//! `fork_blocks` view and the migration from number-keyed blocks.
+//!
+//! Set `TEST_PG_DSN` to a scratch database to run these tests; they are skipped otherwise.
+//! Each test works in its own schema, which it drops first.
+
+use chron_db::{
+    BalanceChange, BalanceChangeReason, BalanceChangeRepository, Block, BlockRepository,
+    ChainRepository, ConnectionPool, DbConfig, DbConnection, SchemaManager,
+};
+use chrono::Utc;
+
+/// Connect with the schema of `chain_id` dropped, or None when no test database is set
+async fn connect(chain_id: &str) -> Option<DbConnection> {
+    let Ok(dsn) = std::env::var("TEST_PG_DSN") else {
+        eprintln!("TEST_PG_DSN is not set; skipping");
+        return None;
+    };
+    let mut pool = ConnectionPool::new(&DbConfig::new(dsn)).await.unwrap();
+    pool.set_chain_id(chain_id.to_string());
+    let conn = pool.get().await.unwrap();
+    SchemaManager::new(chain_id.to_string())
+        .drop_schema(&conn)
+        .await
+        .unwrap();
+    Some(conn)
+}
+
+/// Connect to a freshly initialized schema
+async fn initialized(chain_id: &str) -> Option<DbConnection> {
+    let conn = connect(chain_id).await?;
+    SchemaManager::new(chain_id.to_string())
+        .initialize(&conn)
+        .await
+        .unwrap();
+    Some(conn)
+}
+
+fn hash(number: i64, branch: u8) -> Vec<u8> {
+    let mut hash = vec![branch; 32];
+    hash[..8].copy_from_slice(&number.to_be_bytes());
+    hash
+}
+
+fn block(number: i64, branch: u8, parent_branch: u8) -> Block {
+    Block::new(
+        number,
+        hash(number, branch),
+        hash(number - 1, parent_branch),
+        Utc::now(),
+        1,
+    )
+}
+
+/// Both sides of a transfer made in `block`
+fn transfer(block: &Block, amount: i64) -> Vec<BalanceChange> {
+    [(vec![1; 32], -amount), (vec![2; 32], amount)]
+        .into_iter()
+        .map(|(account, delta)| {
+            let mut change = BalanceChange::new(
+                account,
+                block.number,
+                0,
+                delta.to_string(),
+                BalanceChangeReason::Transfer,
+                None,
+                "Balances".into(),
+                "Transfer".into(),
+                block.timestamp,
+            );
+            change.block_hash = Some(block.hash.clone());
+            change
+        })
+        .collect()
+}
+
+/// Index a block the way the indexer does: take over the height, then replace the block's
+/// own flagged changes with the canonical ones
+async fn index(conn: &DbConnection, block: &Block, amount: i64) {
+    BlockRepository::new(conn).insert(block).await.unwrap();
+    let changes = BalanceChangeRepository::new(conn);
+    changes.delete_non_canonical(&block.hash).await.unwrap();
+    changes
+        .insert_batch(&transfer(block, amount))
+        .await
+        .unwrap();
+}
+
+/// Canonical flags of the blocks at a height, canonical first
+async fn flags(conn: &DbConnection, number: i64) -> Vec<(Vec<u8>, bool)> {
+    BlockRepository::new(conn)
+        .get_at_height(number)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|block| (block.hash, block.is_canonical))
+        .collect()
+}
+
+/// (block hash, is_canonical, delta) of the balance changes at a height
+async fn changes(conn: &DbConnection, schema: &str, number: i64) -> Vec<(Vec<u8>, bool, String)> {
+    let sql = format!(
+        "SELECT block_hash, is_canonical, delta::TEXT FROM \"{}\".balance_changes \
+         WHERE block_number = $1 ORDER BY is_canonical DESC, block_hash, delta",
+        schema
+    );
+    conn.query(&sql, &[&number])
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_competing_block_takes_over_height() {
+    let chain = "ForkTestFlags";
+    let Some(conn) = initialized(chain).await else {
+        return;
+    };
+
+    let a = block(5, 0xa, 0);
+    let b = block(5, 0xb, 0);
+    index(&conn, &a, 100).await;
+    index(&conn, &b, 200).await;
+
+    // B replaced A; A and its changes are kept, flagged
+    assert_eq!(
+        flags(&conn, 5).await,
+        vec![(b.hash.clone(), true), (a.hash.clone(), false)]
+    );
+    assert_eq!(
+        changes(&conn, chain, 5).await,
+        vec![
+            (b.hash.clone(), true, "-200".into()),
+            (b.hash.clone(), true, "200".into()),
+            (a.hash.clone(), false, "-100".into()),
+            (a.hash.clone(), false, "100".into()),
+        ]
+    );
+
+    // A wins the height back: its changes are indexed again rather than duplicated
+    index(&conn, &a, 100).await;
+    assert_eq!(
+        flags(&conn, 5).await,
+        vec![(a.hash.clone(), true), (b.hash.clone(), false)]
+    );
+    assert_eq!(
+        changes(&conn, chain, 5).await,
+        vec![
+            (a.hash.clone(), true, "-100".into()),
+            (a.hash.clone(), true, "100".into()),
+            (b.hash.clone(), false, "-200".into()),
+            (b.hash.clone(), false, "200".into()),
+        ]
+    );
+
+    // Re-indexing the canonical block changes nothing
+    index(&conn, &a, 100).await;
+    assert_eq!(changes(&conn, chain, 5).await.len(), 4);
+    assert_eq!(
+        BlockRepository::new(&conn)
+            .get_by_number(5)
+            .await
+            .unwrap()
+            .map(|block| block.hash),
+        Some(a.hash)
+    );
+}
+
+#[tokio::test]
+async fn test_orphans_and_rewind() {
+    let chain = "ForkTestRewind";
+    let Some(conn) = initialized(chain).await else {
+        return;
+    };
+    let blocks = BlockRepository::new(&conn);
+
+    for number in 1..=4 {
+        index(&conn, &block(number, 0xc, 0xc), number * 10).await;
+    }
+
+    // A fork block seen only as replaced is recorded once, never over a known block
+    let orphan = block(3, 0xf, 0xc);
+    assert_eq!(blocks.insert_orphan(&orphan).await.unwrap(), 1);
+    assert_eq!(blocks.insert_orphan(&orphan).await.unwrap(), 0);
+    assert_eq!(blocks.insert_orphan(&block(3, 0xc, 0xc)).await.unwrap(), 0);
+    assert_eq!(
+        flags(&conn, 3).await,
+        vec![(hash(3, 0xc), true), (orphan.hash.clone(), false)]
+    );
+
+    // Rewinding to #3 flags #3 and #4 and their changes, keeping them, and leaves #2 as
+    // the block to resume from
+    ChainRepository::new(&conn).begin_reorg(3).await.unwrap();
+    assert_eq!(
+        blocks.get_by_number(2).await.unwrap().map(|b| b.hash),
+        Some(hash(2, 0xc))
+    );
+    assert!(blocks.get_by_number(3).await.unwrap().is_none());
+    assert!(blocks.get_by_number(4).await.unwrap().is_none());
+    assert_eq!(
+        changes(&conn, chain, 4).await,
+        vec![
+            (hash(4, 0xc), false, "-40".into()),
+            (hash(4, 0xc), false, "40".into()),
+        ]
+    );
+    assert!(changes(&conn, chain, 2).await.iter().all(|(_, c, _)| *c));
+
+    // The new best chain is indexed over the flagged blocks
+    let replacement = block(3, 0xd, 0xc);
+    index(&conn, &replacement, 33).await;
+    assert_eq!(flags(&conn, 3).await[0], (replacement.hash.clone(), true));
+    assert_eq!(flags(&conn, 3).await.len(), 3);
+    assert_eq!(
+        changes(&conn, chain, 3).await[..2],
+        [
+            (replacement.hash.clone(), true, "-33".into()),
+            (replacement.hash, true, "33".into()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_fork_blocks_view() {
+    let chain = "ForkTestView";
+    let Some(conn) = initialized(chain).await else {
+        return;
+    };
+    let blocks = BlockRepository::new(&conn);
+
+    for number in 1..=5 {
+        blocks.insert(&block(number, 0xc, 0xc)).await.unwrap();
+    }
+    // A two-block fork from #2, and a one-block fork from #4
+    blocks.insert_orphan(&block(3, 0xf, 0xc)).await.unwrap();
+    blocks.insert_orphan(&block(4, 0xf, 0xf)).await.unwrap();
+    blocks.insert_orphan(&block(5, 0xe, 0xc)).await.unwrap();
+
+    let forks: Vec<_> = blocks
+        .get_fork_blocks(0, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|f| {
+            (
+                f.number,
+                f.hash,
+                f.fork_point,
+                f.fork_root,
+                f.depth,
+                f.canonical_hash,
+            )
+        })
+        .collect();
+    assert_eq!(
+        forks,
+        vec![
+            (5, hash(5, 0xe), 4, hash(5, 0xe), 1, Some(hash(5, 0xc))),
+            (4, hash(4, 0xf), 2, hash(3, 0xf), 2, Some(hash(4, 0xc))),
+            (3, hash(3, 0xf), 2, hash(3, 0xf), 1, Some(hash(3, 0xc))),
+        ]
+    );
+
+    // Blocks flagged by a rewind join the view as a fork from the block below them
+    ChainRepository::new(&conn).begin_reorg(5).await.unwrap();
+    let forks = blocks.get_fork_blocks(5, 10).await.unwrap();
+    assert_eq!(forks.len(), 2);
+    assert!(forks
+        .iter()
+        .all(|f| f.fork_point == 4 && f.depth == 1 && f.canonical_hash.is_none()));
+}
+
+#[tokio::test]
+async fn test_migration_from_number_keyed_blocks() {
+    let chain = "ForkTestMigration";
+    let Some(conn) = connect(chain).await else {
+        return;
+    };
+
+    // The blocks and balance_changes tables as the first release created them
+    conn.batch_execute(&format!(
+        r#"
+        CREATE SCHEMA "{chain}";
+        CREATE TABLE "{chain}".blocks (
+            number BIGINT PRIMARY KEY,
+            hash BYTEA NOT NULL UNIQUE,
+            parent_hash BYTEA NOT NULL,
+            timestamp TIMESTAMPTZ NOT NULL,
+            is_canonical BOOLEAN NOT NULL DEFAULT true,
+            runtime_spec BIGINT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE TABLE "{chain}".balance_changes (
+            id BIGSERIAL PRIMARY KEY,
+            account BYTEA NOT NULL,
+            block_number BIGINT NOT NULL,
+            event_index INT NOT NULL,
+            delta NUMERIC(78,0) NOT NULL,
+            reason TEXT NOT NULL,
+            extrinsic_hash BYTEA,
+            event_pallet TEXT NOT NULL,
+            event_variant TEXT NOT NULL,
+            block_ts TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE(block_number, event_index)
+        );
+        INSERT INTO "{chain}".blocks (number, hash, parent_hash, timestamp, runtime_spec)
+            VALUES (7, '\x07', '\x06', NOW(), 1);
+        INSERT INTO "{chain}".balance_changes
+            (account, block_number, event_index, delta, reason, event_pallet, event_variant, block_ts)
+            VALUES ('\x01', 7, 0, 5, 'transfer', 'Balances', 'Transfer', NOW());
+        "#,
+        chain = chain
+    ))
+    .await
+    .unwrap();
+
+    let schema = SchemaManager::new(chain.to_string());
+    schema.initialize(&conn).await.unwrap();
+    schema.initialize(&conn).await.unwrap();
+
+    let primary_key: Vec<String> = conn
+        .query(
+            "SELECT a.attname::TEXT FROM pg_index i \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+             WHERE i.indrelid = $1::TEXT::regclass AND i.indisprimary",
+            &[&format!("\"{}\".blocks", chain)],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(primary_key, vec!["hash".to_string()]);
+
+    let indexes: Vec<String> = conn
+        .query(
+            "SELECT indexname::TEXT FROM pg_indexes WHERE schemaname = $1 ORDER BY 1",
+            &[&chain],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert!(indexes.contains(&"blocks_canonical_number_key".to_string()));
+    assert!(indexes.contains(&"balance_changes_canonical_event_account_key".to_string()));
+    assert!(!indexes.contains(&"balance_changes_block_number_event_index_key".to_string()));
+
+    let views: Vec<String> = conn
+        .query(
+            "SELECT table_name::TEXT FROM information_schema.views \
+             WHERE table_schema = $1 ORDER BY 1",
+            &[&chain],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    for view in [
+        "fork_blocks",
+        "fork_history",
+        "miner_orphans",
+        "orphan_rate_daily",
+    ] {
+        assert!(views.contains(&view.to_string()), "missing view {}", view);
+    }
+
+    // The existing change is tied to its block and stays canonical
+    assert_eq!(
+        changes(&conn, chain, 7).await,
+        vec![(vec![7], true, "5".into())]
+    );
+
+    // A second block at the same height now fits, and takes the height over
+    let mut rival = Block::new(7, vec![0x77], vec![6], Utc::now(), 1);
+    rival.is_canonical = true;
+    index(&conn, &rival, 9).await;
+    assert_eq!(
+        flags(&conn, 7).await,
+        vec![(vec![0x77], true), (vec![7], false)]
+    );
+    assert_eq!(
+        changes(&conn, chain, 7).await,
+        vec![
+            (vec![0x77], true, "-9".into()),
+            (vec![0x77], true, "9".into()),
+            (vec![7], false, "5".into()),
+        ]
+    );
+}
@@ -148,10 +148,56 @@ track_chain_tables() {
     # List of Chronicle tables to track
     local tables=(
         "blocks"
+        "block_headers"
+        "digest_logs"
+        "ledger_checkpoints"
         "balance_changes"
+        "extrinsics"
+        "public_keys"
+        "accounts"
+        "assets"
+        "vesting_updates"
+        "vesting_schedules"
+        "account_balances"
+        "balance_locks"
+        "pending_transfers"
+        "high_security_accounts"
+        "wormhole_proofs"
+        "wormhole_nullifiers"
+        "transfer_proofs"
+        "airdrops"
+        "airdrop_claims"
+        "treasury_spends"
+        "treasury_ledger"
+        "treasury_balances"
+        "referenda"
+        "referendum_status_changes"
+        "referendum_votes"
+        "vote_delegations"
+        "collective_members"
+        "collective_rank_changes"
+        "recovery_configs"
+        "recoveries"
+        "recovery_vouches"
+        "recovered_accounts"
+        "block_mining"
+        "distance_threshold_adjustments"
+        "mining_hashrate_hourly"
+        "miner_share_daily"
+        "fork_blocks"
+        "fork_history"
+        "orphan_rate_daily"
+        "miner_orphans"
+        "sudo_actions"
+        "scheduled_tasks"
+        "scheduler_events"
+        "preimages"
+        "batch_items"
         "index_progress"
+        "chain_info"
         "account_stats"
         "metadata"
+        "runtime_upgrades"
     )
 
     for table in "${tables[@]}"; do
@@ -174,30 +220,38 @@ track_chain_tables() {
     done
 }
 
-create_relationships() {
+create_relationship() {
     local schema=$1
+    local kind=$2          # object | array
+    local table=$3
+    local name=$4
+    local remote_table=$5
+    local column=$6
+    local remote_column=$7
+
+    local arrow="${table} -> ${remote_table}"
+    if [ "$kind" = "array" ]; then
+        arrow="${arrow}[]"
+    fi
 
-    log_info "Creating relationships for schema: ${schema}"
-
-    # Create foreign key relationship from balance_changes to blocks
-    local rel_mutation=$(cat <<EOF
+    local mutation=$(cat <<EOF
 {
-  "type": "pg_create_object_relationship",
+  "type": "pg_create_${kind}_relationship",
   "args": {
     "source": "default",
     "table": {
       "schema": "${schema}",
-      "name": "balance_changes"
+      "name": "${table}"
     },
-    "name": "block",
+    "name": "${name}",
     "using": {
       "manual_configuration": {
         "remote_table": {
           "schema": "${schema}",
-          "name": "blocks"
+          "name": "${remote_table}"
         },
         "column_mapping": {
-          "block_number": "number"
+          "${column}": "${remote_column}"
         }
       }
     }
@@ -209,55 +263,89 @@ EOF
     local response=$(curl -s -X POST "${HASURA_URL}/v1/metadata" \
         -H "X-Hasura-Admin-Secret: ${HASURA_ADMIN_SECRET}" \
         -H "Content-Type: application/json" \
-        -d "${rel_mutation}" 2>/dev/null)
+        -d "${mutation}" 2>/dev/null)
 
     if echo "$response" | jq -e '.message' | grep -q "already exists" 2>/dev/null; then
-        log_warn "  Relationship balance_changes->blocks already exists"
+        log_warn "  Relationship ${table}->${name} already exists"
     elif echo "$response" | jq -e '.error' >/dev/null 2>/dev/null; then
         log_error "  Failed to create relationship: $(echo "$response" | jq -r '.error')"
     else
-        log_success "  Created relationship: balance_changes -> blocks"
+        log_success "  Created relationship: ${arrow}"
     fi
-
-    # Create array relationship from blocks to balance_changes
-    local array_rel_mutation=$(cat <<EOF
-{
-  "type": "pg_create_array_relationship",
-  "args": {
-    "source": "default",
-    "table": {
-      "schema": "${schema}",
-      "name": "blocks"
-    },
-    "name": "balance_changes",
-    "using": {
-      "manual_configuration": {
-        "remote_table": {
-          "schema": "${schema}",
-          "name": "balance_changes"
-        },
-        "column_mapping": {
-          "number": "block_number"
-        }
-      }
-    }
-  }
 }
-EOF
-)
 
-    response=$(curl -s -X POST "${HASURA_URL}/v1/metadata" \
-        -H "X-Hasura-Admin-Secret: ${HASURA_ADMIN_SECRET}" \
-        -H "Content-Type: application/json" \
-        -d "${array_rel_mutation}" 2>/dev/null)
+create_relationships() {
+    local schema=$1
 
-    if echo "$response" | jq -e '.message' | grep -q "already exists" 2>/dev/null; then
-        log_warn "  Relationship blocks->balance_changes already exists"
-    elif echo "$response" | jq -e '.error' >/dev/null 2>/dev/null; then
-        log_error "  Failed to create relationship: $(echo "$response" | jq -r '.error')"
-    else
-        log_success "  Created relationship: blocks -> balance_changes[]"
-    fi
+    log_info "Creating relationships for schema: ${schema}"
+
+    # Balance changes and extrinsics belong to a block
+    create_relationship "${schema}" object balance_changes block blocks block_hash hash
+    create_relationship "${schema}" array blocks balance_changes balance_changes hash block_hash
+    create_relationship "${schema}" object extrinsics block blocks block_number number
+    create_relationship "${schema}" object blocks header block_headers hash block_hash
+    create_relationship "${schema}" array blocks digest_logs digest_logs hash block_hash
+    create_relationship "${schema}" object ledger_checkpoints block blocks block_hash hash
+    create_relationship "${schema}" array blocks extrinsics extrinsics number block_number
+
+    # Balance changes caused by an extrinsic
+    create_relationship "${schema}" array extrinsics balance_changes balance_changes hash extrinsic_hash
+
+    # Extrinsics signed by an account that revealed its key
+    create_relationship "${schema}" array public_keys extrinsics extrinsics account signer
+
+    # Assets pallet tokens and their balance changes
+    create_relationship "${schema}" object balance_changes asset assets asset_id asset_id
+    create_relationship "${schema}" array assets balance_changes balance_changes asset_id asset_id
+    create_relationship "${schema}" object assets owner_address accounts owner account
+
+    # MerkleAirdrop campaigns and their claims
+    create_relationship "${schema}" array airdrops claims airdrop_claims airdrop_id airdrop_id
+    create_relationship "${schema}" object airdrop_claims airdrop airdrops airdrop_id airdrop_id
+
+    # SS58 addresses for account columns
+    create_relationship "${schema}" object balance_changes address accounts account account
+    create_relationship "${schema}" object extrinsics signer_address accounts signer account
+    create_relationship "${schema}" object public_keys address accounts account account
+    create_relationship "${schema}" object account_stats address accounts account account
+    create_relationship "${schema}" object vesting_updates address accounts account account
+    create_relationship "${schema}" object vesting_schedules address accounts account account
+    create_relationship "${schema}" object account_balances address accounts account account
+    create_relationship "${schema}" object balance_locks address accounts account account
+    create_relationship "${schema}" object pending_transfers sender_address accounts from_account account
+    create_relationship "${schema}" object pending_transfers recipient_address accounts to_account account
+    create_relationship "${schema}" object high_security_accounts address accounts account account
+    create_relationship "${schema}" object wormhole_proofs exit_address accounts exit_account account
+    create_relationship "${schema}" object transfer_proofs sender_address accounts from_account account
+    create_relationship "${schema}" object transfer_proofs recipient_address accounts to_account account
+    create_relationship "${schema}" object airdrops creator_address accounts creator account
+    create_relationship "${schema}" object airdrop_claims address accounts account account
+    create_relationship "${schema}" object treasury_spends beneficiary_address accounts beneficiary account
+    create_relationship "${schema}" object treasury_ledger address accounts account account
+    create_relationship "${schema}" object referendum_votes address accounts account account
+    create_relationship "${schema}" object vote_delegations address accounts account account
+    create_relationship "${schema}" object vote_delegations target_address accounts target account
+    create_relationship "${schema}" object collective_members address accounts account account
+    create_relationship "${schema}" object recovery_configs address accounts account account
+    create_relationship "${schema}" object recoveries lost_address accounts lost_account account
+    create_relationship "${schema}" object recoveries rescuer_address accounts rescuer_account account
+    create_relationship "${schema}" object recovery_vouches voucher_address accounts voucher account
+    create_relationship "${schema}" object recovered_accounts lost_address accounts lost_account account
+    create_relationship "${schema}" object recovered_accounts rescuer_address accounts rescuer_account account
+    create_relationship "${schema}" object block_mining miner_address accounts miner account
+    create_relationship "${schema}" object block_mining block blocks block_number number
+    create_relationship "${schema}" object blocks author_address accounts author account
+    create_relationship "${schema}" object fork_blocks block blocks hash hash
+    create_relationship "${schema}" object fork_blocks author_address accounts author account
+    create_relationship "${schema}" object miner_orphans address accounts miner account
+    create_relationship "${schema}" object sudo_actions sudo_key_address accounts sudo_key account
+    create_relationship "${schema}" object sudo_actions extrinsic extrinsics extrinsic_hash hash
+    create_relationship "${schema}" object scheduled_tasks preimage preimages call_hash hash
+    create_relationship "${schema}" object preimages depositor_address accounts depositor account
+    create_relationship "${schema}" object batch_items extrinsic extrinsics extrinsic_hash hash
+    create_relationship "${schema}" object batch_items origin_address accounts origin_account account
+    create_relationship "${schema}" object runtime_upgrades authorizer_address accounts authorizer account
+    create_relationship "${schema}" object runtime_upgrades extrinsic extrinsics extrinsic_hash hash
 }
 
 show_example_queries() {
@@ -285,7 +373,7 @@ show_example_queries() {
     echo "# Get balance changes for an account:"
     echo "query {"
     echo "  ${schema}_balance_changes("
-    echo "    where: {account: {_eq: \"\\\\x...\"}}"
+    echo "    where: {address: {ss58: {_eq: \"qzk...\"}}}"
     echo "    order_by: {block_number: desc}"
     echo "    limit: 10"
     echo "  ) {"
@@ -301,6 +389,22 @@ show_example_queries() {
     echo "  }"
     echo "}"
     echo ""
+    echo "# Get recent failed extrinsics:"
+    echo "query {"
+    echo "  ${schema}_extrinsics("
+    echo "    where: {success: {_eq: false}}"
+    echo "    order_by: {block_number: desc}"
+    echo "    limit: 10"
+    echo "  ) {"
+    echo "    block_number"
+    echo "    extrinsic_index"
+    echo "    call_pallet"
+    echo "    call_function"
+    echo "    dispatch_error"
+    echo "    fee"
+    echo "  }"
+    echo "}"
+    echo ""
     echo "# Get account statistics:"
     echo "query {"
     echo "  ${schema}_account_stats(order_by: {balance: desc}, limit: 10) {"